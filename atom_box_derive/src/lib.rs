@@ -0,0 +1,136 @@
+//! # Atom Box Derive
+//!
+//! Derive macros for the [`atom_box`](https://docs.rs/atom_box) crate. These are re-exported by
+//! `atom_box` when its `derive` feature is enabled, and should be used from there.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Generates a struct holding each field of the annotated struct in its own `AtomBox`.
+///
+/// For a struct `Config`, this generates `AtomConfig<'domain, DOMAIN_ID>`, in which every field is
+/// stored in an `AtomBoxIn` associated with the same domain, so that fields can be loaded and
+/// replaced independently. For each field `name`, `AtomConfig` has methods:
+///
+/// * `name(&self)`, which loads the field, returning a `LoadGuard`,
+/// * `store_name(&self, value)`, which stores a new value in the field,
+/// * `swap_name(&self, value)`, which stores a new value in the field, returning a `StoreGuard` to
+///   the value which was replaced.
+///
+/// These have the same visibility as the field. `AtomConfig` is created from a `Config` with
+/// `AtomConfig::new`, using the shared domain, or `AtomConfig::new_with_domain`.
+///
+/// Only structs with named fields and no generic parameters are supported.
+#[proc_macro_derive(AtomFields)]
+pub fn derive_atom_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    atom_fields(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn atom_fields(input: DeriveInput) -> Result<TokenStream2, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "AtomFields cannot be derived for generic structs",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "AtomFields can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "AtomFields can only be derived for structs",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let atom_name = format_ident!("Atom{}", name);
+    let struct_doc = format!(
+        "The fields of [`{}`], each stored in its own `AtomBox`.",
+        name
+    );
+
+    let field_names: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+    let field_types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let accessors = fields.iter().map(|field| {
+        let field_vis = &field.vis;
+        let field_name = &field.ident;
+        let field_type = &field.ty;
+        let name = field_name
+            .as_ref()
+            .expect("Named fields have identifiers")
+            .to_string();
+        let store = format_ident!("store_{}", name);
+        let swap = format_ident!("swap_{}", name);
+        let load_doc = format!("Loads the value of `{}`.", name);
+        let store_doc = format!("Stores a new value in `{}`.", name);
+        let swap_doc = format!(
+            "Stores a new value in `{}`, returning the value which was replaced.",
+            name
+        );
+        quote! {
+            #[doc = #load_doc]
+            #field_vis fn #field_name(&self) -> ::atom_box::LoadGuard<'domain, #field_type, DOMAIN_ID> {
+                self.#field_name.load()
+            }
+
+            #[doc = #store_doc]
+            #field_vis fn #store(&self, value: #field_type) {
+                self.#field_name.store(value);
+            }
+
+            #[doc = #swap_doc]
+            #field_vis fn #swap(
+                &self,
+                value: #field_type,
+            ) -> ::atom_box::StoreGuard<'domain, #field_type, DOMAIN_ID> {
+                self.#field_name.swap(value)
+            }
+        }
+    });
+
+    Ok(quote! {
+        #[doc = #struct_doc]
+        #vis struct #atom_name<'domain, const DOMAIN_ID: usize> {
+            #(#field_names: ::atom_box::AtomBoxIn<'domain, #field_types, DOMAIN_ID>,)*
+        }
+
+        impl #atom_name<'static, { ::atom_box::__private::SHARED_DOMAIN_ID }> {
+            /// Stores each field of `value` in an `AtomBox` associated with the shared (global)
+            /// domain.
+            #vis fn new(value: #name) -> Self {
+                Self {
+                    #(#field_names: ::atom_box::AtomBox::new(value.#field_names),)*
+                }
+            }
+        }
+
+        impl<'domain, const DOMAIN_ID: usize> #atom_name<'domain, DOMAIN_ID> {
+            /// Stores each field of `value` in an `AtomBox` associated with the given domain.
+            #vis fn new_with_domain(
+                value: #name,
+                domain: &'domain ::atom_box::domain::Domain<DOMAIN_ID>,
+            ) -> Self {
+                Self {
+                    #(#field_names: ::atom_box::AtomBoxIn::new_with_domain(value.#field_names, domain),)*
+                }
+            }
+
+            #(#accessors)*
+        }
+    })
+}
@@ -0,0 +1,191 @@
+use arc_swap::ArcSwap;
+use atom_box::AtomBox;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+const THREAD_COUNTS: [usize; 4] = [1, 2, 4, 8];
+/// One in every `WRITE_RATIO` operations in the mixed benchmark is a write.
+const WRITE_RATIO: u64 = 10;
+
+/// A minimal interface over the different ways of sharing a swappable value between threads,
+/// allowing the same benchmark to be run against each of them.
+trait SharedCell: Sync {
+    const NAME: &'static str;
+
+    fn new(value: usize) -> Self;
+    fn read(&self) -> usize;
+    fn write(&self, value: usize);
+    fn increment(&self);
+}
+
+impl SharedCell for AtomBox<usize> {
+    const NAME: &'static str = "AtomBox";
+
+    fn new(value: usize) -> Self {
+        AtomBox::new(value)
+    }
+
+    fn read(&self) -> usize {
+        *self.load()
+    }
+
+    fn write(&self, value: usize) {
+        self.store(value);
+    }
+
+    fn increment(&self) {
+        let mut current_value = self.load();
+        loop {
+            let new_value = *current_value + 1;
+            match self.compare_exchange(current_value, new_value) {
+                Ok(_) => break,
+                Err(value) => current_value = value,
+            }
+        }
+    }
+}
+
+impl SharedCell for ArcSwap<usize> {
+    const NAME: &'static str = "ArcSwap";
+
+    fn new(value: usize) -> Self {
+        ArcSwap::from_pointee(value)
+    }
+
+    fn read(&self) -> usize {
+        **self.load()
+    }
+
+    fn write(&self, value: usize) {
+        self.store(Arc::new(value));
+    }
+
+    fn increment(&self) {
+        self.rcu(|value| **value + 1);
+    }
+}
+
+impl SharedCell for Mutex<usize> {
+    const NAME: &'static str = "Mutex";
+
+    fn new(value: usize) -> Self {
+        Mutex::new(value)
+    }
+
+    fn read(&self) -> usize {
+        *self.lock().unwrap()
+    }
+
+    fn write(&self, value: usize) {
+        *self.lock().unwrap() = value;
+    }
+
+    fn increment(&self) {
+        *self.lock().unwrap() += 1;
+    }
+}
+
+impl SharedCell for RwLock<usize> {
+    const NAME: &'static str = "RwLock";
+
+    fn new(value: usize) -> Self {
+        RwLock::new(value)
+    }
+
+    fn read(&self) -> usize {
+        *self.read().unwrap()
+    }
+
+    fn write(&self, value: usize) {
+        *self.write().unwrap() = value;
+    }
+
+    fn increment(&self) {
+        *self.write().unwrap() += 1;
+    }
+}
+
+fn bench_load<C: SharedCell>(c: &mut Criterion) {
+    let cell = C::new(42);
+    c.benchmark_group("load")
+        .bench_function(C::NAME, |b| b.iter(|| black_box(cell.read())));
+}
+
+fn bench_swap<C: SharedCell>(c: &mut Criterion) {
+    let cell = C::new(0);
+    c.benchmark_group("swap")
+        .bench_function(C::NAME, |b| b.iter(|| cell.write(black_box(1))));
+}
+
+fn bench_compare_exchange<C: SharedCell>(c: &mut Criterion) {
+    let cell = C::new(0);
+    c.benchmark_group("compare_exchange")
+        .bench_function(C::NAME, |b| b.iter(|| cell.increment()));
+}
+
+/// Runs `iterations` operations on every thread, one in `WRITE_RATIO` of which is a write, and
+/// returns the wall clock time taken for all threads to finish.
+fn run_mixed<C: SharedCell>(cell: &C, threads: usize, iterations: u64) -> Duration {
+    let start = Instant::now();
+    std::thread::scope(|scope| {
+        for thread in 0..threads {
+            scope.spawn(move || {
+                for i in 0..iterations {
+                    if (i + thread as u64).is_multiple_of(WRITE_RATIO) {
+                        cell.write(i as usize);
+                    } else {
+                        black_box(cell.read());
+                    }
+                }
+            });
+        }
+    });
+    start.elapsed()
+}
+
+fn bench_mixed<C: SharedCell>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("mixed/{}", C::NAME));
+    for threads in THREAD_COUNTS {
+        let cell = C::new(0);
+        group.throughput(Throughput::Elements(threads as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads),
+            &threads,
+            |b, &threads| b.iter_custom(|iterations| run_mixed(&cell, threads, iterations)),
+        );
+    }
+    group.finish();
+}
+
+fn load(c: &mut Criterion) {
+    bench_load::<AtomBox<usize>>(c);
+    bench_load::<ArcSwap<usize>>(c);
+    bench_load::<Mutex<usize>>(c);
+    bench_load::<RwLock<usize>>(c);
+}
+
+fn swap(c: &mut Criterion) {
+    bench_swap::<AtomBox<usize>>(c);
+    bench_swap::<ArcSwap<usize>>(c);
+    bench_swap::<Mutex<usize>>(c);
+    bench_swap::<RwLock<usize>>(c);
+}
+
+fn compare_exchange(c: &mut Criterion) {
+    bench_compare_exchange::<AtomBox<usize>>(c);
+    bench_compare_exchange::<ArcSwap<usize>>(c);
+    bench_compare_exchange::<Mutex<usize>>(c);
+    bench_compare_exchange::<RwLock<usize>>(c);
+}
+
+fn mixed(c: &mut Criterion) {
+    bench_mixed::<AtomBox<usize>>(c);
+    bench_mixed::<ArcSwap<usize>>(c);
+    bench_mixed::<Mutex<usize>>(c);
+    bench_mixed::<RwLock<usize>>(c);
+}
+
+criterion_group!(benches, load, swap, compare_exchange, mixed);
+criterion_main!(benches);
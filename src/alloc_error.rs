@@ -0,0 +1,98 @@
+//! Alloc Error
+//!
+//! Fallible allocation of the boxes which hold values stored in an `AtomBox`.
+
+use core::alloc::Layout;
+use core::fmt;
+use core::ptr::NonNull;
+
+/// The error returned when memory for a value could not be allocated.
+///
+/// Contains the value which could not be stored, so that it is not lost.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AllocError, AtomBox};
+///
+/// let atom_box = AtomBox::new(vec![1, 2, 3]);
+/// match atom_box.try_store(vec![4, 5, 6]) {
+///     Ok(()) => {}
+///     Err(AllocError(value)) => eprintln!("Failed to store {:?}", value),
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct AllocError<T>(pub T);
+
+impl<T> AllocError<T> {
+    /// Returns the value which could not be stored.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for AllocError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AllocError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for AllocError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> std::error::Error for AllocError<T> {}
+
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for AllocError<T> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "AllocError(..)")
+    }
+}
+
+/// Moves `value` into a new allocation, as `Box::into_raw(Box::new(value))` would, returning the
+/// value rather than aborting if the allocation fails.
+///
+/// The returned pointer can be converted back into a `Box` via `Box::from_raw`.
+pub(crate) fn try_box<T>(value: T) -> Result<*mut T, AllocError<T>> {
+    let layout = Layout::new::<T>();
+    let ptr = if layout.size() == 0 {
+        NonNull::<T>::dangling().as_ptr()
+    } else {
+        // # Safety
+        //
+        // The layout has a non-zero size.
+        let ptr = unsafe { alloc::alloc::alloc(layout) } as *mut T;
+        if ptr.is_null() {
+            return Err(AllocError(value));
+        }
+        ptr
+    };
+    // # Safety
+    //
+    // The pointer is valid for writes and properly aligned for `T`, having been allocated with
+    // its layout, or being dangling for a zero sized type.
+    unsafe { ptr.write(value) };
+    Ok(ptr)
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::boxed::Box;
+    use alloc::string::String;
+
+    #[test]
+    fn try_box_allocates_boxes() {
+        let ptr = try_box(String::from("Hello")).expect("Allocation should succeed");
+        let zero_sized_ptr = try_box(()).expect("Zero sized values do not allocate");
+
+        let value = unsafe { Box::from_raw(ptr) };
+        assert_eq!(*value, "Hello", "The value is moved into the allocation");
+        drop(unsafe { Box::from_raw(zero_sized_ptr) });
+    }
+}
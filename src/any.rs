@@ -0,0 +1,173 @@
+//! Any
+//!
+//! A dynamically typed `AtomBox`, whose loads can be downcast to the concrete type of the value.
+
+use crate::domain::Domain;
+use crate::{AtomBoxIn, LoadGuard, StoreGuard};
+use alloc::boxed::Box;
+use core::any::Any;
+
+type AnyValue = Box<dyn Any + Send + Sync>;
+
+/// An `AtomBox` holding a value of any type, which need not be known when the box is created.
+///
+/// Each value stored replaces the previous value whatever their types. Loads can be downcast to
+/// the expected type with [`AtomAny::load_downcast`], which fails if the current value is of a
+/// different type.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::AtomAny;
+///
+/// let plugin = AtomAny::new(5_u32);
+/// assert_eq!(*plugin.load_downcast::<u32>().unwrap(), 5);
+///
+/// plugin.store("Hello World");
+/// assert!(plugin.load_downcast::<u32>().is_none());
+/// assert_eq!(*plugin.load_downcast::<&str>().unwrap(), "Hello World");
+/// ```
+#[derive(Debug)]
+pub struct AtomAny<'domain, const DOMAIN_ID: usize> {
+    atom_box: AtomBoxIn<'domain, AnyValue, DOMAIN_ID>,
+}
+
+#[cfg(not(loom))]
+impl AtomAny<'static, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `AtomAny` holding `value`, associated with the shared (global) domain.
+    pub fn new<T: Any + Send + Sync>(value: T) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, const DOMAIN_ID: usize> AtomAny<'domain, DOMAIN_ID> {
+    /// Creates a new `AtomAny` holding `value`, associated with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomAny, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let plugin = AtomAny::new_with_domain(5_u32, &CUSTOM_DOMAIN);
+    /// assert!(plugin.is::<u32>());
+    /// ```
+    pub fn new_with_domain<T: Any + Send + Sync>(
+        value: T,
+        domain: &'domain Domain<DOMAIN_ID>,
+    ) -> Self {
+        Self {
+            atom_box: AtomBoxIn::new_with_domain(Box::new(value), domain),
+        }
+    }
+
+    /// Loads the current value without downcasting it.
+    pub fn load(&self) -> LoadGuard<'domain, AnyValue, DOMAIN_ID> {
+        self.atom_box.load()
+    }
+
+    /// Loads the current value if it is of type `T`, returning `None` otherwise.
+    pub fn load_downcast<T: Any>(&self) -> Option<LoadGuard<'domain, T, DOMAIN_ID>> {
+        let mut guard = self.atom_box.load();
+        let ptr = guard.downcast_ref::<T>()? as *const T;
+        // The value is owned by the boxed value the hazard pointer protects, so it remains valid
+        // for as long as the protection is held.
+        Some(LoadGuard {
+            ptr,
+            domain: guard.domain,
+            haz_ptr: guard.haz_ptr.take(),
+        })
+    }
+
+    /// Returns `true` if the current value is of type `T`.
+    pub fn is<T: Any>(&self) -> bool {
+        self.atom_box.load().is::<T>()
+    }
+
+    /// Stores `value`, replacing the current value whatever its type.
+    pub fn store<T: Any + Send + Sync>(&self, value: T) {
+        self.atom_box.store(Box::new(value));
+    }
+
+    /// Stores `value`, returning the value which was replaced.
+    ///
+    /// The replaced value can be downcast with `downcast_ref`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomAny;
+    ///
+    /// let plugin = AtomAny::new(5_u32);
+    /// let old_value = plugin.swap(String::from("Hello"));
+    /// assert_eq!(old_value.downcast_ref::<u32>(), Some(&5));
+    /// ```
+    pub fn swap<T: Any + Send + Sync>(&self, value: T) -> StoreGuard<'domain, AnyValue, DOMAIN_ID> {
+        self.atom_box.swap(Box::new(value))
+    }
+
+    /// Stores `value` only if the current value is of type `T`, returning the value which was
+    /// replaced.
+    ///
+    /// Otherwise, the current value is left in place and `value` is handed back.
+    pub fn swap_same_type<T: Any + Send + Sync>(
+        &self,
+        value: T,
+    ) -> Result<StoreGuard<'domain, AnyValue, DOMAIN_ID>, T> {
+        self.atom_box
+            .compare_exchange_if(|current_value| current_value.is::<T>(), Box::new(value))
+            .map_err(|(_, rejected)| {
+                *rejected
+                    .downcast::<T>()
+                    .unwrap_or_else(|_| unreachable!("The rejected value is of type `T`"))
+            })
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn downcast_guards_keep_the_value_alive() {
+        let drop_counter = DropCounter::new();
+        let atom_any = AtomAny::new_with_domain(drop_counter.track(1_usize), &TEST_DOMAIN);
+        let guard = atom_any
+            .load_downcast::<crate::test_util::TrackedValue<usize>>()
+            .expect("The value is tracked");
+
+        atom_any.store("Hello");
+        let drops_while_guarded = drop_counter.count();
+        let value = **guard;
+        drop(guard);
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(value, 1);
+        assert_eq!(drops_while_guarded, 0, "The guard protects the value");
+        drop_counter.assert_drops(1);
+    }
+
+    #[test]
+    fn swap_same_type_rejects_values_of_other_types() {
+        let atom_any = AtomAny::new_with_domain(5_u32, &TEST_DOMAIN);
+
+        let rejected = atom_any.swap_same_type("Hello");
+        let accepted = atom_any.swap_same_type(6_u32);
+
+        assert_eq!(rejected.err(), Some("Hello"));
+        assert_eq!(
+            accepted
+                .ok()
+                .and_then(|old| old.downcast_ref::<u32>().copied()),
+            Some(5)
+        );
+        assert_eq!(*atom_any.load_downcast::<u32>().unwrap(), 6);
+    }
+}
@@ -0,0 +1,173 @@
+//! Atom
+//!
+//! A trait for code which loads and replaces values without depending on how the value is kept
+//! alive.
+
+use crate::protection::Protection;
+use crate::{
+    AtomBoxIn, HybridAtomBox, HybridGuard, LoadGuard, LocalAtomBox, LocalGuard, NoUninit,
+    SeqLockAtomBox, StoreGuard,
+};
+use core::ops::Deref;
+
+/// A cell holding a value which can be loaded and replaced concurrently.
+///
+/// Implemented by [`AtomBoxIn`], which protects loaded values with hazard pointers,
+/// [`HybridAtomBox`], which falls back to reference counted snapshots, [`SeqLockAtomBox`] and the
+/// single-threaded [`LocalAtomBox`].
+/// Libraries which accept any `Atom` leave the choice of backend to their users.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{Atom, AtomBox, HybridAtomBox};
+///
+/// fn bump<A: Atom<Value = u32>>(counter: &A) -> u32 {
+///     let next = *counter.load() + 1;
+///     counter.store(next);
+///     next
+/// }
+///
+/// assert_eq!(bump(&AtomBox::new(1)), 2);
+/// assert_eq!(bump(&HybridAtomBox::new(5)), 6);
+/// ```
+pub trait Atom {
+    /// The type of the value held.
+    type Value;
+
+    /// A guard which dereferences to a loaded value.
+    type Guard<'a>: Deref<Target = Self::Value>
+    where
+        Self: 'a;
+
+    /// A guard which dereferences to a value which has been replaced.
+    type Replaced: Deref<Target = Self::Value>;
+
+    /// Loads the current value.
+    fn load(&self) -> Self::Guard<'_>;
+
+    /// Stores a new value, replacing the current value.
+    fn store(&self, value: Self::Value);
+
+    /// Stores a new value, returning the value which was replaced.
+    fn swap(&self, value: Self::Value) -> Self::Replaced;
+}
+
+impl<'domain, T, const DOMAIN_ID: usize, P: Protection> Atom
+    for AtomBoxIn<'domain, T, DOMAIN_ID, P>
+{
+    type Value = T;
+    type Guard<'a>
+        = LoadGuard<'domain, T, DOMAIN_ID, P>
+    where
+        Self: 'a;
+    type Replaced = StoreGuard<'domain, T, DOMAIN_ID, P>;
+
+    fn load(&self) -> Self::Guard<'_> {
+        AtomBoxIn::load(self)
+    }
+
+    fn store(&self, value: T) {
+        AtomBoxIn::store(self, value);
+    }
+
+    fn swap(&self, value: T) -> Self::Replaced {
+        AtomBoxIn::swap(self, value)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Atom for HybridAtomBox<'domain, T, DOMAIN_ID> {
+    type Value = T;
+    type Guard<'a>
+        = HybridGuard<'domain, T, DOMAIN_ID>
+    where
+        Self: 'a;
+    type Replaced = crate::hybrid::Arc<T>;
+
+    fn load(&self) -> Self::Guard<'_> {
+        HybridAtomBox::load(self)
+    }
+
+    fn store(&self, value: T) {
+        HybridAtomBox::store(self, value);
+    }
+
+    fn swap(&self, value: T) -> Self::Replaced {
+        HybridAtomBox::swap(self, value)
+    }
+}
+
+impl<'domain, T: NoUninit, const DOMAIN_ID: usize> Atom for SeqLockAtomBox<'domain, T, DOMAIN_ID> {
+    type Value = T;
+    type Guard<'a>
+        = LoadGuard<'domain, T, DOMAIN_ID>
+    where
+        Self: 'a;
+    type Replaced = StoreGuard<'domain, T, DOMAIN_ID>;
+
+    fn load(&self) -> Self::Guard<'_> {
+        SeqLockAtomBox::load(self)
+    }
+
+    fn store(&self, value: T) {
+        SeqLockAtomBox::store(self, value);
+    }
+
+    fn swap(&self, value: T) -> Self::Replaced {
+        SeqLockAtomBox::swap(self, value)
+    }
+}
+
+impl<T> Atom for LocalAtomBox<T> {
+    type Value = T;
+    type Guard<'a>
+        = LocalGuard<T>
+    where
+        Self: 'a;
+    type Replaced = LocalGuard<T>;
+
+    fn load(&self) -> Self::Guard<'_> {
+        LocalAtomBox::load(self)
+    }
+
+    fn store(&self, value: T) {
+        LocalAtomBox::store(self, value);
+    }
+
+    fn swap(&self, value: T) -> Self::Replaced {
+        LocalAtomBox::swap(self, value)
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::{Domain, ReclaimStrategy};
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+    static LIMITED_DOMAIN: Domain<2> =
+        Domain::new(ReclaimStrategy::Eager).with_hazard_pointer_limit(0);
+
+    fn replace<A: Atom<Value = usize>>(atom: &A) -> (usize, usize) {
+        let old_value = *atom.swap(*atom.load() * 10);
+        (old_value, *atom.load())
+    }
+
+    #[test]
+    fn every_backend_loads_and_replaces_values() {
+        let atom_box = AtomBoxIn::new_with_domain(1, &TEST_DOMAIN);
+        let hybrid = HybridAtomBox::new_with_domain(2, &LIMITED_DOMAIN);
+        let seqlock = SeqLockAtomBox::new_with_domain(3, &TEST_DOMAIN);
+        let local = LocalAtomBox::new(4);
+
+        let results = [
+            replace(&atom_box),
+            replace(&hybrid),
+            replace(&seqlock),
+            replace(&local),
+        ];
+
+        assert_eq!(results, [(1, 10), (2, 20), (3, 30), (4, 40)]);
+    }
+}
@@ -0,0 +1,125 @@
+//! Atom Bytes
+//!
+//! A box holding a [`Bytes`] buffer, enabled by the `bytes` feature.
+
+use crate::domain::Domain;
+use crate::{AtomBoxIn, LoadGuard, StoreGuard};
+use bytes::Bytes;
+
+/// A [`Bytes`] buffer which can be read and swapped concurrently.
+///
+/// [`AtomBytes::load`] borrows the current buffer under the protection of a hazard pointer, so
+/// reading it neither copies the data nor touches its reference count. When a longer lived
+/// handle is needed, [`AtomBytes::load_bytes`] clones the buffer, which only increments its
+/// reference count.
+///
+/// A replaced buffer is retired, and its reference is only released once no reader is protecting
+/// it. The data itself is freed once every other `Bytes` sharing it has also been dropped.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::AtomBytes;
+/// use bytes::Bytes;
+///
+/// let certificate = AtomBytes::new(Bytes::from_static(b"-----BEGIN CERTIFICATE-----"));
+/// let in_use = certificate.load_bytes();
+///
+/// certificate.store(b"-----BEGIN NEW CERTIFICATE-----".to_vec());
+///
+/// assert_eq!(in_use, &b"-----BEGIN CERTIFICATE-----"[..]);
+/// assert!(certificate.load().starts_with(b"-----BEGIN NEW"));
+/// ```
+#[derive(Debug)]
+pub struct AtomBytes<'domain, const DOMAIN_ID: usize> {
+    atom_box: AtomBoxIn<'domain, Bytes, DOMAIN_ID>,
+}
+
+#[cfg(not(loom))]
+impl AtomBytes<'static, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `AtomBytes` associated with the shared (global) domain.
+    pub fn new(value: impl Into<Bytes>) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, const DOMAIN_ID: usize> AtomBytes<'domain, DOMAIN_ID> {
+    /// Creates a new `AtomBytes` and associates it with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBytes, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let routes = AtomBytes::new_with_domain(&b"/health"[..], &CUSTOM_DOMAIN);
+    /// assert_eq!(&routes.load()[..], b"/health");
+    /// ```
+    pub fn new_with_domain(value: impl Into<Bytes>, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            atom_box: AtomBoxIn::new_with_domain(value.into(), domain),
+        }
+    }
+
+    /// Borrows the current buffer without copying it or changing its reference count.
+    pub fn load(&self) -> LoadGuard<'domain, Bytes, DOMAIN_ID> {
+        self.atom_box.load()
+    }
+
+    /// Returns a handle to the current buffer which outlives the `AtomBytes`.
+    ///
+    /// The data is shared rather than copied.
+    pub fn load_bytes(&self) -> Bytes {
+        Bytes::clone(&self.atom_box.load())
+    }
+
+    /// Replaces the current buffer.
+    pub fn store(&self, value: impl Into<Bytes>) {
+        self.atom_box.store(value.into());
+    }
+
+    /// Replaces the current buffer, returning the buffer which was replaced.
+    pub fn swap(&self, value: impl Into<Bytes>) -> StoreGuard<'domain, Bytes, DOMAIN_ID> {
+        self.atom_box.swap(value.into())
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use alloc::vec;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Manual);
+
+    #[test]
+    fn loads_share_the_stored_buffer() {
+        let buffer = Bytes::from(vec![1, 2, 3]);
+        let atom_bytes = AtomBytes::new_with_domain(buffer.clone(), &TEST_DOMAIN);
+
+        let loaded = atom_bytes.load_bytes();
+
+        assert_eq!(loaded.as_ptr(), buffer.as_ptr(), "The data is not copied");
+        assert_eq!(atom_bytes.load().as_ptr(), buffer.as_ptr());
+    }
+
+    #[test]
+    fn replaced_buffers_are_released_once_reclaimed() {
+        let buffer = Bytes::from(vec![1, 2, 3]);
+        let atom_bytes = AtomBytes::new_with_domain(buffer.clone(), &TEST_DOMAIN);
+        let guard = atom_bytes.load();
+
+        drop(atom_bytes.swap(vec![4]));
+        TEST_DOMAIN.reclaim();
+        let released_while_guarded = buffer.is_unique();
+        drop(guard);
+        TEST_DOMAIN.reclaim();
+
+        assert!(!released_while_guarded, "The guard keeps the buffer alive");
+        assert!(buffer.is_unique(), "The retired reference is released");
+        assert_eq!(&atom_bytes.load()[..], [4]);
+    }
+}
@@ -0,0 +1,294 @@
+//! Atom Str
+//!
+//! A box holding a string, stored in a single allocation alongside its length.
+
+use crate::collections::Hazard;
+use crate::domain::Domain;
+use crate::sync::{AtomicPtr, Ordering};
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use alloc::boxed::Box;
+use core::fmt;
+use core::ops::Deref;
+
+/// The layout of a string of `len` bytes, preceded by its length.
+fn layout(len: usize) -> Layout {
+    Layout::new::<usize>()
+        .extend(Layout::array::<u8>(len).expect("String is too long"))
+        .expect("String is too long")
+        .0
+        .pad_to_align()
+}
+
+/// Copies `text` into a new allocation, preceded by its length.
+fn allocate(text: &str) -> *mut usize {
+    let layout = layout(text.len());
+    // # Safety
+    //
+    // The layout is never zero sized, as it always holds the length.
+    let ptr = unsafe { alloc(layout) } as *mut usize;
+    if ptr.is_null() {
+        handle_alloc_error(layout);
+    }
+    // # Safety
+    //
+    // The allocation is large enough for the length followed by the bytes of the string, and the
+    // length is aligned as it is at the start of the allocation.
+    unsafe {
+        ptr.write(text.len());
+        core::ptr::copy_nonoverlapping(text.as_ptr(), ptr.add(1) as *mut u8, text.len());
+    }
+    ptr
+}
+
+/// Returns the string stored in an allocation made by [`allocate`].
+///
+/// # Safety
+///
+/// The allocation must not be freed for the lifetime `'a`.
+unsafe fn as_str<'a>(ptr: *const usize) -> &'a str {
+    // # Safety
+    //
+    // The allocation holds the length followed by the bytes of a valid string.
+    unsafe {
+        let bytes = core::slice::from_raw_parts(ptr.add(1) as *const u8, *ptr);
+        core::str::from_utf8_unchecked(bytes)
+    }
+}
+
+/// Frees an allocation made by [`allocate`].
+///
+/// # Safety
+///
+/// The allocation must not already have been freed, and must no longer be accessed.
+unsafe fn deallocate(ptr: *mut usize) {
+    // # Safety
+    //
+    // The allocation was made with the layout for the length it holds.
+    unsafe { dealloc(ptr as *mut u8, layout(*ptr)) };
+}
+
+/// A string which can be loaded and replaced concurrently.
+///
+/// Unlike an `AtomBox<String>`, which points to a `String` which in turn points to its bytes, the
+/// bytes of the string are stored in the same allocation as its length, so loading the string
+/// follows a single pointer.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::AtomStr;
+///
+/// let endpoint = AtomStr::new("https://example.com/v1");
+/// let current = endpoint.load();
+///
+/// endpoint.store("https://example.com/v2");
+///
+/// assert_eq!(current, "https://example.com/v1");
+/// assert!(endpoint.eq_str("https://example.com/v2"));
+/// assert!(endpoint.load().ends_with("v2"));
+/// ```
+pub struct AtomStr<'domain, const DOMAIN_ID: usize> {
+    ptr: AtomicPtr<usize>,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// Strings are plain bytes, which may be read from, and freed by, any thread.
+unsafe impl<'domain, const DOMAIN_ID: usize> Send for AtomStr<'domain, DOMAIN_ID> {}
+unsafe impl<'domain, const DOMAIN_ID: usize> Sync for AtomStr<'domain, DOMAIN_ID> {}
+
+impl<'domain, const DOMAIN_ID: usize> fmt::Debug for AtomStr<'domain, DOMAIN_ID> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AtomStr").field(&&*self.load()).finish()
+    }
+}
+
+#[cfg(not(loom))]
+impl AtomStr<'static, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `AtomStr` associated with the shared (global) domain.
+    pub fn new(value: impl Into<Box<str>>) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, const DOMAIN_ID: usize> AtomStr<'domain, DOMAIN_ID> {
+    /// Creates a new `AtomStr` and associates it with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomStr, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let token = AtomStr::new_with_domain("secret", &CUSTOM_DOMAIN);
+    /// assert_eq!(token.load(), "secret");
+    /// ```
+    pub fn new_with_domain(value: impl Into<Box<str>>, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            ptr: AtomicPtr::new(allocate(&value.into())),
+            domain,
+        }
+    }
+
+    /// Loads the current string.
+    pub fn load(&self) -> AtomStrGuard<'domain, DOMAIN_ID> {
+        let hazard = Hazard::new(self.domain);
+        let ptr = hazard.protect_ptr(&self.ptr);
+        AtomStrGuard {
+            ptr,
+            _hazard: hazard,
+        }
+    }
+
+    /// Replaces the current string, retiring the string it replaces.
+    pub fn store(&self, value: impl Into<Box<str>>) {
+        let new_ptr = allocate(&value.into());
+        let old_ptr = self.ptr.swap(new_ptr, Ordering::AcqRel);
+        // # Safety
+        //
+        // The old string is no longer reachable from the box, and only the thread which swapped
+        // it out retires it.
+        unsafe { self.retire(old_ptr) };
+    }
+
+    /// Returns `true` if the current string is equal to `other`.
+    pub fn eq_str(&self, other: &str) -> bool {
+        *self.load() == *other
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` must have been made by [`allocate`], and must be retired only once.
+    unsafe fn retire(&self, ptr: *mut usize) {
+        // # Safety
+        //
+        // The allocation holds its length.
+        let size = layout(unsafe { *ptr }).size();
+        unsafe { self.domain.retire_unsized::<str>(ptr, deallocate, size) };
+    }
+}
+
+impl<'domain, const DOMAIN_ID: usize> Drop for AtomStr<'domain, DOMAIN_ID> {
+    fn drop(&mut self) {
+        // # Safety
+        //
+        // We have exclusive access to the box. Guards may still reference the current string, so
+        // it is retired rather than freed.
+        unsafe { self.retire(self.ptr.load(Ordering::Relaxed)) };
+    }
+}
+
+/// A string loaded from an [`AtomStr`].
+///
+/// The string is guaranteed not to be freed before the guard is dropped. Dereferences to `str`.
+pub struct AtomStrGuard<'domain, const DOMAIN_ID: usize> {
+    ptr: *const usize,
+    // Protects the string until the guard is dropped.
+    _hazard: Hazard<'domain, DOMAIN_ID>,
+}
+
+impl<const DOMAIN_ID: usize> Deref for AtomStrGuard<'_, DOMAIN_ID> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        // # Safety
+        //
+        // The string is protected by the hazard, so is not freed while the guard is borrowed.
+        unsafe { as_str(self.ptr) }
+    }
+}
+
+impl<const DOMAIN_ID: usize> AsRef<str> for AtomStrGuard<'_, DOMAIN_ID> {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl<const DOMAIN_ID: usize> fmt::Debug for AtomStrGuard<'_, DOMAIN_ID> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<const DOMAIN_ID: usize> fmt::Display for AtomStrGuard<'_, DOMAIN_ID> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<const DOMAIN_ID: usize> PartialEq<str> for AtomStrGuard<'_, DOMAIN_ID> {
+    fn eq(&self, other: &str) -> bool {
+        **self == *other
+    }
+}
+
+impl<const DOMAIN_ID: usize> PartialEq<&str> for AtomStrGuard<'_, DOMAIN_ID> {
+    fn eq(&self, other: &&str) -> bool {
+        **self == **other
+    }
+}
+
+impl<const DOMAIN_ID: usize, const OTHER_ID: usize> PartialEq<AtomStrGuard<'_, OTHER_ID>>
+    for AtomStrGuard<'_, DOMAIN_ID>
+{
+    fn eq(&self, other: &AtomStrGuard<'_, OTHER_ID>) -> bool {
+        **self == **other
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use alloc::format;
+    use alloc::string::String;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn strings_of_any_length_round_trip() {
+        let atom_str = AtomStr::new_with_domain("", &TEST_DOMAIN);
+
+        let empty = atom_str.load();
+        atom_str.store(String::from("Hello, 世界"));
+
+        assert_eq!(empty, "");
+        assert_eq!(atom_str.load(), "Hello, 世界");
+        assert_eq!(format!("{}", atom_str.load()), "Hello, 世界");
+    }
+
+    #[test]
+    fn guards_outlive_stores() {
+        let atom_str = AtomStr::new_with_domain("first", &TEST_DOMAIN);
+        let guard = atom_str.load();
+
+        atom_str.store("second");
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(guard, "first");
+        assert!(atom_str.eq_str("second"));
+        assert!(guard != atom_str.load());
+    }
+
+    #[test]
+    fn concurrent_loads_and_stores() {
+        let atom_str = AtomStr::new_with_domain("0", &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for value in 1..1000 {
+                    atom_str.store(format!("{}", value));
+                }
+            });
+            scope.spawn(|| {
+                for _ in 0..1000 {
+                    assert!(atom_str.load().parse::<usize>().unwrap() < 1000);
+                }
+            });
+        });
+
+        assert_eq!(atom_str.load(), "999");
+    }
+}
@@ -0,0 +1,334 @@
+//! Broadcast
+//!
+//! A cell which publishes values to any number of subscribers. Each store bumps the version of
+//! the cell, and subscribers receive the latest value only if it is newer than the last value
+//! they received. Subscribers which fall behind skip straight to the latest value.
+
+use crate::collections::Hazard;
+use crate::domain::Domain;
+use crate::sync::{AtomicPtr, Ordering};
+use crate::LoadGuard;
+use alloc::boxed::Box;
+
+struct Versioned<T> {
+    version: usize,
+    value: T,
+}
+
+/// A cell which broadcasts the values stored in it to its [`Subscriber`]s.
+///
+/// Every store increments the version of the cell. A subscriber keeps the last value it received
+/// protected from reclamation until it receives a newer one, so values stay alive until every
+/// subscriber which saw them has moved past them.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::BroadcastBox;
+///
+/// let broadcast = BroadcastBox::new("Hello");
+/// let mut subscriber = broadcast.subscribe();
+///
+/// assert_eq!(*subscriber.recv_latest().unwrap(), "Hello");
+/// assert!(subscriber.recv_latest().is_none());
+///
+/// broadcast.store("Intermediate");
+/// broadcast.store("World");
+///
+/// // Subscribers skip straight to the latest value.
+/// assert_eq!(*subscriber.recv_latest().unwrap(), "World");
+/// assert_eq!(subscriber.last_seen(), Some(&"World"));
+/// ```
+pub struct BroadcastBox<'domain, T, const DOMAIN_ID: usize> {
+    ptr: AtomicPtr<Versioned<T>>,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// Values are handed to other threads through guards, and are dropped by whichever thread reclaims
+// them.
+unsafe impl<'domain, T: Send, const DOMAIN_ID: usize> Send for BroadcastBox<'domain, T, DOMAIN_ID> {}
+unsafe impl<'domain, T: Send + Sync, const DOMAIN_ID: usize> Sync
+    for BroadcastBox<'domain, T, DOMAIN_ID>
+{
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> core::fmt::Debug for BroadcastBox<'domain, T, DOMAIN_ID> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("BroadcastBox")
+            .field("version", &self.version())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> BroadcastBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `BroadcastBox` associated with the shared (global) domain.
+    pub fn new(value: T) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> BroadcastBox<'domain, T, DOMAIN_ID> {
+    /// Creates a new `BroadcastBox` and associates it with the given domain.
+    ///
+    /// The initial value has version zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{BroadcastBox, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let broadcast = BroadcastBox::new_with_domain("Hello", &CUSTOM_DOMAIN);
+    /// assert_eq!(broadcast.version(), 0);
+    /// ```
+    pub fn new_with_domain(value: T, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            ptr: AtomicPtr::new(Box::into_raw(Box::new(Versioned { version: 0, value }))),
+            domain,
+        }
+    }
+
+    /// Returns the version of the current value.
+    pub fn version(&self) -> usize {
+        let hazard = Hazard::new(self.domain);
+        let ptr = hazard.protect_ptr(&self.ptr);
+        // # Safety
+        //
+        // The value is protected by the hazard.
+        unsafe { &*ptr }.version
+    }
+
+    /// Loads the current value, regardless of which values subscribers have seen.
+    pub fn load(&self) -> LoadGuard<'domain, T, DOMAIN_ID> {
+        let hazard = Hazard::new(self.domain);
+        let ptr = hazard.protect_ptr(&self.ptr);
+        // # Safety
+        //
+        // The value is protected by the hazard.
+        hazard.into_load_guard(&unsafe { &*ptr }.value)
+    }
+
+    /// Stores a new value, returning its version.
+    pub fn store(&self, value: T) -> usize {
+        let new_ptr = Box::into_raw(Box::new(Versioned { version: 0, value }));
+        let hazard = Hazard::new(self.domain);
+        let mut current_ptr = hazard.protect_ptr(&self.ptr);
+        loop {
+            // # Safety
+            //
+            // The current value is protected by the hazard, and we have not yet published the
+            // new value.
+            let version = unsafe { &*current_ptr }.version.wrapping_add(1);
+            unsafe { &mut *new_ptr }.version = version;
+            match self.ptr.compare_exchange(
+                current_ptr,
+                new_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    // # Safety
+                    //
+                    // The old value was created via `Box::into_raw` and is no longer reachable
+                    // from the cell. Only the thread which replaced it can retire it.
+                    unsafe { self.domain.retire(current_ptr) };
+                    break version;
+                }
+                Err(_) => current_ptr = hazard.protect_ptr(&self.ptr),
+            }
+        }
+    }
+
+    /// Creates a new `Subscriber` which has not yet seen any values.
+    pub fn subscribe(&self) -> Subscriber<'_, 'domain, T, DOMAIN_ID> {
+        Subscriber {
+            broadcast: self,
+            last_seen: None,
+            hazard: Hazard::new(self.domain),
+        }
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Drop for BroadcastBox<'domain, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        // # Safety
+        //
+        // We have exclusive access to the cell. Guards may still reference the current value, so
+        // it is retired rather than dropped.
+        unsafe { self.domain.retire(self.ptr.load(Ordering::Relaxed)) };
+    }
+}
+
+/// A subscriber to a [`BroadcastBox`].
+///
+/// The last value received by the subscriber is kept alive until it receives a newer one or is
+/// dropped.
+pub struct Subscriber<'broadcast, 'domain, T, const DOMAIN_ID: usize> {
+    broadcast: &'broadcast BroadcastBox<'domain, T, DOMAIN_ID>,
+    // Protected by the subscriber's hazard.
+    last_seen: Option<*const Versioned<T>>,
+    hazard: Hazard<'domain, DOMAIN_ID>,
+}
+
+unsafe impl<'broadcast, 'domain, T: Send + Sync, const DOMAIN_ID: usize> Send
+    for Subscriber<'broadcast, 'domain, T, DOMAIN_ID>
+{
+}
+
+impl<'broadcast, 'domain, T, const DOMAIN_ID: usize> core::fmt::Debug
+    for Subscriber<'broadcast, 'domain, T, DOMAIN_ID>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Subscriber")
+            .field("version", &self.version())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'broadcast, 'domain, T, const DOMAIN_ID: usize> Subscriber<'broadcast, 'domain, T, DOMAIN_ID> {
+    /// Returns the latest value if it is newer than the last value this subscriber received.
+    ///
+    /// Any values stored since the last call which have already been replaced are skipped.
+    pub fn recv_latest(&mut self) -> Option<LoadGuard<'domain, T, DOMAIN_ID>> {
+        let hazard = Hazard::new(self.broadcast.domain);
+        let ptr = hazard.protect_ptr(&self.broadcast.ptr);
+        // # Safety
+        //
+        // The value is protected by the hazard.
+        let latest = unsafe { &*ptr };
+        if self
+            .version()
+            .is_some_and(|version| latest.version.wrapping_sub(version) as isize <= 0)
+        {
+            return None;
+        }
+        // The value is already protected by the guard's hazard, so it cannot have been reclaimed
+        // before being protected by the subscriber's hazard.
+        self.hazard.protect(ptr);
+        self.last_seen = Some(ptr);
+        Some(hazard.into_load_guard(&latest.value))
+    }
+
+    /// Returns the last value received by this subscriber.
+    pub fn last_seen(&self) -> Option<&T> {
+        // # Safety
+        //
+        // The last seen value is protected by the subscriber's hazard.
+        self.last_seen.map(|ptr| &unsafe { &*ptr }.value)
+    }
+
+    /// Returns the version of the last value received by this subscriber.
+    pub fn version(&self) -> Option<usize> {
+        // # Safety
+        //
+        // The last seen value is protected by the subscriber's hazard.
+        self.last_seen.map(|ptr| unsafe { &*ptr }.version)
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn subscribers_only_receive_newer_values() {
+        let broadcast = BroadcastBox::new_with_domain(0, &TEST_DOMAIN);
+        let mut first = broadcast.subscribe();
+        let mut second = broadcast.subscribe();
+
+        let initial = first.recv_latest();
+        let versions: alloc::vec::Vec<_> = (1..=3).map(|value| broadcast.store(value)).collect();
+
+        assert_eq!(
+            initial.as_deref(),
+            Some(&0),
+            "First receives the initial value"
+        );
+        assert_eq!(versions, [1, 2, 3], "Each store bumps the version");
+        assert_eq!(
+            *first.recv_latest().unwrap(),
+            3,
+            "Intermediate values are skipped"
+        );
+        assert!(
+            first.recv_latest().is_none(),
+            "Nothing newer has been stored"
+        );
+        assert_eq!(
+            first.version(),
+            Some(3),
+            "First has seen the latest version"
+        );
+        assert_eq!(
+            *second.recv_latest().unwrap(),
+            3,
+            "Second receives the latest value"
+        );
+        assert_eq!(*broadcast.load(), 3, "Load returns the latest value");
+    }
+
+    #[test]
+    fn last_seen_value_is_kept_alive() {
+        let domain: Domain<2> = Domain::new(ReclaimStrategy::Eager);
+        let drop_counter = DropCounter::new();
+        let broadcast = BroadcastBox::new_with_domain(drop_counter.track(0), &domain);
+        let mut subscriber = broadcast.subscribe();
+        drop(subscriber.recv_latest());
+
+        broadcast.store(drop_counter.track(1));
+        broadcast.store(drop_counter.track(2));
+        domain.reclaim();
+
+        drop_counter.assert_drops(1);
+        assert_eq!(
+            subscriber.last_seen().map(|value| **value),
+            Some(0),
+            "The subscriber's last value is still alive"
+        );
+        drop(subscriber.recv_latest());
+        domain.reclaim();
+        drop_counter.assert_drops(2);
+        drop(subscriber);
+        drop(broadcast);
+        domain.reclaim();
+        drop_counter.assert_drops(3);
+    }
+
+    #[test]
+    fn concurrent_subscribers_see_increasing_versions() {
+        let broadcast = BroadcastBox::new_with_domain(0_usize, &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        broadcast.store(0);
+                    }
+                });
+            }
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    let mut subscriber = broadcast.subscribe();
+                    let mut last_version = None;
+                    for _ in 0..1000 {
+                        if subscriber.recv_latest().is_some() {
+                            let version = subscriber.version();
+                            assert!(version > last_version, "Versions should increase");
+                            last_version = version;
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(broadcast.version(), 2000, "Every store bumps the version");
+    }
+}
@@ -0,0 +1,147 @@
+//! Builder
+//!
+//! A fluent constructor for `AtomBox`es which need more than a value and a domain.
+
+use crate::domain::{Domain, RetirePolicy};
+use crate::AtomBoxIn;
+
+/// Configures and creates an `AtomBox`.
+///
+/// The builder starts out with the same configuration as [`AtomBox::new`](crate::AtomBox::new):
+/// the shared domain, the domain's retire policy and no reserved hazard pointers. Each option
+/// can then be changed before [`AtomBoxBuilder::build`] creates the box.
+///
+/// Created by [`AtomBox::builder`](crate::AtomBox::builder).
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBox, domain::{Domain, ReclaimStrategy, RetirePolicy}};
+///
+/// const CUSTOM_DOMAIN_ID: usize = 42;
+/// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+///
+/// let atom_box = AtomBox::builder("Hello")
+///     .domain(&CUSTOM_DOMAIN)
+///     .retire_policy(RetirePolicy::Immediate)
+///     .preallocate_guards(2)
+///     .build();
+///
+/// let (hello, still_hello) = (atom_box.try_load().unwrap(), atom_box.try_load().unwrap());
+/// assert_eq!((*hello, *still_hello), ("Hello", "Hello"));
+/// ```
+#[must_use = "The builder does nothing until `build` is called"]
+pub struct AtomBoxBuilder<'domain, T, const DOMAIN_ID: usize> {
+    value: T,
+    domain: &'domain Domain<DOMAIN_ID>,
+    retire_policy: RetirePolicy,
+    preallocated_guards: usize,
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> core::fmt::Debug
+    for AtomBoxBuilder<'domain, T, DOMAIN_ID>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AtomBoxBuilder")
+            .field("domain", &DOMAIN_ID)
+            .field("retire_policy", &self.retire_policy)
+            .field("preallocated_guards", &self.preallocated_guards)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> crate::AtomBox<T> {
+    /// Starts building an `AtomBox` holding `value`.
+    ///
+    /// See [`AtomBoxBuilder`].
+    pub fn builder(value: T) -> AtomBoxBuilder<'static, T, { crate::SHARED_DOMAIN_ID }> {
+        AtomBoxBuilder {
+            value,
+            domain: &crate::SHARED_DOMAIN,
+            retire_policy: RetirePolicy::Domain,
+            preallocated_guards: 0,
+        }
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> AtomBoxBuilder<'domain, T, DOMAIN_ID> {
+    /// Associates the box with `domain` rather than the shared domain.
+    pub fn domain<'new_domain, const NEW_DOMAIN_ID: usize>(
+        self,
+        domain: &'new_domain Domain<NEW_DOMAIN_ID>,
+    ) -> AtomBoxBuilder<'new_domain, T, NEW_DOMAIN_ID> {
+        AtomBoxBuilder {
+            value: self.value,
+            domain,
+            retire_policy: self.retire_policy,
+            preallocated_guards: self.preallocated_guards,
+        }
+    }
+
+    /// Sets how the values replaced in the box are retired.
+    ///
+    /// See [`AtomBoxIn::with_retire_policy`].
+    pub fn retire_policy(mut self, retire_policy: RetirePolicy) -> Self {
+        self.retire_policy = retire_policy;
+        self
+    }
+
+    /// Reserves hazard pointers in the domain for `count` guards to be held at once, so that
+    /// loading the box does not need to allocate them.
+    ///
+    /// See [`Domain::reserve_hazard_pointers`].
+    pub fn preallocate_guards(mut self, count: usize) -> Self {
+        self.preallocated_guards = count;
+        self
+    }
+
+    /// Creates the `AtomBox`.
+    pub fn build(self) -> AtomBoxIn<'domain, T, DOMAIN_ID> {
+        if self.preallocated_guards > 0 {
+            self.domain
+                .reserve_hazard_pointers(self.preallocated_guards);
+        }
+        AtomBoxIn::new_with_domain(self.value, self.domain).with_retire_policy(self.retire_policy)
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+    use crate::AtomBox;
+
+    #[test]
+    fn builds_with_the_shared_domain_by_default() {
+        let atom_box = AtomBox::builder(5).build();
+
+        assert_eq!(*atom_box.load(), 5);
+        assert!(
+            core::ptr::eq(atom_box.domain, &crate::SHARED_DOMAIN),
+            "The box uses the shared domain"
+        );
+    }
+
+    #[test]
+    fn applies_every_option() {
+        let drop_counter = DropCounter::new();
+        let domain: Domain<1> = Domain::new(ReclaimStrategy::Manual);
+
+        let atom_box = AtomBox::builder(drop_counter.track(1))
+            .domain(&domain)
+            .retire_policy(RetirePolicy::Immediate)
+            .preallocate_guards(3)
+            .build();
+        let guards: [_; 3] = core::array::from_fn(|_| atom_box.try_load());
+        let all_loaded = guards.iter().all(Option::is_some);
+        drop(guards);
+        atom_box.store(drop_counter.track(2));
+
+        assert!(core::ptr::eq(atom_box.domain, &domain));
+        assert!(all_loaded, "Hazard pointers were reserved for every guard");
+        drop_counter.assert_drops(1);
+    }
+}
@@ -0,0 +1,253 @@
+//! Cache
+//!
+//! A snapshot of the value in an `AtomBox` which is only reloaded once the value changes.
+//!
+//! A snapshot is reused while the box still points to its value. The snapshot is protected, so
+//! its value cannot have been freed and its address reused by a newer value.
+
+use crate::protection::Protection;
+use crate::{AtomBoxIn, LoadGuard};
+
+/// A protected snapshot of the value held by an `AtomBox`, which is reused until the value is
+/// replaced.
+///
+/// Loading through the cache reads the box's pointer and compares it with the pointer of the
+/// snapshot. Only if the value has been replaced is a hazard pointer published and validated to
+/// load the new value, so threads which load a box far more often than it changes only read
+/// shared memory which they rarely contend on. Zero-sized values all share an address, so a
+/// replaced zero-sized value is never reloaded, though it could not differ from the snapshot.
+///
+/// The cache is owned by the thread which loads through it, typically for as long as that thread
+/// runs. While it holds a snapshot, the snapshot's value cannot be reclaimed, even once it has
+/// been replaced, until the cache is loaded again or dropped.
+///
+/// Created by [`AtomBoxIn::cache`]. Boxes with a static lifetime can instead keep a snapshot for
+/// each thread with [`AtomBoxIn::load_cached`].
+///
+/// # Example
+///
+/// ```
+/// use atom_box::AtomBox;
+///
+/// let feature_flags = AtomBox::new(vec!["dark-mode"]);
+/// let mut flags = feature_flags.cache();
+///
+/// assert_eq!(**flags.load(), ["dark-mode"]);
+/// assert!(!flags.load().contains(&"beta"), "The snapshot is reused");
+///
+/// feature_flags.store(vec!["dark-mode", "beta"]);
+/// assert!(flags.load().contains(&"beta"), "The value changed, so it is reloaded");
+/// ```
+pub struct AtomCache<'a, 'domain, T, const DOMAIN_ID: usize, P: Protection + 'domain> {
+    atom_box: &'a AtomBoxIn<'domain, T, DOMAIN_ID, P>,
+    snapshot: LoadGuard<'domain, T, DOMAIN_ID, P>,
+}
+
+impl<'a, 'domain, T, const DOMAIN_ID: usize, P: Protection> core::fmt::Debug
+    for AtomCache<'a, 'domain, T, DOMAIN_ID, P>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AtomCache")
+            .field("snapshot", &self.snapshot.ptr)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, 'domain, T, const DOMAIN_ID: usize, P: Protection>
+    AtomCache<'a, 'domain, T, DOMAIN_ID, P>
+{
+    /// Returns the current value, reusing the snapshot if the value has not been replaced since
+    /// it was taken.
+    pub fn load(&mut self) -> &LoadGuard<'domain, T, DOMAIN_ID, P> {
+        if !self.atom_box.current_ptr_eq(&self.snapshot) {
+            self.snapshot = self.atom_box.load();
+        }
+        &self.snapshot
+    }
+
+    /// Returns the snapshot without checking whether the value has been replaced.
+    pub fn snapshot(&self) -> &LoadGuard<'domain, T, DOMAIN_ID, P> {
+        &self.snapshot
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize, P: Protection> AtomBoxIn<'domain, T, DOMAIN_ID, P> {
+    /// Creates a cache which reloads the value of this `AtomBox` only once it has been replaced.
+    ///
+    /// See [`AtomCache`].
+    pub fn cache(&self) -> AtomCache<'_, 'domain, T, DOMAIN_ID, P> {
+        AtomCache {
+            atom_box: self,
+            snapshot: self.load(),
+        }
+    }
+}
+
+#[cfg(all(feature = "std", not(loom)))]
+impl<T: 'static, const DOMAIN_ID: usize> AtomBoxIn<'static, T, DOMAIN_ID> {
+    /// Calls `f` with the current value, reusing a snapshot cached by the calling thread until
+    /// the value changes.
+    ///
+    /// Each thread keeps a protected snapshot of the box. While the box still points to the
+    /// snapshot's value, calls only read the box's pointer, without publishing a hazard pointer, so a box which is loaded far more often than it
+    /// changes is read without contention. This is [`AtomCache`] kept in thread local storage.
+    ///
+    /// The snapshot of a replaced value keeps it from being reclaimed until the thread calls
+    /// this again or exits. The box must therefore live as long as the thread's snapshot, hence
+    /// the static lifetime.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let routes: &'static _ = AtomBox::new_static(vec!["/health"]);
+    ///
+    /// assert_eq!(routes.load_cached(|routes| routes.len()), 1);
+    /// assert!(!routes.load_cached(|routes| routes.contains(&"/metrics")));
+    ///
+    /// routes.store(vec!["/health", "/metrics"]);
+    /// assert!(routes.load_cached(|routes| routes.contains(&"/metrics")));
+    /// ```
+    pub fn load_cached<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+        thread_cache::with_snapshot(self, |snapshot| f(snapshot))
+    }
+}
+
+#[cfg(all(feature = "std", not(loom)))]
+mod thread_cache {
+    use crate::{AtomBoxIn, LoadGuard};
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use core::any::Any;
+    use core::cell::RefCell;
+
+    // The snapshots of the current thread, keyed by the address of their box. They are dropped
+    // by the thread local destructor when the thread exits.
+    std::thread_local! {
+        static SNAPSHOTS: RefCell<Vec<(usize, Box<dyn Any>)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub(super) fn with_snapshot<T: 'static, const DOMAIN_ID: usize, R>(
+        atom_box: &'static AtomBoxIn<'static, T, DOMAIN_ID>,
+        f: impl FnOnce(&LoadGuard<'static, T, DOMAIN_ID>) -> R,
+    ) -> R {
+        let key = atom_box as *const AtomBoxIn<'static, T, DOMAIN_ID> as usize;
+        // The snapshot is taken out while `f` runs, so that `f` can load other boxes.
+        let snapshot = SNAPSHOTS
+            .try_with(|snapshots| {
+                let mut snapshots = snapshots.borrow_mut();
+                let index = snapshots.iter().position(|(box_key, _)| *box_key == key)?;
+                Some(snapshots.swap_remove(index).1)
+            })
+            .ok()
+            .flatten()
+            .map(|snapshot| {
+                snapshot
+                    .downcast::<LoadGuard<'static, T, DOMAIN_ID>>()
+                    .expect("Snapshots are keyed by their box")
+            });
+        let snapshot = match snapshot {
+            Some(snapshot) if atom_box.current_ptr_eq(&snapshot) => snapshot,
+            stale => {
+                // The stale snapshot's hazard pointer is released before another is acquired.
+                drop(stale);
+                Box::new(atom_box.load())
+            }
+        };
+        let result = f(&snapshot);
+        // If the thread is exiting, or a nested call has put back its own snapshot, this snapshot
+        // is dropped, releasing its hazard pointer.
+        let _ = SNAPSHOTS.try_with(|snapshots| {
+            let mut snapshots = snapshots.borrow_mut();
+            if snapshots.iter().all(|(box_key, _)| *box_key != key) {
+                snapshots.push((key, snapshot));
+            }
+        });
+        result
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use crate::domain::{Domain, ReclaimStrategy};
+    use crate::test_util::DropCounter;
+    use crate::AtomBoxIn;
+    #[cfg(feature = "std")]
+    use alloc::boxed::Box;
+
+    #[test]
+    fn snapshots_are_reused_until_the_value_changes() {
+        let domain: Domain<1> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box = AtomBoxIn::new_with_domain(1, &domain);
+        let mut cache = atom_box.cache();
+
+        let first = cache.load().ptr;
+        let reused = cache.load().ptr;
+        atom_box.store(2);
+        let reloaded = **cache.load();
+
+        assert_eq!(first, reused, "The snapshot is reused");
+        assert_eq!(reloaded, 2, "The replaced value is reloaded");
+    }
+
+    #[test]
+    fn snapshots_are_released_once_reloaded() {
+        let drop_counter = DropCounter::new();
+        let domain: Domain<2> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &domain);
+        let mut cache = atom_box.cache();
+
+        atom_box.store(drop_counter.track(2));
+        let reclaimed_while_cached = domain.reclaim();
+        cache.load();
+        let reclaimed_after_reload = domain.reclaim();
+
+        assert_eq!(
+            reclaimed_while_cached, 0,
+            "The snapshot protects the old value"
+        );
+        assert_eq!(reclaimed_after_reload, 1);
+        drop_counter.assert_drops(1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn thread_snapshots_are_reused_until_the_value_changes() {
+        static DOMAIN: Domain<3> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box: &'static _ = Box::leak(Box::new(AtomBoxIn::new_with_domain(1, &DOMAIN)));
+
+        let first = atom_box.load_cached(|value| *value);
+        atom_box.store(2);
+        let reclaimed_while_cached = DOMAIN.reclaim();
+        let reloaded = atom_box.load_cached(|value| *value);
+
+        assert_eq!(first, 1);
+        assert_eq!(
+            reclaimed_while_cached, 0,
+            "The thread's snapshot protects the old value"
+        );
+        assert_eq!(reloaded, 2, "The changed value is reloaded");
+        assert_eq!(DOMAIN.reclaim(), 1, "The old snapshot is released");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn thread_snapshots_are_kept_for_each_box() {
+        static DOMAIN: Domain<4> = Domain::new(ReclaimStrategy::Manual);
+        let first: &'static _ = Box::leak(Box::new(AtomBoxIn::new_with_domain(1, &DOMAIN)));
+        let second: &'static _ = Box::leak(Box::new(AtomBoxIn::new_with_domain(2, &DOMAIN)));
+
+        let sum = first.load_cached(|one| second.load_cached(|two| one + two));
+        let on_other_thread = std::thread::spawn(move || first.load_cached(|value| *value))
+            .join()
+            .unwrap();
+
+        assert_eq!(
+            sum, 3,
+            "Boxes can be loaded while another snapshot is in use"
+        );
+        assert_eq!(on_other_thread, 1);
+    }
+}
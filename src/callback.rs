@@ -0,0 +1,153 @@
+//! Callback
+//!
+//! A cell holding a function which can be called from any thread and hot-swapped at any time.
+
+use crate::domain::Domain;
+use crate::{AtomBoxIn, StoreGuard};
+use alloc::boxed::Box;
+
+type Handler<Args, Ret> = Box<dyn Fn(Args) -> Ret + Send + Sync>;
+
+/// A cell holding a function which can be called and replaced concurrently.
+///
+/// [`AtomCallback::call`] protects the current handler for the duration of the call, so a handler
+/// which is swapped out while it is running is only dropped once every in-flight call has returned.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::AtomCallback;
+///
+/// let callback = AtomCallback::new(|x: u32| x + 1);
+/// assert_eq!(callback.call(1), 2);
+///
+/// callback.store(|x: u32| x * 10);
+/// assert_eq!(callback.call(1), 10);
+/// ```
+pub struct AtomCallback<'domain, Args, Ret, const DOMAIN_ID: usize> {
+    handler: AtomBoxIn<'domain, Handler<Args, Ret>, DOMAIN_ID>,
+}
+
+impl<'domain, Args, Ret, const DOMAIN_ID: usize> core::fmt::Debug
+    for AtomCallback<'domain, Args, Ret, DOMAIN_ID>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AtomCallback").finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(loom))]
+impl<Args, Ret> AtomCallback<'static, Args, Ret, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `AtomCallback` associated with the shared (global) domain.
+    pub fn new(handler: impl Fn(Args) -> Ret + Send + Sync + 'static) -> Self {
+        Self::new_with_domain(handler, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, Args, Ret, const DOMAIN_ID: usize> AtomCallback<'domain, Args, Ret, DOMAIN_ID> {
+    /// Creates a new `AtomCallback` and associates it with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomCallback, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let callback = AtomCallback::new_with_domain(|name: &str| name.len(), &CUSTOM_DOMAIN);
+    /// assert_eq!(callback.call("Hello"), 5);
+    /// ```
+    pub fn new_with_domain(
+        handler: impl Fn(Args) -> Ret + Send + Sync + 'static,
+        domain: &'domain Domain<DOMAIN_ID>,
+    ) -> Self {
+        Self {
+            handler: AtomBoxIn::new_with_domain(Box::new(handler), domain),
+        }
+    }
+
+    /// Calls the current handler with `args`.
+    pub fn call(&self, args: Args) -> Ret {
+        let handler = self.handler.load();
+        (*handler)(args)
+    }
+
+    /// Replaces the current handler.
+    pub fn store(&self, handler: impl Fn(Args) -> Ret + Send + Sync + 'static) {
+        let _ = self.swap(handler);
+    }
+
+    /// Replaces the current handler, returning a `StoreGuard` to the previous handler.
+    ///
+    /// The previous handler can still be called through the guard.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomCallback;
+    ///
+    /// let callback = AtomCallback::new(|x: u32| x + 1);
+    /// let previous = callback.swap(|x: u32| x + 2);
+    ///
+    /// assert_eq!((*previous)(1), 2);
+    /// assert_eq!(callback.call(1), 3);
+    /// ```
+    pub fn swap(
+        &self,
+        handler: impl Fn(Args) -> Ret + Send + Sync + 'static,
+    ) -> StoreGuard<'domain, Handler<Args, Ret>, DOMAIN_ID> {
+        self.handler.swap(Box::new(handler))
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn swapped_handlers_are_dropped_once_calls_return() {
+        let drop_counter = DropCounter::new();
+        let captured = drop_counter.track(5);
+        let callback = AtomCallback::new_with_domain(move |x: i32| x + *captured, &TEST_DOMAIN);
+
+        let previous = callback.swap(|x: i32| x * 2);
+
+        assert_eq!(callback.call(4), 8, "Calls use the new handler");
+        assert_eq!(
+            (*previous)(4),
+            9,
+            "The previous handler can still be called"
+        );
+        drop_counter.assert_drops(0);
+        drop(previous);
+        callback.store(|x: i32| x);
+        drop_counter.assert_drops(1);
+    }
+
+    #[test]
+    fn concurrent_calls_and_swaps() {
+        let callback = AtomCallback::new_with_domain(|x: usize| x, &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for offset in 0..1000 {
+                    callback.store(move |x: usize| x + offset % 2);
+                }
+            });
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        let result = callback.call(10);
+                        assert!(result == 10 || result == 11, "Unexpected result {}", result);
+                    }
+                });
+            }
+        });
+    }
+}
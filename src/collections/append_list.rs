@@ -0,0 +1,305 @@
+//! An append-only lock-free list whose values can be read through snapshots.
+
+use super::Hazard;
+use crate::domain::Domain;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use alloc::boxed::Box;
+
+#[derive(Debug)]
+struct Node<T> {
+    value: T,
+    // Set before the node is published and never changed afterwards.
+    next: *mut Node<T>,
+}
+
+/// The values pushed since the list was created or last cleared.
+///
+/// Readers protect the generation rather than individual nodes, so clearing the list retires the
+/// whole generation at once and its nodes are freed together.
+#[derive(Debug)]
+struct Generation<T> {
+    head: AtomicPtr<Node<T>>,
+    len: AtomicUsize,
+}
+
+impl<T> Generation<T> {
+    fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(core::ptr::null_mut()),
+            len: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T> Drop for Generation<T> {
+    fn drop(&mut self) {
+        let mut node_ptr = self.head.load(Ordering::Acquire);
+        while !node_ptr.is_null() {
+            // # Safety
+            //
+            // The nodes were allocated via box and are only freed when their generation is
+            // reclaimed, at which point nothing else references them.
+            let node = unsafe { Box::from_raw(node_ptr) };
+            node_ptr = node.next;
+        }
+    }
+}
+
+/// An append-only lock-free list.
+///
+/// Values can be pushed concurrently and are never removed individually. The values pushed so far
+/// can be read through a [`Snapshot`], which keeps them alive even if the list is
+/// [cleared](AppendList::clear) in the meantime. Cleared values are retired to the domain, and
+/// are reclaimed once no snapshot protects them.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::collections::AppendList;
+///
+/// let list = AppendList::new();
+/// list.push("Hello");
+/// list.push("World");
+///
+/// let snapshot = list.snapshot();
+/// list.clear();
+///
+/// assert!(list.is_empty());
+/// assert_eq!(snapshot.iter().copied().collect::<Vec<_>>(), ["World", "Hello"]);
+/// ```
+#[derive(Debug)]
+pub struct AppendList<'domain, T, const DOMAIN_ID: usize> {
+    generation: AtomicPtr<Generation<T>>,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// Values are handed to other threads through snapshots, and are dropped by whichever thread
+// reclaims them.
+unsafe impl<'domain, T: Send, const DOMAIN_ID: usize> Send for AppendList<'domain, T, DOMAIN_ID> {}
+unsafe impl<'domain, T: Send + Sync, const DOMAIN_ID: usize> Sync
+    for AppendList<'domain, T, DOMAIN_ID>
+{
+}
+
+#[cfg(not(loom))]
+impl<T> AppendList<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new, empty `AppendList` associated with the shared (global) domain.
+    pub fn new() -> Self {
+        Self::new_with_domain(&crate::SHARED_DOMAIN)
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> Default for AppendList<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> AppendList<'domain, T, DOMAIN_ID> {
+    /// Creates a new, empty `AppendList` associated with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{collections::AppendList, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let list = AppendList::new_with_domain(&CUSTOM_DOMAIN);
+    /// list.push(1);
+    /// assert_eq!(list.len(), 1);
+    /// ```
+    pub fn new_with_domain(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            generation: AtomicPtr::new(Box::into_raw(Box::new(Generation::new()))),
+            domain,
+        }
+    }
+
+    /// Appends `value` to the list.
+    ///
+    /// A push which races with [`AppendList::clear`] may be cleared along with the values pushed
+    /// before it.
+    pub fn push(&self, value: T) {
+        let hazard = Hazard::new(self.domain);
+        // # Safety
+        //
+        // The generation is protected by the hazard pointer.
+        let generation = unsafe { &*hazard.protect_ptr(&self.generation) };
+        let node_ptr = Box::into_raw(Box::new(Node {
+            value,
+            next: generation.head.load(Ordering::Relaxed),
+        }));
+        loop {
+            // # Safety
+            //
+            // The node has not been published yet, so we have exclusive access to it.
+            let next = unsafe { (*node_ptr).next };
+            match generation.head.compare_exchange_weak(
+                next,
+                node_ptr,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(head) => unsafe { (*node_ptr).next = head },
+            }
+        }
+        generation.len.fetch_add(1, Ordering::Release);
+    }
+
+    /// Returns the number of values in the list.
+    ///
+    /// Since other threads may be pushing concurrently this is only a snapshot.
+    pub fn len(&self) -> usize {
+        let hazard = Hazard::new(self.domain);
+        // # Safety
+        //
+        // The generation is protected by the hazard pointer.
+        let generation = unsafe { &*hazard.protect_ptr(&self.generation) };
+        generation.len.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the list contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes a snapshot of the values pushed so far.
+    ///
+    /// The values remain alive until the snapshot is dropped, even if the list is cleared.
+    pub fn snapshot(&self) -> Snapshot<'domain, T, DOMAIN_ID> {
+        let hazard = Hazard::new(self.domain);
+        let generation = hazard.protect_ptr(&self.generation);
+        // # Safety
+        //
+        // The generation is protected by the hazard pointer.
+        let head = unsafe { (*generation).head.load(Ordering::Acquire) };
+        Snapshot {
+            head,
+            _hazard: hazard,
+        }
+    }
+
+    /// Removes every value from the list, retiring them to the domain.
+    pub fn clear(&self) {
+        let generation = Box::into_raw(Box::new(Generation::new()));
+        let old_generation = self.generation.swap(generation, Ordering::AcqRel);
+        // # Safety
+        //
+        // The old generation has been swapped out, so can no longer be protected by new readers,
+        // and is retired exactly once.
+        unsafe { self.domain.retire(old_generation) };
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Drop for AppendList<'domain, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        // # Safety
+        //
+        // We have exclusive access to the list, so the generation is retired exactly once.
+        unsafe { self.domain.retire(self.generation.load(Ordering::Relaxed)) };
+    }
+}
+
+/// The values of an [`AppendList`] at the time the snapshot was taken.
+///
+/// The values are kept alive until the snapshot is dropped.
+pub struct Snapshot<'domain, T, const DOMAIN_ID: usize> {
+    head: *const Node<T>,
+    _hazard: Hazard<'domain, DOMAIN_ID>,
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Snapshot<'domain, T, DOMAIN_ID> {
+    /// Iterates over the values in the snapshot, from the most recently pushed.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            node: self.head,
+            _snapshot: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<'domain, T: core::fmt::Debug, const DOMAIN_ID: usize> core::fmt::Debug
+    for Snapshot<'domain, T, DOMAIN_ID>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the values in a [`Snapshot`].
+#[derive(Debug)]
+pub struct Iter<'a, T> {
+    node: *const Node<T>,
+    _snapshot: core::marker::PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // # Safety
+        //
+        // The node belongs to the generation protected by the snapshot, whose nodes are only
+        // freed when the generation is reclaimed.
+        let node = unsafe { self.node.as_ref() }?;
+        self.node = node.next;
+        Some(&node.value)
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+    use alloc::vec::Vec;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn snapshots_keep_cleared_values_alive() {
+        let drop_counter = DropCounter::new();
+        let list = AppendList::new_with_domain(&TEST_DOMAIN);
+        list.push(drop_counter.track(1));
+        list.push(drop_counter.track(2));
+        let snapshot = list.snapshot();
+
+        list.clear();
+        list.push(drop_counter.track(3));
+        let values: Vec<_> = snapshot.iter().map(|value| **value).collect();
+        let drops_while_protected = drop_counter.count();
+        drop(snapshot);
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(values, [2, 1], "Snapshots iterate from the newest value");
+        assert_eq!(drops_while_protected, 0);
+        drop_counter.assert_drops(2);
+        assert_eq!(list.len(), 1);
+    }
+
+    #[test]
+    fn concurrent_pushes_are_all_kept() {
+        let list = AppendList::new_with_domain(&TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let list = &list;
+                scope.spawn(move || {
+                    for value in 0..100 {
+                        list.push(thread * 100 + value);
+                    }
+                });
+            }
+        });
+
+        let mut values: Vec<_> = list.snapshot().iter().copied().collect();
+        values.sort_unstable();
+        assert_eq!(values, (0..400).collect::<Vec<_>>());
+        assert_eq!(list.len(), 400);
+    }
+}
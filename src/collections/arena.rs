@@ -0,0 +1,315 @@
+use super::Hazard;
+use crate::domain::Domain;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use crate::LoadGuard;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// A value stored in an arena, along with the generation of the key it was inserted under.
+#[derive(Debug)]
+struct Entry<T> {
+    generation: usize,
+    value: T,
+}
+
+/// Identifies a value inserted into an [`AtomArena`].
+///
+/// Keys are never reused. Once its value has been removed, a key no longer refers to any value,
+/// even if another value has been inserted into the same slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ArenaKey {
+    index: usize,
+    generation: usize,
+}
+
+/// A fixed capacity generational arena, whose values can be read while other threads insert and
+/// remove values.
+///
+/// Inserting a value returns an [`ArenaKey`], which can be used to load or remove it. Loaded
+/// values are protected by hazard pointers, and removed values are retired to the domain, so a
+/// reader holding a guard never observes freed memory.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::collections::AtomArena;
+///
+/// let entities = AtomArena::new(2);
+///
+/// let player = entities.insert("Player").unwrap();
+/// let enemy = entities.insert("Enemy").unwrap();
+/// assert_eq!(entities.insert("Full"), Err("Full"));
+///
+/// let guard = entities.get(enemy).unwrap();
+/// assert!(entities.remove(enemy));
+/// assert_eq!(*guard, "Enemy");
+///
+/// assert!(entities.get(enemy).is_none());
+/// assert_eq!(*entities.get(player).unwrap(), "Player");
+/// ```
+#[derive(Debug)]
+pub struct AtomArena<'domain, T, const DOMAIN_ID: usize> {
+    slots: Box<[AtomicPtr<Entry<T>>]>,
+    // The generation of the next inserted value.
+    generation: AtomicUsize,
+    // The slot at which the next insert starts looking for a free slot.
+    next_free: AtomicUsize,
+    len: AtomicUsize,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// Values inserted on one thread may be read from, and dropped by, any other thread.
+unsafe impl<'domain, T: Send, const DOMAIN_ID: usize> Send for AtomArena<'domain, T, DOMAIN_ID> {}
+unsafe impl<'domain, T: Send + Sync, const DOMAIN_ID: usize> Sync
+    for AtomArena<'domain, T, DOMAIN_ID>
+{
+}
+
+#[cfg(not(loom))]
+impl<T> AtomArena<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `AtomArena` with the given capacity associated with the shared (global)
+    /// domain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        Self::new_with_domain(capacity, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> AtomArena<'domain, T, DOMAIN_ID> {
+    /// Creates a new `AtomArena` with the given capacity and associates it with the given domain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{collections::AtomArena, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let arena = AtomArena::new_with_domain(16, &CUSTOM_DOMAIN);
+    /// let key = arena.insert(1).unwrap();
+    /// assert_eq!(*arena.get(key).unwrap(), 1);
+    /// ```
+    pub fn new_with_domain(capacity: usize, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        assert!(capacity > 0, "Capacity must be greater than zero");
+        let slots = (0..capacity)
+            .map(|_| AtomicPtr::new(core::ptr::null_mut()))
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+        Self {
+            slots,
+            generation: AtomicUsize::new(0),
+            next_free: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+            domain,
+        }
+    }
+
+    /// Returns the maximum number of values the arena can hold.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the number of values in the arena.
+    ///
+    /// Since other threads may be inserting and removing concurrently this is only a snapshot.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the arena contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts a value into a free slot, returning the key which refers to it.
+    ///
+    /// If every slot is in use, the value is handed back in the `Err`.
+    pub fn insert(&self, value: T) -> Result<ArenaKey, T> {
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed);
+        let entry = Box::into_raw(Box::new(Entry { generation, value }));
+        let start = self.next_free.load(Ordering::Relaxed);
+        for offset in 0..self.slots.len() {
+            let index = (start + offset) % self.slots.len();
+            if self.slots[index]
+                .compare_exchange(
+                    core::ptr::null_mut(),
+                    entry,
+                    Ordering::AcqRel,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+            {
+                self.next_free.store(index + 1, Ordering::Relaxed);
+                self.len.fetch_add(1, Ordering::Release);
+                return Ok(ArenaKey { index, generation });
+            }
+        }
+        // # Safety
+        //
+        // The entry was never published, so we still own it.
+        Err(unsafe { Box::from_raw(entry) }.value)
+    }
+
+    /// Loads the value referred to by `key`, returning `None` if it has been removed.
+    pub fn get(&self, key: ArenaKey) -> Option<LoadGuard<'domain, T, DOMAIN_ID>> {
+        let hazard = Hazard::new(self.domain);
+        let entry = self.protect(&hazard, key)?;
+        // # Safety
+        //
+        // The entry is protected by the hazard pointer.
+        let value = unsafe { &(*entry).value };
+        Some(hazard.into_load_guard(value))
+    }
+
+    /// Returns `true` if `key` refers to a value which has not been removed.
+    pub fn contains(&self, key: ArenaKey) -> bool {
+        self.protect(&Hazard::new(self.domain), key).is_some()
+    }
+
+    /// Removes the value referred to by `key`, retiring it to the domain.
+    ///
+    /// Returns `false` if the value has already been removed.
+    pub fn remove(&self, key: ArenaKey) -> bool {
+        let hazard = Hazard::new(self.domain);
+        let entry = match self.protect(&hazard, key) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        // The entry cannot be reclaimed, and its address reused, while it is protected, so only
+        // one remover can succeed.
+        if self.slots[key.index]
+            .compare_exchange(
+                entry,
+                core::ptr::null_mut(),
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            )
+            .is_err()
+        {
+            return false;
+        }
+        self.len.fetch_sub(1, Ordering::Release);
+        // # Safety
+        //
+        // The entry has been unlinked from the arena, so can no longer be protected by new
+        // readers, and only the remover which unlinked it retires it.
+        unsafe { self.domain.retire(entry) };
+        true
+    }
+
+    /// Protects the entry referred to by `key` with `hazard`, if it has not been removed.
+    fn protect(&self, hazard: &Hazard<'domain, DOMAIN_ID>, key: ArenaKey) -> Option<*mut Entry<T>> {
+        let entry = hazard.protect_ptr(self.slots.get(key.index)?);
+        // # Safety
+        //
+        // The entry is protected by the hazard pointer.
+        let generation = unsafe { entry.as_ref() }?.generation;
+        (generation == key.generation).then_some(entry)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Drop for AtomArena<'domain, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        for slot in self.slots.iter() {
+            let entry = slot.load(Ordering::Relaxed);
+            if !entry.is_null() {
+                // # Safety
+                //
+                // We have exclusive access to the arena, so each entry is retired exactly once.
+                // Guards may still be protecting values, so they are retired rather than dropped.
+                unsafe { self.domain.retire(entry) };
+            }
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+    use alloc::vec::Vec;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn stale_keys_do_not_refer_to_reused_slots() {
+        let arena = AtomArena::new_with_domain(1, &TEST_DOMAIN);
+        let key = arena.insert("Hello").unwrap();
+        arena.remove(key);
+
+        let new_key = arena.insert("World").unwrap();
+
+        assert_ne!(key, new_key);
+        assert!(arena.get(key).is_none(), "The stale key refers to nothing");
+        assert!(
+            !arena.remove(key),
+            "The stale key cannot remove the new value"
+        );
+        assert_eq!(*arena.get(new_key).unwrap(), "World");
+        assert_eq!(arena.len(), 1);
+    }
+
+    #[test]
+    fn removed_values_outlive_their_guards() {
+        let drop_counter = DropCounter::new();
+        let arena = AtomArena::new_with_domain(4, &TEST_DOMAIN);
+        let key = arena.insert(drop_counter.track(1)).unwrap();
+        let guard = arena.get(key).unwrap();
+
+        let removed = arena.remove(key);
+        let drops_while_guarded = drop_counter.count();
+        drop(guard);
+        TEST_DOMAIN.reclaim();
+
+        assert!(removed);
+        assert_eq!(drops_while_guarded, 0, "The guard keeps the value alive");
+        drop_counter.assert_drops(1);
+        assert!(arena.is_empty());
+    }
+
+    #[test]
+    fn drop_retires_remaining_values() {
+        let drop_counter = DropCounter::new();
+        let arena = AtomArena::new_with_domain(4, &TEST_DOMAIN);
+        arena.insert(drop_counter.track(1)).unwrap();
+        arena.insert(drop_counter.track(2)).unwrap();
+
+        drop(arena);
+        TEST_DOMAIN.reclaim();
+
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    fn concurrent_inserts_and_removes() {
+        let arena = AtomArena::new_with_domain(64, &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let arena = &arena;
+                scope.spawn(move || {
+                    for value in 0..1000 {
+                        let key = arena.insert(thread * 1000 + value).unwrap();
+                        assert_eq!(*arena.get(key).unwrap(), thread * 1000 + value);
+                        assert!(arena.remove(key));
+                    }
+                });
+            }
+        });
+
+        let keys: Vec<_> = (0..4).map(|value| arena.insert(value).unwrap()).collect();
+        assert_eq!(arena.len(), 4);
+        assert!(keys.iter().all(|key| arena.contains(*key)));
+    }
+}
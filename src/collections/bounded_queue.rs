@@ -0,0 +1,385 @@
+use crate::domain::{Domain, RetirePolicy};
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use crate::{LoadGuard, StoreGuard};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+#[derive(Debug)]
+struct Slot<T> {
+    sequence: AtomicUsize,
+    value: AtomicPtr<T>,
+}
+
+/// A fixed capacity multi-producer multi-consumer lock-free queue.
+///
+/// Values are stored as heap allocations associated with a domain. Popping a value returns a
+/// `StoreGuard`, and the value is retired to the domain when the guard is dropped. This allows
+/// [`BoundedQueue::peek`] to hand out a hazard protected view of the value at the front of the
+/// queue.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::collections::BoundedQueue;
+///
+/// let queue = BoundedQueue::new(2);
+///
+/// assert!(queue.try_push("Hello").is_ok());
+/// assert!(queue.try_push("World").is_ok());
+/// assert_eq!(queue.try_push("Full"), Err("Full"));
+///
+/// assert_eq!(*queue.peek().unwrap(), "Hello");
+/// assert_eq!(*queue.try_pop().unwrap(), "Hello");
+/// assert_eq!(*queue.try_pop().unwrap(), "World");
+/// assert!(queue.try_pop().is_none());
+/// ```
+#[derive(Debug)]
+pub struct BoundedQueue<'domain, T, const DOMAIN_ID: usize> {
+    slots: Box<[Slot<T>]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// The queue hands values to other threads, so `T` must be `Send` for it to be shared. Unlike a
+// channel, `peek` additionally gives shared access to the value from any thread.
+unsafe impl<'domain, T: Send, const DOMAIN_ID: usize> Send for BoundedQueue<'domain, T, DOMAIN_ID> {}
+unsafe impl<'domain, T: Send + Sync, const DOMAIN_ID: usize> Sync
+    for BoundedQueue<'domain, T, DOMAIN_ID>
+{
+}
+
+#[cfg(not(loom))]
+impl<T> BoundedQueue<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `BoundedQueue` with the given capacity associated with the shared (global)
+    /// domain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        Self::new_with_domain(capacity, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> BoundedQueue<'domain, T, DOMAIN_ID> {
+    /// Creates a new `BoundedQueue` with the given capacity and associates it with the given
+    /// domain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{collections::BoundedQueue, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let queue = BoundedQueue::new_with_domain(16, &CUSTOM_DOMAIN);
+    /// queue.try_push(1).unwrap();
+    /// ```
+    pub fn new_with_domain(capacity: usize, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        assert!(capacity > 0, "Capacity must be greater than zero");
+        let slots = (0..capacity)
+            .map(|index| Slot {
+                sequence: AtomicUsize::new(index),
+                value: AtomicPtr::new(core::ptr::null_mut()),
+            })
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+        Self {
+            slots,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            domain,
+        }
+    }
+
+    /// Returns the maximum number of values the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the number of values in the queue.
+    ///
+    /// Since other threads may be pushing and popping concurrently this is only a snapshot.
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(head).min(self.capacity())
+    }
+
+    /// Returns `true` if the queue contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn slot(&self, position: usize) -> &Slot<T> {
+        &self.slots[position % self.slots.len()]
+    }
+
+    /// Attempts to push a value onto the back of the queue.
+    ///
+    /// If the queue is full, the value is handed back in the `Err`.
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        let new_ptr = Box::into_raw(Box::new(value));
+        let mut position = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = self.slot(position);
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            let difference = sequence.wrapping_sub(position) as isize;
+            if difference == 0 {
+                match self.tail.compare_exchange_weak(
+                    position,
+                    position.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        slot.value.store(new_ptr, Ordering::Release);
+                        slot.sequence
+                            .store(position.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current_position) => position = current_position,
+                }
+            } else if difference < 0 {
+                // # Safety
+                //
+                // We created the pointer above via `Box::into_raw` and never published it.
+                return Err(*unsafe { Box::from_raw(new_ptr) });
+            } else {
+                position = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Attempts to pop the value from the front of the queue.
+    ///
+    /// Returns `None` if the queue is empty. The popped value is retired to the domain when the
+    /// returned `StoreGuard` is dropped.
+    pub fn try_pop(&self) -> Option<StoreGuard<'domain, T, DOMAIN_ID>> {
+        let mut position = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = self.slot(position);
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            let difference = sequence.wrapping_sub(position.wrapping_add(1)) as isize;
+            if difference == 0 {
+                match self.head.compare_exchange_weak(
+                    position,
+                    position.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let ptr = slot.value.swap(core::ptr::null_mut(), Ordering::AcqRel);
+                        slot.sequence
+                            .store(position.wrapping_add(self.slots.len()), Ordering::Release);
+                        return Some(StoreGuard {
+                            ptr,
+                            domain: self.domain,
+                            retire_policy: RetirePolicy::Domain,
+                        });
+                    }
+                    Err(current_position) => position = current_position,
+                }
+            } else if difference < 0 {
+                return None;
+            } else {
+                position = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Pushes a value onto the back of the queue, spinning until space is available.
+    pub fn push(&self, mut value: T) {
+        loop {
+            match self.try_push(value) {
+                Ok(()) => return,
+                Err(returned_value) => {
+                    value = returned_value;
+                    backoff();
+                }
+            }
+        }
+    }
+
+    /// Pops the value from the front of the queue, spinning until a value is available.
+    pub fn pop(&self) -> StoreGuard<'domain, T, DOMAIN_ID> {
+        loop {
+            if let Some(value) = self.try_pop() {
+                return value;
+            }
+            backoff();
+        }
+    }
+
+    /// Returns a hazard protected view of the value at the front of the queue without removing it.
+    ///
+    /// The value may be popped by another thread while the `LoadGuard` is held, however, it will
+    /// not be reclaimed until the guard is dropped.
+    pub fn peek(&self) -> Option<LoadGuard<'domain, T, DOMAIN_ID>> {
+        let haz_ptr = self.domain.acquire_haz_ptr();
+        loop {
+            let position = self.head.load(Ordering::Acquire);
+            let slot = self.slot(position);
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            if sequence != position.wrapping_add(1) {
+                if (sequence.wrapping_sub(position.wrapping_add(1)) as isize) < 0 {
+                    self.domain.release_hazard_ptr(haz_ptr);
+                    return None;
+                }
+                continue;
+            }
+            let ptr = haz_ptr.protect_ptr(&slot.value);
+            // A null pointer means the slot has been claimed by a consumer, and a changed head
+            // means that this is no longer the front of the queue.
+            if !ptr.is_null() && self.head.load(Ordering::Acquire) == position {
+                return Some(LoadGuard {
+                    ptr,
+                    domain: self.domain,
+                    haz_ptr: Some(haz_ptr),
+                });
+            }
+            haz_ptr.reset();
+        }
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Drop for BoundedQueue<'domain, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        // Values may still be protected by guards returned from `peek`, so they are retired
+        // rather than dropped directly.
+        while self.try_pop().is_some() {}
+    }
+}
+
+#[cfg(feature = "std")]
+fn backoff() {
+    std::thread::yield_now();
+}
+
+#[cfg(not(feature = "std"))]
+fn backoff() {
+    core::hint::spin_loop();
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn push_and_pop_in_order() {
+        let queue = BoundedQueue::new_with_domain(3, &TEST_DOMAIN);
+
+        for value in 0..3 {
+            queue.try_push(value).expect("Queue should not be full");
+        }
+
+        assert_eq!(queue.len(), 3, "Queue should contain three values");
+        assert_eq!(queue.try_push(3), Err(3), "Queue should be full");
+        for expected in 0..3 {
+            assert_eq!(
+                *queue.try_pop().expect("Queue should not be empty"),
+                expected,
+                "Values should be popped in the order they were pushed"
+            );
+        }
+        assert!(queue.try_pop().is_none(), "Queue should be empty");
+        assert!(queue.is_empty(), "Queue should be empty");
+    }
+
+    #[test]
+    fn wraps_around() {
+        let queue = BoundedQueue::new_with_domain(2, &TEST_DOMAIN);
+
+        for value in 0..10 {
+            queue.push(value);
+            let popped = queue.pop();
+
+            assert_eq!(*popped, value, "Should pop the value just pushed");
+        }
+    }
+
+    #[test]
+    fn len_counts_values_across_position_wrap_around() {
+        let queue = BoundedQueue::new_with_domain(4, &TEST_DOMAIN);
+        let start = usize::MAX - 1;
+        queue.head.store(start, Ordering::Relaxed);
+        queue.tail.store(start, Ordering::Relaxed);
+        for offset in 0..4 {
+            let position = start.wrapping_add(offset);
+            queue
+                .slot(position)
+                .sequence
+                .store(position, Ordering::Relaxed);
+        }
+
+        for value in 0..3 {
+            queue.push(value);
+        }
+
+        assert_eq!(queue.len(), 3, "Queue should contain three values");
+        assert_eq!(*queue.pop(), 0, "Should pop the first value pushed");
+        assert_eq!(queue.len(), 2, "Queue should contain two values");
+    }
+
+    #[test]
+    fn peeked_value_outlives_pop() {
+        let drop_counter = DropCounter::new();
+        let queue = BoundedQueue::new_with_domain(2, &TEST_DOMAIN);
+        queue.push(drop_counter.track(1));
+
+        let peeked = queue.peek().expect("Queue should not be empty");
+        drop(queue.pop());
+
+        drop_counter.assert_no_drops();
+        assert_eq!(**peeked, 1, "Peeked value should still be accessible");
+        drop(peeked);
+        queue.push(drop_counter.track(2));
+        drop(queue.pop());
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    fn drop_retires_remaining_values() {
+        let drop_counter = DropCounter::new();
+        let queue = BoundedQueue::new_with_domain(4, &TEST_DOMAIN);
+        queue.push(drop_counter.track(1));
+        queue.push(drop_counter.track(2));
+
+        drop(queue);
+
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    fn concurrent_push_and_pop() {
+        const ITERATIONS: usize = 1000;
+        let queue = BoundedQueue::new_with_domain(8, &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for value in 0..ITERATIONS {
+                        queue.push(value);
+                    }
+                });
+            }
+            let consumer = scope.spawn(|| (0..2 * ITERATIONS).map(|_| *queue.pop()).sum::<usize>());
+            assert_eq!(
+                consumer.join().unwrap(),
+                2 * (0..ITERATIONS).sum::<usize>(),
+                "Every pushed value should be popped exactly once"
+            );
+        });
+    }
+}
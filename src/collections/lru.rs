@@ -0,0 +1,551 @@
+//! A concurrent least recently used cache.
+//!
+//! The cache is split into shards, each of which is a fixed size hash table. Every bucket of the
+//! table is an immutable snapshot of the entries hashed to it. Readers protect the snapshot and
+//! then the entry they are looking for, so lookups never take a lock. Writers serialise on a
+//! per-shard lock and replace snapshots wholesale, retiring the old snapshot and any removed
+//! entries to the domain.
+
+use super::Hazard;
+use crate::domain::Domain;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use crate::LoadGuard;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+use std::collections::hash_map::RandomState;
+use std::sync::{Mutex, MutexGuard};
+
+// Small caches use a single shard so that eviction is exactly least recently used.
+const MIN_SHARD_CAPACITY: usize = 64;
+const MAX_SHARDS: usize = 16;
+
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    // The shard's clock at the time of the most recent insert or lookup.
+    last_used: AtomicUsize,
+}
+
+/// An immutable snapshot of the entries in a bucket.
+struct Bucket<K, V> {
+    entries: Vec<*mut Entry<K, V>>,
+}
+
+impl<K, V> Bucket<K, V> {
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        // # Safety
+        //
+        // Entries are only retired after being removed from every snapshot, and the caller has
+        // either protected this snapshot or holds the shard's lock.
+        self.entries
+            .iter()
+            .position(|entry| unsafe { &**entry }.key.borrow() == key)
+    }
+}
+
+struct Shard<K, V> {
+    // Each bucket is null while it has no entries.
+    buckets: Box<[AtomicPtr<Bucket<K, V>>]>,
+    // Serialises writers, readers never take the lock.
+    writer: Mutex<()>,
+    len: AtomicUsize,
+    clock: AtomicUsize,
+}
+
+impl<K, V> Shard<K, V> {
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        // The shard is only modified by replacing a bucket with a complete snapshot, so a panic
+        // while holding the lock cannot leave it inconsistent.
+        self.writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn tick(&self) -> usize {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+/// A concurrent least recently used cache.
+///
+/// Lookups are lock-free and return a [`LoadGuard`] to the cached value, which remains valid
+/// even if the entry is evicted or replaced while the guard is held. Insertions and removals take
+/// a lock on one of the cache's shards. Entries removed from the cache are retired to the domain.
+///
+/// Recency is tracked per shard: when a shard exceeds its share of the capacity, its least
+/// recently used entry is evicted. Caches with a capacity below 128 have a single shard, and so
+/// always evict the least recently used entry in the cache. Finding the entry to evict requires a
+/// scan of the shard, so writes are linear in the capacity of the shard.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::collections::AtomLru;
+///
+/// let cache = AtomLru::new(2);
+/// cache.insert("a", 1);
+/// cache.insert("b", 2);
+///
+/// // Using "a" makes "b" the least recently used entry.
+/// assert_eq!(*cache.get("a").unwrap(), 1);
+/// cache.insert("c", 3);
+///
+/// assert!(cache.get("b").is_none());
+/// assert_eq!(*cache.get("c").unwrap(), 3);
+/// ```
+pub struct AtomLru<'domain, K, V, const DOMAIN_ID: usize> {
+    shards: Box<[Shard<K, V>]>,
+    shard_capacity: usize,
+    capacity: usize,
+    hasher: RandomState,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// Values are handed to other threads through guards, and are dropped by whichever thread reclaims
+// them.
+unsafe impl<'domain, K: Send, V: Send, const DOMAIN_ID: usize> Send
+    for AtomLru<'domain, K, V, DOMAIN_ID>
+{
+}
+unsafe impl<'domain, K: Send + Sync, V: Send + Sync, const DOMAIN_ID: usize> Sync
+    for AtomLru<'domain, K, V, DOMAIN_ID>
+{
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> core::fmt::Debug for AtomLru<'domain, K, V, DOMAIN_ID> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AtomLru")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> AtomLru<'domain, K, V, DOMAIN_ID> {
+    /// Returns the maximum number of entries the cache holds before evicting entries.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of entries in the cache.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.len.load(Ordering::Acquire))
+            .sum()
+    }
+
+    /// Returns `true` if the cache contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(not(loom))]
+impl<K: Hash + Eq, V> AtomLru<'static, K, V, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `AtomLru` with the given capacity associated with the shared (global) domain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        Self::new_with_domain(capacity, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, K: Hash + Eq, V, const DOMAIN_ID: usize> AtomLru<'domain, K, V, DOMAIN_ID> {
+    /// Creates a new `AtomLru` with the given capacity and associates it with the given domain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{collections::AtomLru, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let cache = AtomLru::new_with_domain(100, &CUSTOM_DOMAIN);
+    /// cache.insert(1, "Hello");
+    /// assert_eq!(*cache.get(&1).unwrap(), "Hello");
+    /// ```
+    pub fn new_with_domain(capacity: usize, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        assert!(capacity > 0, "AtomLru capacity must be non-zero");
+        let number_of_shards = (capacity / MIN_SHARD_CAPACITY).clamp(1, MAX_SHARDS);
+        let shard_capacity = capacity.div_ceil(number_of_shards);
+        let shards = (0..number_of_shards)
+            .map(|_| Shard {
+                buckets: (0..shard_capacity.next_power_of_two())
+                    .map(|_| AtomicPtr::new(core::ptr::null_mut()))
+                    .collect(),
+                writer: Mutex::new(()),
+                len: AtomicUsize::new(0),
+                clock: AtomicUsize::new(0),
+            })
+            .collect();
+        Self {
+            shards,
+            shard_capacity,
+            capacity,
+            hasher: RandomState::new(),
+            domain,
+        }
+    }
+
+    /// Returns a guard to the value associated with `key`, marking it as the most recently used
+    /// entry.
+    pub fn get<Q>(&self, key: &Q) -> Option<LoadGuard<'domain, V, DOMAIN_ID>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (shard, bucket) = self.locate(key);
+        let bucket_hazard = Hazard::new(self.domain);
+        let entry_hazard = Hazard::new(self.domain);
+        let entry = loop {
+            let bucket_ptr = bucket_hazard.protect_ptr(bucket);
+            // # Safety
+            //
+            // The snapshot is protected by the bucket hazard.
+            let snapshot = unsafe { bucket_ptr.as_ref() }?;
+            let entry_ptr = snapshot.entries[snapshot.find(key)?];
+            entry_hazard.protect(entry_ptr);
+
+            crate::sync::fence(Ordering::SeqCst);
+
+            // Entries are only retired once they have been removed from the current snapshot. The
+            // protected snapshot cannot be reused, so if it is still current the entry is safe.
+            if bucket.load(Ordering::Acquire) == bucket_ptr {
+                // # Safety
+                //
+                // The entry is protected by the entry hazard.
+                break unsafe { &*entry_ptr };
+            }
+        };
+        entry.last_used.store(shard.tick(), Ordering::Relaxed);
+        Some(entry_hazard.into_load_guard(&entry.value))
+    }
+
+    /// Returns `true` if the cache contains an entry for `key`.
+    ///
+    /// Unlike [`AtomLru::get`], this does not mark the entry as used.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (_, bucket) = self.locate(key);
+        let bucket_hazard = Hazard::new(self.domain);
+        let bucket_ptr = bucket_hazard.protect_ptr(bucket);
+        // # Safety
+        //
+        // The snapshot is protected by the bucket hazard.
+        unsafe { bucket_ptr.as_ref() }.is_some_and(|snapshot| snapshot.find(key).is_some())
+    }
+
+    /// Inserts an entry into the cache, evicting the least recently used entry of its shard if
+    /// the shard is full.
+    ///
+    /// If the cache already contains an entry for `key`, it is replaced and a guard to the
+    /// previous value is returned.
+    pub fn insert(&self, key: K, value: V) -> Option<LoadGuard<'domain, V, DOMAIN_ID>> {
+        let (shard, bucket) = self.locate(&key);
+        let _writer = shard.lock();
+        let entry_ptr = Box::into_raw(Box::new(Entry {
+            key,
+            value,
+            last_used: AtomicUsize::new(shard.tick()),
+        }));
+        let mut entries = Self::entries(bucket);
+        // # Safety
+        //
+        // We have just created the entry and not yet published it.
+        let key = &unsafe { &*entry_ptr }.key;
+        let previous = match Self::snapshot(bucket).and_then(|snapshot| snapshot.find(key)) {
+            Some(index) => Some(core::mem::replace(&mut entries[index], entry_ptr)),
+            None => {
+                entries.push(entry_ptr);
+                None
+            }
+        };
+        self.replace_bucket(bucket, entries);
+
+        match previous {
+            // # Safety
+            //
+            // The previous entry is no longer in the snapshot and we hold the shard's lock.
+            Some(previous) => Some(unsafe { self.retire_entry(previous) }),
+            None => {
+                if shard.len.fetch_add(1, Ordering::AcqRel) == self.shard_capacity {
+                    self.evict(shard);
+                }
+                None
+            }
+        }
+    }
+
+    /// Removes the entry for `key` from the cache, returning a guard to its value.
+    pub fn remove<Q>(&self, key: &Q) -> Option<LoadGuard<'domain, V, DOMAIN_ID>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (shard, bucket) = self.locate(key);
+        let _writer = shard.lock();
+        let index = Self::snapshot(bucket)?.find(key)?;
+        let mut entries = Self::entries(bucket);
+        let removed = entries.swap_remove(index);
+        self.replace_bucket(bucket, entries);
+        shard.len.fetch_sub(1, Ordering::AcqRel);
+        // # Safety
+        //
+        // The entry is no longer in the snapshot and we hold the shard's lock.
+        Some(unsafe { self.retire_entry(removed) })
+    }
+
+    fn locate<Q: Hash + ?Sized>(&self, key: &Q) -> (&Shard<K, V>, &AtomicPtr<Bucket<K, V>>) {
+        let hash = self.hasher.hash_one(key) as usize;
+        let shard = &self.shards[hash % self.shards.len()];
+        let bucket = &shard.buckets[(hash / self.shards.len()) % shard.buckets.len()];
+        (shard, bucket)
+    }
+
+    /// Evicts the least recently used entry of the shard.
+    ///
+    /// Must only be called while holding the shard's lock.
+    fn evict(&self, shard: &Shard<K, V>) {
+        let least_recently_used = shard
+            .buckets
+            .iter()
+            .filter_map(|bucket| Some((bucket, Self::snapshot(bucket)?)))
+            .flat_map(|(bucket, snapshot)| {
+                snapshot
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, entry)| {
+                        // # Safety
+                        //
+                        // We hold the shard's lock so none of its entries have been retired.
+                        let last_used = unsafe { &**entry }.last_used.load(Ordering::Relaxed);
+                        // Compare ages rather than stamps so that the clock may wrap around.
+                        let age = shard.clock.load(Ordering::Relaxed).wrapping_sub(last_used);
+                        (age, bucket, index)
+                    })
+            })
+            .max_by_key(|(age, _, _)| *age);
+        if let Some((_, bucket, index)) = least_recently_used {
+            let mut entries = Self::entries(bucket);
+            let evicted = entries.swap_remove(index);
+            self.replace_bucket(bucket, entries);
+            shard.len.fetch_sub(1, Ordering::AcqRel);
+            // # Safety
+            //
+            // The entry is no longer in the snapshot and we hold the shard's lock, so it is
+            // retired exactly once.
+            unsafe { self.domain.retire(evicted) };
+        }
+    }
+
+    /// Returns the current snapshot of a bucket.
+    ///
+    /// Must only be called while holding the lock of the bucket's shard.
+    fn snapshot(bucket: &AtomicPtr<Bucket<K, V>>) -> Option<&Bucket<K, V>> {
+        // # Safety
+        //
+        // Snapshots are only retired by writers after they have been replaced, and we hold the
+        // shard's lock.
+        unsafe { bucket.load(Ordering::Acquire).as_ref() }
+    }
+
+    /// Returns a copy of the entries in the current snapshot of a bucket.
+    ///
+    /// Must only be called while holding the lock of the bucket's shard.
+    fn entries(bucket: &AtomicPtr<Bucket<K, V>>) -> Vec<*mut Entry<K, V>> {
+        Self::snapshot(bucket).map_or_else(Vec::new, |snapshot| snapshot.entries.clone())
+    }
+
+    /// Installs a new snapshot for a bucket, retiring the old one.
+    ///
+    /// Must only be called while holding the lock of the bucket's shard.
+    fn replace_bucket(&self, bucket: &AtomicPtr<Bucket<K, V>>, entries: Vec<*mut Entry<K, V>>) {
+        let new_ptr = if entries.is_empty() {
+            core::ptr::null_mut()
+        } else {
+            Box::into_raw(Box::new(Bucket { entries }))
+        };
+        let old_ptr = bucket.swap(new_ptr, Ordering::AcqRel);
+        if !old_ptr.is_null() {
+            // # Safety
+            //
+            // The old snapshot is no longer reachable and we hold the shard's lock, so it is
+            // retired exactly once.
+            unsafe { self.domain.retire(old_ptr) };
+        }
+    }
+
+    /// Retires an entry, returning a guard to its value.
+    ///
+    /// # Safety
+    ///
+    /// The entry must have been removed from its bucket's snapshot by the caller, who must still
+    /// hold the shard's lock.
+    unsafe fn retire_entry(&self, entry_ptr: *mut Entry<K, V>) -> LoadGuard<'domain, V, DOMAIN_ID> {
+        let hazard = Hazard::new(self.domain);
+        hazard.protect(entry_ptr);
+        // # Safety
+        //
+        // The entry has not been retired yet so protecting it now is sufficient. According to
+        // the safety contract of this function, it is no longer reachable and is retired exactly
+        // once.
+        unsafe { self.domain.retire(entry_ptr) };
+        // # Safety
+        //
+        // The entry is protected by the hazard.
+        hazard.into_load_guard(&unsafe { &*entry_ptr }.value)
+    }
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> Drop for AtomLru<'domain, K, V, DOMAIN_ID> {
+    fn drop(&mut self) {
+        // Guards may still reference the entries, so every entry is retired rather than dropped.
+        for bucket in self.shards.iter().flat_map(|shard| shard.buckets.iter()) {
+            let bucket_ptr = bucket.load(Ordering::Acquire);
+            if bucket_ptr.is_null() {
+                continue;
+            }
+            // # Safety
+            //
+            // We have exclusive access to the cache so the snapshot is still current.
+            for entry_ptr in unsafe { &*bucket_ptr }.entries.iter() {
+                // # Safety
+                //
+                // Every entry is in exactly one current snapshot, and no further operations on
+                // the cache can take place.
+                unsafe { self.domain.retire(*entry_ptr) };
+            }
+            // # Safety
+            //
+            // As above, the snapshot is retired exactly once.
+            unsafe { self.domain.retire(bucket_ptr) };
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn insert_get_and_remove() {
+        let cache = AtomLru::new_with_domain(10, &TEST_DOMAIN);
+
+        for key in 0..5 {
+            assert!(cache.insert(key, key * 10).is_none(), "Key is new");
+        }
+
+        assert_eq!(cache.len(), 5, "Cache should contain five entries");
+        assert_eq!(*cache.get(&3).unwrap(), 30, "Should get the inserted value");
+        assert_eq!(
+            *cache.insert(3, 31).unwrap(),
+            30,
+            "Replacing an entry returns the previous value"
+        );
+        assert_eq!(*cache.remove(&3).unwrap(), 31, "Should remove the value");
+        assert!(cache.get(&3).is_none(), "Removed key should not be found");
+        assert!(cache.remove(&3).is_none(), "Key can only be removed once");
+        assert!(cache.contains_key(&4), "Other keys are unaffected");
+        assert_eq!(cache.len(), 4, "Cache should contain four entries");
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entry() {
+        let domain: Domain<2> = Domain::new(ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let cache = AtomLru::new_with_domain(3, &domain);
+        for key in 0..3 {
+            cache.insert(key, drop_counter.track(key));
+        }
+        let _ = cache.get(&0);
+        let _ = cache.get(&2);
+
+        cache.insert(3, drop_counter.track(3));
+
+        assert!(
+            !cache.contains_key(&1),
+            "Least recently used entry is evicted"
+        );
+        for key in [0, 2, 3] {
+            assert!(cache.contains_key(&key), "Key {} should be retained", key);
+        }
+        assert_eq!(cache.len(), 3, "Cache should remain at capacity");
+        drop_counter.assert_drops(0);
+        domain.reclaim();
+        drop_counter.assert_drops(1);
+        drop(cache);
+        domain.reclaim();
+        drop_counter.assert_drops(4);
+    }
+
+    #[test]
+    fn guards_outlive_eviction() {
+        let domain: Domain<3> = Domain::new(ReclaimStrategy::Eager);
+        let drop_counter = DropCounter::new();
+        let cache = AtomLru::new_with_domain(1, &domain);
+        cache.insert("a", drop_counter.track(1));
+        let guard = cache.get("a").unwrap();
+
+        cache.insert("b", drop_counter.track(2));
+        domain.reclaim();
+
+        assert!(cache.get("a").is_none(), "Entry should have been evicted");
+        assert_eq!(**guard, 1, "Guard should still reference the value");
+        drop_counter.assert_drops(0);
+        drop(guard);
+        domain.reclaim();
+        drop_counter.assert_drops(1);
+    }
+
+    #[test]
+    fn concurrent_reads_and_writes() {
+        let cache = AtomLru::new_with_domain(256, &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for key in 0..2000 {
+                        cache.insert(key % 512, key);
+                    }
+                });
+            }
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for key in 0..2000 {
+                        if let Some(value) = cache.get(&(key % 512)) {
+                            assert_eq!(*value % 512, key % 512, "Value belongs to another key");
+                        }
+                    }
+                });
+            }
+        });
+
+        assert!(
+            cache.len() <= cache.capacity(),
+            "Cache should not exceed its capacity"
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Collections
+//!
+//! Concurrent data structures built on top of a [`Domain`] for safe memory
+//! reclamation.
+//!
+//! Like `AtomBox`, each collection is associated with a domain. Values removed from a collection
+//! are retired to that domain and are only reclaimed once no hazard pointers protect them.
+
+pub mod append_list;
+mod arena;
+mod bounded_queue;
+#[cfg(feature = "std")]
+mod lru;
+mod object_pool;
+pub mod priority_queue;
+mod radix_tree;
+pub mod skip_list;
+pub mod tree;
+#[cfg(feature = "std")]
+mod ttl_cache;
+
+pub use append_list::AppendList;
+pub use arena::{ArenaKey, AtomArena};
+pub use bounded_queue::BoundedQueue;
+#[cfg(feature = "std")]
+pub use lru::AtomLru;
+pub use object_pool::ObjectPool;
+pub use priority_queue::PriorityQueue;
+pub use radix_tree::RadixTreeMap;
+pub use skip_list::SkipListMap;
+pub use tree::TreeMap;
+#[cfg(feature = "std")]
+pub use ttl_cache::AtomTtlCache;
+
+use crate::domain::{Domain, HazardPointer};
+use crate::sync::AtomicPtr;
+use crate::LoadGuard;
+
+/// A hazard pointer which is released back to its domain when dropped.
+pub(crate) struct Hazard<'domain, const DOMAIN_ID: usize> {
+    haz_ptr: Option<HazardPointer<'domain>>,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+impl<'domain, const DOMAIN_ID: usize> Hazard<'domain, DOMAIN_ID> {
+    pub(crate) fn new(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            haz_ptr: Some(domain.acquire_haz_ptr()),
+            domain,
+        }
+    }
+
+    fn haz_ptr(&self) -> &HazardPointer<'domain> {
+        self.haz_ptr
+            .as_ref()
+            .expect("Hazard pointer is only taken on drop")
+    }
+
+    pub(crate) fn protect<T>(&self, ptr: *const T) {
+        self.haz_ptr().protect(ptr as *mut usize);
+    }
+
+    /// Protects the pointer currently stored in `source`, returning the protected pointer.
+    pub(crate) fn protect_ptr<T>(&self, source: &AtomicPtr<T>) -> *mut T {
+        self.haz_ptr().protect_ptr(source)
+    }
+
+    /// Converts this hazard into a `LoadGuard` for `ptr`.
+    ///
+    /// The caller must ensure that `ptr` points into an allocation this hazard is protecting.
+    pub(crate) fn into_load_guard<T>(mut self, ptr: *const T) -> LoadGuard<'domain, T, DOMAIN_ID> {
+        LoadGuard {
+            ptr,
+            domain: self.domain,
+            haz_ptr: self.haz_ptr.take(),
+        }
+    }
+}
+
+impl<'domain, const DOMAIN_ID: usize> Drop for Hazard<'domain, DOMAIN_ID> {
+    fn drop(&mut self) {
+        if let Some(haz_ptr) = self.haz_ptr.take() {
+            self.domain.release_hazard_ptr(haz_ptr);
+        }
+    }
+}
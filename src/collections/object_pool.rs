@@ -0,0 +1,304 @@
+use super::Hazard;
+use crate::domain::Domain;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use alloc::boxed::Box;
+use core::mem::ManuallyDrop;
+
+struct Node<T> {
+    // Moved out by the thread which takes the node, so it is never dropped with the node.
+    value: ManuallyDrop<T>,
+    next: *mut Node<T>,
+}
+
+/// A lock-free pool of reusable objects, such as buffers or connections.
+///
+/// Objects are handed out by value, most recently returned first, which keeps recently used
+/// objects warm. The pool is a stack of heap allocated nodes, and a node is retired to the domain
+/// once its object has been taken, so a concurrent `take` can never read a freed node.
+///
+/// A pool may optionally be bounded, in which case [`ObjectPool::put`] hands back objects which
+/// do not fit.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::collections::ObjectPool;
+///
+/// let buffers = ObjectPool::new().with_capacity(1);
+///
+/// let mut buffer = buffers.take().unwrap_or_else(|| Vec::with_capacity(4096));
+/// buffer.extend_from_slice(b"response");
+/// buffer.clear();
+///
+/// assert!(buffers.put(buffer).is_ok());
+/// assert!(buffers.put(Vec::new()).is_err(), "The pool is full");
+/// assert_eq!(buffers.take().unwrap().capacity(), 4096);
+/// ```
+pub struct ObjectPool<'domain, T, const DOMAIN_ID: usize> {
+    head: AtomicPtr<Node<T>>,
+    len: AtomicUsize,
+    capacity: Option<usize>,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// Objects are moved between threads but never shared.
+unsafe impl<'domain, T: Send, const DOMAIN_ID: usize> Send for ObjectPool<'domain, T, DOMAIN_ID> {}
+unsafe impl<'domain, T: Send, const DOMAIN_ID: usize> Sync for ObjectPool<'domain, T, DOMAIN_ID> {}
+
+impl<'domain, T, const DOMAIN_ID: usize> core::fmt::Debug for ObjectPool<'domain, T, DOMAIN_ID> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ObjectPool")
+            .field("len", &self.len.load(Ordering::Relaxed))
+            .field("capacity", &self.capacity)
+            .field("domain", &self.domain)
+            .finish()
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> ObjectPool<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new, empty and unbounded `ObjectPool` associated with the shared (global) domain.
+    pub fn new() -> Self {
+        Self::new_with_domain(&crate::SHARED_DOMAIN)
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> Default for ObjectPool<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> ObjectPool<'domain, T, DOMAIN_ID> {
+    /// Creates a new, empty and unbounded `ObjectPool` associated with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{collections::ObjectPool, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let pool = ObjectPool::new_with_domain(&CUSTOM_DOMAIN);
+    /// pool.put(String::from("connection")).unwrap();
+    /// ```
+    pub fn new_with_domain(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            head: AtomicPtr::new(core::ptr::null_mut()),
+            len: AtomicUsize::new(0),
+            capacity: None,
+            domain,
+        }
+    }
+
+    /// Limits the number of objects the pool holds to `capacity`.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Returns the maximum number of objects the pool holds, if it is bounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Returns the number of objects in the pool.
+    ///
+    /// Since other threads may be putting and taking objects concurrently this is only a
+    /// snapshot.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the pool contains no objects.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an object to the pool.
+    ///
+    /// If the pool is full, the object is handed back in the `Err`.
+    pub fn put(&self, value: T) -> Result<(), T> {
+        let reserved = match self.capacity {
+            Some(capacity) => self
+                .len
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |len| {
+                    (len < capacity).then_some(len + 1)
+                })
+                .is_ok(),
+            None => {
+                self.len.fetch_add(1, Ordering::AcqRel);
+                true
+            }
+        };
+        if !reserved {
+            return Err(value);
+        }
+        let node_ptr = Box::into_raw(Box::new(Node {
+            value: ManuallyDrop::new(value),
+            next: core::ptr::null_mut(),
+        }));
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            // # Safety
+            //
+            // The node has not been published, so we still have exclusive access to it.
+            unsafe { (*node_ptr).next = head };
+            match self.head.compare_exchange_weak(
+                head,
+                node_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return Ok(()),
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Takes an object from the pool, returning `None` if the pool is empty.
+    pub fn take(&self) -> Option<T> {
+        let hazard = Hazard::new(self.domain);
+        loop {
+            let node_ptr = hazard.protect_ptr(&self.head);
+            if node_ptr.is_null() {
+                return None;
+            }
+            // # Safety
+            //
+            // The node is protected by the hazard pointer and its successor was set before it was
+            // published. Nodes are never pushed twice, so if it is still the head its successor
+            // is unchanged.
+            let next = unsafe { (*node_ptr).next };
+            if self
+                .head
+                .compare_exchange_weak(node_ptr, next, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                self.len.fetch_sub(1, Ordering::AcqRel);
+                // # Safety
+                //
+                // Only the thread which unlinked the node moves its value out, and the node is
+                // retired without dropping it. Nodes are allocated via `Box::into_raw`.
+                unsafe {
+                    let value = ManuallyDrop::take(&mut (*node_ptr).value);
+                    self.domain.retire(node_ptr);
+                    return Some(value);
+                }
+            }
+        }
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Drop for ObjectPool<'domain, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        let mut node_ptr = self.head.load(Ordering::Acquire);
+        while !node_ptr.is_null() {
+            // # Safety
+            //
+            // We have exclusive access to the pool, and no taker is still reading the node since
+            // every `take` borrows the pool. Nodes are allocated via `Box::into_raw`.
+            let mut node = unsafe { Box::from_raw(node_ptr) };
+            node_ptr = node.next;
+            // # Safety
+            //
+            // The node is still in the pool, so its value has not been taken.
+            unsafe { ManuallyDrop::drop(&mut node.value) };
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+    use alloc::vec::Vec;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn takes_the_most_recently_returned_object() {
+        let pool = ObjectPool::new_with_domain(&TEST_DOMAIN);
+
+        for value in 0..3 {
+            pool.put(value).unwrap();
+        }
+        let taken: Vec<_> = core::iter::from_fn(|| pool.take()).collect();
+
+        assert_eq!(taken, [2, 1, 0], "Most recently returned first");
+        assert!(pool.is_empty(), "Every object has been taken");
+    }
+
+    #[test]
+    fn bounded_pools_hand_back_objects_which_do_not_fit() {
+        let pool = ObjectPool::new_with_domain(&TEST_DOMAIN).with_capacity(2);
+
+        let results: Vec<_> = (0..3).map(|value| pool.put(value)).collect();
+        pool.take();
+        let after_take = pool.put(3);
+
+        assert_eq!(results, [Ok(()), Ok(()), Err(2)]);
+        assert_eq!(after_take, Ok(()), "Taking frees a place");
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn objects_are_dropped_exactly_once() {
+        let drop_counter = DropCounter::new();
+        let pool = ObjectPool::new_with_domain(&TEST_DOMAIN);
+        for value in 0..4 {
+            pool.put(drop_counter.track(value)).unwrap();
+        }
+
+        let taken = pool.take().unwrap();
+        TEST_DOMAIN.reclaim();
+        let drops_after_take = drop_counter.count();
+        drop(pool);
+
+        assert_eq!(
+            drops_after_take, 0,
+            "Retiring a node does not drop its object"
+        );
+        drop_counter.assert_drops(3);
+        drop(taken);
+        drop_counter.assert_drops(4);
+    }
+
+    #[test]
+    fn concurrent_puts_and_takes() {
+        const OBJECTS: usize = 400;
+        let pool = ObjectPool::new_with_domain(&TEST_DOMAIN);
+
+        let mut taken: Vec<usize> = std::thread::scope(|scope| {
+            let takers: Vec<_> = (0..4)
+                .map(|thread| {
+                    let pool = &pool;
+                    scope.spawn(move || {
+                        let mut taken = Vec::new();
+                        for value in (thread..OBJECTS).step_by(4) {
+                            pool.put(value).unwrap();
+                            taken.extend(pool.take());
+                        }
+                        taken
+                    })
+                })
+                .collect();
+            takers
+                .into_iter()
+                .flat_map(|taker| taker.join().unwrap())
+                .collect()
+        });
+
+        taken.sort_unstable();
+        assert_eq!(
+            taken,
+            (0..OBJECTS).collect::<Vec<_>>(),
+            "Each object is taken once"
+        );
+        assert!(pool.is_empty());
+    }
+}
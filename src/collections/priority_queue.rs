@@ -0,0 +1,245 @@
+//! A concurrent priority queue built on a lock-free skip list.
+
+use super::skip_list::{self, SkipListMap};
+use crate::domain::Domain;
+use crate::sync::{AtomicUsize, Ordering};
+use crate::LoadGuard;
+
+/// A concurrent min-priority queue.
+///
+/// Values are ordered by priority, and values with equal priorities are popped in the order they
+/// were pushed. The queue is a [`SkipListMap`] keyed by priority and push order, so pushes and
+/// pops never block one another.
+///
+/// [`PriorityQueue::peek_min`] protects the first value with a hazard pointer rather than copying
+/// it. Popped values are retired to the domain and reclaimed once the returned entry, and any other
+/// guards referencing them, are dropped.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::collections::PriorityQueue;
+///
+/// let timers = PriorityQueue::new();
+/// timers.push(30, "flush");
+/// timers.push(10, "heartbeat");
+/// timers.push(10, "poll");
+///
+/// assert_eq!(*timers.peek_min().unwrap().value(), "heartbeat");
+///
+/// let next = timers.pop_min().unwrap();
+/// assert_eq!((*next.priority(), *next.value()), (10, "heartbeat"));
+/// assert_eq!(*timers.pop_min().unwrap().value(), "poll");
+/// assert_eq!(timers.len(), 1);
+/// ```
+pub struct PriorityQueue<'domain, P, V, const DOMAIN_ID: usize> {
+    entries: SkipListMap<'domain, (P, usize), V, DOMAIN_ID>,
+    // Orders values with equal priorities by when they were pushed.
+    sequence: AtomicUsize,
+}
+
+impl<'domain, P, V, const DOMAIN_ID: usize> core::fmt::Debug
+    for PriorityQueue<'domain, P, V, DOMAIN_ID>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PriorityQueue")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+#[cfg(not(loom))]
+impl<P: Ord, V> PriorityQueue<'static, P, V, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new, empty `PriorityQueue` associated with the shared (global) domain.
+    pub fn new() -> Self {
+        Self::new_with_domain(&crate::SHARED_DOMAIN)
+    }
+}
+
+#[cfg(not(loom))]
+impl<P: Ord, V> Default for PriorityQueue<'static, P, V, { crate::SHARED_DOMAIN_ID }> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'domain, P: Ord, V, const DOMAIN_ID: usize> PriorityQueue<'domain, P, V, DOMAIN_ID> {
+    /// Creates a new, empty `PriorityQueue` associated with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{collections::PriorityQueue, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let queue = PriorityQueue::new_with_domain(&CUSTOM_DOMAIN);
+    /// queue.push(1, "task");
+    /// ```
+    pub fn new_with_domain(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            entries: SkipListMap::new_with_domain(domain),
+            sequence: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the number of values in the queue.
+    ///
+    /// Since other threads may be modifying the queue concurrently this is only a snapshot.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the queue contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a value to the queue with the given priority.
+    pub fn push(&self, priority: P, value: V) {
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        if self.entries.insert((priority, sequence), value).is_err() {
+            unreachable!("Sequence numbers are unique");
+        }
+    }
+
+    /// Returns the value with the lowest priority, without removing it.
+    ///
+    /// The value is protected while the entry is held, even if it is concurrently popped.
+    pub fn peek_min(&self) -> Option<Entry<'domain, P, V, DOMAIN_ID>> {
+        self.entries.range(..).next().map(|entry| Entry { entry })
+    }
+
+    /// Removes the value with the lowest priority, returning an entry which protects it.
+    ///
+    /// If several threads pop concurrently, each value is returned to exactly one of them.
+    pub fn pop_min(&self) -> Option<Entry<'domain, P, V, DOMAIN_ID>> {
+        loop {
+            let entry = self.entries.range(..).next()?;
+            // Keys are never reused, so if the key is removed it was this entry's node which was
+            // removed. The entry keeps it protected after it has been retired.
+            if self.entries.remove(entry.key()).is_some() {
+                return Some(Entry { entry });
+            }
+            // Another thread popped the value first.
+        }
+    }
+}
+
+/// A value in a [`PriorityQueue`], protected from reclamation while held.
+pub struct Entry<'domain, P, V, const DOMAIN_ID: usize> {
+    entry: skip_list::Entry<'domain, (P, usize), V, DOMAIN_ID>,
+}
+
+impl<'domain, P, V, const DOMAIN_ID: usize> Entry<'domain, P, V, DOMAIN_ID> {
+    /// Returns the priority the value was pushed with.
+    pub fn priority(&self) -> &P {
+        &self.entry.key().0
+    }
+
+    /// Returns the value.
+    pub fn value(&self) -> &V {
+        self.entry.value()
+    }
+
+    /// Converts the entry into a guard over its value.
+    pub fn into_value(self) -> LoadGuard<'domain, V, DOMAIN_ID> {
+        self.entry.into_value()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+    use alloc::vec::Vec;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn pops_in_priority_then_push_order() {
+        let queue = PriorityQueue::new_with_domain(&TEST_DOMAIN);
+        for (priority, value) in [(3, 'a'), (1, 'b'), (2, 'c'), (1, 'd')] {
+            queue.push(priority, value);
+        }
+
+        let peeked = *queue.peek_min().unwrap().value();
+        let popped: Vec<_> = core::iter::from_fn(|| queue.pop_min())
+            .map(|entry| (*entry.priority(), *entry.value()))
+            .collect();
+
+        assert_eq!(peeked, 'b', "Peeking returns the first value");
+        assert_eq!(popped, [(1, 'b'), (1, 'd'), (2, 'c'), (3, 'a')]);
+        assert!(queue.is_empty(), "Every value has been popped");
+    }
+
+    #[test]
+    fn popped_values_are_reclaimed_once_released() {
+        let drop_counter = DropCounter::new();
+        let queue = PriorityQueue::new_with_domain(&TEST_DOMAIN);
+        queue.push(1, drop_counter.track(1));
+        queue.push(2, drop_counter.track(2));
+        let peeked = queue.peek_min().unwrap();
+
+        let popped = queue.pop_min().unwrap().into_value();
+        TEST_DOMAIN.reclaim();
+        let drops_while_guarded = drop_counter.count();
+        drop(popped);
+        drop(peeked);
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(
+            drops_while_guarded, 0,
+            "The entries protect the popped value"
+        );
+        drop_counter.assert_drops(1);
+        drop(queue);
+        TEST_DOMAIN.reclaim();
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    fn concurrent_pops_return_each_value_once() {
+        const VALUES: usize = 400;
+        let queue = PriorityQueue::new_with_domain(&TEST_DOMAIN);
+
+        let mut popped: Vec<usize> = std::thread::scope(|scope| {
+            for thread in 0..2 {
+                let queue = &queue;
+                scope.spawn(move || {
+                    for value in (thread..VALUES).step_by(2) {
+                        queue.push(VALUES - value, value);
+                    }
+                });
+            }
+            let poppers: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut popped = Vec::new();
+                        while popped.len() < VALUES / 4 {
+                            if let Some(entry) = queue.pop_min() {
+                                popped.push(*entry.value());
+                            }
+                        }
+                        popped
+                    })
+                })
+                .collect();
+            poppers
+                .into_iter()
+                .flat_map(|popper| popper.join().unwrap())
+                .collect()
+        });
+
+        popped.sort_unstable();
+        assert_eq!(
+            popped,
+            (0..VALUES).collect::<Vec<_>>(),
+            "Each value is popped once"
+        );
+        assert!(queue.is_empty());
+    }
+}
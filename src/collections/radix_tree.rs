@@ -0,0 +1,685 @@
+//! A concurrent map keyed by byte strings, implemented as an adaptive radix tree.
+//!
+//! The tree is persistent: an update copies the nodes on the path to the changed key, sharing
+//! every other node with the previous version, and then swaps the new root into an [`AtomBoxIn`].
+//! A lookup protects the root it started from with a hazard pointer, which keeps that whole
+//! version of the tree alive. Replaced roots are retired to the domain, and the nodes only they
+//! referenced are freed once the root is reclaimed.
+//!
+//! Each node stores the bytes it shares with all of its descendants, so chains of nodes with a
+//! single child are collapsed into one. The children of a node are kept in one of three layouts
+//! depending on how many there are: a sorted list for up to 16, a byte index for up to 48 and a
+//! direct table otherwise.
+
+use super::Hazard;
+use crate::domain::Domain;
+use crate::sync::Ordering;
+use crate::{AtomBoxIn, LoadGuard};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+const SORTED_CAPACITY: usize = 16;
+const INDEXED_CAPACITY: usize = 48;
+// Nodes only shrink once they are well below the capacity of the smaller layout, so that
+// alternating inserts and removes do not convert a node back and forth.
+const INDEXED_SHRINK_AT: usize = 12;
+const DIRECT_SHRINK_AT: usize = 40;
+const EMPTY: u8 = u8::MAX;
+
+enum Children<V> {
+    Sorted {
+        bytes: Vec<u8>,
+        nodes: Vec<Arc<Node<V>>>,
+    },
+    Indexed {
+        // The position in `nodes` of the child for each byte, or `EMPTY`.
+        index: Box<[u8; 256]>,
+        nodes: Vec<Option<Arc<Node<V>>>>,
+        len: usize,
+    },
+    Direct {
+        nodes: Box<[Option<Arc<Node<V>>>; 256]>,
+        len: usize,
+    },
+}
+
+impl<V> Clone for Children<V> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Sorted { bytes, nodes } => Self::Sorted {
+                bytes: bytes.clone(),
+                nodes: nodes.clone(),
+            },
+            Self::Indexed { index, nodes, len } => Self::Indexed {
+                index: index.clone(),
+                nodes: nodes.clone(),
+                len: *len,
+            },
+            Self::Direct { nodes, len } => Self::Direct {
+                nodes: nodes.clone(),
+                len: *len,
+            },
+        }
+    }
+}
+
+impl<V> Children<V> {
+    fn new() -> Self {
+        Self::Sorted {
+            bytes: Vec::new(),
+            nodes: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Sorted { bytes, .. } => bytes.len(),
+            Self::Indexed { len, .. } | Self::Direct { len, .. } => *len,
+        }
+    }
+
+    fn get(&self, byte: u8) -> Option<&Arc<Node<V>>> {
+        match self {
+            Self::Sorted { bytes, nodes } => bytes
+                .binary_search(&byte)
+                .ok()
+                .map(|position| &nodes[position]),
+            Self::Indexed { index, nodes, .. } => match index[usize::from(byte)] {
+                EMPTY => None,
+                position => nodes[usize::from(position)].as_ref(),
+            },
+            Self::Direct { nodes, .. } => nodes[usize::from(byte)].as_ref(),
+        }
+    }
+
+    /// Returns the children in order of their bytes.
+    fn iter(&self) -> impl Iterator<Item = (u8, &Arc<Node<V>>)> {
+        (0..=u8::MAX).filter_map(move |byte| self.get(byte).map(|node| (byte, node)))
+    }
+
+    /// Sets the child for `byte`, growing into a larger layout if needed.
+    fn insert(&mut self, byte: u8, node: Arc<Node<V>>) {
+        match self {
+            Self::Sorted { bytes, nodes } => match bytes.binary_search(&byte) {
+                Ok(position) => nodes[position] = node,
+                Err(_) if bytes.len() == SORTED_CAPACITY => {
+                    self.grow();
+                    self.insert(byte, node);
+                }
+                Err(position) => {
+                    bytes.insert(position, byte);
+                    nodes.insert(position, node);
+                }
+            },
+            Self::Indexed { index, nodes, len } => match index[usize::from(byte)] {
+                EMPTY if *len == INDEXED_CAPACITY => {
+                    self.grow();
+                    self.insert(byte, node);
+                }
+                EMPTY => {
+                    let position = match nodes.iter().position(Option::is_none) {
+                        Some(position) => {
+                            nodes[position] = Some(node);
+                            position
+                        }
+                        None => {
+                            nodes.push(Some(node));
+                            nodes.len() - 1
+                        }
+                    };
+                    index[usize::from(byte)] = position as u8;
+                    *len += 1;
+                }
+                position => nodes[usize::from(position)] = Some(node),
+            },
+            Self::Direct { nodes, len } => {
+                if nodes[usize::from(byte)].replace(node).is_none() {
+                    *len += 1;
+                }
+            }
+        }
+    }
+
+    /// Removes the child for `byte`, shrinking into a smaller layout if it is sparse enough.
+    fn remove(&mut self, byte: u8) {
+        match self {
+            Self::Sorted { bytes, nodes } => {
+                if let Ok(position) = bytes.binary_search(&byte) {
+                    bytes.remove(position);
+                    nodes.remove(position);
+                }
+            }
+            Self::Indexed { index, nodes, len } => {
+                let position = core::mem::replace(&mut index[usize::from(byte)], EMPTY);
+                if position != EMPTY {
+                    nodes[usize::from(position)] = None;
+                    *len -= 1;
+                    if *len <= INDEXED_SHRINK_AT {
+                        self.shrink();
+                    }
+                }
+            }
+            Self::Direct { nodes, len } => {
+                if nodes[usize::from(byte)].take().is_some() {
+                    *len -= 1;
+                    if *len <= DIRECT_SHRINK_AT {
+                        self.shrink();
+                    }
+                }
+            }
+        }
+    }
+
+    fn grow(&mut self) {
+        let children: Vec<_> = self
+            .iter()
+            .map(|(byte, node)| (byte, node.clone()))
+            .collect();
+        *self = match self {
+            Self::Sorted { .. } => Self::Indexed {
+                index: Box::new([EMPTY; 256]),
+                nodes: Vec::with_capacity(INDEXED_CAPACITY),
+                len: 0,
+            },
+            _ => Self::Direct {
+                nodes: Box::new(core::array::from_fn(|_| None)),
+                len: 0,
+            },
+        };
+        for (byte, node) in children {
+            self.insert(byte, node);
+        }
+    }
+
+    fn shrink(&mut self) {
+        let children: Vec<_> = self
+            .iter()
+            .map(|(byte, node)| (byte, node.clone()))
+            .collect();
+        *self = match self {
+            Self::Direct { .. } => Self::Indexed {
+                index: Box::new([EMPTY; 256]),
+                nodes: Vec::with_capacity(INDEXED_CAPACITY),
+                len: 0,
+            },
+            _ => Self::new(),
+        };
+        for (byte, node) in children {
+            self.insert(byte, node);
+        }
+    }
+}
+
+struct Node<V> {
+    // The bytes following the edge to this node which every key below it shares.
+    prefix: Box<[u8]>,
+    value: Option<Arc<V>>,
+    children: Children<V>,
+}
+
+impl<V> Clone for Node<V> {
+    fn clone(&self) -> Self {
+        Self {
+            prefix: self.prefix.clone(),
+            value: self.value.clone(),
+            children: self.children.clone(),
+        }
+    }
+}
+
+impl<V> Node<V> {
+    fn leaf(prefix: &[u8], value: Arc<V>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            value: Some(value),
+            children: Children::new(),
+        }
+    }
+
+    fn with_prefix(&self, prefix: &[u8]) -> Self {
+        Self {
+            prefix: prefix.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of the node with `value` stored at `key`, along with the value it replaced.
+    ///
+    /// `key` starts from the first byte of the node's prefix.
+    fn insert(&self, key: &[u8], value: Arc<V>) -> (Self, Option<Arc<V>>) {
+        let common = self
+            .prefix
+            .iter()
+            .zip(key)
+            .take_while(|(a, b)| a == b)
+            .count();
+        if common < self.prefix.len() {
+            // Split the prefix, moving this node below a new node holding the shared bytes.
+            let mut parent = Self {
+                prefix: key[..common].into(),
+                value: None,
+                children: Children::new(),
+            };
+            parent.children.insert(
+                self.prefix[common],
+                Arc::new(self.with_prefix(&self.prefix[common + 1..])),
+            );
+            match key.get(common) {
+                None => parent.value = Some(value),
+                Some(&byte) => parent
+                    .children
+                    .insert(byte, Arc::new(Self::leaf(&key[common + 1..], value))),
+            }
+            return (parent, None);
+        }
+        let mut node = self.clone();
+        let replaced = match key.get(common) {
+            None => node.value.replace(value),
+            Some(&byte) => {
+                let rest = &key[common + 1..];
+                let (child, replaced) = match self.children.get(byte) {
+                    Some(child) => child.insert(rest, value),
+                    None => (Self::leaf(rest, value), None),
+                };
+                node.children.insert(byte, Arc::new(child));
+                replaced
+            }
+        };
+        (node, replaced)
+    }
+
+    /// Returns a copy of the node without a value at `key`, along with the removed value, or
+    /// `None` if there is no value at `key`.
+    ///
+    /// The copy is `None` if it would be left empty. Unless `is_root`, a node left with a single
+    /// child is merged with it.
+    fn remove(&self, key: &[u8], is_root: bool) -> Option<(Option<Self>, Arc<V>)> {
+        let rest = key.strip_prefix(&*self.prefix)?;
+        let mut node = self.clone();
+        let removed = match rest.split_first() {
+            None => node.value.take()?,
+            Some((&byte, rest)) => {
+                let (child, removed) = self.children.get(byte)?.remove(rest, false)?;
+                match child {
+                    Some(child) => node.children.insert(byte, Arc::new(child)),
+                    None => node.children.remove(byte),
+                }
+                removed
+            }
+        };
+        if is_root || node.value.is_some() || node.children.len() > 1 {
+            return Some((Some(node), removed));
+        }
+        let merged = node.children.iter().next().map(|(byte, child)| {
+            let mut prefix = Vec::with_capacity(node.prefix.len() + 1 + child.prefix.len());
+            prefix.extend_from_slice(&node.prefix);
+            prefix.push(byte);
+            prefix.extend_from_slice(&child.prefix);
+            child.with_prefix(&prefix)
+        });
+        Some((merged, removed))
+    }
+}
+
+struct Root<V> {
+    node: Node<V>,
+    len: usize,
+}
+
+/// A concurrent map keyed by byte strings, supporting longest prefix matches.
+///
+/// The map is an adaptive radix tree which is updated by copying the path to the changed key, so
+/// lookups never wait for, or retry because of, concurrent updates. Updates replace the root of
+/// the tree, so concurrent updates retry until they apply to the latest version. This suits
+/// read-heavy workloads such as routing tables.
+///
+/// Lookups return guards which keep the value alive even if it is concurrently replaced or
+/// removed. While a guard is held, the version of the tree it was read from is not reclaimed.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::collections::RadixTreeMap;
+///
+/// let routes = RadixTreeMap::new();
+/// routes.insert("/api", "api");
+/// routes.insert("/api/users", "users");
+/// routes.insert("/static", "files");
+///
+/// assert_eq!(*routes.get("/api").unwrap(), "api");
+/// assert!(routes.get("/api/users/42").is_none());
+///
+/// let (matched, handler) = routes.longest_prefix_match("/api/users/42").unwrap();
+/// assert_eq!((matched, *handler), (10, "users"));
+///
+/// assert_eq!(*routes.remove("/api/users").unwrap(), "users");
+/// assert_eq!(*routes.longest_prefix_match("/api/users/42").unwrap().1, "api");
+/// ```
+pub struct RadixTreeMap<'domain, V, const DOMAIN_ID: usize> {
+    root: AtomBoxIn<'domain, Root<V>, DOMAIN_ID>,
+}
+
+impl<'domain, V, const DOMAIN_ID: usize> core::fmt::Debug for RadixTreeMap<'domain, V, DOMAIN_ID> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RadixTreeMap")
+            .field("len", &self.len())
+            .field("domain", &self.root.domain)
+            .finish()
+    }
+}
+
+#[cfg(not(loom))]
+impl<V> RadixTreeMap<'static, V, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new, empty `RadixTreeMap` associated with the shared (global) domain.
+    pub fn new() -> Self {
+        Self::new_with_domain(&crate::SHARED_DOMAIN)
+    }
+}
+
+#[cfg(not(loom))]
+impl<V> Default for RadixTreeMap<'static, V, { crate::SHARED_DOMAIN_ID }> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'domain, V, const DOMAIN_ID: usize> RadixTreeMap<'domain, V, DOMAIN_ID> {
+    /// Creates a new, empty `RadixTreeMap` associated with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{collections::RadixTreeMap, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let map = RadixTreeMap::new_with_domain(&CUSTOM_DOMAIN);
+    /// map.insert(b"10.0.0.0", "internal");
+    /// ```
+    pub fn new_with_domain(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        let root = Root {
+            node: Node {
+                prefix: Box::new([]),
+                value: None,
+                children: Children::new(),
+            },
+            len: 0,
+        };
+        Self {
+            root: AtomBoxIn::new_with_domain(root, domain),
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    ///
+    /// Since other threads may be modifying the map concurrently this is only a snapshot.
+    pub fn len(&self) -> usize {
+        self.root.load().len
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a guard to the value associated with `key`.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<LoadGuard<'domain, V, DOMAIN_ID>> {
+        let root = self.root.load();
+        let mut node = &root.node;
+        let mut key = key.as_ref().strip_prefix(&*node.prefix)?;
+        while let Some((&byte, rest)) = key.split_first() {
+            node = node.children.get(byte)?;
+            key = rest.strip_prefix(&*node.prefix)?;
+        }
+        let value = Arc::as_ptr(node.value.as_ref()?);
+        Some(Self::project(root, value))
+    }
+
+    /// Returns `true` if the map contains an entry for `key`.
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the value of the longest key in the map which is a prefix of `key`, along with the
+    /// length of that prefix.
+    pub fn longest_prefix_match(
+        &self,
+        key: impl AsRef<[u8]>,
+    ) -> Option<(usize, LoadGuard<'domain, V, DOMAIN_ID>)> {
+        let key = key.as_ref();
+        let root = self.root.load();
+        let mut node = &root.node;
+        let mut matched = 0;
+        let mut longest = None;
+        loop {
+            if !key[matched..].starts_with(&node.prefix) {
+                break;
+            }
+            matched += node.prefix.len();
+            if let Some(value) = &node.value {
+                longest = Some((matched, Arc::as_ptr(value)));
+            }
+            match key.get(matched).and_then(|&byte| node.children.get(byte)) {
+                Some(child) => {
+                    node = child;
+                    matched += 1;
+                }
+                None => break,
+            }
+        }
+        let (length, value) = longest?;
+        Some((length, Self::project(root, value)))
+    }
+
+    /// Inserts a value into the map, returning a guard to the value it replaced.
+    pub fn insert(
+        &self,
+        key: impl AsRef<[u8]>,
+        value: V,
+    ) -> Option<LoadGuard<'domain, V, DOMAIN_ID>> {
+        let key = key.as_ref();
+        let value = Arc::new(value);
+        self.update(|root| {
+            let (node, replaced) = root.node.insert(key, value.clone());
+            let len = root.len + usize::from(replaced.is_none());
+            (Some(Root { node, len }), replaced)
+        })
+    }
+
+    /// Removes the entry for `key` from the map, returning a guard to its value.
+    ///
+    /// The value is reclaimed once the guard, and any other guards referencing it, are dropped.
+    pub fn remove(&self, key: impl AsRef<[u8]>) -> Option<LoadGuard<'domain, V, DOMAIN_ID>> {
+        let key = key.as_ref();
+        self.update(|root| match root.node.remove(key, true) {
+            Some((node, removed)) => {
+                let node = node.expect("The root is never removed");
+                let len = root.len - 1;
+                (Some(Root { node, len }), Some(removed))
+            }
+            None => (None, None),
+        })
+    }
+
+    /// Replaces the root with the one returned by `update`, retrying if another thread replaces it
+    /// first, and returns a guard to the value `update` returns.
+    fn update(
+        &self,
+        update: impl Fn(&Root<V>) -> (Option<Root<V>>, Option<Arc<V>>),
+    ) -> Option<LoadGuard<'domain, V, DOMAIN_ID>> {
+        let mut current = self.root.load();
+        loop {
+            let (root, value) = update(&current);
+            let root = match root {
+                Some(root) => root,
+                None => return value.map(|value| Self::project(current, Arc::as_ptr(&value))),
+            };
+            match self.root.compare_exchange(current, root) {
+                Ok(replaced) => {
+                    let value = value?;
+                    // The replaced root still owns the value and has not been retired yet, so
+                    // protecting it keeps the value alive once it is.
+                    let hazard = Hazard::new(self.root.domain);
+                    hazard.protect(&*replaced as *const Root<V>);
+                    crate::sync::fence(Ordering::SeqCst);
+                    drop(replaced);
+                    return Some(hazard.into_load_guard(Arc::as_ptr(&value)));
+                }
+                Err(_) => current = self.root.load(),
+            }
+        }
+    }
+
+    /// Converts a guard over a root into a guard over a value owned by that version of the tree.
+    fn project(
+        mut root: LoadGuard<'domain, Root<V>, DOMAIN_ID>,
+        value: *const V,
+    ) -> LoadGuard<'domain, V, DOMAIN_ID> {
+        // The value is owned by the root the hazard pointer protects, so it remains valid for as
+        // long as the protection is held.
+        LoadGuard {
+            ptr: value,
+            domain: root.domain,
+            haz_ptr: root.haz_ptr.take(),
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn insert_get_and_remove() {
+        let map = RadixTreeMap::new_with_domain(&TEST_DOMAIN);
+        let keys = ["", "a", "ab", "abc", "abd", "b", "ba"];
+
+        for key in keys {
+            assert!(map.insert(key, key).is_none(), "Key should not be present");
+        }
+        let replaced = map.insert("ab", "replaced").map(|guard| *guard);
+
+        assert_eq!(map.len(), keys.len(), "Replacing does not add an entry");
+        assert_eq!(replaced, Some("ab"), "Should return the replaced value");
+        for key in ["", "a", "abc", "abd", "b", "ba"] {
+            assert_eq!(*map.get(key).unwrap(), key, "Should get the inserted value");
+        }
+        assert!(
+            map.get("abe").is_none(),
+            "Keys sharing a prefix are distinct"
+        );
+        assert_eq!(
+            *map.remove("ab").unwrap(),
+            "replaced",
+            "Should remove the value"
+        );
+        assert!(map.get("ab").is_none(), "Removed key should not be found");
+        assert!(map.remove("ab").is_none(), "Key can only be removed once");
+        assert_eq!(*map.get("abc").unwrap(), "abc", "Descendants are kept");
+        assert_eq!(map.len(), keys.len() - 1);
+    }
+
+    #[test]
+    fn longest_prefix_match_returns_the_most_specific_entry() {
+        let map = RadixTreeMap::new_with_domain(&TEST_DOMAIN);
+        map.insert([10], "10/8");
+        map.insert([10, 1], "10.1/16");
+        map.insert([10, 1, 2, 3], "10.1.2.3/32");
+
+        let lookup = |key: &[u8]| {
+            map.longest_prefix_match(key)
+                .map(|(length, guard)| (length, *guard))
+        };
+
+        assert_eq!(lookup(&[10, 1, 2, 3]), Some((4, "10.1.2.3/32")));
+        assert_eq!(lookup(&[10, 1, 2, 4]), Some((2, "10.1/16")));
+        assert_eq!(lookup(&[10, 2, 0, 0]), Some((1, "10/8")));
+        assert_eq!(lookup(&[11, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn nodes_grow_and_shrink_between_layouts() {
+        let map = RadixTreeMap::new_with_domain(&TEST_DOMAIN);
+
+        for byte in 0..=u8::MAX {
+            map.insert([b'k', byte], usize::from(byte));
+        }
+        let all_present =
+            (0..=u8::MAX).all(|byte| *map.get([b'k', byte]).unwrap() == usize::from(byte));
+        for byte in (0..=u8::MAX).filter(|byte| byte % 16 != 0) {
+            map.remove([b'k', byte]);
+        }
+
+        assert!(all_present, "Every child is found in the direct layout");
+        let remaining: Vec<_> = (0..=u8::MAX)
+            .filter_map(|byte| map.get([b'k', byte]).map(|guard| *guard))
+            .collect();
+        assert_eq!(remaining, (0..256).step_by(16).collect::<Vec<_>>());
+        assert_eq!(map.len(), 16);
+    }
+
+    #[test]
+    fn values_are_reclaimed_once_unreferenced() {
+        let drop_counter = DropCounter::new();
+        let map = RadixTreeMap::new_with_domain(&TEST_DOMAIN);
+        for key in 0..4_u8 {
+            map.insert([key], drop_counter.track(key));
+        }
+        let guard = map.get([0]).unwrap();
+
+        drop(map.remove([0]));
+        drop(map.insert([1], drop_counter.track(1)));
+        TEST_DOMAIN.reclaim();
+        let drops_while_guarded = drop_counter.count();
+        drop(guard);
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(
+            drops_while_guarded, 0,
+            "The guard keeps the version of the tree it was read from alive"
+        );
+        drop_counter.assert_drops(2);
+        drop(map);
+        TEST_DOMAIN.reclaim();
+        drop_counter.assert_drops(5);
+    }
+
+    #[test]
+    fn concurrent_inserts_and_lookups() {
+        const KEYS: usize = 200;
+        let map = RadixTreeMap::new_with_domain(&TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let map = &map;
+                scope.spawn(move || {
+                    for key in (thread..KEYS).step_by(4) {
+                        map.insert(format!("/{}", key), key);
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for key in 0..KEYS {
+                    if let Some(value) = map.get(format!("/{}", key)) {
+                        assert_eq!(*value, key);
+                    }
+                }
+            });
+        });
+
+        assert_eq!(map.len(), KEYS);
+        let values: Vec<_> = (0..KEYS)
+            .map(|key| *map.get(format!("/{}", key)).unwrap())
+            .collect();
+        assert_eq!(values, (0..KEYS).collect::<Vec<_>>());
+    }
+}
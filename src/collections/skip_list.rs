@@ -0,0 +1,848 @@
+//! A concurrent ordered map implemented as a lock-free skip list.
+
+use super::Hazard;
+use crate::domain::Domain;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use crate::LoadGuard;
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::ops::{Bound, RangeBounds};
+
+const MAX_HEIGHT: usize = 16;
+const MARK: usize = 1;
+
+fn is_marked<T>(ptr: *mut T) -> bool {
+    ptr as usize & MARK == MARK
+}
+
+fn marked<T>(ptr: *mut T) -> *mut T {
+    (ptr as usize | MARK) as *mut T
+}
+
+fn unmarked<T>(ptr: *mut T) -> *mut T {
+    (ptr as usize & !MARK) as *mut T
+}
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // The number of levels this node is linked into plus one while its inserting thread is still
+    // linking it. The node is retired when this reaches zero.
+    links: AtomicUsize,
+    // The low bit of each pointer marks this node as removed at that level.
+    next: Box<[AtomicPtr<Node<K, V>>]>,
+}
+
+impl<K, V> Node<K, V> {
+    fn height(&self) -> usize {
+        self.next.len()
+    }
+}
+
+/// The hazard pointers and search results used while traversing the list.
+struct Search<'domain, K, V, const DOMAIN_ID: usize> {
+    pred: Hazard<'domain, DOMAIN_ID>,
+    curr: Hazard<'domain, DOMAIN_ID>,
+    // Protect the predecessors at each level while inserting.
+    saved_preds: Vec<Hazard<'domain, DOMAIN_ID>>,
+    preds: [*mut Node<K, V>; MAX_HEIGHT],
+    succs: [*mut Node<K, V>; MAX_HEIGHT],
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> Search<'domain, K, V, DOMAIN_ID> {
+    fn new(domain: &'domain Domain<DOMAIN_ID>, saved_levels: usize) -> Self {
+        Self {
+            pred: Hazard::new(domain),
+            curr: Hazard::new(domain),
+            saved_preds: (0..saved_levels).map(|_| Hazard::new(domain)).collect(),
+            preds: [core::ptr::null_mut(); MAX_HEIGHT],
+            succs: [core::ptr::null_mut(); MAX_HEIGHT],
+        }
+    }
+}
+
+/// An ordered concurrent map implemented as a lock-free skip list.
+///
+/// Lookups return guards which keep the value alive even if the entry is concurrently removed.
+/// Removed nodes are retired to the domain once they have been unlinked from every level of the
+/// list.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::collections::SkipListMap;
+///
+/// let map = SkipListMap::new();
+/// map.insert(2, "two").unwrap();
+/// map.insert(1, "one").unwrap();
+/// map.insert(3, "three").unwrap();
+///
+/// assert_eq!(*map.get(&2).unwrap(), "two");
+///
+/// let removed = map.remove(&2).unwrap();
+/// assert_eq!(*removed, "two");
+/// assert!(map.get(&2).is_none());
+///
+/// let keys: Vec<_> = map.range(..).map(|entry| *entry.key()).collect();
+/// assert_eq!(keys, [1, 3]);
+/// ```
+pub struct SkipListMap<'domain, K, V, const DOMAIN_ID: usize> {
+    head: [AtomicPtr<Node<K, V>>; MAX_HEIGHT],
+    len: AtomicUsize,
+    seed: AtomicUsize,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+unsafe impl<'domain, K: Send + Sync, V: Send + Sync, const DOMAIN_ID: usize> Send
+    for SkipListMap<'domain, K, V, DOMAIN_ID>
+{
+}
+unsafe impl<'domain, K: Send + Sync, V: Send + Sync, const DOMAIN_ID: usize> Sync
+    for SkipListMap<'domain, K, V, DOMAIN_ID>
+{
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> core::fmt::Debug
+    for SkipListMap<'domain, K, V, DOMAIN_ID>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SkipListMap")
+            .field("len", &self.len.load(Ordering::Relaxed))
+            .field("domain", &self.domain)
+            .finish()
+    }
+}
+
+#[cfg(not(loom))]
+impl<K: Ord, V> SkipListMap<'static, K, V, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new, empty `SkipListMap` associated with the shared (global) domain.
+    pub fn new() -> Self {
+        Self::new_with_domain(&crate::SHARED_DOMAIN)
+    }
+}
+
+#[cfg(not(loom))]
+impl<K: Ord, V> Default for SkipListMap<'static, K, V, { crate::SHARED_DOMAIN_ID }> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'domain, K: Ord, V, const DOMAIN_ID: usize> SkipListMap<'domain, K, V, DOMAIN_ID> {
+    /// Creates a new, empty `SkipListMap` associated with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{collections::SkipListMap, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let map = SkipListMap::new_with_domain(&CUSTOM_DOMAIN);
+    /// map.insert("key", "value").unwrap();
+    /// ```
+    pub fn new_with_domain(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            head: core::array::from_fn(|_| AtomicPtr::new(core::ptr::null_mut())),
+            len: AtomicUsize::new(0),
+            seed: AtomicUsize::new(0),
+            domain,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    ///
+    /// Since other threads may be modifying the map concurrently this is only a snapshot.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a guard to the value associated with `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<LoadGuard<'domain, V, DOMAIN_ID>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_entry(key).map(Entry::into_value)
+    }
+
+    /// Returns the entry associated with `key`.
+    pub fn get_entry<Q>(&self, key: &Q) -> Option<Entry<'domain, K, V, DOMAIN_ID>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut search = Search::new(self.domain, 0);
+        if self.find(key, &mut search) {
+            Some(Entry {
+                node: search.succs[0],
+                hazard: search.curr,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the map contains an entry for `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key, &mut Search::new(self.domain, 0))
+    }
+
+    /// Inserts a new entry into the map.
+    ///
+    /// If the map already contains an entry for `key`, the key and value are handed back in the
+    /// `Err`.
+    pub fn insert(&self, key: K, value: V) -> Result<(), (K, V)> {
+        let height = self.random_height();
+        let mut search = Search::new(self.domain, height);
+        if self.find(&key, &mut search) {
+            return Err((key, value));
+        }
+        let next = (0..height)
+            .map(|level| AtomicPtr::new(search.succs[level]))
+            .collect();
+        let node_ptr = Box::into_raw(Box::new(Node {
+            key,
+            value,
+            links: AtomicUsize::new(1),
+            next,
+        }));
+        self.link(node_ptr, height, &mut search)
+    }
+
+    fn link(
+        &self,
+        node_ptr: *mut Node<K, V>,
+        height: usize,
+        search: &mut Search<'domain, K, V, DOMAIN_ID>,
+    ) -> Result<(), (K, V)> {
+        // # Safety
+        //
+        // We hold a link to the node, so it will not be retired until we release it.
+        let node = unsafe { &*node_ptr };
+        loop {
+            node.links.fetch_add(1, Ordering::AcqRel);
+            if self
+                .next(search.preds[0], 0)
+                .compare_exchange(
+                    search.succs[0],
+                    node_ptr,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            {
+                break;
+            }
+            node.links.fetch_sub(1, Ordering::AcqRel);
+            if self.find(&node.key, search) {
+                // Another thread inserted the same key first.
+                // # Safety
+                //
+                // The node was never published so we still have exclusive ownership.
+                let node = unsafe { Box::from_raw(node_ptr) };
+                let Node { key, value, .. } = *node;
+                return Err((key, value));
+            }
+            for level in 0..height {
+                node.next[level].store(search.succs[level], Ordering::Release);
+            }
+        }
+        self.len.fetch_add(1, Ordering::AcqRel);
+
+        'levels: for level in 1..height {
+            loop {
+                let next = node.next[level].load(Ordering::Acquire);
+                if is_marked(next) {
+                    // The node is being removed, do not link it any higher.
+                    break 'levels;
+                }
+                if next != search.succs[level]
+                    && node.next[level]
+                        .compare_exchange(
+                            next,
+                            search.succs[level],
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                        )
+                        .is_err()
+                {
+                    break 'levels;
+                }
+                node.links.fetch_add(1, Ordering::AcqRel);
+                if self
+                    .next(search.preds[level], level)
+                    .compare_exchange(
+                        search.succs[level],
+                        node_ptr,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    )
+                    .is_ok()
+                {
+                    break;
+                }
+                node.links.fetch_sub(1, Ordering::AcqRel);
+                if !self.find(&node.key, search) || search.succs[0] != node_ptr {
+                    // The node has already been removed.
+                    break 'levels;
+                }
+            }
+        }
+
+        if is_marked(node.next[0].load(Ordering::Acquire)) {
+            // The node was removed while we were linking it, make sure it is not left linked at
+            // a level the remover has already passed.
+            self.find(&node.key, search);
+        }
+        // # Safety
+        //
+        // We hold a link to the node.
+        unsafe { self.release(node_ptr) };
+        Ok(())
+    }
+
+    /// Removes the entry for `key` from the map, returning a guard to its value.
+    ///
+    /// The value is retired to the domain and will be reclaimed once the guard, and any other
+    /// guards referencing it, are dropped.
+    pub fn remove<Q>(&self, key: &Q) -> Option<LoadGuard<'domain, V, DOMAIN_ID>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut search = Search::new(self.domain, 0);
+        if !self.find(key, &mut search) {
+            return None;
+        }
+        let node_ptr = search.succs[0];
+        // # Safety
+        //
+        // The node is protected by the search's current hazard pointer.
+        let node = unsafe { &*node_ptr };
+        for level in (1..node.height()).rev() {
+            let mut next = node.next[level].load(Ordering::Acquire);
+            while !is_marked(next) {
+                match node.next[level].compare_exchange_weak(
+                    next,
+                    marked(next),
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => break,
+                    Err(current) => next = current,
+                }
+            }
+        }
+        let mut next = node.next[0].load(Ordering::Acquire);
+        loop {
+            if is_marked(next) {
+                // Another thread removed the node first.
+                return None;
+            }
+            match node.next[0].compare_exchange_weak(
+                next,
+                marked(next),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(current) => next = current,
+            }
+        }
+        self.len.fetch_sub(1, Ordering::AcqRel);
+
+        // Keep the node protected for the returned guard and unlink it from every level.
+        let hazard = core::mem::replace(&mut search.curr, Hazard::new(self.domain));
+        self.find(key, &mut search);
+        Some(hazard.into_load_guard(&node.value))
+    }
+
+    /// Returns an iterator over the entries with keys in `range`, in ascending order.
+    ///
+    /// Each entry is protected as the iterator reaches it, so entries inserted or removed
+    /// concurrently may or may not be observed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::collections::SkipListMap;
+    ///
+    /// let map = SkipListMap::new();
+    /// for i in 0..10 {
+    ///     map.insert(i, i * 10).unwrap();
+    /// }
+    ///
+    /// let values: Vec<_> = map.range(3..6).map(|entry| *entry.value()).collect();
+    /// assert_eq!(values, [30, 40, 50]);
+    /// ```
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, 'domain, K, V, R, DOMAIN_ID> {
+        Range {
+            map: self,
+            range,
+            search: Search::new(self.domain, 0),
+            last: Hazard::new(self.domain),
+            current: None,
+            finished: false,
+        }
+    }
+
+    fn next(&self, node: *mut Node<K, V>, level: usize) -> &AtomicPtr<Node<K, V>> {
+        if node.is_null() {
+            &self.head[level]
+        } else {
+            // # Safety
+            //
+            // Callers only pass nodes which are protected by a hazard pointer or which they hold
+            // a link to.
+            &unsafe { &*node }.next[level]
+        }
+    }
+
+    /// Decrements the link count of the node, retiring it when it is no longer linked anywhere.
+    ///
+    /// # Safety
+    ///
+    /// The caller must own one of the links of the node.
+    unsafe fn release(&self, node_ptr: *mut Node<K, V>) {
+        // # Safety
+        //
+        // We own a link so the node has not been retired.
+        if unsafe { &*node_ptr }.links.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // # Safety
+            //
+            // The node has been unlinked from every level and its inserter has finished with it,
+            // so it is no longer reachable. Nodes are allocated via `Box::into_raw`.
+            unsafe { self.domain.retire(node_ptr) };
+        }
+    }
+
+    /// Protects the pointer stored in `source` with `hazard`, returning `None` if it is marked.
+    fn protect_next(
+        hazard: &Hazard<'domain, DOMAIN_ID>,
+        source: &AtomicPtr<Node<K, V>>,
+    ) -> Option<*mut Node<K, V>> {
+        let mut ptr = source.load(Ordering::Acquire);
+        loop {
+            if is_marked(ptr) {
+                return None;
+            }
+            hazard.protect(ptr);
+
+            crate::sync::fence(Ordering::SeqCst);
+
+            let current_ptr = source.load(Ordering::Acquire);
+            if current_ptr == ptr {
+                return Some(ptr);
+            }
+            ptr = current_ptr;
+        }
+    }
+
+    /// Protects a node which is already protected by another hazard pointer with `hazard`.
+    ///
+    /// Returns `false` if the node may have been retired before the new protection was published,
+    /// in which case `hazard` must not be relied upon.
+    fn copy_protection(hazard: &Hazard<'domain, DOMAIN_ID>, node_ptr: *mut Node<K, V>) -> bool {
+        hazard.protect(node_ptr);
+
+        crate::sync::fence(Ordering::SeqCst);
+
+        // # Safety
+        //
+        // The node is still protected by the caller's hazard pointer. Nodes are only retired once
+        // their link count reaches zero, which is permanent.
+        unsafe { &*node_ptr }.links.load(Ordering::Acquire) > 0
+    }
+
+    /// Finds the predecessors and successors of `key` at every level, unlinking any removed nodes
+    /// found along the way.
+    ///
+    /// Returns `true` if the map contains `key`, in which case `search.succs[0]` is its node and
+    /// is protected by `search.curr`.
+    fn find<Q>(&self, key: &Q, search: &mut Search<'domain, K, V, DOMAIN_ID>) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        'retry: loop {
+            let mut pred: *mut Node<K, V> = core::ptr::null_mut();
+            for level in (0..MAX_HEIGHT).rev() {
+                let mut curr = match Self::protect_next(&search.curr, self.next(pred, level)) {
+                    Some(curr) => curr,
+                    None => continue 'retry,
+                };
+                while !curr.is_null() {
+                    // # Safety
+                    //
+                    // `curr` is protected by a hazard pointer and was validated as reachable.
+                    let curr_node = unsafe { &*curr };
+                    let succ = curr_node.next[level].load(Ordering::Acquire);
+                    if is_marked(succ) {
+                        if self
+                            .next(pred, level)
+                            .compare_exchange(
+                                curr,
+                                unmarked(succ),
+                                Ordering::AcqRel,
+                                Ordering::Acquire,
+                            )
+                            .is_err()
+                        {
+                            continue 'retry;
+                        }
+                        // # Safety
+                        //
+                        // We have just removed the link to `curr` at this level.
+                        unsafe { self.release(curr) };
+                        curr = match Self::protect_next(&search.curr, self.next(pred, level)) {
+                            Some(curr) => curr,
+                            None => continue 'retry,
+                        };
+                    } else if curr_node.key.borrow() < key {
+                        pred = curr;
+                        core::mem::swap(&mut search.pred, &mut search.curr);
+                        curr = match Self::protect_next(&search.curr, &curr_node.next[level]) {
+                            Some(curr) => curr,
+                            None => continue 'retry,
+                        };
+                    } else {
+                        break;
+                    }
+                }
+                if let Some(saved_pred) = search.saved_preds.get(level) {
+                    saved_pred.protect(pred);
+
+                    crate::sync::fence(Ordering::SeqCst);
+
+                    // If the predecessor is still linked at this level after publishing the
+                    // protection, it cannot have been retired.
+                    if self.next(pred, level).load(Ordering::Acquire) != curr {
+                        continue 'retry;
+                    }
+                }
+                search.preds[level] = pred;
+                search.succs[level] = curr;
+            }
+            let found = search.succs[0];
+            // # Safety
+            //
+            // `found` is protected by `search.curr`.
+            return !found.is_null() && unsafe { &*found }.key.borrow() == key;
+        }
+    }
+
+    fn random_height(&self) -> usize {
+        // SplitMix style mixing of a shared counter
+        let mut bits = self
+            .seed
+            .fetch_add(0x9E37_79B9_7F4A_7C15_u64 as usize, Ordering::Relaxed)
+            as u64;
+        bits = (bits ^ (bits >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        bits = (bits ^ (bits >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        bits ^= bits >> 31;
+        (bits.trailing_ones() as usize + 1).min(MAX_HEIGHT)
+    }
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> Drop for SkipListMap<'domain, K, V, DOMAIN_ID> {
+    fn drop(&mut self) {
+        // A node may still be linked at some levels but not others, so collect every node that is
+        // still linked anywhere. Guards may still reference the nodes, so they are retired rather
+        // than dropped.
+        let mut nodes = BTreeSet::new();
+        for level in 0..MAX_HEIGHT {
+            let mut node_ptr = self.head[level].load(Ordering::Acquire);
+            while !node_ptr.is_null() {
+                nodes.insert(node_ptr);
+                // # Safety
+                //
+                // We have exclusive access to the map and the node is still linked.
+                node_ptr = unmarked(unsafe { &*node_ptr }.next[level].load(Ordering::Acquire));
+            }
+        }
+        for node_ptr in nodes {
+            // # Safety
+            //
+            // Every linked node is retired exactly once here and no further operations on the map
+            // can take place.
+            unsafe { self.domain.retire(node_ptr) };
+        }
+    }
+}
+
+/// An entry in a [`SkipListMap`], protected from reclamation while held.
+pub struct Entry<'domain, K, V, const DOMAIN_ID: usize> {
+    node: *mut Node<K, V>,
+    hazard: Hazard<'domain, DOMAIN_ID>,
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> Entry<'domain, K, V, DOMAIN_ID> {
+    fn node(&self) -> &Node<K, V> {
+        // # Safety
+        //
+        // The node is protected by the hazard pointer.
+        unsafe { &*self.node }
+    }
+
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.node().key
+    }
+
+    /// Returns the value of the entry.
+    pub fn value(&self) -> &V {
+        &self.node().value
+    }
+
+    /// Converts the entry into a guard over its value.
+    pub fn into_value(self) -> LoadGuard<'domain, V, DOMAIN_ID> {
+        let value = &self.node().value as *const V;
+        self.hazard.into_load_guard(value)
+    }
+}
+
+/// An iterator over a range of entries in a [`SkipListMap`].
+///
+/// Created by [`SkipListMap::range`].
+pub struct Range<'map, 'domain, K, V, R, const DOMAIN_ID: usize> {
+    map: &'map SkipListMap<'domain, K, V, DOMAIN_ID>,
+    range: R,
+    search: Search<'domain, K, V, DOMAIN_ID>,
+    // Protects the node most recently reached so the iterator can continue from it.
+    last: Hazard<'domain, DOMAIN_ID>,
+    current: Option<*mut Node<K, V>>,
+    finished: bool,
+}
+
+impl<'map, 'domain, K: Ord, V, R, const DOMAIN_ID: usize> Range<'map, 'domain, K, V, R, DOMAIN_ID> {
+    /// Finds the first node satisfying `start`, protected by `search.curr`.
+    fn seek(
+        map: &SkipListMap<'domain, K, V, DOMAIN_ID>,
+        search: &mut Search<'domain, K, V, DOMAIN_ID>,
+        start: Bound<&K>,
+    ) -> *mut Node<K, V> {
+        match start {
+            Bound::Unbounded => loop {
+                if let Some(node) = SkipListMap::protect_next(&search.curr, &map.head[0]) {
+                    return node;
+                }
+            },
+            Bound::Included(key) => {
+                map.find(key, search);
+                search.succs[0]
+            }
+            Bound::Excluded(key) => loop {
+                if !map.find(key, search) {
+                    return search.succs[0];
+                }
+                // # Safety
+                //
+                // The node equal to the key is protected by `search.curr`.
+                let node = unsafe { &*search.succs[0] };
+                if let Some(next) = SkipListMap::protect_next(&search.pred, &node.next[0]) {
+                    core::mem::swap(&mut search.pred, &mut search.curr);
+                    return next;
+                }
+                // The node equal to the key was removed, search again.
+            },
+        }
+    }
+}
+
+impl<'map, 'domain, K: Ord, V, R: RangeBounds<K>, const DOMAIN_ID: usize> Iterator
+    for Range<'map, 'domain, K, V, R, DOMAIN_ID>
+{
+    type Item = Entry<'domain, K, V, DOMAIN_ID>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            let node_ptr = match self.current {
+                None => Self::seek(self.map, &mut self.search, self.range.start_bound()),
+                Some(current) => {
+                    // # Safety
+                    //
+                    // The current node is protected by `self.last`.
+                    let current_node = unsafe { &*current };
+                    match SkipListMap::protect_next(&self.search.curr, &current_node.next[0]) {
+                        Some(next) => next,
+                        // The current node has been removed, find its successor instead.
+                        None => Self::seek(
+                            self.map,
+                            &mut self.search,
+                            Bound::Excluded(&current_node.key),
+                        ),
+                    }
+                }
+            };
+            if node_ptr.is_null() {
+                self.finished = true;
+                break;
+            }
+            // # Safety
+            //
+            // The node is protected by `search.curr`.
+            let node = unsafe { &*node_ptr };
+            let in_range = match self.range.end_bound() {
+                Bound::Included(end) => node.key <= *end,
+                Bound::Excluded(end) => node.key < *end,
+                Bound::Unbounded => true,
+            };
+            if !in_range {
+                self.finished = true;
+                break;
+            }
+            core::mem::swap(&mut self.last, &mut self.search.curr);
+            self.current = Some(node_ptr);
+            let hazard = Hazard::new(self.map.domain);
+            if SkipListMap::copy_protection(&hazard, node_ptr) {
+                return Some(Entry {
+                    node: node_ptr,
+                    hazard,
+                });
+            }
+            // The node has been removed from the map, continue from its successor.
+        }
+        None
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+    use alloc::vec;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn insert_get_and_remove() {
+        let map = SkipListMap::new_with_domain(&TEST_DOMAIN);
+
+        for key in [5, 1, 4, 2, 3] {
+            map.insert(key, key * 10)
+                .expect("Key should not be present");
+        }
+
+        assert_eq!(map.len(), 5, "Map should contain five entries");
+        assert_eq!(map.insert(3, 0), Err((3, 0)), "Duplicate keys are rejected");
+        assert_eq!(*map.get(&3).unwrap(), 30, "Should get the inserted value");
+        assert_eq!(*map.remove(&3).unwrap(), 30, "Should remove the value");
+        assert!(map.get(&3).is_none(), "Removed key should not be found");
+        assert!(map.remove(&3).is_none(), "Key can only be removed once");
+        assert_eq!(map.len(), 4, "Map should contain four entries");
+    }
+
+    #[test]
+    fn range_iterates_in_order() {
+        let map = SkipListMap::new_with_domain(&TEST_DOMAIN);
+        for key in (0..20).rev() {
+            map.insert(key, ()).unwrap();
+        }
+
+        let all: Vec<_> = map.range(..).map(|entry| *entry.key()).collect();
+        let bounded: Vec<_> = map.range(5..=8).map(|entry| *entry.key()).collect();
+        let excluded: Vec<_> = map
+            .range((Bound::Excluded(17), Bound::Unbounded))
+            .map(|entry| *entry.key())
+            .collect();
+
+        assert_eq!(all, (0..20).collect::<Vec<_>>(), "All keys in order");
+        assert_eq!(bounded, vec![5, 6, 7, 8], "Only keys in range");
+        assert_eq!(excluded, vec![18, 19], "Start bound is excluded");
+    }
+
+    #[test]
+    fn range_continues_past_removed_entry() {
+        let map = SkipListMap::new_with_domain(&TEST_DOMAIN);
+        for key in 0..5 {
+            map.insert(key, ()).unwrap();
+        }
+        let mut range = map.range(..);
+
+        assert_eq!(*range.next().unwrap().key(), 0);
+        assert_eq!(*range.next().unwrap().key(), 1);
+        map.remove(&1);
+        map.remove(&2);
+        let rest: Vec<_> = range.map(|entry| *entry.key()).collect();
+
+        assert_eq!(rest, vec![3, 4], "Removed entries are skipped");
+    }
+
+    #[test]
+    fn values_are_reclaimed() {
+        let drop_counter = DropCounter::new();
+        let map = SkipListMap::new_with_domain(&TEST_DOMAIN);
+        for key in 0..10 {
+            map.insert(key, drop_counter.track(key)).unwrap();
+        }
+
+        let guard = map.get(&0).unwrap();
+        let removed = map.remove(&0).unwrap();
+        drop(removed);
+        for key in 1..5 {
+            drop(map.remove(&key));
+        }
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(
+            drop_counter.count(),
+            4,
+            "Removed values should be reclaimed except the one still guarded"
+        );
+        assert_eq!(**guard, 0, "Guarded value is still accessible");
+        drop(guard);
+        drop(map);
+        TEST_DOMAIN.reclaim();
+        drop_counter.assert_drops(10);
+    }
+
+    #[test]
+    fn concurrent_inserts_and_removes() {
+        const KEYS: usize = 200;
+        let map = SkipListMap::new_with_domain(&TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let map = &map;
+                scope.spawn(move || {
+                    for key in (thread..KEYS).step_by(4) {
+                        map.insert(key, key).unwrap();
+                    }
+                    for key in (thread..KEYS).step_by(8) {
+                        assert_eq!(*map.remove(&key).unwrap(), key);
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for _ in 0..10 {
+                    let keys: Vec<_> = map.range(..).map(|entry| *entry.key()).collect();
+                    assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+                }
+            });
+        });
+
+        let keys: Vec<_> = map.range(..).map(|entry| *entry.key()).collect();
+        let expected: Vec<_> = (0..KEYS).filter(|key| key % 8 >= 4).collect();
+        assert_eq!(keys, expected, "Only keys which were not removed remain");
+        assert_eq!(
+            map.len(),
+            expected.len(),
+            "Length matches the remaining keys"
+        );
+    }
+}
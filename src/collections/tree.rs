@@ -0,0 +1,934 @@
+//! A concurrent ordered map implemented as a lock-free binary search tree.
+//!
+//! The tree follows the external (leaf oriented) design of Natarajan and Mittal: entries are only
+//! stored in the leaves and internal nodes are used purely for routing. A removal first flags the
+//! edge to its leaf, then tags the edge to the leaf's sibling and finally swings the edge above
+//! the leaf's parent to the sibling. Flagged and tagged edges are never changed again, which is
+//! what allows readers to validate their hazard pointers.
+
+use super::Hazard;
+use crate::domain::Domain;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use crate::LoadGuard;
+use alloc::boxed::Box;
+use alloc::vec;
+use core::borrow::Borrow;
+use core::ops::Bound;
+
+// The leaf at the end of this edge is being removed.
+const FLAG: usize = 1;
+// This edge is frozen while its parent is being removed.
+const TAG: usize = 2;
+const MARKS: usize = FLAG | TAG;
+
+// Enough for every role in a `Seek` plus the child being protected.
+const SLOTS: usize = 6;
+const UNPROTECTED: usize = SLOTS;
+
+fn address<T>(ptr: *mut T) -> *mut T {
+    (ptr as usize & !MARKS) as *mut T
+}
+
+fn is_flagged<T>(ptr: *mut T) -> bool {
+    ptr as usize & FLAG == FLAG
+}
+
+fn is_tagged<T>(ptr: *mut T) -> bool {
+    ptr as usize & TAG == TAG
+}
+
+fn is_clean<T>(ptr: *mut T) -> bool {
+    ptr as usize & MARKS == 0
+}
+
+/// The keys of the tree, extended with the sentinel key which is greater than every user key.
+///
+/// Only user keys are ever routed through the tree, so sentinel keys never need to be compared with
+/// each other.
+#[derive(Clone)]
+enum Key<K> {
+    Finite(K),
+    Infinite,
+}
+
+/// Returns `true` if `key` is routed to the left of a node with `node_key`.
+fn goes_left<K: Borrow<Q>, Q: Ord + ?Sized>(key: &Q, node_key: &Key<K>) -> bool {
+    match node_key {
+        Key::Finite(node_key) => key < node_key.borrow(),
+        Key::Infinite => true,
+    }
+}
+
+struct Node<K, V> {
+    key: Key<K>,
+    // Only the leaves holding user keys have values.
+    value: Option<V>,
+    // Both children are null for leaves and non-null for internal nodes. The low bits of each
+    // edge hold the `FLAG` and `TAG` marks.
+    left: AtomicPtr<Node<K, V>>,
+    right: AtomicPtr<Node<K, V>>,
+}
+
+impl<K, V> Node<K, V> {
+    fn leaf(key: Key<K>, value: Option<V>) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            key,
+            value,
+            left: AtomicPtr::new(core::ptr::null_mut()),
+            right: AtomicPtr::new(core::ptr::null_mut()),
+        }))
+    }
+
+    fn internal(key: Key<K>, left: *mut Self, right: *mut Self) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            key,
+            value: None,
+            left: AtomicPtr::new(left),
+            right: AtomicPtr::new(right),
+        }))
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.left.load(Ordering::Relaxed).is_null()
+    }
+
+    fn edge(&self, left: bool) -> &AtomicPtr<Node<K, V>> {
+        if left {
+            &self.left
+        } else {
+            &self.right
+        }
+    }
+}
+
+/// A node reached during a seek along with the slot of the hazard pointer protecting it.
+struct Protected<K, V> {
+    node: *mut Node<K, V>,
+    slot: usize,
+}
+
+impl<K, V> Clone for Protected<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for Protected<K, V> {}
+
+impl<K, V> Protected<K, V> {
+    /// The sentinel internal nodes are never removed so do not need protecting.
+    fn sentinel(node: *mut Node<K, V>) -> Self {
+        Self {
+            node,
+            slot: UNPROTECTED,
+        }
+    }
+}
+
+/// The hazard pointers and results of a seek through the tree.
+struct Seek<'domain, K, V, const DOMAIN_ID: usize> {
+    hazards: [Hazard<'domain, DOMAIN_ID>; SLOTS],
+    // The source and target of the last untagged edge above `parent`.
+    ancestor: Protected<K, V>,
+    successor: Protected<K, V>,
+    parent: Protected<K, V>,
+    leaf: Protected<K, V>,
+    // The value of the edge from `parent` to `leaf`.
+    leaf_edge: *mut Node<K, V>,
+    // The source of the last clean edge on the path, the edge itself and its value. Every edge
+    // below it on the path is marked and so can no longer change.
+    anchor: Protected<K, V>,
+    anchor_edge: *const AtomicPtr<Node<K, V>>,
+    anchor_value: *mut Node<K, V>,
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> Seek<'domain, K, V, DOMAIN_ID> {
+    fn new(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            hazards: core::array::from_fn(|_| Hazard::new(domain)),
+            ancestor: Protected::sentinel(core::ptr::null_mut()),
+            successor: Protected::sentinel(core::ptr::null_mut()),
+            parent: Protected::sentinel(core::ptr::null_mut()),
+            leaf: Protected::sentinel(core::ptr::null_mut()),
+            leaf_edge: core::ptr::null_mut(),
+            anchor: Protected::sentinel(core::ptr::null_mut()),
+            anchor_edge: core::ptr::null(),
+            anchor_value: core::ptr::null_mut(),
+        }
+    }
+
+    fn leaf(&self) -> &Node<K, V> {
+        // # Safety
+        //
+        // The leaf is protected by one of the seek's hazard pointers.
+        unsafe { &*self.leaf.node }
+    }
+
+    /// Takes the hazard pointer protecting the leaf, replacing it with a fresh one.
+    fn take_leaf_hazard(
+        &mut self,
+        domain: &'domain Domain<DOMAIN_ID>,
+    ) -> Hazard<'domain, DOMAIN_ID> {
+        core::mem::replace(&mut self.hazards[self.leaf.slot], Hazard::new(domain))
+    }
+
+    fn free_slot(&self) -> usize {
+        let roles = [
+            self.ancestor,
+            self.successor,
+            self.parent,
+            self.leaf,
+            self.anchor,
+        ];
+        (0..SLOTS)
+            .find(|slot| roles.iter().all(|role| role.slot != *slot))
+            .expect("There are more slots than roles")
+    }
+
+    /// Protects the node at the end of `edge`, which was read as `value`.
+    ///
+    /// Returns `None` if the path to the node may have changed, in which case the node may have
+    /// been retired before it was protected.
+    fn protect_child(
+        &self,
+        edge: &AtomicPtr<Node<K, V>>,
+        value: *mut Node<K, V>,
+    ) -> Option<Protected<K, V>> {
+        let slot = self.free_slot();
+        self.hazards[slot].protect(address(value));
+
+        crate::sync::fence(Ordering::SeqCst);
+
+        // If the last clean edge is unchanged then its source has not been removed, and as the
+        // marked edges below it are frozen the whole path is still in the tree.
+        //
+        // # Safety
+        //
+        // The anchor is protected by one of the seek's hazard pointers.
+        let anchor_edge = unsafe { &*self.anchor_edge };
+        if edge.load(Ordering::Acquire) != value
+            || anchor_edge.load(Ordering::Acquire) != self.anchor_value
+        {
+            return None;
+        }
+        Some(Protected {
+            node: address(value),
+            slot,
+        })
+    }
+}
+
+/// An ordered concurrent map implemented as a lock-free binary search tree.
+///
+/// Compared with [`SkipListMap`](super::SkipListMap) each lookup visits fewer, smaller nodes,
+/// which suits read-heavy workloads. Keys are cloned into the internal routing nodes of the tree.
+///
+/// Lookups return guards which keep the value alive even if the entry is concurrently removed.
+/// Removed nodes are retired to the domain.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::collections::TreeMap;
+///
+/// let map = TreeMap::new();
+/// map.insert(2, "two").unwrap();
+/// map.insert(1, "one").unwrap();
+/// map.insert(3, "three").unwrap();
+///
+/// assert_eq!(*map.get(&2).unwrap(), "two");
+///
+/// let removed = map.remove(&2).unwrap();
+/// assert_eq!(*removed, "two");
+/// assert!(map.get(&2).is_none());
+///
+/// let keys: Vec<_> = map.iter().map(|entry| *entry.key()).collect();
+/// assert_eq!(keys, [1, 3]);
+/// ```
+pub struct TreeMap<'domain, K, V, const DOMAIN_ID: usize> {
+    root: *mut Node<K, V>,
+    len: AtomicUsize,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+unsafe impl<'domain, K: Send + Sync, V: Send + Sync, const DOMAIN_ID: usize> Send
+    for TreeMap<'domain, K, V, DOMAIN_ID>
+{
+}
+unsafe impl<'domain, K: Send + Sync, V: Send + Sync, const DOMAIN_ID: usize> Sync
+    for TreeMap<'domain, K, V, DOMAIN_ID>
+{
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> core::fmt::Debug for TreeMap<'domain, K, V, DOMAIN_ID> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TreeMap")
+            .field("len", &self.len.load(Ordering::Relaxed))
+            .field("domain", &self.domain)
+            .finish()
+    }
+}
+
+#[cfg(not(loom))]
+impl<K: Ord + Clone, V> TreeMap<'static, K, V, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new, empty `TreeMap` associated with the shared (global) domain.
+    pub fn new() -> Self {
+        Self::new_with_domain(&crate::SHARED_DOMAIN)
+    }
+}
+
+#[cfg(not(loom))]
+impl<K: Ord + Clone, V> Default for TreeMap<'static, K, V, { crate::SHARED_DOMAIN_ID }> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'domain, K: Ord + Clone, V, const DOMAIN_ID: usize> TreeMap<'domain, K, V, DOMAIN_ID> {
+    /// Creates a new, empty `TreeMap` associated with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{collections::TreeMap, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let map = TreeMap::new_with_domain(&CUSTOM_DOMAIN);
+    /// map.insert("key", "value").unwrap();
+    /// ```
+    pub fn new_with_domain(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        // The sentinels are never removed, so every user leaf has an internal parent and
+        // grandparent.
+        let sentinel = Node::internal(
+            Key::Infinite,
+            Node::leaf(Key::Infinite, None),
+            Node::leaf(Key::Infinite, None),
+        );
+        let root = Node::internal(Key::Infinite, sentinel, Node::leaf(Key::Infinite, None));
+        Self {
+            root,
+            len: AtomicUsize::new(0),
+            domain,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    ///
+    /// Since other threads may be modifying the map concurrently this is only a snapshot.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a guard to the value associated with `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<LoadGuard<'domain, V, DOMAIN_ID>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_entry(key).map(Entry::into_value)
+    }
+
+    /// Returns the entry associated with `key`.
+    pub fn get_entry<Q>(&self, key: &Q) -> Option<Entry<'domain, K, V, DOMAIN_ID>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut seek = Seek::new(self.domain);
+        if self.find(key, &mut seek) {
+            Some(Entry {
+                node: seek.leaf.node,
+                hazard: seek.take_leaf_hazard(self.domain),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the map contains an entry for `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key, &mut Seek::new(self.domain))
+    }
+
+    /// Inserts a new entry into the map.
+    ///
+    /// If the map already contains an entry for `key`, the key and value are handed back in the
+    /// `Err`.
+    pub fn insert(&self, key: K, value: V) -> Result<(), (K, V)> {
+        let mut seek = Seek::new(self.domain);
+        let new_leaf = Node::leaf(Key::Finite(key), Some(value));
+        // # Safety
+        //
+        // We own the new leaf until it is published.
+        let Key::Finite(key) = &unsafe { &*new_leaf }.key else {
+            unreachable!("The new leaf has a finite key")
+        };
+        loop {
+            self.seek_key(key, &mut seek);
+            let leaf = seek.leaf();
+            let leaf_ptr = seek.leaf.node;
+            if !is_clean(seek.leaf_edge) {
+                // The leaf, or its sibling, is being removed; help before trying again.
+                self.cleanup(key, &seek);
+                continue;
+            }
+            if matches!(&leaf.key, Key::Finite(leaf_key) if leaf_key == key) {
+                // # Safety
+                //
+                // The new leaf was never published so we still have exclusive ownership.
+                let node = unsafe { Box::from_raw(new_leaf) };
+                let Node {
+                    key: Key::Finite(key),
+                    value: Some(value),
+                    ..
+                } = *node
+                else {
+                    unreachable!("The new leaf has a key and a value")
+                };
+                return Err((key, value));
+            }
+            let new_internal = if goes_left(key, &leaf.key) {
+                Node::internal(leaf.key.clone(), new_leaf, leaf_ptr)
+            } else {
+                Node::internal(Key::Finite(key.clone()), leaf_ptr, new_leaf)
+            };
+            // # Safety
+            //
+            // The parent is protected by one of the seek's hazard pointers.
+            let parent = unsafe { &*seek.parent.node };
+            let edge = parent.edge(goes_left(key, &parent.key));
+            match edge.compare_exchange(leaf_ptr, new_internal, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    self.len.fetch_add(1, Ordering::AcqRel);
+                    return Ok(());
+                }
+                Err(current) => {
+                    // # Safety
+                    //
+                    // The internal node was never published so we still have exclusive
+                    // ownership. Dropping it does not drop its children.
+                    drop(unsafe { Box::from_raw(new_internal) });
+                    if address(current) == leaf_ptr && !is_clean(current) {
+                        self.cleanup(key, &seek);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes the entry for `key` from the map, returning a guard to its value.
+    ///
+    /// The value is retired to the domain and will be reclaimed once the guard, and any other
+    /// guards referencing it, are dropped.
+    pub fn remove<Q>(&self, key: &Q) -> Option<LoadGuard<'domain, V, DOMAIN_ID>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut seek = Seek::new(self.domain);
+        // Flag the edge to the leaf, which logically removes the entry.
+        let (leaf_ptr, hazard) = loop {
+            if !self.find(key, &mut seek) {
+                return None;
+            }
+            let leaf_ptr = seek.leaf.node;
+            // # Safety
+            //
+            // The parent is protected by one of the seek's hazard pointers.
+            let parent = unsafe { &*seek.parent.node };
+            let edge = parent.edge(goes_left(key, &parent.key));
+            match edge.compare_exchange(
+                leaf_ptr,
+                (leaf_ptr as usize | FLAG) as *mut _,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    self.len.fetch_sub(1, Ordering::AcqRel);
+                    break (leaf_ptr, seek.take_leaf_hazard(self.domain));
+                }
+                Err(current) => {
+                    if address(current) == leaf_ptr && !is_clean(current) {
+                        self.cleanup(key, &seek);
+                    }
+                }
+            }
+        };
+        // Physically remove the leaf, unless another thread does so first.
+        while !self.cleanup(key, &seek) {
+            self.seek_key(key, &mut seek);
+            if seek.leaf.node != leaf_ptr {
+                break;
+            }
+        }
+        // # Safety
+        //
+        // The leaf is protected by the hazard and leaves with user keys always have values.
+        let value = unsafe { &*leaf_ptr }
+            .value
+            .as_ref()
+            .expect("Leaves with user keys have values");
+        Some(hazard.into_load_guard(value))
+    }
+
+    /// Returns an iterator over the entries of the map, in ascending order of their keys.
+    ///
+    /// Each entry is protected as the iterator reaches it, so entries inserted or removed
+    /// concurrently may or may not be observed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::collections::TreeMap;
+    ///
+    /// let map = TreeMap::new();
+    /// for i in [3, 1, 2] {
+    ///     map.insert(i, i * 10).unwrap();
+    /// }
+    ///
+    /// let values: Vec<_> = map.iter().map(|entry| *entry.value()).collect();
+    /// assert_eq!(values, [10, 20, 30]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, 'domain, K, V, DOMAIN_ID> {
+        Iter {
+            map: self,
+            seek: Seek::new(self.domain),
+            last: None,
+            finished: false,
+        }
+    }
+
+    /// Seeks `key`, returning `true` if the map contains it.
+    ///
+    /// If found the leaf is protected by the seek.
+    fn find<Q>(&self, key: &Q, seek: &mut Seek<'domain, K, V, DOMAIN_ID>) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.seek_key(key, seek);
+        // A flagged leaf has already been logically removed.
+        !is_flagged(seek.leaf_edge)
+            && matches!(&seek.leaf().key, Key::Finite(leaf_key) if leaf_key.borrow() == key)
+    }
+
+    fn seek_key<Q>(&self, key: &Q, seek: &mut Seek<'domain, K, V, DOMAIN_ID>)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.seek(seek, |node_key| goes_left(key, node_key));
+    }
+
+    /// Descends from the root to a leaf, turning left at each internal node for which `route`
+    /// returns `true`.
+    ///
+    /// Every node recorded in the seek is protected or is a sentinel.
+    fn seek(
+        &self,
+        seek: &mut Seek<'domain, K, V, DOMAIN_ID>,
+        mut route: impl FnMut(&Key<K>) -> bool,
+    ) {
+        // # Safety
+        //
+        // The root and its left child are sentinels which live as long as the map.
+        let root = unsafe { &*self.root };
+        let sentinel_ptr = root.left.load(Ordering::Acquire);
+        let sentinel = unsafe { &*sentinel_ptr };
+        'retry: loop {
+            // Keep callers which track their turns consistent, user keys always go left here.
+            route(&root.key);
+            route(&sentinel.key);
+            seek.ancestor = Protected::sentinel(self.root);
+            seek.successor = Protected::sentinel(sentinel_ptr);
+            seek.parent = Protected::sentinel(sentinel_ptr);
+            seek.anchor = Protected::sentinel(sentinel_ptr);
+            seek.anchor_edge = &sentinel.left;
+            seek.anchor_value = sentinel.left.load(Ordering::Acquire);
+            seek.leaf = Protected::sentinel(core::ptr::null_mut());
+            seek.leaf = match seek.protect_child(&sentinel.left, seek.anchor_value) {
+                Some(leaf) => leaf,
+                None => continue 'retry,
+            };
+            seek.leaf_edge = seek.anchor_value;
+            loop {
+                // # Safety
+                //
+                // The leaf is protected by one of the seek's hazard pointers.
+                let leaf = unsafe { &*seek.leaf.node };
+                if leaf.is_leaf() {
+                    return;
+                }
+                let edge = leaf.edge(route(&leaf.key));
+                let value = edge.load(Ordering::Acquire);
+                let current = match seek.protect_child(edge, value) {
+                    Some(current) => current,
+                    None => continue 'retry,
+                };
+                if !is_tagged(seek.leaf_edge) {
+                    seek.ancestor = seek.parent;
+                    seek.successor = seek.leaf;
+                }
+                if is_clean(value) {
+                    seek.anchor = seek.leaf;
+                    seek.anchor_edge = edge;
+                    seek.anchor_value = value;
+                }
+                seek.parent = seek.leaf;
+                seek.leaf = current;
+                seek.leaf_edge = value;
+            }
+        }
+    }
+
+    /// Completes the removal of a flagged leaf below the seek's parent by swinging the
+    /// ancestor's edge to the leaf's sibling.
+    ///
+    /// Returns `true` if this call removed the nodes, in which case it has retired them.
+    fn cleanup<Q>(&self, key: &Q, seek: &Seek<'domain, K, V, DOMAIN_ID>) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        // # Safety
+        //
+        // The ancestor and parent are protected by the seek's hazard pointers.
+        let ancestor = unsafe { &*seek.ancestor.node };
+        let parent = unsafe { &*seek.parent.node };
+        let successor_edge = ancestor.edge(goes_left(key, &ancestor.key));
+        let towards_key = goes_left(key, &parent.key);
+        let (mut removed_edge, mut sibling_edge) =
+            (parent.edge(towards_key), parent.edge(!towards_key));
+        if !is_flagged(removed_edge.load(Ordering::Acquire)) {
+            // The leaf being removed is on the other side of the parent.
+            core::mem::swap(&mut removed_edge, &mut sibling_edge);
+        }
+
+        // Freeze the edge to the sibling so that it cannot change while it is moved up.
+        let mut sibling = sibling_edge.load(Ordering::Acquire);
+        while !is_tagged(sibling) {
+            match sibling_edge.compare_exchange_weak(
+                sibling,
+                (sibling as usize | TAG) as *mut _,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(current) => sibling = current,
+            }
+        }
+        // Keep the flag, the sibling may itself be a leaf which is being removed.
+        let sibling = (sibling as usize & !TAG) as *mut Node<K, V>;
+
+        if successor_edge
+            .compare_exchange(
+                seek.successor.node,
+                sibling,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_err()
+        {
+            return false;
+        }
+
+        // Retire the internal nodes from the successor down to the parent, along with the leaf
+        // each of them was removing. Their edges are frozen so the path is unchanged.
+        let mut node_ptr = seek.successor.node;
+        loop {
+            // # Safety
+            //
+            // We have just unlinked these nodes so we are the only ones able to retire them.
+            let node = unsafe { &*node_ptr };
+            let (path, removed) = if node_ptr == seek.parent.node {
+                (core::ptr::null_mut(), removed_edge.load(Ordering::Acquire))
+            } else {
+                let towards_key = goes_left(key, &node.key);
+                (
+                    node.edge(towards_key).load(Ordering::Acquire),
+                    node.edge(!towards_key).load(Ordering::Acquire),
+                )
+            };
+            // # Safety
+            //
+            // Nodes are allocated via `Box::into_raw` and each is retired exactly once, by the
+            // thread which unlinked it.
+            unsafe {
+                self.domain.retire(address(removed));
+                self.domain.retire(node_ptr);
+            }
+            if path.is_null() {
+                return true;
+            }
+            node_ptr = address(path);
+        }
+    }
+
+    /// Finds the first leaf with a key satisfying `start`, protected by the seek.
+    fn seek_first(&self, seek: &mut Seek<'domain, K, V, DOMAIN_ID>, mut start: Bound<K>) -> bool {
+        loop {
+            // The key of the last node at which the seek turned left. Every leaf to the right of
+            // the one reached has a key of at least this.
+            let mut turn = None;
+            self.seek(seek, |node_key| {
+                let left = match &start {
+                    Bound::Included(key) | Bound::Excluded(key) => goes_left(key, node_key),
+                    Bound::Unbounded => true,
+                };
+                if left {
+                    turn = Some(node_key.clone());
+                }
+                left
+            });
+            if let Key::Finite(leaf_key) = &seek.leaf().key {
+                let satisfies = match &start {
+                    Bound::Included(key) => leaf_key >= key,
+                    Bound::Excluded(key) => leaf_key > key,
+                    Bound::Unbounded => true,
+                };
+                if satisfies && !is_flagged(seek.leaf_edge) {
+                    return true;
+                }
+            }
+            match turn {
+                Some(Key::Finite(key)) => start = Bound::Included(key),
+                _ => return false,
+            }
+        }
+    }
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> Drop for TreeMap<'domain, K, V, DOMAIN_ID> {
+    fn drop(&mut self) {
+        // Guards may still reference the leaves, so every node is retired rather than dropped.
+        let mut nodes = vec![self.root];
+        while let Some(node_ptr) = nodes.pop() {
+            // # Safety
+            //
+            // We have exclusive access to the map and the node is still in the tree.
+            let node = unsafe { &*node_ptr };
+            if !node.is_leaf() {
+                nodes.push(address(node.left.load(Ordering::Acquire)));
+                nodes.push(address(node.right.load(Ordering::Acquire)));
+            }
+            // # Safety
+            //
+            // Every node in the tree is retired exactly once here and no further operations on
+            // the map can take place.
+            unsafe { self.domain.retire(node_ptr) };
+        }
+    }
+}
+
+/// An entry in a [`TreeMap`], protected from reclamation while held.
+pub struct Entry<'domain, K, V, const DOMAIN_ID: usize> {
+    node: *mut Node<K, V>,
+    hazard: Hazard<'domain, DOMAIN_ID>,
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> Entry<'domain, K, V, DOMAIN_ID> {
+    fn node(&self) -> &Node<K, V> {
+        // # Safety
+        //
+        // The node is protected by the hazard pointer.
+        unsafe { &*self.node }
+    }
+
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        match &self.node().key {
+            Key::Finite(key) => key,
+            Key::Infinite => unreachable!("Entries are only created for user keys"),
+        }
+    }
+
+    /// Returns the value of the entry.
+    pub fn value(&self) -> &V {
+        self.node()
+            .value
+            .as_ref()
+            .expect("Leaves with user keys have values")
+    }
+
+    /// Converts the entry into a guard over its value.
+    pub fn into_value(self) -> LoadGuard<'domain, V, DOMAIN_ID> {
+        let value = self.value() as *const V;
+        self.hazard.into_load_guard(value)
+    }
+}
+
+/// An in-order iterator over the entries of a [`TreeMap`].
+///
+/// Created by [`TreeMap::iter`].
+pub struct Iter<'map, 'domain, K, V, const DOMAIN_ID: usize> {
+    map: &'map TreeMap<'domain, K, V, DOMAIN_ID>,
+    seek: Seek<'domain, K, V, DOMAIN_ID>,
+    last: Option<K>,
+    finished: bool,
+}
+
+impl<'map, 'domain, K: Ord + Clone, V, const DOMAIN_ID: usize> Iterator
+    for Iter<'map, 'domain, K, V, DOMAIN_ID>
+{
+    type Item = Entry<'domain, K, V, DOMAIN_ID>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let start = match self.last.take() {
+            Some(key) => Bound::Excluded(key),
+            None => Bound::Unbounded,
+        };
+        if !self.map.seek_first(&mut self.seek, start) {
+            self.finished = true;
+            return None;
+        }
+        let entry = Entry {
+            node: self.seek.leaf.node,
+            hazard: self.seek.take_leaf_hazard(self.map.domain),
+        };
+        self.last = Some(entry.key().clone());
+        Some(entry)
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+    use alloc::vec::Vec;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn insert_get_and_remove() {
+        let map = TreeMap::new_with_domain(&TEST_DOMAIN);
+
+        for key in [5, 1, 4, 2, 3] {
+            map.insert(key, key * 10)
+                .expect("Key should not be present");
+        }
+
+        assert_eq!(map.len(), 5, "Map should contain five entries");
+        assert_eq!(map.insert(3, 0), Err((3, 0)), "Duplicate keys are rejected");
+        assert_eq!(*map.get(&3).unwrap(), 30, "Should get the inserted value");
+        assert_eq!(*map.remove(&3).unwrap(), 30, "Should remove the value");
+        assert!(map.get(&3).is_none(), "Removed key should not be found");
+        assert!(map.remove(&3).is_none(), "Key can only be removed once");
+        assert!(map.contains_key(&4), "Other keys are unaffected");
+        assert_eq!(map.len(), 4, "Map should contain four entries");
+    }
+
+    #[test]
+    fn iter_visits_keys_in_order() {
+        let map = TreeMap::new_with_domain(&TEST_DOMAIN);
+        for key in [7, 3, 11, 1, 5, 9, 13, 0, 2, 4, 6, 8, 10, 12, 14] {
+            map.insert(key, ()).unwrap();
+        }
+        for key in [0, 5, 6, 14] {
+            map.remove(&key).unwrap();
+        }
+
+        let keys: Vec<_> = map.iter().map(|entry| *entry.key()).collect();
+
+        assert_eq!(
+            keys,
+            vec![1, 2, 3, 4, 7, 8, 9, 10, 11, 12, 13],
+            "Remaining keys in order"
+        );
+    }
+
+    #[test]
+    fn iter_continues_past_removed_entry() {
+        let map = TreeMap::new_with_domain(&TEST_DOMAIN);
+        for key in 0..5 {
+            map.insert(key, ()).unwrap();
+        }
+        let mut iter = map.iter();
+
+        assert_eq!(*iter.next().unwrap().key(), 0);
+        assert_eq!(*iter.next().unwrap().key(), 1);
+        map.remove(&1);
+        map.remove(&2);
+        let rest: Vec<_> = iter.map(|entry| *entry.key()).collect();
+
+        assert_eq!(rest, vec![3, 4], "Removed entries are skipped");
+    }
+
+    #[test]
+    fn values_are_reclaimed() {
+        let drop_counter = DropCounter::new();
+        let map = TreeMap::new_with_domain(&TEST_DOMAIN);
+        for key in 0..10 {
+            map.insert(key, drop_counter.track(key)).unwrap();
+        }
+
+        let guard = map.get(&0).unwrap();
+        drop(map.remove(&0).unwrap());
+        for key in 1..5 {
+            drop(map.remove(&key));
+        }
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(
+            drop_counter.count(),
+            4,
+            "Removed values should be reclaimed except the one still guarded"
+        );
+        assert_eq!(**guard, 0, "Guarded value is still accessible");
+        drop(guard);
+        drop(map);
+        TEST_DOMAIN.reclaim();
+        drop_counter.assert_drops(10);
+    }
+
+    #[test]
+    fn concurrent_inserts_and_removes() {
+        const KEYS: usize = 200;
+        let map = TreeMap::new_with_domain(&TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let map = &map;
+                scope.spawn(move || {
+                    for key in (thread..KEYS).step_by(4) {
+                        map.insert(key, key).unwrap();
+                    }
+                    for key in (thread..KEYS).step_by(8) {
+                        assert_eq!(*map.remove(&key).unwrap(), key);
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for _ in 0..10 {
+                    let keys: Vec<_> = map.iter().map(|entry| *entry.key()).collect();
+                    assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+                }
+            });
+        });
+
+        let keys: Vec<_> = map.iter().map(|entry| *entry.key()).collect();
+        let expected: Vec<_> = (0..KEYS).filter(|key| key % 8 >= 4).collect();
+        assert_eq!(keys, expected, "Only keys which were not removed remain");
+        assert_eq!(
+            map.len(),
+            expected.len(),
+            "Length matches the remaining keys"
+        );
+    }
+}
@@ -0,0 +1,598 @@
+//! A concurrent cache whose entries expire after a time to live.
+//!
+//! The cache is a fixed size hash table. Every bucket of the table is an immutable snapshot of the
+//! entries hashed to it, as in [`AtomLru`](super::AtomLru). Readers protect the snapshot and then
+//! the entry they are looking for, so lookups never block. Writers serialise on a per-bucket lock
+//! and replace snapshots wholesale, retiring the old snapshot and any removed or expired entries
+//! to the domain.
+
+use super::Hazard;
+use crate::domain::Domain;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use crate::LoadGuard;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+use core::time::Duration;
+use std::collections::hash_map::RandomState;
+use std::sync::{Mutex, MutexGuard, TryLockError};
+use std::time::Instant;
+
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    // `None` if the time to live is too long to be represented.
+    expires_at: Option<Instant>,
+}
+
+impl<K, V> Entry<K, V> {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// An immutable snapshot of the entries in a bucket.
+struct Snapshot<K, V> {
+    entries: Vec<*mut Entry<K, V>>,
+}
+
+impl<K, V> Snapshot<K, V> {
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        // # Safety
+        //
+        // Entries are only retired after being removed from every snapshot, and the caller has
+        // either protected this snapshot or holds the bucket's lock.
+        self.entries
+            .iter()
+            .position(|entry| unsafe { &**entry }.key.borrow() == key)
+    }
+}
+
+struct Bucket<K, V> {
+    // Null while the bucket has no entries.
+    snapshot: AtomicPtr<Snapshot<K, V>>,
+    // Serialises writers, readers never take the lock.
+    writer: Mutex<()>,
+}
+
+impl<K, V> Bucket<K, V> {
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        // The bucket is only modified by replacing its snapshot with a complete one, so a panic
+        // while holding the lock cannot leave it inconsistent.
+        self.writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn try_lock(&self) -> Option<MutexGuard<'_, ()>> {
+        match self.writer.try_lock() {
+            Ok(writer) => Some(writer),
+            Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    /// Returns the current snapshot.
+    ///
+    /// Must only be called while holding the bucket's lock.
+    fn snapshot(&self) -> Option<&Snapshot<K, V>> {
+        // # Safety
+        //
+        // Snapshots are only retired by writers after they have been replaced, and we hold the
+        // bucket's lock.
+        unsafe { self.snapshot.load(Ordering::Acquire).as_ref() }
+    }
+
+    /// Returns a copy of the entries in the current snapshot.
+    ///
+    /// Must only be called while holding the bucket's lock.
+    fn entries(&self) -> Vec<*mut Entry<K, V>> {
+        self.snapshot()
+            .map_or_else(Vec::new, |snapshot| snapshot.entries.clone())
+    }
+}
+
+/// A concurrent cache whose entries expire once their time to live has elapsed.
+///
+/// Lookups never block and return a [`LoadGuard`] to the cached value, which remains valid even
+/// if the entry expires, or is replaced or removed, while the guard is held. Insertions and
+/// removals take a lock on the entry's bucket.
+///
+/// Expired entries are never returned. They are removed and retired to the domain lazily, when a
+/// lookup finds them and can take their bucket's lock without waiting, or when an entry is
+/// inserted into their bucket. Entries which are never looked up again are removed by
+/// [`AtomTtlCache::sweep`], which can be called periodically.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::collections::AtomTtlCache;
+/// use core::time::Duration;
+///
+/// let sessions = AtomTtlCache::new(64);
+/// sessions.insert("alice", 1, Duration::from_secs(60));
+/// sessions.insert("bob", 2, Duration::ZERO);
+///
+/// assert_eq!(*sessions.get("alice").unwrap(), 1);
+/// assert!(sessions.get("bob").is_none(), "Bob's session has expired");
+/// ```
+pub struct AtomTtlCache<'domain, K, V, const DOMAIN_ID: usize> {
+    buckets: Box<[Bucket<K, V>]>,
+    // The number of entries, including those which have expired but have not yet been removed.
+    len: AtomicUsize,
+    hasher: RandomState,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// Values are handed to other threads through guards, and are dropped by whichever thread reclaims
+// them.
+unsafe impl<'domain, K: Send, V: Send, const DOMAIN_ID: usize> Send
+    for AtomTtlCache<'domain, K, V, DOMAIN_ID>
+{
+}
+unsafe impl<'domain, K: Send + Sync, V: Send + Sync, const DOMAIN_ID: usize> Sync
+    for AtomTtlCache<'domain, K, V, DOMAIN_ID>
+{
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> core::fmt::Debug
+    for AtomTtlCache<'domain, K, V, DOMAIN_ID>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AtomTtlCache")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> AtomTtlCache<'domain, K, V, DOMAIN_ID> {
+    /// Returns the number of entries in the cache, including expired entries which have not yet
+    /// been removed.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the cache contains no entries, expired or not.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(not(loom))]
+impl<K: Hash + Eq, V> AtomTtlCache<'static, K, V, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `AtomTtlCache` sized for `capacity` entries, associated with the shared
+    /// (global) domain.
+    ///
+    /// The capacity sizes the hash table and is not a limit; more entries can be inserted, at the
+    /// cost of slower operations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        Self::new_with_domain(capacity, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, K: Hash + Eq, V, const DOMAIN_ID: usize> AtomTtlCache<'domain, K, V, DOMAIN_ID> {
+    /// Creates a new `AtomTtlCache` sized for `capacity` entries, and associates it with the given
+    /// domain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{collections::AtomTtlCache, domain::{Domain, ReclaimStrategy}};
+    /// use core::time::Duration;
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let cache = AtomTtlCache::new_with_domain(100, &CUSTOM_DOMAIN);
+    /// cache.insert(1, "Hello", Duration::from_secs(1));
+    /// assert_eq!(*cache.get(&1).unwrap(), "Hello");
+    /// ```
+    pub fn new_with_domain(capacity: usize, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        assert!(capacity > 0, "AtomTtlCache capacity must be non-zero");
+        let buckets = (0..capacity.next_power_of_two())
+            .map(|_| Bucket {
+                snapshot: AtomicPtr::new(core::ptr::null_mut()),
+                writer: Mutex::new(()),
+            })
+            .collect();
+        Self {
+            buckets,
+            len: AtomicUsize::new(0),
+            hasher: RandomState::new(),
+            domain,
+        }
+    }
+
+    /// Returns a guard to the value associated with `key`, unless it has expired.
+    ///
+    /// If the entry has expired, it is removed, provided its bucket is not locked by a writer.
+    pub fn get<Q>(&self, key: &Q) -> Option<LoadGuard<'domain, V, DOMAIN_ID>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let bucket = self.locate(key);
+        let snapshot_hazard = Hazard::new(self.domain);
+        let entry_hazard = Hazard::new(self.domain);
+        let entry = loop {
+            let snapshot_ptr = snapshot_hazard.protect_ptr(&bucket.snapshot);
+            // # Safety
+            //
+            // The snapshot is protected by the snapshot hazard.
+            let snapshot = unsafe { snapshot_ptr.as_ref() }?;
+            let entry_ptr = snapshot.entries[snapshot.find(key)?];
+            entry_hazard.protect(entry_ptr);
+
+            crate::sync::fence(Ordering::SeqCst);
+
+            // Entries are only retired once they have been removed from the current snapshot. The
+            // protected snapshot cannot be reused, so if it is still current the entry is safe.
+            if bucket.snapshot.load(Ordering::Acquire) == snapshot_ptr {
+                // # Safety
+                //
+                // The entry is protected by the entry hazard.
+                break unsafe { &*entry_ptr };
+            }
+        };
+        let now = Instant::now();
+        if entry.is_expired(now) {
+            drop((snapshot_hazard, entry_hazard));
+            if let Some(_writer) = bucket.try_lock() {
+                self.remove_expired(bucket, now);
+            }
+            return None;
+        }
+        Some(entry_hazard.into_load_guard(&entry.value))
+    }
+
+    /// Returns `true` if the cache contains an entry for `key` which has not expired.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Inserts an entry into the cache, which expires once `ttl` has elapsed.
+    ///
+    /// If the cache already contains an unexpired entry for `key`, it is replaced and a guard to
+    /// the previous value is returned. Expired entries in the same bucket are removed.
+    pub fn insert(
+        &self,
+        key: K,
+        value: V,
+        ttl: Duration,
+    ) -> Option<LoadGuard<'domain, V, DOMAIN_ID>> {
+        let bucket = self.locate(&key);
+        let _writer = bucket.lock();
+        let now = Instant::now();
+        self.remove_expired(bucket, now);
+        let entry_ptr = Box::into_raw(Box::new(Entry {
+            key,
+            value,
+            expires_at: now.checked_add(ttl),
+        }));
+        let mut entries = bucket.entries();
+        // # Safety
+        //
+        // We have just created the entry and not yet published it.
+        let key = &unsafe { &*entry_ptr }.key;
+        let previous = match bucket.snapshot().and_then(|snapshot| snapshot.find(key)) {
+            Some(index) => Some(core::mem::replace(&mut entries[index], entry_ptr)),
+            None => {
+                entries.push(entry_ptr);
+                None
+            }
+        };
+        self.replace_snapshot(bucket, entries);
+        match previous {
+            // # Safety
+            //
+            // The previous entry is no longer in the snapshot and we hold the bucket's lock.
+            Some(previous) => Some(unsafe { self.retire_entry(previous) }),
+            None => {
+                self.len.fetch_add(1, Ordering::AcqRel);
+                None
+            }
+        }
+    }
+
+    /// Removes the entry for `key` from the cache, returning a guard to its value unless it had
+    /// expired.
+    pub fn remove<Q>(&self, key: &Q) -> Option<LoadGuard<'domain, V, DOMAIN_ID>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let bucket = self.locate(key);
+        let _writer = bucket.lock();
+        self.remove_expired(bucket, Instant::now());
+        let index = bucket.snapshot()?.find(key)?;
+        let mut entries = bucket.entries();
+        let removed = entries.swap_remove(index);
+        self.replace_snapshot(bucket, entries);
+        self.len.fetch_sub(1, Ordering::AcqRel);
+        // # Safety
+        //
+        // The entry is no longer in the snapshot and we hold the bucket's lock.
+        Some(unsafe { self.retire_entry(removed) })
+    }
+
+    fn locate<Q: Hash + ?Sized>(&self, key: &Q) -> &Bucket<K, V> {
+        let hash = self.hasher.hash_one(key) as usize;
+        &self.buckets[hash % self.buckets.len()]
+    }
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> AtomTtlCache<'domain, K, V, DOMAIN_ID> {
+    /// Removes every expired entry from the cache, returning the number removed.
+    ///
+    /// Entries which are looked up after they expire are removed lazily, so this only needs to
+    /// be called to bound the memory held by entries which are never looked up again, for
+    /// example from a periodic maintenance task.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::collections::AtomTtlCache;
+    /// use core::time::Duration;
+    ///
+    /// let cache = AtomTtlCache::new(64);
+    /// cache.insert(1, "fresh", Duration::from_secs(60));
+    /// cache.insert(2, "stale", Duration::ZERO);
+    ///
+    /// assert_eq!(cache.sweep(), 1);
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    pub fn sweep(&self) -> usize {
+        let now = Instant::now();
+        self.buckets
+            .iter()
+            .filter(|bucket| !bucket.snapshot.load(Ordering::Acquire).is_null())
+            .map(|bucket| {
+                let _writer = bucket.lock();
+                self.remove_expired(bucket, now)
+            })
+            .sum()
+    }
+
+    /// Removes the entries of `bucket` which had expired by `now`, returning the number removed.
+    ///
+    /// Must only be called while holding the bucket's lock.
+    fn remove_expired(&self, bucket: &Bucket<K, V>, now: Instant) -> usize {
+        let mut entries = bucket.entries();
+        let mut expired = Vec::new();
+        entries.retain(|entry| {
+            // # Safety
+            //
+            // We hold the bucket's lock so none of its entries have been retired.
+            let is_expired = unsafe { &**entry }.is_expired(now);
+            if is_expired {
+                expired.push(*entry);
+            }
+            !is_expired
+        });
+        if expired.is_empty() {
+            return 0;
+        }
+        self.replace_snapshot(bucket, entries);
+        self.len.fetch_sub(expired.len(), Ordering::AcqRel);
+        for entry_ptr in expired.iter() {
+            // # Safety
+            //
+            // The entry is no longer in the snapshot and we hold the bucket's lock, so it is
+            // retired exactly once.
+            unsafe { self.domain.retire(*entry_ptr) };
+        }
+        expired.len()
+    }
+
+    /// Installs a new snapshot for a bucket, retiring the old one.
+    ///
+    /// Must only be called while holding the bucket's lock.
+    fn replace_snapshot(&self, bucket: &Bucket<K, V>, entries: Vec<*mut Entry<K, V>>) {
+        let new_ptr = if entries.is_empty() {
+            core::ptr::null_mut()
+        } else {
+            Box::into_raw(Box::new(Snapshot { entries }))
+        };
+        let old_ptr = bucket.snapshot.swap(new_ptr, Ordering::AcqRel);
+        if !old_ptr.is_null() {
+            // # Safety
+            //
+            // The old snapshot is no longer reachable and we hold the bucket's lock, so it is
+            // retired exactly once.
+            unsafe { self.domain.retire(old_ptr) };
+        }
+    }
+
+    /// Retires an entry, returning a guard to its value.
+    ///
+    /// # Safety
+    ///
+    /// The entry must have been removed from its bucket's snapshot by the caller, who must still
+    /// hold the bucket's lock.
+    unsafe fn retire_entry(&self, entry_ptr: *mut Entry<K, V>) -> LoadGuard<'domain, V, DOMAIN_ID> {
+        let hazard = Hazard::new(self.domain);
+        hazard.protect(entry_ptr);
+        // # Safety
+        //
+        // The entry has not been retired yet so protecting it now is sufficient. According to
+        // the safety contract of this function, it is no longer reachable and is retired exactly
+        // once.
+        unsafe { self.domain.retire(entry_ptr) };
+        // # Safety
+        //
+        // The entry is protected by the hazard.
+        hazard.into_load_guard(&unsafe { &*entry_ptr }.value)
+    }
+}
+
+impl<'domain, K, V, const DOMAIN_ID: usize> Drop for AtomTtlCache<'domain, K, V, DOMAIN_ID> {
+    fn drop(&mut self) {
+        // Guards may still reference the entries, so every entry is retired rather than dropped.
+        for bucket in self.buckets.iter() {
+            let snapshot_ptr = bucket.snapshot.load(Ordering::Acquire);
+            if snapshot_ptr.is_null() {
+                continue;
+            }
+            // # Safety
+            //
+            // We have exclusive access to the cache so the snapshot is still current.
+            for entry_ptr in unsafe { &*snapshot_ptr }.entries.iter() {
+                // # Safety
+                //
+                // Every entry is in exactly one current snapshot, and no further operations on
+                // the cache can take place.
+                unsafe { self.domain.retire(*entry_ptr) };
+            }
+            // # Safety
+            //
+            // As above, the snapshot is retired exactly once.
+            unsafe { self.domain.retire(snapshot_ptr) };
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    #[test]
+    fn insert_get_and_remove() {
+        let domain: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+        let cache = AtomTtlCache::new_with_domain(8, &domain);
+
+        for key in 0..5 {
+            assert!(cache.insert(key, key * 10, HOUR).is_none(), "Key is new");
+        }
+
+        assert_eq!(cache.len(), 5);
+        assert_eq!(*cache.get(&3).unwrap(), 30);
+        assert_eq!(
+            *cache.insert(3, 31, HOUR).unwrap(),
+            30,
+            "Replacing an entry returns the previous value"
+        );
+        assert_eq!(*cache.remove(&3).unwrap(), 31);
+        assert!(cache.get(&3).is_none(), "Removed key should not be found");
+        assert!(cache.remove(&3).is_none(), "Key can only be removed once");
+        assert!(cache.contains_key(&4), "Other keys are unaffected");
+        assert_eq!(cache.len(), 4);
+    }
+
+    #[test]
+    fn expired_entries_are_removed_when_looked_up() {
+        let domain: Domain<2> = Domain::new(ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let cache = AtomTtlCache::new_with_domain(8, &domain);
+        cache.insert(1, drop_counter.track(1), Duration::ZERO);
+        cache.insert(2, drop_counter.track(2), HOUR);
+
+        let expired = cache.get(&1);
+        domain.reclaim();
+
+        assert!(expired.is_none(), "Expired entries are not returned");
+        assert_eq!(cache.len(), 1, "The expired entry was removed");
+        drop_counter.assert_drops(1);
+        assert!(
+            cache.insert(1, drop_counter.track(3), HOUR).is_none(),
+            "Expired entries are not replaced"
+        );
+    }
+
+    #[test]
+    fn sweep_removes_every_expired_entry() {
+        let domain: Domain<3> = Domain::new(ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let cache = AtomTtlCache::new_with_domain(4, &domain);
+        for key in 0..10 {
+            let ttl = if key % 2 == 0 { Duration::ZERO } else { HOUR };
+            cache.insert(key, drop_counter.track(key), ttl);
+        }
+
+        cache.sweep();
+        domain.reclaim();
+
+        assert_eq!(cache.len(), 5, "Only unexpired entries remain");
+        drop_counter.assert_drops(5);
+        assert_eq!(cache.sweep(), 0, "Entries are only swept once");
+    }
+
+    #[test]
+    #[cfg_attr(
+        miri,
+        ignore = "expiry relies on the wall clock, which is too slow under Miri"
+    )]
+    fn guards_outlive_expiry() {
+        let domain: Domain<4> = Domain::new(ReclaimStrategy::Eager);
+        let drop_counter = DropCounter::new();
+        let cache = AtomTtlCache::new_with_domain(1, &domain);
+        cache.insert("a", drop_counter.track(1), Duration::from_millis(200));
+        let guard = cache.get("a").unwrap();
+
+        std::thread::sleep(Duration::from_millis(250));
+        cache.sweep();
+        domain.reclaim();
+
+        assert!(cache.get("a").is_none(), "Entry should have expired");
+        assert_eq!(**guard, 1, "Guard should still reference the value");
+        drop_counter.assert_drops(0);
+        drop(guard);
+        domain.reclaim();
+        drop_counter.assert_drops(1);
+    }
+
+    #[test]
+    fn concurrent_reads_writes_and_sweeps() {
+        let domain: Domain<5> = Domain::new(ReclaimStrategy::Eager);
+        let cache = AtomTtlCache::new_with_domain(64, &domain);
+
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for key in 0..2000 {
+                        let ttl = if key % 3 == 0 { Duration::ZERO } else { HOUR };
+                        cache.insert(key % 128, key, ttl);
+                    }
+                });
+            }
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for key in 0..2000 {
+                        if let Some(value) = cache.get(&(key % 128)) {
+                            assert_eq!(*value % 128, key % 128, "Value belongs to another key");
+                            assert_ne!(*value % 3, 0, "Expired values are never returned");
+                        }
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for _ in 0..100 {
+                    cache.sweep();
+                }
+            });
+        });
+
+        assert!(cache.len() <= 128);
+    }
+}
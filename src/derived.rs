@@ -0,0 +1,234 @@
+//! Derived
+//!
+//! A cell holding a value derived from the value of another box, which is only recomputed when
+//! the source value changes.
+
+use crate::collections::Hazard;
+use crate::domain::Domain;
+use crate::sync::{AtomicPtr, Ordering};
+use crate::{AtomBoxIn, LoadGuard};
+use alloc::boxed::Box;
+
+struct Derived<'domain, S, D, const DOMAIN_ID: usize> {
+    // The source value the value was derived from. Keeping it protected while the value is cached
+    // stops its address being reused by a later source value, which would otherwise be mistaken
+    // for the one the value was derived from.
+    source: LoadGuard<'domain, S, DOMAIN_ID>,
+    value: D,
+}
+
+/// A cell holding the result of applying a function to the value of a source
+/// [`AtomBox`](crate::AtomBox).
+///
+/// The derived value is computed lazily. The first load after the source value changes computes
+/// the new derived value and publishes it for every other reader, which is then served the cached
+/// value until the source changes again. If several readers race to recompute the value, only one
+/// of them publishes it and the others use the published value.
+///
+/// The cached value keeps the source value it was derived from alive until it is replaced.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBox, DerivedAtomBox};
+///
+/// let config = AtomBox::new(String::from("port=80"));
+/// let port = DerivedAtomBox::new(&config, |config: &String| {
+///     config.trim_start_matches("port=").parse::<u16>().unwrap()
+/// });
+///
+/// assert_eq!(*port.load(), 80);
+///
+/// config.store(String::from("port=8080"));
+/// assert_eq!(*port.load(), 8080);
+/// ```
+pub struct DerivedAtomBox<'source, 'domain, S, D, F, const DOMAIN_ID: usize> {
+    source: &'source AtomBoxIn<'domain, S, DOMAIN_ID>,
+    derive: F,
+    // Null until the value is first loaded.
+    derived: AtomicPtr<Derived<'domain, S, D, DOMAIN_ID>>,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// Derived values are computed on, handed to, and dropped by any thread, and the source values
+// they keep alive are released by whichever thread reclaims them.
+unsafe impl<'source, 'domain, S, D, F, const DOMAIN_ID: usize> Send
+    for DerivedAtomBox<'source, 'domain, S, D, F, DOMAIN_ID>
+where
+    S: Send + Sync,
+    D: Send,
+    F: Send,
+{
+}
+unsafe impl<'source, 'domain, S, D, F, const DOMAIN_ID: usize> Sync
+    for DerivedAtomBox<'source, 'domain, S, D, F, DOMAIN_ID>
+where
+    S: Send + Sync,
+    D: Send + Sync,
+    F: Sync,
+{
+}
+
+impl<'source, 'domain, S, D, F, const DOMAIN_ID: usize> core::fmt::Debug
+    for DerivedAtomBox<'source, 'domain, S, D, F, DOMAIN_ID>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DerivedAtomBox").finish_non_exhaustive()
+    }
+}
+
+impl<'source, 'domain, S, D, F, const DOMAIN_ID: usize>
+    DerivedAtomBox<'source, 'domain, S, D, F, DOMAIN_ID>
+where
+    F: Fn(&S) -> D,
+{
+    /// Creates a new `DerivedAtomBox` holding `derive` applied to the value of `source`.
+    ///
+    /// The derived value is associated with the same domain as `source`. It is not computed until
+    /// it is first loaded.
+    pub fn new(source: &'source AtomBoxIn<'domain, S, DOMAIN_ID>, derive: F) -> Self {
+        Self {
+            source,
+            derive,
+            derived: AtomicPtr::new(core::ptr::null_mut()),
+            domain: source.domain,
+        }
+    }
+
+    /// Loads the value derived from the current value of the source, computing it if the source
+    /// has changed since it was last computed.
+    pub fn load(&self) -> LoadGuard<'domain, D, DOMAIN_ID> {
+        let hazard = Hazard::new(self.domain);
+        loop {
+            let current_ptr = hazard.protect_ptr(&self.derived);
+            let source = self.source.load();
+            // # Safety
+            //
+            // The cached value is protected by the hazard.
+            if let Some(current) = unsafe { current_ptr.as_ref() } {
+                if core::ptr::eq(current.source.ptr, source.ptr) {
+                    return hazard.into_load_guard(&current.value);
+                }
+            }
+            let value = (self.derive)(&source);
+            let new_ptr = Box::into_raw(Box::new(Derived { source, value }));
+            // The new value must be protected before it is published, as it may be replaced and
+            // retired as soon as it is. The current value is no longer read.
+            hazard.protect(new_ptr);
+            match self.derived.compare_exchange(
+                current_ptr,
+                new_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    if !current_ptr.is_null() {
+                        // # Safety
+                        //
+                        // The old value was created via `Box::into_raw` and is no longer
+                        // reachable from the cell. Only the thread which replaced it can retire
+                        // it.
+                        unsafe { self.domain.retire(current_ptr) };
+                    }
+                    // # Safety
+                    //
+                    // The new value is protected by the hazard.
+                    return hazard.into_load_guard(&unsafe { &*new_ptr }.value);
+                }
+                // # Safety
+                //
+                // The new value was never published, so we still own it.
+                Err(_) => drop(unsafe { Box::from_raw(new_ptr) }),
+            }
+        }
+    }
+}
+
+impl<'source, 'domain, S, D, F, const DOMAIN_ID: usize> Drop
+    for DerivedAtomBox<'source, 'domain, S, D, F, DOMAIN_ID>
+{
+    fn drop(&mut self) {
+        let derived = self.derived.load(Ordering::Relaxed);
+        if !derived.is_null() {
+            // # Safety
+            //
+            // We have exclusive access to the cell. Guards may still reference the derived value,
+            // so it is retired rather than dropped.
+            unsafe { self.domain.retire(derived) };
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::sync::AtomicUsize;
+    use alloc::string::String;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn derived_values_are_only_recomputed_when_the_source_changes() {
+        let computed = AtomicUsize::new(0);
+        let source = AtomBoxIn::new_with_domain(1, &TEST_DOMAIN);
+        let doubled = DerivedAtomBox::new(&source, |value: &i32| {
+            computed.fetch_add(1, Ordering::Relaxed);
+            value * 2
+        });
+
+        let first = *doubled.load();
+        let cached = *doubled.load();
+        source.store(5);
+        let recomputed = *doubled.load();
+        let _ = doubled.load();
+
+        assert_eq!((first, cached, recomputed), (2, 2, 10));
+        assert_eq!(
+            computed.load(Ordering::Relaxed),
+            2,
+            "Computed once per source"
+        );
+    }
+
+    #[test]
+    fn guards_outlive_recomputation() {
+        let source = AtomBoxIn::new_with_domain(String::from("Hello"), &TEST_DOMAIN);
+        let upper = DerivedAtomBox::new(&source, |value: &String| value.to_uppercase());
+        let guard = upper.load();
+
+        source.store(String::from("World"));
+        let recomputed = upper.load();
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(*guard, "HELLO");
+        assert_eq!(*recomputed, "WORLD");
+    }
+
+    #[test]
+    fn concurrent_readers_see_values_derived_from_recent_sources() {
+        let source = AtomBoxIn::new_with_domain(0_usize, &TEST_DOMAIN);
+        let derived = DerivedAtomBox::new(&source, |value: &usize| value + 1);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for value in 1..=1000 {
+                    source.store(value);
+                }
+            });
+            for _ in 0..3 {
+                scope.spawn(|| {
+                    let mut last = 0;
+                    for _ in 0..1000 {
+                        let value = *derived.load();
+                        assert!(value >= last, "Derived values never go backwards");
+                        last = value;
+                    }
+                });
+            }
+        });
+
+        assert_eq!(*derived.load(), 1001);
+    }
+}
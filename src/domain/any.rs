@@ -0,0 +1,200 @@
+use super::{needs_reclaim, reclaim_box, Domain, HazardPointer, RetiredType};
+use crate::protection::Protection;
+use crate::sync::AtomicPtr;
+
+/// The operations of a [`Domain`] which do not depend on its ID.
+trait ErasedDomain: Sync {
+    fn acquire_haz_ptr(&self) -> HazardPointer<'_>;
+
+    fn release_hazard_ptr<'a>(&'a self, haz_ptr: HazardPointer<'a>);
+
+    /// # Safety
+    ///
+    /// See [`Domain::retire_erased`].
+    unsafe fn retire_erased(
+        &self,
+        value: *mut usize,
+        reclaim: unsafe fn(*mut usize),
+        retired_type: RetiredType,
+    );
+
+    fn name(&self) -> Option<&'static str>;
+}
+
+impl<const DOMAIN_ID: usize> ErasedDomain for Domain<DOMAIN_ID> {
+    fn acquire_haz_ptr(&self) -> HazardPointer<'_> {
+        Domain::acquire_haz_ptr(self)
+    }
+
+    fn release_hazard_ptr<'a>(&'a self, haz_ptr: HazardPointer<'a>) {
+        Domain::release_hazard_ptr(self, haz_ptr);
+    }
+
+    unsafe fn retire_erased(
+        &self,
+        value: *mut usize,
+        reclaim: unsafe fn(*mut usize),
+        retired_type: RetiredType,
+    ) {
+        unsafe { Domain::retire_erased(self, value, reclaim, retired_type) };
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+}
+
+/// A reference to a [`Domain`] with its ID erased.
+///
+/// Boxes associated with domains of different IDs have different types, so cannot be stored
+/// together. Boxes associated with an `AnyDomain` all have the same type, see
+/// [`AnyAtomBox`](crate::AnyAtomBox), whichever domain they are associated with. This gives up the
+/// compile time check that values are not moved between domains, which is instead checked at
+/// runtime.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AnyAtomBox, AtomBoxIn, domain::{AnyDomain, Domain, ReclaimStrategy}};
+///
+/// static CACHE_DOMAIN: Domain<42> = Domain::new(ReclaimStrategy::Eager);
+/// static SESSION_DOMAIN: Domain<43> = Domain::new(ReclaimStrategy::default());
+///
+/// static CACHE: AnyDomain<'static> = AnyDomain::new(&CACHE_DOMAIN);
+/// static SESSIONS: AnyDomain<'static> = AnyDomain::new(&SESSION_DOMAIN);
+///
+/// let boxes: Vec<AnyAtomBox<'_, &str>> = vec![
+///     AtomBoxIn::new_with_protection("cached", &CACHE),
+///     AtomBoxIn::new_with_protection("session", &SESSIONS),
+/// ];
+/// assert_eq!(*boxes[0].load(), "cached");
+/// assert_eq!(*boxes[1].load(), "session");
+/// ```
+#[derive(Clone, Copy)]
+pub struct AnyDomain<'domain> {
+    domain: &'domain dyn ErasedDomain,
+}
+
+impl<'domain> AnyDomain<'domain> {
+    /// Erases the ID of `domain`.
+    pub const fn new<const DOMAIN_ID: usize>(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self { domain }
+    }
+}
+
+impl<'domain, const DOMAIN_ID: usize> From<&'domain Domain<DOMAIN_ID>> for AnyDomain<'domain> {
+    fn from(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self::new(domain)
+    }
+}
+
+impl core::fmt::Debug for AnyDomain<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AnyDomain")
+            .field("name", &self.domain.name())
+            .finish_non_exhaustive()
+    }
+}
+
+// # Safety
+//
+// Every operation is delegated to the domain, which upholds the contract of `Protection`.
+unsafe impl<'domain> Protection for AnyDomain<'domain> {
+    type Guard<'a>
+        = HazardPointer<'a>
+    where
+        Self: 'a;
+
+    fn acquire(&self) -> Self::Guard<'_> {
+        self.domain.acquire_haz_ptr()
+    }
+
+    fn release<'a>(&'a self, guard: Self::Guard<'a>) {
+        self.domain.release_hazard_ptr(guard);
+    }
+
+    fn protect<'a, T>(&'a self, guard: &Self::Guard<'a>, ptr: *mut T) {
+        guard.protect(ptr as *mut usize);
+    }
+
+    unsafe fn retire<T>(&self, ptr: *mut T) {
+        if needs_reclaim::<T>() {
+            unsafe {
+                self.domain.retire_erased(
+                    ptr as *mut usize,
+                    reclaim_box::<T>,
+                    RetiredType::of::<T>(),
+                )
+            };
+        }
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.domain.name()
+    }
+
+    fn is_same(&self, other: &Self) -> bool {
+        core::ptr::eq(
+            self.domain as *const dyn ErasedDomain as *const (),
+            other.domain as *const dyn ErasedDomain as *const (),
+        )
+    }
+
+    fn protect_ptr<'a, T>(&'a self, guard: &Self::Guard<'a>, source: &AtomicPtr<T>) -> *mut T {
+        guard.protect_ptr(source)
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+    use crate::{AnyAtomBox, AtomBoxIn};
+
+    static FIRST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+    static SECOND_DOMAIN: Domain<2> = Domain::new_named("second", ReclaimStrategy::Eager);
+
+    #[test]
+    fn boxes_from_different_domains_can_be_stored_together() {
+        let drop_counter = DropCounter::new();
+        let first = AnyDomain::new(&FIRST_DOMAIN);
+        let second = AnyDomain::new(&SECOND_DOMAIN);
+        let boxes: alloc::vec::Vec<AnyAtomBox<'_, _>> = alloc::vec![
+            AtomBoxIn::new_with_protection(drop_counter.track(1), &first),
+            AtomBoxIn::new_with_protection(drop_counter.track(2), &second),
+        ];
+
+        boxes[0].store(drop_counter.track(3));
+        boxes[1].store(drop_counter.track(4));
+
+        drop_counter.assert_drops(2);
+        assert_eq!(**boxes[0].load(), 3);
+        assert_eq!(**boxes[1].load(), 4);
+    }
+
+    #[test]
+    fn values_can_move_between_handles_to_the_same_domain() {
+        let handle = AnyDomain::new(&FIRST_DOMAIN);
+        let other_handle = AnyDomain::from(&FIRST_DOMAIN);
+        let from: AnyAtomBox<'_, _> = AtomBoxIn::new_with_protection(1, &handle);
+        let to: AnyAtomBox<'_, _> = AtomBoxIn::new_with_protection(2, &other_handle);
+
+        let old_value = to.swap_from_guard(from.swap(3));
+
+        assert_eq!(*old_value, 2);
+        assert_eq!(*to.load(), 1, "The value was moved");
+    }
+
+    #[test]
+    #[should_panic(expected = "guard from an unnamed domain, box in domain `second`")]
+    fn moving_values_between_domains_panics() {
+        let first = AnyDomain::new(&FIRST_DOMAIN);
+        let second = AnyDomain::new(&SECOND_DOMAIN);
+        let from: AnyAtomBox<'_, _> = AtomBoxIn::new_with_protection(1, &first);
+        let to: AnyAtomBox<'_, _> = AtomBoxIn::new_with_protection(2, &second);
+
+        to.store_from_guard(from.swap(3));
+    }
+}
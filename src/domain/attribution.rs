@@ -0,0 +1,185 @@
+use super::ProtectedPtr;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::thread::{Thread, ThreadId};
+
+// Threads are recorded in hazard pointer slots by key, so that acquiring and releasing a hazard
+// pointer only stores integers. Key zero is never assigned and marks a slot without an owner.
+static NEXT_THREAD_KEY: AtomicU64 = AtomicU64::new(1);
+// The head of an intrusive list of the registered threads. Each entry lives in the thread local
+// storage of its thread, so registering a thread does not allocate, and is unlinked before its
+// thread exits.
+static THREADS: Mutex<ThreadList> = Mutex::new(ThreadList(core::ptr::null()));
+// Labels are recorded by their index in this list plus one, so zero marks no label.
+static LABELS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+std::thread_local! {
+    static THREAD_KEY: ThreadKey = ThreadKey {
+        key: NEXT_THREAD_KEY.fetch_add(1, Ordering::Relaxed),
+        thread: std::thread::current(),
+        next: Cell::new(core::ptr::null()),
+        registered: Cell::new(false),
+    };
+    static GUARD_LABEL: Cell<(Option<&'static str>, usize)> = const { Cell::new((None, 0)) };
+}
+
+struct ThreadList(*const ThreadKey);
+
+// # Safety
+//
+// The entries of the list are only accessed while holding the lock.
+unsafe impl Send for ThreadList {}
+
+impl ThreadList {
+    fn iter(&self) -> impl Iterator<Item = &ThreadKey> {
+        // # Safety
+        //
+        // Entries are unlinked under the lock before their thread local storage is destroyed.
+        core::iter::successors(unsafe { self.0.as_ref() }, |entry| unsafe {
+            entry.next.get().as_ref()
+        })
+    }
+}
+
+/// The key of the current thread, which is registered while the thread is running.
+struct ThreadKey {
+    key: u64,
+    thread: Thread,
+    // The next entry in `THREADS`, only accessed while holding its lock.
+    next: Cell<*const ThreadKey>,
+    registered: Cell<bool>,
+}
+
+impl ThreadKey {
+    /// Links the entry into `THREADS` the first time it is used, once it has its final address.
+    fn register(&self) -> u64 {
+        if !self.registered.replace(true) {
+            let mut threads = lock(&THREADS);
+            self.next.set(threads.0);
+            threads.0 = self;
+        }
+        self.key
+    }
+}
+
+impl Drop for ThreadKey {
+    fn drop(&mut self) {
+        if !self.registered.get() {
+            return;
+        }
+        let mut threads = lock(&THREADS);
+        let this: *const ThreadKey = self;
+        if threads.0 == this {
+            threads.0 = self.next.get();
+        } else if let Some(previous) = threads.iter().find(|entry| entry.next.get() == this) {
+            previous.next.set(self.next.get());
+        }
+    }
+}
+
+// The registries cannot be left inconsistent by a panic, so poisoning is ignored.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Sets the label recorded with the hazard pointers acquired by the current thread from now on,
+/// returning the previous label.
+///
+/// Labels identify what a thread was doing when it acquired a hazard pointer, such as the request
+/// or task it was serving, in the [`HazardOwner`]s of a domain.
+///
+/// Requires the `thread-attribution` feature.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBoxIn, domain::{self, Domain, ReclaimStrategy}};
+///
+/// const CUSTOM_DOMAIN_ID: usize = 42;
+/// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+///
+/// let atom_box = AtomBoxIn::new_with_domain("Hello", &CUSTOM_DOMAIN);
+/// let previous = domain::set_guard_label(Some("report-job"));
+/// let guard = atom_box.load();
+/// domain::set_guard_label(previous);
+///
+/// let owners = CUSTOM_DOMAIN.hazard_owners();
+/// assert_eq!(owners[0].label, Some("report-job"));
+/// assert_eq!(owners[0].thread_id, std::thread::current().id());
+/// ```
+pub fn set_guard_label(label: Option<&'static str>) -> Option<&'static str> {
+    let id = label.map_or(0, |label| {
+        let mut labels = lock(&LABELS);
+        match labels.iter().position(|known| *known == label) {
+            Some(index) => index + 1,
+            None => {
+                labels.push(label);
+                labels.len()
+            }
+        }
+    });
+    GUARD_LABEL.with(|guard_label| guard_label.replace((label, id)).0)
+}
+
+/// The thread which acquired a hazard pointer, recorded in its slot while it is in use.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct SlotOwner {
+    /// The key registered for the thread, never zero.
+    pub(super) thread: u64,
+    /// The index of the thread's label plus one, or zero if it had no label.
+    pub(super) label: usize,
+}
+
+impl SlotOwner {
+    /// The current thread, with its guard label.
+    ///
+    /// Returns `None` if the thread is exiting and its key has already been unregistered.
+    pub(super) fn current() -> Option<Self> {
+        Some(Self {
+            thread: THREAD_KEY.try_with(ThreadKey::register).ok()?,
+            label: GUARD_LABEL.try_with(|label| label.get().1).unwrap_or(0),
+        })
+    }
+}
+
+/// A hazard pointer in use, and the thread which acquired it.
+///
+/// Returned by [`Domain::hazard_owners`](super::Domain::hazard_owners).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HazardOwner {
+    /// The ID of the thread which acquired the hazard pointer.
+    pub thread_id: ThreadId,
+    /// The name of the thread which acquired the hazard pointer, if it has one.
+    pub thread_name: Option<String>,
+    /// The [label](set_guard_label) the thread had set when it acquired the hazard pointer.
+    pub label: Option<&'static str>,
+    /// What the hazard pointer protects, or `None` if it is not currently protecting anything.
+    pub protected: Option<ProtectedPtr>,
+}
+
+impl HazardOwner {
+    /// Returns `None` if the owning thread has since exited.
+    pub(super) fn new(owner: SlotOwner, protected: Option<ProtectedPtr>) -> Option<Self> {
+        let thread = lock(&THREADS)
+            .iter()
+            .find(|entry| entry.key == owner.thread)?
+            .thread
+            .clone();
+        let label = owner
+            .label
+            .checked_sub(1)
+            .and_then(|index| lock(&LABELS).get(index).copied());
+        Some(Self {
+            thread_id: thread.id(),
+            thread_name: thread.name().map(String::from),
+            label,
+            protected,
+        })
+    }
+}
@@ -0,0 +1,70 @@
+use super::list::LockFreeList;
+use super::notify::Callback;
+use crate::sync::{AtomicPtr, Ordering};
+use alloc::vec::Vec;
+
+/// A callback to invoke once the hazard pointers which were protecting values when it was
+/// deferred have been released or moved on to other values.
+pub(super) struct Deferred {
+    // The hazard pointers which were in use, and the pointers they were protecting.
+    waiting_on: Vec<(*const AtomicPtr<usize>, *mut usize)>,
+    callback: Callback,
+}
+
+impl Deferred {
+    pub(super) fn new(
+        waiting_on: Vec<(*const AtomicPtr<usize>, *mut usize)>,
+        callback: Callback,
+    ) -> Self {
+        Self {
+            waiting_on,
+            callback,
+        }
+    }
+
+    /// Whether every hazard pointer this is waiting on has moved on.
+    fn is_ready(&mut self) -> bool {
+        self.waiting_on.retain(|&(haz_ptr, protected_ptr)| {
+            // # Safety
+            //
+            // Hazard pointers are only deallocated when the domain owning them is dropped, which
+            // outlives the domain's deferred callbacks.
+            unsafe { &*haz_ptr }.load(Ordering::Acquire) == protected_ptr
+        });
+        self.waiting_on.is_empty()
+    }
+
+    pub(super) fn run(self) {
+        (self.callback)();
+    }
+}
+
+impl core::fmt::Debug for Deferred {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Deferred")
+            .field("waiting_on", &self.waiting_on.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Invokes the callbacks in `list` which are ready, returning the others to it.
+///
+/// Returns the number of callbacks invoked.
+pub(super) fn run_ready(list: &LockFreeList<Deferred>) -> usize {
+    if list.count.load(Ordering::Acquire) == 0 {
+        return 0;
+    }
+    let mut ready = Vec::new();
+    for mut deferred in list.take_all() {
+        if deferred.is_ready() {
+            ready.push(deferred);
+        } else {
+            list.push(deferred);
+        }
+    }
+    let invoked = ready.len();
+    for deferred in ready {
+        deferred.run();
+    }
+    invoked
+}
@@ -0,0 +1,68 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// The number of reclaimed values a domain remembers unless configured otherwise.
+pub(super) const DEFAULT_HISTORY_LEN: usize = 64;
+
+/// A value which has been reclaimed by a [`Domain`](super::Domain).
+///
+/// Returned by [`Domain::recent_reclaims`](super::Domain::recent_reclaims) to help answer which
+/// values were freed, and when, after a use after free has been detected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ReclaimRecord {
+    /// The type of the value, as given by [`core::any::type_name`].
+    pub type_name: &'static str,
+    /// The size of the value in bytes.
+    pub size: usize,
+    /// When the value was retired, if the domain recorded it.
+    ///
+    /// This is not recorded by domains using
+    /// [`ReclaimStrategy::FrameBased`](super::ReclaimStrategy::FrameBased), or for values retired
+    /// through a [`WaitFreeHandle`](super::WaitFreeHandle).
+    pub retired_at: Option<SystemTime>,
+    /// When the value was reclaimed.
+    pub reclaimed_at: SystemTime,
+}
+
+/// The most recently reclaimed values of a domain, oldest first.
+#[derive(Debug)]
+pub(super) struct ReclaimHistory {
+    records: Mutex<VecDeque<ReclaimRecord>>,
+    // The number of records kept.
+    pub(super) len: usize,
+}
+
+impl ReclaimHistory {
+    pub(super) const fn new(len: usize) -> Self {
+        Self {
+            records: Mutex::new(VecDeque::new()),
+            len,
+        }
+    }
+
+    pub(super) fn record(&self, record: ReclaimRecord) {
+        if self.len == 0 {
+            return;
+        }
+        // A panic while recording cannot leave the records inconsistent, so poisoning is ignored.
+        let mut records = self
+            .records
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if records.len() == self.len {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    pub(super) fn snapshot(&self) -> Vec<ReclaimRecord> {
+        let records = self
+            .records
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        records.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,223 @@
+use super::{Retire, RetiredAt};
+use crate::macros::conditional_const;
+use crate::sync::{AtomicPtr, Ordering};
+use core::cell::UnsafeCell;
+
+/// A link which allows a value to be placed on a domain's retired list without allocating.
+///
+/// Types which embed a `RetireLink` and implement [`IntrusiveRetire`] can be retired via
+/// [`Domain::retire_intrusive`](super::Domain::retire_intrusive).
+pub struct RetireLink {
+    pub(super) next: AtomicPtr<RetireLink>,
+    // Written by the retiring thread before the link is published, and only read by the thread
+    // reclaiming the retired list.
+    retired: UnsafeCell<Option<Retire>>,
+}
+
+// # Safety
+//
+// The only non thread safe field is only accessed by the thread that owns the link, see above.
+unsafe impl Send for RetireLink {}
+unsafe impl Sync for RetireLink {}
+
+// The link is only written by the domain while the value is being retired, so a panic elsewhere
+// cannot leave it observably inconsistent.
+impl core::panic::RefUnwindSafe for RetireLink {}
+
+impl RetireLink {
+    conditional_const!(
+        "Creates a new, unlinked `RetireLink`.",
+        pub,
+        fn new() -> Self {
+            Self {
+                next: AtomicPtr::new(core::ptr::null_mut()),
+                retired: UnsafeCell::new(None),
+            }
+        }
+    );
+}
+
+impl Default for RetireLink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for RetireLink {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RetireLink").finish_non_exhaustive()
+    }
+}
+
+/// A type which embeds its own [`RetireLink`], so that retiring it does not allocate.
+///
+/// # Safety
+///
+/// `retire_link` must always return the same link, which must be a field of `self`, and which
+/// must not be returned by any other value.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::domain::{Domain, IntrusiveRetire, ReclaimStrategy, RetireLink};
+///
+/// struct Node {
+///     value: usize,
+///     link: RetireLink,
+/// }
+///
+/// unsafe impl IntrusiveRetire for Node {
+///     fn retire_link(&self) -> &RetireLink {
+///         &self.link
+///     }
+/// }
+///
+/// const CUSTOM_DOMAIN_ID: usize = 42;
+/// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+///
+/// let node = Box::into_raw(Box::new(Node { value: 1, link: RetireLink::new() }));
+/// unsafe { CUSTOM_DOMAIN.retire_intrusive(node) };
+/// assert_eq!(CUSTOM_DOMAIN.reclaim(), 1);
+/// ```
+pub unsafe trait IntrusiveRetire {
+    /// Returns the link embedded in this value.
+    fn retire_link(&self) -> &RetireLink;
+}
+
+/// A lock-free stack of retired values linked through their embedded [`RetireLink`]s.
+#[derive(Debug)]
+pub(super) struct IntrusiveList {
+    pub(super) head: AtomicPtr<RetireLink>,
+}
+
+impl IntrusiveList {
+    conditional_const!(
+        "Creates a new `IntrusiveList`",
+        pub,
+        fn new() -> Self {
+            Self {
+                head: AtomicPtr::new(core::ptr::null_mut()),
+            }
+        }
+    );
+
+    /// Pushes the value owning `link` onto the list.
+    ///
+    /// # Safety
+    ///
+    /// `link` must be embedded in the value pointed to by `ptr`, which must have been created via
+    /// `Box::<T>::into_raw`. Ownership of the value is transferred to the list.
+    pub(super) unsafe fn push<T>(&self, link: &RetireLink, ptr: *mut T, retired_at: RetiredAt) {
+        // # Safety
+        //
+        // The link has not yet been published, so we have exclusive access to it.
+        unsafe { *link.retired.get() = Some(Retire::new(ptr, retired_at)) };
+        let link_ptr = link as *const RetireLink as *mut RetireLink;
+        // # Safety
+        //
+        // The link forms a list of one element.
+        unsafe { self.push_all(link_ptr, link) };
+    }
+
+    /// Pushes a chain of links, from `first` to `last`, onto the list.
+    ///
+    /// # Safety
+    ///
+    /// Ownership of every value in the chain is transferred to the list.
+    pub(super) unsafe fn push_all(&self, first: *mut RetireLink, last: &RetireLink) {
+        let mut head_ptr = self.head.load(Ordering::Acquire);
+        loop {
+            last.next.store(head_ptr, Ordering::Release);
+            match self.head.compare_exchange_weak(
+                head_ptr,
+                first,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(new_head_ptr) => head_ptr = new_head_ptr,
+            }
+        }
+    }
+}
+
+/// The retired value owning a link, read by the thread which has taken the list.
+///
+/// # Safety
+///
+/// The caller must have exclusive access to the link, which must have been pushed onto an
+/// [`IntrusiveList`].
+pub(super) unsafe fn retired(link: &RetireLink) -> (&Retire, *mut RetireLink) {
+    // # Safety
+    //
+    // According to the safety contract we have exclusive access to the link and it has been
+    // initialised by `push`.
+    let retired = unsafe { &*link.retired.get() }
+        .as_ref()
+        .expect("Links are initialised before being pushed");
+    (retired, link.next.load(Ordering::Acquire))
+}
+
+/// Replaces the time at which the value owning a link was retired.
+///
+/// # Safety
+///
+/// The caller must have exclusive access to the link, which must have been pushed onto an
+/// [`IntrusiveList`].
+pub(super) unsafe fn set_retired_at(link: &RetireLink, retired_at: RetiredAt) {
+    // # Safety
+    //
+    // According to the safety contract we have exclusive access to the link and it has been
+    // initialised by `push`.
+    let retired = unsafe { &mut *link.retired.get() }
+        .as_mut()
+        .expect("Links are initialised before being pushed");
+    retired.retired_at = retired_at;
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::super::{Domain, ReclaimStrategy};
+    use super::*;
+    use crate::test_util::{DropCounter, TrackedValue};
+    use alloc::boxed::Box;
+
+    struct Node {
+        _value: TrackedValue<usize>,
+        link: RetireLink,
+    }
+
+    unsafe impl IntrusiveRetire for Node {
+        fn retire_link(&self) -> &RetireLink {
+            &self.link
+        }
+    }
+
+    #[test]
+    fn retired_values_are_reclaimed_unless_protected() {
+        let domain: Domain<1> = Domain::new(ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let nodes: alloc::vec::Vec<_> = (0..3)
+            .map(|value| {
+                Box::into_raw(Box::new(Node {
+                    _value: drop_counter.track(value),
+                    link: RetireLink::new(),
+                }))
+            })
+            .collect();
+        let haz_ptr = domain.acquire_haz_ptr();
+        haz_ptr.protect(nodes[1] as *mut usize);
+
+        for node in &nodes {
+            unsafe { domain.retire_intrusive(*node) };
+        }
+        let reclaimed = domain.reclaim();
+
+        assert_eq!(reclaimed, 2, "Unprotected nodes should be reclaimed");
+        drop_counter.assert_drops(2);
+        domain.release_hazard_ptr(haz_ptr);
+        assert_eq!(domain.reclaim(), 1, "The node is reclaimed once released");
+        drop_counter.assert_drops(3);
+    }
+}
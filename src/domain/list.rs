@@ -1,4 +1,4 @@
-#[cfg(any(test, not(feature = "bicephany")))]
+#[cfg(all(test, not(loom)))]
 use core::marker::PhantomData;
 
 use crate::macros::conditional_const;
@@ -17,13 +17,13 @@ pub(super) struct Node<T> {
     pub(super) next: AtomicPtr<Node<T>>,
 }
 
-#[cfg(any(test, not(feature = "bicephany")))]
+#[cfg(all(test, not(loom)))]
 pub(super) struct ListIterator<'a, T> {
     node: *const Node<T>,
     _list: PhantomData<&'a LockFreeList<T>>,
 }
 
-#[cfg(any(test, not(feature = "bicephany")))]
+#[cfg(all(test, not(loom)))]
 impl<'a, T> Iterator for ListIterator<'a, T> {
     type Item = &'a T;
 
@@ -54,6 +54,11 @@ impl<T> LockFreeList<T> {
     );
 
     pub(super) fn push(&self, value: T) -> *mut Node<T> {
+        #[cfg(feature = "leak-audit")]
+        crate::leak_audit::track(
+            crate::leak_audit::AllocationKind::ListNode,
+            core::mem::size_of::<Node<T>>(),
+        );
         let node = Box::into_raw(Box::new(Node {
             value,
             next: AtomicPtr::new(core::ptr::null_mut()),
@@ -100,8 +105,74 @@ impl<T> LockFreeList<T> {
         }
     }
 
-    #[cfg(any(test, not(feature = "bicephany")))]
-    pub(super) fn iter(&self) -> ListIterator<T> {
+    /// Removes the head of the list and returns its value.
+    ///
+    /// # Safety
+    ///
+    /// Nodes are deallocated as soon as they are popped, therefore, this must not be called
+    /// concurrently with any other operation on the list.
+    #[cfg(all(test, not(loom)))]
+    pub(super) unsafe fn pop(&self) -> Option<T> {
+        let mut head_ptr = self.head.load(Ordering::Acquire);
+        while !head_ptr.is_null() {
+            // # Safety
+            //
+            // The pointer is non null and, according to the safety contract of this function, no
+            // one else can have popped and deallocated the node.
+            let next_ptr = unsafe { &*head_ptr }.next.load(Ordering::Acquire);
+            match self.head.compare_exchange_weak(
+                head_ptr,
+                next_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    self.count.fetch_add(-1, Ordering::Release);
+                    // # Safety
+                    //
+                    // The node was allocated via box and we have just unlinked it from the list,
+                    // so we now have exclusive ownership of it.
+                    let node = unsafe { Box::from_raw(head_ptr) };
+                    #[cfg(feature = "leak-audit")]
+                    crate::leak_audit::untrack(
+                        crate::leak_audit::AllocationKind::ListNode,
+                        core::mem::size_of::<Node<T>>(),
+                    );
+                    return Some(node.value);
+                }
+                Err(updated_head_ptr) => {
+                    head_ptr = updated_head_ptr;
+                }
+            }
+        }
+        None
+    }
+
+    /// Removes every value from the list, most recently pushed first.
+    pub(super) fn take_all(&self) -> alloc::vec::Vec<T> {
+        let mut node_ptr = self.head.swap(core::ptr::null_mut(), Ordering::Acquire);
+        let mut values = alloc::vec::Vec::new();
+        while !node_ptr.is_null() {
+            // # Safety
+            //
+            // The nodes were allocated via box, and having swapped them out of the list we have
+            // exclusive ownership of them.
+            let node = unsafe { Box::from_raw(node_ptr) };
+            #[cfg(feature = "leak-audit")]
+            crate::leak_audit::untrack(
+                crate::leak_audit::AllocationKind::ListNode,
+                core::mem::size_of::<Node<T>>(),
+            );
+            node_ptr = node.next.load(Ordering::Relaxed);
+            values.push(node.value);
+        }
+        self.count
+            .fetch_sub(values.len() as isize, Ordering::Release);
+        values
+    }
+
+    #[cfg(all(test, not(loom)))]
+    pub(super) fn iter(&self) -> ListIterator<'_, T> {
         ListIterator {
             node: self.head.load(Ordering::Acquire),
             _list: PhantomData,
@@ -114,6 +185,11 @@ impl<T> Drop for LockFreeList<T> {
         let mut node_ptr = self.head.load(Ordering::Relaxed);
         while !node_ptr.is_null() {
             let node: Box<Node<T>> = unsafe { Box::from_raw(node_ptr) };
+            #[cfg(feature = "leak-audit")]
+            crate::leak_audit::untrack(
+                crate::leak_audit::AllocationKind::ListNode,
+                core::mem::size_of::<Node<T>>(),
+            );
             node_ptr = node.next.load(Ordering::Relaxed);
         }
     }
@@ -125,6 +201,7 @@ mod test {
     use super::*;
     use alloc::vec;
     use alloc::vec::Vec;
+    use proptest::prelude::*;
 
     #[test]
     fn test_push() {
@@ -204,4 +281,77 @@ mod test {
         // To avoid dropping the nodes which we moved from list2 to list1
         core::mem::forget(list2);
     }
+
+    #[derive(Debug, Clone)]
+    enum Operation {
+        Push(usize),
+        PushAll(Vec<usize>),
+        Pop,
+    }
+
+    fn operation() -> impl Strategy<Value = Operation> {
+        prop_oneof![
+            any::<usize>().prop_map(Operation::Push),
+            proptest::collection::vec(any::<usize>(), 1..8).prop_map(Operation::PushAll),
+            Just(Operation::Pop),
+        ]
+    }
+
+    /// Builds a detached chain of nodes in the order given, returning its head and tail.
+    fn chain(values: &[usize]) -> (*mut Node<usize>, *mut Node<usize>) {
+        let mut head_ptr = core::ptr::null_mut();
+        let mut tail_ptr: *mut Node<usize> = core::ptr::null_mut();
+        for &value in values.iter().rev() {
+            head_ptr = Box::into_raw(Box::new(Node {
+                value,
+                next: AtomicPtr::new(head_ptr),
+            }));
+            if tail_ptr.is_null() {
+                tail_ptr = head_ptr;
+            }
+        }
+        (head_ptr, tail_ptr)
+    }
+
+    proptest! {
+        #[test]
+        fn operations_match_model(operations in proptest::collection::vec(operation(), 0..64)) {
+            let list = LockFreeList::new();
+            // The model is a stack whose top is the last element
+            let mut model = Vec::new();
+
+            for operation in operations {
+                match operation {
+                    Operation::Push(value) => {
+                        list.push(value);
+                        model.push(value);
+                    }
+                    Operation::PushAll(values) => {
+                        let (head_ptr, tail_ptr) = chain(&values);
+                        // # Safety
+                        //
+                        // The chain was just created and ownership is moved into the list.
+                        unsafe { list.push_all(head_ptr, &(*tail_ptr).next, values.len() as isize) };
+                        model.extend(values.iter().rev());
+                    }
+                    Operation::Pop => {
+                        // # Safety
+                        //
+                        // The list is only accessed from this thread.
+                        let popped = unsafe { list.pop() };
+                        prop_assert_eq!(popped, model.pop(), "Popped value should match the model");
+                    }
+                }
+
+                prop_assert_eq!(
+                    list.count.load(Ordering::Acquire),
+                    model.len() as isize,
+                    "The count should match the number of items in the model"
+                );
+                let members: Vec<_> = list.iter().copied().collect();
+                let expected: Vec<_> = model.iter().rev().copied().collect();
+                prop_assert_eq!(members, expected, "The list should be linked in model order");
+            }
+        }
+    }
 }
@@ -18,87 +18,324 @@
 //!
 //! Creating an `AtomBox` using a custom domain.
 //! ```
-//! use atom_box::{AtomBox, domain::{Domain, ReclaimStrategy}};
+//! use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
 //!
 //! const CUSTOM_DOMAIN_ID: usize = 42;
 //! static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
 //!
-//! let atom_box = AtomBox::new_with_domain("Hello World", &CUSTOM_DOMAIN);
+//! let atom_box = AtomBoxIn::new_with_domain("Hello World", &CUSTOM_DOMAIN);
 //! ```
 
-#[cfg(feature = "bicephany")]
-mod bicephaly;
-#[cfg(not(feature = "bicephany"))]
-pub(crate) mod hazard_pointer_list;
+mod any;
+#[cfg(feature = "thread-attribution")]
+mod attribution;
+mod defer;
+#[cfg(feature = "reclaim-history")]
+mod history;
+mod intrusive;
 mod list;
+mod notify;
+#[cfg(feature = "std")]
+mod pointer_hasher;
+mod reclaim_future;
 mod reclaim_strategy;
+#[cfg(feature = "domain-registry")]
+mod registry;
+#[cfg(feature = "stats-reporter")]
+mod reporter;
+mod scoped;
+mod slots;
+#[cfg(feature = "stats")]
+mod stats;
+mod wait_free;
 
 use crate::macros::conditional_const;
-use crate::sync::{AtomicPtr, Ordering};
+use crate::protection::Protection;
+#[cfg(feature = "std")]
+use crate::sync::AtomicU64;
+use crate::sync::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
 use alloc::boxed::Box;
 #[cfg(not(feature = "std"))]
 use alloc::collections::BTreeSet as Set;
-#[cfg(feature = "bicephany")]
-use bicephaly::Bicephaly;
+pub use any::AnyDomain;
+#[cfg(feature = "thread-attribution")]
+pub use attribution::{set_guard_label, HazardOwner};
+use core::cell::Cell;
+#[cfg(feature = "std")]
+use core::time::Duration;
+use defer::Deferred;
+#[cfg(feature = "reclaim-history")]
+use history::ReclaimHistory;
+#[cfg(feature = "reclaim-history")]
+pub use history::ReclaimRecord;
+use intrusive::IntrusiveList;
+pub use intrusive::{IntrusiveRetire, RetireLink};
 use list::{LockFreeList, Node};
-pub use reclaim_strategy::{ReclaimStrategy, TimedCappedSettings};
+use notify::{Notification, Notifications};
+pub use reclaim_future::ReclaimFuture;
+use reclaim_future::ReclaimPass;
+use reclaim_strategy::ReclaimTrigger;
+pub use reclaim_strategy::{ReclaimStrategy, RetirePolicy, TimedCappedSettings};
+#[cfg(all(feature = "domain-registry", not(loom)))]
+pub(crate) use registry::snapshot as registered_domains;
+#[cfg(feature = "domain-registry")]
+pub use registry::DomainInfo;
+#[cfg(feature = "stats-reporter")]
+pub use reporter::{StatsReport, StatsReporter};
+pub use scoped::DomainScope;
+use slots::Slots;
+#[cfg(all(feature = "stats", feature = "std"))]
+use stats::DwellCounters;
+#[cfg(all(feature = "stats", feature = "std"))]
+pub use stats::DwellHistogram;
+#[cfg(feature = "stats")]
+use stats::ReclaimCounters;
+#[cfg(feature = "stats")]
+pub use stats::ReclaimStats;
+pub use wait_free::{WaitFreeGuard, WaitFreeHandle};
 #[cfg(feature = "std")]
-use std::collections::HashSet as Set;
+type Set<T> =
+    std::collections::HashSet<T, core::hash::BuildHasherDefault<pointer_hasher::PointerHasher>>;
 
-#[cfg(not(feature = "bicephany"))]
-use self::hazard_pointer_list::HazardPointerList;
+type HazardPointers = Slots<AtomicPtr<usize>>;
 
-pub(crate) trait Retirable {}
+/// Published in place of a pointer to protect every value, pinning the domain so that nothing is
+/// reclaimed until it is replaced.
+const PINNED: *mut usize = usize::MAX as *mut usize;
 
-#[cfg(not(feature = "bicephany"))]
-pub(crate) type HazardPointer<'a> = Pointer<'a, hazard_pointer_list::Node>;
-#[cfg(not(feature = "bicephany"))]
-type HazardPointers = HazardPointerList;
+/// A protection currently published in one of a domain's hazard pointers.
+///
+/// Returned by [`Domain::protected_ptrs`].
+#[cfg(feature = "introspection")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ProtectedPtr {
+    /// A reader is protecting the value at this address.
+    Ptr(*const ()),
+    /// A reader has pinned the domain, protecting every value.
+    All,
+}
 
-#[cfg(feature = "bicephany")]
-pub(crate) type HazardPointer<'a> = Pointer<'a, bicephaly::Node<AtomicPtr<usize>>>;
-#[cfg(feature = "bicephany")]
-type HazardPointers = Bicephaly<AtomicPtr<usize>>;
+/// Stored in place of a configured strategy once a domain has consulted its strategy, so that it
+/// can no longer be configured.
+fn strategy_frozen() -> *mut ReclaimStrategy {
+    core::ptr::NonNull::dangling().as_ptr()
+}
 
+/// A hazard pointer acquired from a [`Domain`], protecting at most one value at a time.
+///
+/// This is the [`Protection::Guard`] of a `Domain`.
 #[cfg(not(test))]
-pub(crate) struct Pointer<'a, T>(&'a T);
+pub struct HazardPointer<'a>(
+    &'a slots::Slot<AtomicPtr<usize>>,
+    Cell<usize>,
+    #[cfg(all(feature = "stats", feature = "std"))] std::time::Instant,
+);
+/// A hazard pointer acquired from a [`Domain`], protecting at most one value at a time.
+///
+/// This is the [`Protection::Guard`] of a `Domain`.
 #[cfg(test)]
-pub(crate) struct Pointer<'a, T>(pub(super) &'a T);
+pub struct HazardPointer<'a>(
+    pub(crate) &'a slots::Slot<AtomicPtr<usize>>,
+    Cell<usize>,
+    #[cfg(all(feature = "stats", feature = "std"))] std::time::Instant,
+);
 
-impl<'a, T> Pointer<'a, T> {
-    fn new(value: &'a T) -> Self {
-        Pointer(value)
+impl<'a> core::fmt::Debug for HazardPointer<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("HazardPointer").finish_non_exhaustive()
     }
 }
 
+// The memoized pointer is only updated once a pointer has been validated, and no user code runs
+// while a hazard pointer is being updated, so a panic cannot leave it inconsistent.
+impl core::panic::RefUnwindSafe for HazardPointer<'_> {}
+
+// The second field memoizes the pointer which this hazard pointer has protected and validated
+// since it last published a pointer, or null. A validated pointer has been protected
+// continuously, so loading it again does not need to be published or fenced.
 impl<'a> HazardPointer<'a> {
+    #[cfg(not(all(feature = "stats", feature = "std")))]
+    fn new(value: &'a slots::Slot<AtomicPtr<usize>>) -> Self {
+        #[cfg(feature = "thread-attribution")]
+        value.set_owner(attribution::SlotOwner::current());
+        HazardPointer(value, Cell::new(0))
+    }
+
+    // The third field records when the hazard pointer was acquired.
+    #[cfg(all(feature = "stats", feature = "std"))]
+    fn new(value: &'a slots::Slot<AtomicPtr<usize>>) -> Self {
+        #[cfg(feature = "thread-attribution")]
+        value.set_owner(attribution::SlotOwner::current());
+        HazardPointer(value, Cell::new(0), std::time::Instant::now())
+    }
+
     pub(crate) fn reset(&self) {
+        self.1.set(0);
         self.0.store(core::ptr::null_mut(), Ordering::Release);
     }
 
     pub(crate) fn protect(&self, ptr: *mut usize) {
+        #[cfg(feature = "fault-inject")]
+        crate::fault_inject::delay_protection();
+        self.1.set(0);
         self.0.store(ptr, Ordering::Release);
     }
+
+    /// Protects the pointer currently stored in `source`, returning the protected pointer.
+    ///
+    /// Retries until the protected pointer is confirmed to still be the one stored in `source`.
+    /// If `source` still holds the pointer this hazard pointer last protected, it is returned
+    /// without being published again.
+    pub(crate) fn protect_ptr<T>(&self, source: &AtomicPtr<T>) -> *mut T {
+        let mut original_ptr = source.load(Ordering::Acquire);
+        if original_ptr as usize == self.1.get() {
+            return original_ptr;
+        }
+        loop {
+            self.protect(original_ptr as *mut usize);
+
+            crate::sync::fence(Ordering::SeqCst);
+
+            let current_ptr = source.load(Ordering::Acquire);
+            if current_ptr == original_ptr {
+                // The pointer is the same, we have successfully protected its value.
+                self.1.set(current_ptr as usize);
+                break current_ptr;
+            }
+            self.reset();
+            original_ptr = current_ptr;
+        }
+    }
+
+    /// Protects the pointer currently stored in `source`, making at most `attempts` attempts.
+    ///
+    /// Returns `None` if `source` changed during every attempt.
+    pub(crate) fn try_protect_ptr<T>(
+        &self,
+        source: &AtomicPtr<T>,
+        attempts: usize,
+    ) -> Option<*mut T> {
+        let mut original_ptr = source.load(Ordering::Relaxed);
+        for _ in 0..attempts {
+            self.protect(original_ptr as *mut usize);
+
+            crate::sync::fence(Ordering::SeqCst);
+
+            let current_ptr = source.load(Ordering::Acquire);
+            if current_ptr == original_ptr {
+                self.1.set(current_ptr as usize);
+                return Some(current_ptr);
+            }
+            original_ptr = current_ptr;
+        }
+        self.reset();
+        None
+    }
+}
+
+/// When a value was retired, in the unit tracked by its domain's strategy at the time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RetiredAt {
+    /// The retirement time was not tracked.
+    Untracked,
+    /// The frame in which the value was retired, for frame based reclamation.
+    Frame(u64),
+    /// Nanoseconds since the unix epoch.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    Nanos(u64),
 }
 
-impl<T> Retirable for T {}
+impl RetiredAt {
+    /// Whether the value was retired after `retired_by`, so may not be reclaimed yet.
+    ///
+    /// A `retired_by` of `Untracked` places no limit. Values whose time is in a different unit
+    /// were retired before the strategy last changed, so are not held back.
+    fn is_later_than(self, retired_by: RetiredAt) -> bool {
+        match (self, retired_by) {
+            (Self::Frame(frame), Self::Frame(by)) => frame > by,
+            (Self::Nanos(nanos), Self::Nanos(by)) => nanos > by,
+            _ => false,
+        }
+    }
+
+    /// The time the value was retired, in nanoseconds since the unix epoch, if it was timed.
+    fn nanos(self) -> Option<u64> {
+        match self {
+            Self::Nanos(nanos) => Some(nanos),
+            Self::Untracked | Self::Frame(_) => None,
+        }
+    }
+}
 
-// TODO: consider using TraitObject
 #[derive(Debug)]
 struct Retire {
     ptr: *mut usize,
-    retirable: *mut dyn Retirable,
+    reclaim: unsafe fn(*mut usize),
+    retired_at: RetiredAt,
+    retired_type: RetiredType,
 }
 
 impl Retire {
-    fn new<T>(ptr: *mut T) -> Self {
+    fn new<T>(ptr: *mut T, retired_at: RetiredAt) -> Self {
         Self {
             ptr: ptr as *mut usize,
-            retirable: ptr as *mut dyn Retirable,
+            reclaim: reclaim_box::<T>,
+            retired_at,
+            retired_type: RetiredType::of::<T>(),
+        }
+    }
+}
+
+/// The type of a retired value, kept for diagnostics.
+#[derive(Clone, Copy, Debug)]
+struct RetiredType {
+    #[cfg_attr(
+        not(any(feature = "leak-audit", feature = "reclaim-history")),
+        allow(dead_code)
+    )]
+    size: usize,
+    #[cfg(feature = "reclaim-history")]
+    name: &'static str,
+}
+
+impl RetiredType {
+    fn of<T>() -> Self {
+        Self::of_unsized::<T>(core::mem::size_of::<T>())
+    }
+
+    // The type is only needed for its name, which is only kept for the reclaim history.
+    #[cfg_attr(
+        not(feature = "reclaim-history"),
+        allow(clippy::extra_unused_type_parameters)
+    )]
+    fn of_unsized<T: ?Sized>(size: usize) -> Self {
+        Self {
+            size,
+            #[cfg(feature = "reclaim-history")]
+            name: core::any::type_name::<T>(),
         }
     }
 }
 
+/// Whether values of type `T` need to be protected and reclaimed.
+///
+/// Boxing a zero sized value does not allocate, so unless it has drop glue there is nothing to
+/// reclaim and readers have nothing to protect.
+pub(crate) const fn needs_reclaim<T>() -> bool {
+    core::mem::size_of::<T>() != 0 || core::mem::needs_drop::<T>()
+}
+
+/// Drops and deallocates a type erased pointer which was originally created via `Box::into_raw`.
+///
+/// # Safety
+///
+/// The pointer must have been created via `Box::<T>::into_raw` and must not have already been
+/// dropped.
+unsafe fn reclaim_box<T>(ptr: *mut usize) {
+    drop(unsafe { Box::from_raw(ptr as *mut T) });
+}
+
 /// A holder of hazard pointers protecting the access to the values stored in all associated `AtomBox`s.
 ///
 /// A domain is responsible for handing out hazard pointer to protect the access to the values
@@ -108,9 +345,49 @@ impl Retire {
 /// reclaimed.
 #[derive(Debug)]
 pub struct Domain<const DOMAIN_ID: usize> {
+    name: Option<&'static str>,
     retired: LockFreeList<Retire>,
+    // Values retired via their own embedded link. Counted in `retired.count`.
+    retired_intrusive: IntrusiveList,
     hazard_ptrs: HazardPointers,
+    // The hazard pointers of the root domain, if this is a child domain sharing them.
+    parent_hazard_ptrs: Option<&'static HazardPointers>,
+    hazard_ptr_limit: usize,
+    // Whether values are freed when retired if no hazard pointer protects them.
+    immediate_free: bool,
+    // The minimum time for which retired values are kept, even if they are not protected.
+    #[cfg(feature = "std")]
+    quarantine: Option<Duration>,
+    // The tag bits ignored when comparing protected pointers with retired values.
+    tag_mask: usize,
     reclaim_strategy: ReclaimStrategy,
+    // A strategy configured after construction, which replaces `reclaim_strategy`, or a frozen
+    // marker once the strategy has been consulted.
+    configured_strategy: AtomicPtr<ReclaimStrategy>,
+    // Threads reading a configured strategy, counted in the slot selected by `strategy_epoch`
+    // when they began.
+    strategy_readers: [AtomicUsize; 2],
+    // Incremented by `set_reclaim_strategy` to direct new readers to the other counter, so that
+    // the counter of the readers which may hold the replaced strategy drains.
+    strategy_epoch: AtomicUsize,
+    // Whether a strategy is being replaced. Replacements are serialised.
+    replacing_strategy: AtomicBool,
+    notifications: LockFreeList<Notification>,
+    deferred: LockFreeList<Deferred>,
+    // The current frame, counted from one, for frame based reclamation.
+    frame: AtomicUsize,
+    // Incremented before any retired value may be reclaimed, or handed to another domain to be
+    // reclaimed, so that an `AtomWeak` can tell whether its value may have been freed.
+    reclaim_passes: AtomicUsize,
+    // The time the oldest tracked value was retired, or `u64::MAX` if none are.
+    #[cfg(feature = "std")]
+    oldest_retired_at: AtomicU64,
+    #[cfg(feature = "stats")]
+    reclaim_counters: ReclaimCounters,
+    #[cfg(all(feature = "stats", feature = "std"))]
+    dwell_counters: DwellCounters,
+    #[cfg(feature = "reclaim-history")]
+    reclaim_history: ReclaimHistory,
 }
 
 impl<const DOMAIN_ID: usize> Domain<DOMAIN_ID> {
@@ -142,36 +419,365 @@ On nightly this will panic if the domain id is equal to the shared domain's id (
         }
     );
 
+    conditional_const!(
+        "Create a new `Domain` with provided `ReclaimStrategy` and a name used in diagnostics.
+
+The name is included in the domain's `Debug` output and in panic messages, such as when a value
+guarded by one domain is used with an `AtomBox` associated with another.
+
+# Example
+
+```
+use atom_box::domain::{Domain, ReclaimStrategy};
+
+const CACHE_DOMAIN_ID: usize = 42;
+static CACHE_DOMAIN: Domain<CACHE_DOMAIN_ID> =
+    Domain::new_named(\"cache\", ReclaimStrategy::Eager);
+
+assert_eq!(CACHE_DOMAIN.name(), Some(\"cache\"));
+```
+",
+        pub,
+        fn new_named(name: &'static str, reclaim_strategy: ReclaimStrategy) -> Self {
+            let mut domain = Self::new(reclaim_strategy);
+            domain.name = Some(name);
+            domain
+        }
+    );
+
+    /// Creates a `Domain` owned by a single test, which is dropped rather than leaked.
+    ///
+    /// The domain reclaims values eagerly, so that they are reclaimed as soon as possible during
+    /// the test. See [`TestDomain`](crate::test_util::TestDomain).
+    #[cfg(any(test, loom, feature = "test-util"))]
+    pub fn new_for_test() -> crate::test_util::TestDomain<DOMAIN_ID> {
+        crate::test_util::TestDomain::new(ReclaimStrategy::Eager)
+    }
+
+    conditional_const!(
+        "Create a child `Domain` which shares the hazard pointers of `parent`.
+
+A child domain has its own retired list and `ReclaimStrategy`, so retired values can be accounted
+for and reclaimed separately for each subsystem, but its hazard pointers are allocated from the
+same pool as its parent's. Reclaiming values in any of the domains scans the hazard pointers of
+them all once, rather than each domain adding more hazard pointers to scan. Children of a child
+domain share the hazard pointers of the root domain.
+
+# Example
+
+```
+use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+
+const PARENT_DOMAIN_ID: usize = 42;
+static PARENT_DOMAIN: Domain<PARENT_DOMAIN_ID> = Domain::new(ReclaimStrategy::default());
+
+const CACHE_DOMAIN_ID: usize = 43;
+static CACHE_DOMAIN: Domain<CACHE_DOMAIN_ID> =
+    Domain::new_child(&PARENT_DOMAIN, ReclaimStrategy::Eager);
+
+let atom_box = AtomBoxIn::new_with_domain(\"Hello World\", &CACHE_DOMAIN);
+assert_eq!(*atom_box.load(), \"Hello World\");
+```
+",
+        pub,
+        fn new_child<const PARENT_ID: usize>(
+            parent: &'static Domain<PARENT_ID>,
+            reclaim_strategy: ReclaimStrategy,
+        ) -> Self {
+            let mut domain = Self::new(reclaim_strategy);
+            domain.parent_hazard_ptrs = match parent.parent_hazard_ptrs {
+                Some(hazard_ptrs) => Some(hazard_ptrs),
+                None => Some(&parent.hazard_ptrs),
+            };
+            domain
+        }
+    );
+
+    /// Returns the name of the domain, if it was created with [`Domain::new_named`].
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    /// Adds the domain to the process wide registry, so that it is reported by
+    /// [`domains`](crate::domains).
+    ///
+    /// Registering a domain more than once has no further effect. Only domains which live for the
+    /// rest of the process, such as statics, can be registered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::domain::{Domain, ReclaimStrategy};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> =
+    ///     Domain::new_named("sessions", ReclaimStrategy::Eager);
+    ///
+    /// CUSTOM_DOMAIN.register();
+    ///
+    /// let sessions = atom_box::domains()
+    ///     .into_iter()
+    ///     .find(|domain| domain.name == Some("sessions"))
+    ///     .unwrap();
+    /// assert_eq!(sessions.id, CUSTOM_DOMAIN_ID);
+    /// ```
+    #[cfg(feature = "domain-registry")]
+    pub fn register(&'static self) {
+        registry::register(self);
+    }
+
+    conditional_const!(
+        "Sets the number of hazard pointers after which types with a fallback stop allocating
+hazard pointers.
+
+Hazard pointers are allocated in blocks, so the limit is rounded up to a whole block. Once the
+limit is reached, [`HybridAtomBox`](crate::HybridAtomBox) hands out reference counted snapshots
+instead of hazard protected values. Other types continue to allocate hazard pointers as needed.
+
+# Example
+
+```
+use atom_box::domain::{Domain, ReclaimStrategy};
+
+const CUSTOM_DOMAIN_ID: usize = 42;
+static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> =
+    Domain::new(ReclaimStrategy::Eager).with_hazard_pointer_limit(64);
+```
+",
+        pub,
+        fn with_hazard_pointer_limit(mut self, limit: usize) -> Self {
+            self.hazard_ptr_limit = limit;
+            self
+        }
+    );
+
+    conditional_const!(
+        "Frees retired values immediately if no hazard pointer protects them when they are retired.
+
+Before a value is placed on the retired list, the hazard pointers are checked for that one
+pointer. If none protects it, the value is dropped straight away, skipping the retired list and
+the scan of a later reclamation. This is cheap when the domain has few hazard pointers, and pays
+off for workloads in which values are rarely being read when they are replaced. Values which are
+protected are retired as usual.
+
+Values are dropped by the thread which retires them, so stores may run the destructor of the
+value they replace. Values embedding a [`RetireLink`] are always retired as usual.
+
+# Example
+
+```
+use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+
+const CUSTOM_DOMAIN_ID: usize = 42;
+static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> =
+    Domain::new(ReclaimStrategy::Manual).with_immediate_free();
+
+let atom_box = AtomBoxIn::new_with_domain(\"Hello World\", &CUSTOM_DOMAIN);
+atom_box.store(\"Goodbye World\");
+
+assert_eq!(CUSTOM_DOMAIN.reclaim(), 0, \"The replaced value has already been freed\");
+```
+",
+        pub,
+        fn with_immediate_free(mut self) -> Self {
+            self.immediate_free = true;
+            self
+        }
+    );
+
+    #[cfg(feature = "reclaim-history")]
+    conditional_const!(
+        "Sets the number of reclaimed values remembered by [`Domain::recent_reclaims`].
+
+# Example
+
+```
+use atom_box::domain::{Domain, ReclaimStrategy};
+
+const CUSTOM_DOMAIN_ID: usize = 42;
+static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> =
+    Domain::new(ReclaimStrategy::Eager).with_reclaim_history_len(1024);
+```
+",
+        pub,
+        fn with_reclaim_history_len(mut self, len: usize) -> Self {
+            self.reclaim_history.len = len;
+            self
+        }
+    );
+
+    #[cfg(feature = "std")]
+    conditional_const!(
+        "Keeps retired values for at least `quarantine` before they can be reclaimed, even if no
+hazard pointer protects them.
+
+This leaves time for latent use after free bugs in unsafe code to read values which are still
+intact, which is useful when debugging, or when running under a sanitizer. Values are only freed
+by a reclamation which runs once their quarantine has elapsed, and the domain's
+[`ReclaimStrategy`] decides when reclamations run. Values are not freed when retired, even if
+the domain was created [`with_immediate_free`](Domain::with_immediate_free). The quarantine is
+not applied with [`ReclaimStrategy::FrameBased`], which already delays reclamation, or when the
+domain is dropped.
+
+# Example
+
+```
+use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+use core::time::Duration;
+
+const CUSTOM_DOMAIN_ID: usize = 42;
+static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> =
+    Domain::new(ReclaimStrategy::Manual).with_quarantine(Duration::from_millis(10));
+
+let atom_box = AtomBoxIn::new_with_domain(\"Hello World\", &CUSTOM_DOMAIN);
+atom_box.store(\"Goodbye World\");
+assert_eq!(CUSTOM_DOMAIN.reclaim(), 0, \"The replaced value is quarantined\");
+
+std::thread::sleep(Duration::from_millis(10));
+assert_eq!(CUSTOM_DOMAIN.reclaim(), 1);
+```
+",
+        pub,
+        fn with_quarantine(mut self, quarantine: Duration) -> Self {
+            self.quarantine = Some(quarantine);
+            self
+        }
+    );
+
+    conditional_const!(
+        "Ignores the tag bits in `tag_mask` of protected pointers when deciding whether a retired
+value is protected.
+
+Values are retired by their untagged address. Protecting a tagged pointer, for example one
+carrying a mark bit in a lock-free list, would otherwise never match the retired value. Pointers
+can also be untagged as they are protected, see
+[`Protection::protect_ptr_tagged`].
+
+# Example
+
+```
+use atom_box::domain::{Domain, ReclaimStrategy};
+
+const CUSTOM_DOMAIN_ID: usize = 42;
+static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> =
+    Domain::new(ReclaimStrategy::Eager).with_tag_mask(0b11);
+```
+",
+        pub,
+        fn with_tag_mask(mut self, tag_mask: usize) -> Self {
+            self.tag_mask = tag_mask;
+            self
+        }
+    );
+
     conditional_const!(
         "Internal function for creating a new `Domain`",
         pub(crate),
         fn _new(reclaim_strategy: ReclaimStrategy) -> Self {
             Self {
+                name: None,
                 hazard_ptrs: HazardPointers::new(),
+                parent_hazard_ptrs: None,
+                hazard_ptr_limit: usize::MAX,
+                immediate_free: false,
+                #[cfg(feature = "std")]
+                quarantine: None,
+                tag_mask: 0,
                 retired: LockFreeList::new(),
+                retired_intrusive: IntrusiveList::new(),
                 reclaim_strategy,
+                configured_strategy: AtomicPtr::new(core::ptr::null_mut()),
+                strategy_readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+                strategy_epoch: AtomicUsize::new(0),
+                replacing_strategy: AtomicBool::new(false),
+                notifications: LockFreeList::new(),
+                deferred: LockFreeList::new(),
+                frame: AtomicUsize::new(1),
+                reclaim_passes: AtomicUsize::new(0),
+                #[cfg(feature = "std")]
+                oldest_retired_at: AtomicU64::new(u64::MAX),
+                #[cfg(feature = "stats")]
+                reclaim_counters: ReclaimCounters::new(),
+                #[cfg(all(feature = "stats", feature = "std"))]
+                dwell_counters: DwellCounters::new(),
+                #[cfg(feature = "reclaim-history")]
+                reclaim_history: ReclaimHistory::new(history::DEFAULT_HISTORY_LEN),
             }
         }
     );
 
-    pub(crate) fn acquire_haz_ptr(&self) -> HazardPointer {
-        if let Some(haz_ptr) = self.hazard_ptrs.get_available() {
-            HazardPointer::new(haz_ptr)
-        } else {
-            self.acquire_new_haz_ptr()
+    fn hazard_ptrs(&self) -> &HazardPointers {
+        match self.parent_hazard_ptrs {
+            Some(hazard_ptrs) => hazard_ptrs,
+            None => &self.hazard_ptrs,
         }
     }
 
+    pub(crate) fn acquire_haz_ptr(&self) -> HazardPointer<'_> {
+        HazardPointer::new(self.hazard_ptrs().acquire())
+    }
+
+    /// Acquires a hazard pointer without allocating, returning `None` if every reserved hazard
+    /// pointer is in use.
+    pub(crate) fn try_acquire_haz_ptr(&self) -> Option<HazardPointer<'_>> {
+        self.hazard_ptrs().try_acquire().map(HazardPointer::new)
+    }
+
+    /// Acquires a hazard pointer, returning `None` rather than allocating past the domain's
+    /// hazard pointer limit.
+    pub(crate) fn acquire_haz_ptr_within_limit(&self) -> Option<HazardPointer<'_>> {
+        self.hazard_ptrs()
+            .acquire_within(self.hazard_ptr_limit)
+            .map(HazardPointer::new)
+    }
+
+    /// Preallocates hazard pointers so that at least `count` are available without allocating.
+    ///
+    /// Hazard pointers are allocated in blocks, so more than `count` may be allocated. Reserving
+    /// hazard pointers allows [`AtomBoxIn::try_load`](crate::AtomBoxIn::try_load) to be used where
+    /// allocation is not possible, such as in interrupt handlers, provided no more than `count`
+    /// values are protected at once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// CUSTOM_DOMAIN.reserve_hazard_pointers(4);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain(1, &CUSTOM_DOMAIN);
+    /// assert_eq!(*atom_box.try_load().unwrap(), 1);
+    /// ```
+    pub fn reserve_hazard_pointers(&self, count: usize) {
+        self.hazard_ptrs().reserve(count);
+    }
+
     pub(crate) fn release_hazard_ptr(&self, haz_ptr: HazardPointer) {
-        haz_ptr.reset();
-        self.hazard_ptrs.set_node_available(haz_ptr.0);
+        self.release_hazard_ptrs(core::iter::once(haz_ptr));
     }
 
-    fn acquire_new_haz_ptr(&self) -> HazardPointer {
-        HazardPointer::new(
-            self.hazard_ptrs
-                .push_in_use(AtomicPtr::new(core::ptr::null_mut())),
-        )
+    /// Releases a batch of hazard pointers, checking for stale retired values once for the whole
+    /// batch.
+    pub(crate) fn release_hazard_ptrs<'a>(
+        &'a self,
+        haz_ptrs: impl Iterator<Item = HazardPointer<'a>>,
+    ) {
+        for haz_ptr in haz_ptrs {
+            haz_ptr.reset();
+            #[cfg(all(feature = "stats", feature = "std"))]
+            self.dwell_counters.record(haz_ptr.2.elapsed());
+            self.hazard_ptrs().release(haz_ptr.0);
+        }
+        // Values are rarely retired when traffic is quiet, so releasing a hazard pointer is also
+        // an opportunity to reclaim values which have been retired for too long.
+        if self.retired_too_long() {
+            #[cfg(feature = "std")]
+            self.record_reclaim_decision(Some(ReclaimTrigger::MaxAge));
+            self.bulk_reclaim();
+        }
     }
 
     /// Places a pointer on the retire list to be safely reclaimed when no hazard pointers are
@@ -183,75 +789,1272 @@ On nightly this will panic if the domain id is equal to the shared domain's id (
     /// Value must be associated with this domain.
     /// Value must be able to live as long as the domain.
     pub(crate) unsafe fn retire<T>(&self, value: *mut T) {
-        core::sync::atomic::fence(Ordering::SeqCst);
+        unsafe { self.retire_with_policy(value, RetirePolicy::Domain) };
+    }
 
-        self.retired.push(Retire::new(value));
-        if self.should_reclaim() {
-            self.bulk_reclaim();
+    /// Places a value on the retire list as for [`Domain::retire`], or frees it straight away
+    /// if `retire_policy` allows and no hazard pointer protects it.
+    ///
+    /// # Safety
+    ///
+    /// As for [`Domain::retire`].
+    pub(crate) unsafe fn retire_with_policy<T>(&self, value: *mut T, retire_policy: RetirePolicy) {
+        if !needs_reclaim::<T>() {
+            return;
         }
+        // # Safety
+        //
+        // The value was created via `Box::<T>::into_raw`, according to the safety contract of
+        // this function.
+        unsafe {
+            self.retire_erased_with_policy(
+                value as *mut usize,
+                reclaim_box::<T>,
+                RetiredType::of::<T>(),
+                retire_policy,
+            )
+        };
     }
 
-    fn should_reclaim(&self) -> bool {
-        self.reclaim_strategy.should_reclaim(
-            self.retired.count.load(Ordering::Acquire),
-            self.retired.count.load(Ordering::Acquire),
-        )
+    /// Places a dynamically sized value of `size` bytes on the retire list, to be reclaimed by
+    /// calling `reclaim` when no hazard pointers are referencing it.
+    ///
+    /// # Safety
+    ///
+    /// As for [`Domain::retire`], and `reclaim` must be safe to call with `value` once it is no
+    /// longer protected.
+    pub(crate) unsafe fn retire_unsized<T: ?Sized>(
+        &self,
+        value: *mut usize,
+        reclaim: unsafe fn(*mut usize),
+        size: usize,
+    ) {
+        unsafe { self.retire_erased(value, reclaim, RetiredType::of_unsized::<T>(size)) };
     }
 
-    /// Reclaim all unprotected retired items.
+    /// Places a type erased pointer on the retire list, to be reclaimed by calling `reclaim`
+    /// when no hazard pointers are referencing it.
     ///
+    /// # Safety
     ///
-    /// # Example
-    ///
-    /// ```
-    /// use atom_box::{AtomBox, domain::{Domain, ReclaimStrategy}};
+    /// As for [`Domain::retire`], and `reclaim` must be safe to call with `value` once it is no
+    /// longer protected. `retired_type` describes the value.
+    unsafe fn retire_erased(
+        &self,
+        value: *mut usize,
+        reclaim: unsafe fn(*mut usize),
+        retired_type: RetiredType,
+    ) {
+        unsafe {
+            self.retire_erased_with_policy(value, reclaim, retired_type, RetirePolicy::Domain)
+        };
+    }
+
+    unsafe fn retire_erased_with_policy(
+        &self,
+        value: *mut usize,
+        reclaim: unsafe fn(*mut usize),
+        retired_type: RetiredType,
+        retire_policy: RetirePolicy,
+    ) {
+        if self.leaks_retired() {
+            return;
+        }
+        crate::sync::fence(Ordering::SeqCst);
+
+        if (self.immediate_free || retire_policy == RetirePolicy::Immediate)
+            && !self.quarantines_retired()
+            // Callbacks are only invoked for values reclaimed from the retired list.
+            && self.notifications.count.load(Ordering::Acquire) == 0
+            && !self.is_protected(value)
+        {
+            // # Safety
+            //
+            // Values are only retired once they can no longer be loaded, and no hazard pointer
+            // protects this one, so no reader can still be accessing it.
+            unsafe { reclaim(value) };
+            self.record_reclaimed(retired_type, self.retire_timestamp());
+            return;
+        }
+
+        #[cfg(feature = "log")]
+        log::trace!("Retired {:p} in {}", value, self.display_name());
+        #[cfg(feature = "defmt")]
+        defmt::trace!("Retired {} in {}", value, self.name);
+        #[cfg(feature = "leak-audit")]
+        crate::leak_audit::track(
+            crate::leak_audit::AllocationKind::Retired,
+            retired_type.size,
+        );
+        let retired_at = self.retire_timestamp();
+        self.retired.push(Retire {
+            ptr: value,
+            reclaim,
+            retired_at,
+            retired_type,
+        });
+        self.track_retired_at(retired_at.nanos().unwrap_or(u64::MAX));
+        self.reclaim_if_needed();
+    }
+
+    /// Places a value which embeds its own [`RetireLink`] on the retire list, to be safely
+    /// reclaimed when no hazard pointers are referencing it.
     ///
-    /// const CUSTOM_DOMAIN_ID: usize = 42;
-    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    /// Unlike retiring other values, this does not allocate.
     ///
-    /// let atom_box = AtomBox::new_with_domain("Hello World", &CUSTOM_DOMAIN);
-    /// atom_box.swap("Goodbye World");
+    /// # Safety
     ///
-    /// CUSTOM_DOMAIN.reclaim();
-    /// ```
-    pub fn reclaim(&self) -> usize {
-        self.bulk_reclaim()
-    }
-
-    fn bulk_reclaim(&self) -> usize {
-        let retired_list = self
-            .retired
-            .head
-            .swap(core::ptr::null_mut(), Ordering::Acquire);
+    /// The value must have been created via `Box::into_raw` and must no longer be reachable by
+    /// threads which have not already protected it. No-one else may call retire on the same
+    /// value, and the value must be able to live as long as the domain.
+    pub unsafe fn retire_intrusive<T: IntrusiveRetire>(&self, value: *mut T) {
+        if self.leaks_retired() {
+            return;
+        }
+        crate::sync::fence(Ordering::SeqCst);
 
-        core::sync::atomic::fence(Ordering::SeqCst);
+        // # Safety
+        //
+        // The value is valid according to the safety contract of this function, and the trait
+        // guarantees the link is embedded within it.
+        #[cfg(feature = "log")]
+        log::trace!("Retired {:p} in {}", value, self.display_name());
+        #[cfg(feature = "defmt")]
+        defmt::trace!("Retired {} in {}", value, self.name);
+        #[cfg(feature = "leak-audit")]
+        crate::leak_audit::track(
+            crate::leak_audit::AllocationKind::Retired,
+            core::mem::size_of::<T>(),
+        );
+        let retired_at = self.retire_timestamp();
+        unsafe {
+            self.retired_intrusive
+                .push((*value).retire_link(), value, retired_at)
+        };
+        self.retired.count.fetch_add(1, Ordering::Release);
+        self.track_retired_at(retired_at.nanos().unwrap_or(u64::MAX));
+        self.reclaim_if_needed();
+    }
 
-        self.retired.count.store(0, Ordering::Release);
-        if retired_list.is_null() {
-            return 0;
+    /// Calls `f` with the strategy used by this domain, which can no longer be configured once
+    /// this is called.
+    fn with_reclaim_strategy<R>(&self, f: impl FnOnce(&ReclaimStrategy) -> R) -> R {
+        let mut configured = self.configured_strategy.load(Ordering::Acquire);
+        if configured.is_null() {
+            configured = match self.configured_strategy.compare_exchange(
+                core::ptr::null_mut(),
+                strategy_frozen(),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => strategy_frozen(),
+                Err(configured) => configured,
+            };
         }
-        let guarded_ptrs = self.get_guarded_ptrs();
-        self.reclaim_unguarded(guarded_ptrs, retired_list)
+        if configured == strategy_frozen() {
+            return f(&self.reclaim_strategy);
+        }
+        let readers = loop {
+            let epoch = self.strategy_epoch.load(Ordering::SeqCst);
+            let readers = &self.strategy_readers[epoch & 1];
+            readers.fetch_add(1, Ordering::SeqCst);
+            crate::sync::fence(Ordering::SeqCst);
+            if self.strategy_epoch.load(Ordering::SeqCst) == epoch {
+                break readers;
+            }
+            // A replacement may already be waiting for the other counter to drain.
+            readers.fetch_sub(1, Ordering::Release);
+        };
+        // # Safety
+        //
+        // Replaced strategies are only dropped once the readers counted before they were
+        // replaced have finished, and this reader is counted.
+        let result = f(unsafe { &*self.configured_strategy.load(Ordering::SeqCst) });
+        readers.fetch_sub(1, Ordering::Release);
+        result
     }
 
-    fn reclaim_unguarded(
+    /// Replaces the strategy the domain was created with, unless the domain has already consulted
+    /// its strategy or been configured.
+    ///
+    /// The strategy is consulted when values are retired or reclaimed, or hazard pointers are
+    /// released. If it is too late to configure the domain, `reclaim_strategy` is returned.
+    #[cfg(not(loom))]
+    pub(crate) fn configure_reclaim_strategy(
         &self,
-        guarded_ptrs: Set<*const usize>,
-        retired_list: *mut Node<Retire>,
-    ) -> usize {
-        let mut node_ptr = retired_list;
-        let mut still_retired = core::ptr::null_mut();
-        let mut tail_ptr = None;
-        let mut reclaimed = 0;
-        let mut number_remaining = 0;
+        reclaim_strategy: ReclaimStrategy,
+    ) -> Result<(), ReclaimStrategy> {
+        let configured = Box::into_raw(Box::new(reclaim_strategy));
+        match self.configured_strategy.compare_exchange(
+            core::ptr::null_mut(),
+            configured,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => Ok(()),
+            // # Safety
+            //
+            // The strategy was not published, so we still own it.
+            Err(_) => Err(*unsafe { Box::from_raw(configured) }),
+        }
+    }
+
+    /// Replaces the domain's reclamation strategy while it is in use.
+    ///
+    /// Values which have already been retired are reclaimed according to the new strategy. Threads
+    /// which are concurrently retiring values may still follow the previous strategy for the value
+    /// they are retiring. The replaced strategy is dropped once those threads have finished
+    /// consulting it, which this waits for, so this is intended for occasional changes, such as in
+    /// response to an operator, rather than to be called in a loop.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain("Hello", &CUSTOM_DOMAIN);
+    /// atom_box.store("World");
+    ///
+    /// CUSTOM_DOMAIN.set_reclaim_strategy(ReclaimStrategy::Eager);
+    /// atom_box.store("Goodbye");
+    ///
+    /// // Both replaced values were reclaimed eagerly.
+    /// assert_eq!(CUSTOM_DOMAIN.reclaim(), 0);
+    /// ```
+    pub fn set_reclaim_strategy(&self, reclaim_strategy: ReclaimStrategy) {
+        let configured = Box::into_raw(Box::new(reclaim_strategy));
+        while self
+            .replacing_strategy
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            backoff();
+        }
+        let replaced = self.configured_strategy.swap(configured, Ordering::SeqCst);
+        if !replaced.is_null() && replaced != strategy_frozen() {
+            // Readers which may hold the replaced strategy are counted in either counter, so each
+            // is drained in turn while new readers are directed to the other.
+            for _ in 0..2 {
+                let epoch = self.strategy_epoch.fetch_add(1, Ordering::SeqCst);
+                crate::sync::fence(Ordering::SeqCst);
+                while self.strategy_readers[epoch & 1].load(Ordering::SeqCst) != 0 {
+                    backoff();
+                }
+            }
+            // # Safety
+            //
+            // The replaced strategy was configured via a box, has been swapped out, and every
+            // reader which may have loaded it has finished.
+            drop(unsafe { Box::from_raw(replaced) });
+        }
+        self.replacing_strategy.store(false, Ordering::Release);
+    }
+
+    /// The number of frames for which the strategy keeps retired values, if it is frame based.
+    fn frame_delay(&self) -> Option<usize> {
+        self.with_reclaim_strategy(ReclaimStrategy::frame_delay)
+    }
+
+    fn leaks_retired(&self) -> bool {
+        self.with_reclaim_strategy(|strategy| matches!(strategy, ReclaimStrategy::LeakAll))
+    }
+
+    fn reclaim_if_needed(&self) {
+        let trigger = self.should_reclaim();
+        self.record_reclaim_decision(trigger);
+        if trigger.is_some() || forced_reclaim() {
+            self.bulk_reclaim();
+        }
+    }
+
+    fn should_reclaim(&self) -> Option<ReclaimTrigger> {
+        let trigger = self.with_reclaim_strategy(|strategy| {
+            strategy.should_reclaim(
+                self.retired.count.load(Ordering::Acquire),
+                self.retired.count.load(Ordering::Acquire),
+            )
+        });
+        #[cfg(feature = "std")]
+        if trigger.is_none() && self.retired_too_long() {
+            return Some(ReclaimTrigger::MaxAge);
+        }
+        trigger
+    }
+
+    #[cfg(feature = "stats")]
+    fn record_reclaim_decision(&self, trigger: Option<ReclaimTrigger>) {
+        self.reclaim_counters.record(trigger);
+    }
+
+    #[cfg(not(feature = "stats"))]
+    #[inline(always)]
+    fn record_reclaim_decision(&self, _trigger: Option<ReclaimTrigger>) {}
+
+    /// Returns a snapshot of why this domain has reclaimed its retired items.
+    ///
+    /// Reclamations run when the domain is dropped are not counted.
+    #[cfg(feature = "stats")]
+    pub fn reclaim_stats(&self) -> ReclaimStats {
+        self.reclaim_counters.snapshot()
+    }
+
+    /// Spawns a thread which invokes `callback` with a [`StatsReport`] of this domain every
+    /// `interval`, until the returned [`StatsReporter`] is dropped.
+    ///
+    /// Each report includes the retired backlog, the values reclaimed since the previous report
+    /// and the hazard pointers in use, giving small services some observability without a
+    /// metrics stack. Only domains which live for the rest of the process, such as statics, can
+    /// be reported on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::domain::{Domain, ReclaimStrategy};
+    /// use core::time::Duration;
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> =
+    ///     Domain::new_named("sessions", ReclaimStrategy::Eager);
+    ///
+    /// let reporter = CUSTOM_DOMAIN.spawn_stats_reporter(Duration::from_secs(60), |report| {
+    ///     eprintln!("{report}");
+    /// });
+    /// ```
+    #[cfg(feature = "stats-reporter")]
+    pub fn spawn_stats_reporter(
+        &'static self,
+        interval: Duration,
+        callback: impl FnMut(&StatsReport) + Send + 'static,
+    ) -> StatsReporter {
+        reporter::spawn(self, interval, callback)
+    }
+
+    /// Spawns a thread which logs a [`StatsReport`] of this domain at the info level every
+    /// `interval`, until the returned [`StatsReporter`] is dropped.
+    ///
+    /// See [`Domain::spawn_stats_reporter`].
+    #[cfg(all(feature = "stats-reporter", feature = "log"))]
+    pub fn spawn_stats_logger(&'static self, interval: Duration) -> StatsReporter {
+        self.spawn_stats_reporter(interval, |report| log::info!("{}", report))
+    }
+
+    /// Returns a histogram of how long this domain's hazard pointers were held before being
+    /// released.
+    #[cfg(all(feature = "stats", feature = "std"))]
+    pub fn hazard_dwell_histogram(&self) -> DwellHistogram {
+        self.dwell_counters.snapshot()
+    }
+
+    /// The time at which a value retired now should be recorded as retired.
+    ///
+    /// Reading the clock is only worthwhile if the strategy limits the age of retired values,
+    /// values are quarantined, or reclaimed values are recorded.
+    fn retire_timestamp(&self) -> RetiredAt {
+        if self.frame_delay().is_some() {
+            return RetiredAt::Frame(self.frame.load(Ordering::Acquire) as u64);
+        }
+        #[cfg(feature = "std")]
+        if self.with_reclaim_strategy(|strategy| strategy.max_retired_age().is_some())
+            || self.quarantine.is_some()
+            || cfg!(feature = "reclaim-history")
+        {
+            return RetiredAt::Nanos(reclaim_strategy::now_nanos());
+        }
+        RetiredAt::Untracked
+    }
+
+    #[inline(always)]
+    fn record_reclaimed(&self, _retired_type: RetiredType, _retired_at: RetiredAt) {
+        #[cfg(feature = "stats")]
+        self.reclaim_counters.record_reclaimed();
+        #[cfg(feature = "reclaim-history")]
+        self.record_history(_retired_type, _retired_at);
+    }
+
+    #[cfg(feature = "reclaim-history")]
+    fn record_history(&self, retired_type: RetiredType, retired_at: RetiredAt) {
+        let from_nanos = |nanos| std::time::UNIX_EPOCH + Duration::from_nanos(nanos);
+        let retired_at = retired_at.nanos().map(from_nanos);
+        self.reclaim_history.record(ReclaimRecord {
+            type_name: retired_type.name,
+            size: retired_type.size,
+            retired_at,
+            reclaimed_at: from_nanos(reclaim_strategy::now_nanos()),
+        });
+    }
+
+    /// Returns the values most recently reclaimed by this domain, oldest first.
+    ///
+    /// Up to 64 values are remembered, unless configured otherwise with
+    /// [`Domain::with_reclaim_history_len`]. Values reclaimed when the domain is dropped are not
+    /// included. Each reclamation is recorded under a lock, so the `reclaim-history` feature is
+    /// intended for debugging rather than production builds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain(1_u32, &CUSTOM_DOMAIN);
+    /// atom_box.store(2);
+    /// CUSTOM_DOMAIN.reclaim();
+    ///
+    /// let reclaims = CUSTOM_DOMAIN.recent_reclaims();
+    /// assert_eq!(reclaims[0].type_name, "u32");
+    /// assert!(reclaims[0].retired_at.unwrap() <= reclaims[0].reclaimed_at);
+    /// ```
+    #[cfg(feature = "reclaim-history")]
+    pub fn recent_reclaims(&self) -> alloc::vec::Vec<ReclaimRecord> {
+        self.reclaim_history.snapshot()
+    }
+
+    /// Returns the protections currently published in the domain's hazard pointers.
+    ///
+    /// This allows tools such as allocators and leak detectors to tell memory which is still being
+    /// read apart from memory which has leaked. A value whose address is returned may not be
+    /// reclaimed until the protection is released. The result is only a snapshot, since readers
+    /// publish and release protections concurrently.
+    ///
+    /// Requires the `introspection` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ProtectedPtr, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain(String::from("config"), &CUSTOM_DOMAIN);
+    /// let guard = atom_box.load();
+    /// let address = &*guard as *const String as *const ();
+    ///
+    /// assert!(CUSTOM_DOMAIN.protected_ptrs().any(|ptr| ptr == ProtectedPtr::Ptr(address)));
+    ///
+    /// drop(guard);
+    /// assert_eq!(CUSTOM_DOMAIN.protected_ptrs().count(), 0);
+    /// ```
+    #[cfg(feature = "introspection")]
+    pub fn protected_ptrs(&self) -> impl Iterator<Item = ProtectedPtr> + '_ {
+        crate::sync::fence(Ordering::SeqCst);
+        self.hazard_ptrs()
+            .iter()
+            .filter_map(move |haz_ptr| self.protected_ptr(haz_ptr))
+    }
+
+    /// What `haz_ptr` protects, if anything.
+    #[cfg(feature = "introspection")]
+    fn protected_ptr(&self, haz_ptr: &AtomicPtr<usize>) -> Option<ProtectedPtr> {
+        let guarded_ptr = self.guarded_ptr(haz_ptr);
+        if guarded_ptr.is_null() {
+            None
+        } else if guarded_ptr == PINNED {
+            Some(ProtectedPtr::All)
+        } else {
+            Some(ProtectedPtr::Ptr(guarded_ptr as *const ()))
+        }
+    }
+
+    /// Returns the hazard pointers currently in use in the domain, and the threads which acquired
+    /// them.
+    ///
+    /// When retired values are not being reclaimed because some guard is being held, this tells
+    /// which thread is holding it, and the [label](set_guard_label) it had set. Child domains
+    /// report the hazard pointers they share with their parent. The result is only a snapshot,
+    /// since hazard pointers are acquired and released concurrently.
+    ///
+    /// Requires the `thread-attribution` feature. Acquiring or releasing a hazard pointer only
+    /// records its owner with relaxed stores, but each thread takes a lock once, to register
+    /// itself, and setting a label takes a lock to intern it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain("Hello", &CUSTOM_DOMAIN);
+    /// let (held, holding) = std::sync::mpsc::channel();
+    /// let (release, released) = std::sync::mpsc::channel();
+    ///
+    /// std::thread::scope(|scope| {
+    ///     std::thread::Builder::new()
+    ///         .name("straggler".into())
+    ///         .spawn_scoped(scope, move || {
+    ///             let _guard = atom_box.load();
+    ///             held.send(()).unwrap();
+    ///             released.recv().unwrap();
+    ///         })
+    ///         .unwrap();
+    ///     holding.recv().unwrap();
+    ///
+    ///     let owners = CUSTOM_DOMAIN.hazard_owners();
+    ///     assert_eq!(owners[0].thread_name.as_deref(), Some("straggler"));
+    ///     release.send(()).unwrap();
+    /// });
+    /// ```
+    #[cfg(feature = "thread-attribution")]
+    pub fn hazard_owners(&self) -> alloc::vec::Vec<HazardOwner> {
+        crate::sync::fence(Ordering::SeqCst);
+        self.hazard_ptrs()
+            .slots()
+            .filter_map(|slot| {
+                // The slot is released between being found in use and its owner being read.
+                // The owning thread may also have exited, leaking its guard.
+                let owner = slot.owner()?;
+                HazardOwner::new(owner, self.protected_ptr(slot))
+            })
+            .collect()
+    }
+
+    /// Whether retired values must be kept for a minimum time before they are reclaimed.
+    #[cfg(feature = "std")]
+    fn quarantines_retired(&self) -> bool {
+        self.quarantine.is_some() && self.frame_delay().is_none()
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[inline(always)]
+    fn quarantines_retired(&self) -> bool {
+        false
+    }
+
+    /// Tracks a value timed as retired `oldest_nanos` after the unix epoch, or none if it is
+    /// `u64::MAX`.
+    #[cfg(feature = "std")]
+    fn track_retired_at(&self, oldest_nanos: u64) {
+        if oldest_nanos != u64::MAX {
+            self.oldest_retired_at
+                .fetch_min(oldest_nanos, Ordering::AcqRel);
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[inline(always)]
+    fn track_retired_at(&self, _oldest_nanos: u64) {}
+
+    /// Whether the oldest retired value has exceeded the strategy's maximum age.
+    #[cfg(feature = "std")]
+    fn retired_too_long(&self) -> bool {
+        let max_retired_age = match self.with_reclaim_strategy(ReclaimStrategy::max_retired_age) {
+            Some(max_retired_age) => max_retired_age,
+            None => return false,
+        };
+        let oldest_retired_at = self.oldest_retired_at.load(Ordering::Acquire);
+        oldest_retired_at != u64::MAX
+            && u128::from(reclaim_strategy::now_nanos().saturating_sub(oldest_retired_at))
+                >= max_retired_age.as_nanos()
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[inline(always)]
+    fn retired_too_long(&self) -> bool {
+        false
+    }
+
+    /// Reclaim all unprotected retired items.
+    ///
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain("Hello World", &CUSTOM_DOMAIN);
+    /// atom_box.swap("Goodbye World");
+    ///
+    /// CUSTOM_DOMAIN.reclaim();
+    /// ```
+    pub fn reclaim(&self) -> usize {
+        self.record_reclaim_decision(Some(ReclaimTrigger::Manual));
+        self.bulk_reclaim()
+    }
+
+    /// Returns a future which reclaims all unprotected retired items, resolving to the number
+    /// reclaimed.
+    ///
+    /// Unlike [`Domain::reclaim`], the retired items are processed a chunk at a time, with the
+    /// future yielding to the executor between chunks, so that services using
+    /// [`ReclaimStrategy::Manual`] can schedule reclamation on their runtime without blocking a
+    /// worker thread for a full sweep. The size of the chunks can be set with
+    /// [`ReclaimFuture::chunk_size`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// async fn maintenance() -> usize {
+    ///     CUSTOM_DOMAIN.reclaim_async().chunk_size(16).await
+    /// }
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain("Hello World", &CUSTOM_DOMAIN);
+    /// atom_box.swap("Goodbye World");
+    ///
+    /// # struct NoopWake;
+    /// # impl std::task::Wake for NoopWake {
+    /// #     fn wake(self: std::sync::Arc<Self>) {}
+    /// # }
+    /// # let waker = std::task::Waker::from(std::sync::Arc::new(NoopWake));
+    /// # let mut context = std::task::Context::from_waker(&waker);
+    /// # let mut future = std::pin::pin!(maintenance());
+    /// # let reclaimed = loop {
+    /// #     if let std::task::Poll::Ready(reclaimed) = future.as_mut().poll(&mut context) {
+    /// #         break reclaimed;
+    /// #     }
+    /// # };
+    /// # use std::future::Future;
+    /// assert_eq!(reclaimed, 1);
+    /// ```
+    pub fn reclaim_async(&self) -> ReclaimFuture<'_, DOMAIN_ID> {
+        ReclaimFuture::new(self)
+    }
+
+    /// Ends the current frame, returning the number of values reclaimed.
+    ///
+    /// If the domain uses [`ReclaimStrategy::FrameBased`], the unprotected values retired the
+    /// configured number of frames ago, or earlier, are reclaimed. Otherwise, this only advances
+    /// the frame count.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const FRAME_DOMAIN_ID: usize = 42;
+    /// static FRAME_DOMAIN: Domain<FRAME_DOMAIN_ID> = Domain::new(ReclaimStrategy::FrameBased(2));
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain("Hello World", &FRAME_DOMAIN);
+    /// atom_box.store("Goodbye World");
+    ///
+    /// assert_eq!(FRAME_DOMAIN.advance_frame(), 0);
+    /// assert_eq!(FRAME_DOMAIN.advance_frame(), 1);
+    /// ```
+    pub fn advance_frame(&self) -> usize {
+        let frame = self.frame.fetch_add(1, Ordering::AcqRel) + 1;
+        match self.frame_delay() {
+            Some(delay) => {
+                self.record_reclaim_decision(Some(ReclaimTrigger::Frame));
+                self.bulk_reclaim_retired_by(RetiredAt::Frame(frame.saturating_sub(delay) as u64))
+            }
+            None => 0,
+        }
+    }
+
+    /// Registers `callback` to be invoked once the value at `ptr` has been reclaimed.
+    ///
+    /// This allows resources associated with a value to be released only once no reader can still
+    /// be accessing the value. The callback is invoked by whichever thread reclaims the value,
+    /// after the value has been dropped.
+    ///
+    /// The value must be retired in this domain after the callback is registered, for example
+    /// by registering the callback while holding the [`StoreGuard`](crate::StoreGuard) of the
+    /// replaced value. Callbacks for a value handed back by
+    /// [`AtomBoxIn::replace_owned`](crate::AtomBoxIn::replace_owned) are invoked before it is
+    /// returned. Callbacks for values which are never reclaimed by this domain are dropped without
+    /// being invoked when the domain is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain(String::from("Hello"), &CUSTOM_DOMAIN);
+    /// let reclaimed = Arc::new(AtomicBool::new(false));
+    ///
+    /// let old_value = atom_box.swap(String::from("World"));
+    /// let notified = reclaimed.clone();
+    /// CUSTOM_DOMAIN.notify_on_reclaim(&*old_value, move || notified.store(true, Ordering::SeqCst));
+    /// drop(old_value);
+    ///
+    /// assert!(reclaimed.load(Ordering::SeqCst));
+    /// ```
+    pub fn notify_on_reclaim<T>(&self, ptr: *const T, callback: impl FnOnce() + Send + 'static) {
+        self.notifications
+            .push(Notification::new(ptr as *const usize, Box::new(callback)));
+    }
+
+    /// Invokes the callbacks registered for the value at `ptr`, which no reader can access any
+    /// more but which is handed back to its owner rather than reclaimed.
+    #[cfg(feature = "std")]
+    pub(crate) fn notify_released<T>(&self, ptr: *const T) {
+        if self.notifications.count.load(Ordering::Acquire) == 0 {
+            return;
+        }
+        let mut notifications = Notifications::take(&self.notifications);
+        notifications.reclaimed(ptr as *const usize);
+        notifications.finish(&self.notifications);
+    }
+
+    /// Blocks until every hazard pointer which protected a value retired in this domain when this
+    /// was called has been released or moved on to another value.
+    ///
+    /// Once a value has been swapped out of every box, no new guards to it can be loaded, so after
+    /// `synchronize` returns no reader can still be accessing it. This allows resources which are
+    /// referenced by, but not owned by, the replaced value to be freed immediately, without waiting
+    /// for the value itself to be reclaimed. Only the hazard pointers protecting retired values
+    /// when this was called are waited for, so guards to values still stored in a box, and hazard
+    /// pointers acquired afterwards, do not hold it up. A replaced value is only retired once its
+    /// [`StoreGuard`](crate::StoreGuard) has been dropped.
+    ///
+    /// This must not be called while the calling thread holds a guard from this domain (or any
+    /// domain sharing its hazard pointers) to a retired value, as it would wait for that guard
+    /// forever.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain("Hello World", &CUSTOM_DOMAIN);
+    /// let old_value = atom_box.swap("Goodbye World");
+    /// drop(old_value);
+    ///
+    /// CUSTOM_DOMAIN.synchronize();
+    /// // No reader can still observe "Hello World".
+    /// ```
+    pub fn synchronize(&self) {
+        let retired_ptrs = self.retired_ptrs();
+        if retired_ptrs.is_empty() {
+            return;
+        }
+        crate::sync::fence(Ordering::SeqCst);
+        let protected: alloc::vec::Vec<(&AtomicPtr<usize>, *mut usize)> = self
+            .hazard_ptrs()
+            .iter()
+            .filter_map(|haz_ptr| {
+                let protected_ptr = haz_ptr.load(Ordering::Acquire);
+                let guarded_ptr = self.untagged(protected_ptr);
+                (guarded_ptr == PINNED || retired_ptrs.contains(&(guarded_ptr as *const usize)))
+                    .then_some((haz_ptr, protected_ptr))
+            })
+            .collect();
+        for (haz_ptr, protected_ptr) in protected {
+            while haz_ptr.load(Ordering::Acquire) == protected_ptr {
+                backoff();
+            }
+        }
+    }
+
+    /// Gathers the pointers of the values currently retired in this domain.
+    ///
+    /// The retired lists are taken while they are walked and put back afterwards, so no value is
+    /// reclaimed in the meantime. Values put back are not counted again.
+    fn retired_ptrs(&self) -> Set<*const usize> {
+        let mut retired_ptrs = Set::default();
+        let retired_list = self
+            .retired
+            .head
+            .swap(core::ptr::null_mut(), Ordering::Acquire);
+        let mut node_ptr = retired_list;
+        let mut tail_ptr = None;
+        while !node_ptr.is_null() {
+            // # Safety
+            //
+            // We have exclusive access to the list of retired pointers.
+            let node = unsafe { &*node_ptr };
+            retired_ptrs.insert(node.value.ptr as *const usize);
+            tail_ptr = Some(&node.next);
+            node_ptr = node.next.load(Ordering::Relaxed);
+        }
+        if let Some(tail) = tail_ptr {
+            // # Safety
+            //
+            // All of the nodes in this list were owned by the retired list, and are still counted
+            // by it.
+            unsafe { self.retired.push_all(retired_list, tail, 0) };
+        }
+
+        let retired_intrusive_list = self
+            .retired_intrusive
+            .head
+            .swap(core::ptr::null_mut(), Ordering::Acquire);
+        let mut link_ptr = retired_intrusive_list;
+        let mut tail = None;
+        while !link_ptr.is_null() {
+            // # Safety
+            //
+            // We have exclusive access to the list of retired values, and the value owning the
+            // link has not yet been reclaimed.
+            let link = unsafe { &*link_ptr };
+            let (retired, next) = unsafe { intrusive::retired(link) };
+            retired_ptrs.insert(retired.ptr as *const usize);
+            tail = Some(link);
+            link_ptr = next;
+        }
+        if let Some(tail) = tail {
+            // # Safety
+            //
+            // All of the values in this list were owned by the retired list. We are putting them
+            // back in.
+            unsafe {
+                self.retired_intrusive
+                    .push_all(retired_intrusive_list, tail)
+            };
+        }
+        retired_ptrs
+    }
+
+    /// Defers `callback` until every hazard pointer which protected a value when this was called
+    /// has been released or moved on to another value.
+    ///
+    /// This is a non-blocking [`synchronize`](Domain::synchronize): once a value has been swapped
+    /// out of every box, side effects tied to it, such as closing a file descriptor it refers to,
+    /// can be deferred until no reader can still be accessing it. If no value is protected, the
+    /// callback is invoked straight away. Otherwise it is invoked by a later reclamation of the
+    /// domain, after the hazard pointers it waits on have moved on, or when the domain is
+    /// dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain("/tmp/a.sock", &CUSTOM_DOMAIN);
+    /// let reader = atom_box.load();
+    /// drop(atom_box.swap("/tmp/b.sock"));
+    ///
+    /// let closed = Arc::new(AtomicBool::new(false));
+    /// let close = closed.clone();
+    /// CUSTOM_DOMAIN.defer(move || close.store(true, Ordering::SeqCst));
+    /// assert!(!closed.load(Ordering::SeqCst), "A reader may still use the old socket");
+    ///
+    /// drop(reader);
+    /// CUSTOM_DOMAIN.reclaim();
+    /// assert!(closed.load(Ordering::SeqCst));
+    /// ```
+    pub fn defer(&self, callback: impl FnOnce() + Send + 'static) {
+        crate::sync::fence(Ordering::SeqCst);
+        let waiting_on: alloc::vec::Vec<(*const AtomicPtr<usize>, *mut usize)> = self
+            .hazard_ptrs()
+            .iter()
+            .filter_map(|haz_ptr| {
+                let protected_ptr = haz_ptr.load(Ordering::Acquire);
+                (!protected_ptr.is_null())
+                    .then_some((haz_ptr as *const AtomicPtr<usize>, protected_ptr))
+            })
+            .collect();
+        if waiting_on.is_empty() {
+            callback();
+            return;
+        }
+        self.deferred
+            .push(Deferred::new(waiting_on, Box::new(callback)));
+    }
+
+    /// Moves every value retired in this domain onto the retired list of `to`, returning the
+    /// number of values moved.
+    ///
+    /// The values are reclaimed according to the reclamation strategy of `to`. This allows a
+    /// short-lived domain to be torn down while some of its retired values are still guarded,
+    /// leaving them to be reclaimed by a longer-lived domain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the domains do not share their hazard pointers, that is, unless both are
+    /// [children](Domain::new_child) of the same domain or one is a child of the other. The
+    /// transferred values may still be protected by this domain's hazard pointers, so they can
+    /// only be reclaimed safely by a domain which scans them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const PARENT_DOMAIN_ID: usize = 42;
+    /// static PARENT_DOMAIN: Domain<PARENT_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let tenant_domain: Domain<43> = Domain::new_child(&PARENT_DOMAIN, ReclaimStrategy::Manual);
+    /// let atom_box = AtomBoxIn::new_with_domain("Hello World", &tenant_domain);
+    /// atom_box.store("Goodbye World");
+    ///
+    /// assert_eq!(tenant_domain.transfer_retired(&PARENT_DOMAIN), 1);
+    /// assert_eq!(PARENT_DOMAIN.reclaim(), 1);
+    /// ```
+    pub fn transfer_retired<const OTHER_ID: usize>(&self, to: &Domain<OTHER_ID>) -> usize {
+        assert!(
+            core::ptr::eq(self.hazard_ptrs(), to.hazard_ptrs()),
+            "Cannot transfer retired values from domain {} to domain {}, which does not share its \
+             hazard pointers",
+            crate::DomainName(self.name),
+            crate::DomainName(to.name)
+        );
+        #[cfg(feature = "std")]
+        let oldest_retired_at = self.oldest_retired_at.swap(u64::MAX, Ordering::AcqRel);
+        let retired_list = self
+            .retired
+            .head
+            .swap(core::ptr::null_mut(), Ordering::Acquire);
+        let retired_intrusive_list = self
+            .retired_intrusive
+            .head
+            .swap(core::ptr::null_mut(), Ordering::Acquire);
+        // The receiving domain's reclaim passes are not counted by this domain. As in
+        // `begin_bulk_reclaim`, the pass begins once the lists have been taken, so that a value
+        // loaded before it began cannot be among the transferred values without a weak handle
+        // seeing the pass.
+        self.begin_reclaim_pass();
+        crate::sync::fence(Ordering::SeqCst);
+        self.retired.count.swap(0, Ordering::AcqRel);
+        // Callbacks registered for the transferred values move with them, and must be visible to
+        // the receiving domain before the values are.
+        Notifications::take(&self.notifications).finish(&to.notifications);
+
+        let mut transferred = 0;
+        let mut node_ptr = retired_list;
+        let mut tail_ptr = None;
+        while !node_ptr.is_null() {
+            // # Safety
+            //
+            // We have exclusive access to the list of retired pointers.
+            let node = unsafe { &mut *node_ptr };
+            node.value.retired_at = to.transferred_retired_at(node.value.retired_at);
+            tail_ptr = Some(&node.next);
+            transferred += 1;
+            node_ptr = node.next.load(Ordering::Relaxed);
+        }
+        if let Some(tail) = tail_ptr {
+            // # Safety
+            //
+            // The nodes were taken from a retired list, and are guarded by the same hazard
+            // pointers in either domain.
+            unsafe { to.retired.push_all(retired_list, tail, transferred) };
+        }
+
+        let mut intrusive_transferred = 0;
+        let mut link_ptr = retired_intrusive_list;
+        let mut tail = None;
+        while !link_ptr.is_null() {
+            // # Safety
+            //
+            // We have exclusive access to the list of retired values, and the value owning the
+            // link has not yet been reclaimed.
+            let link = unsafe { &*link_ptr };
+            let (retired, _) = unsafe { intrusive::retired(link) };
+            let retired_at = to.transferred_retired_at(retired.retired_at);
+            unsafe { intrusive::set_retired_at(link, retired_at) };
+            tail = Some(link);
+            intrusive_transferred += 1;
+            link_ptr = link.next.load(Ordering::Relaxed);
+        }
+        if let Some(tail) = tail {
+            // # Safety
+            //
+            // The values were taken from a retired list, and are guarded by the same hazard
+            // pointers in either domain.
+            unsafe { to.retired_intrusive.push_all(retired_intrusive_list, tail) };
+            to.retired
+                .count
+                .fetch_add(intrusive_transferred, Ordering::Release);
+        }
+
+        #[cfg(feature = "std")]
+        to.track_retired_at(oldest_retired_at);
+        to.reclaim_if_needed();
+        (transferred + intrusive_transferred) as usize
+    }
+
+    /// When a value retired at `retired_at` in another domain counts as retired in this one.
+    ///
+    /// Frames are counted separately by each domain, so transferred values are treated as
+    /// retired in the current frame.
+    fn transferred_retired_at(&self, retired_at: RetiredAt) -> RetiredAt {
+        match retired_at {
+            RetiredAt::Frame(_) => RetiredAt::Frame(self.frame.load(Ordering::Acquire) as u64),
+            RetiredAt::Untracked | RetiredAt::Nanos(_) => retired_at,
+        }
+    }
+
+    fn bulk_reclaim(&self) -> usize {
+        self.bulk_reclaim_retired_by(self.reclaimable_retired_by())
+    }
+
+    /// The latest `retired_at` of the values which may be reclaimed now.
+    fn reclaimable_retired_by(&self) -> RetiredAt {
+        #[cfg(feature = "std")]
+        if let (Some(quarantine), true) = (self.quarantine, self.quarantines_retired()) {
+            return RetiredAt::Nanos(reclaim_strategy::now_nanos().saturating_sub(
+                core::convert::TryFrom::try_from(quarantine.as_nanos()).unwrap_or(u64::MAX),
+            ));
+        }
+        RetiredAt::Untracked
+    }
+
+    /// Reclaims the unprotected values whose `retired_at` is no later than `retired_by`.
+    fn bulk_reclaim_retired_by(&self, retired_by: RetiredAt) -> usize {
+        let mut pass = match self.begin_bulk_reclaim() {
+            Some(pass) => pass,
+            None => return 0,
+        };
+        let retired_intrusive_list =
+            core::mem::replace(&mut pass.retired_intrusive_list, core::ptr::null_mut());
+        let retired_list = core::mem::replace(&mut pass.retired_list, core::ptr::null_mut());
+        pass.reclaimed = self.reclaim_unguarded_intrusive(
+            &pass.guarded_ptrs,
+            retired_by,
+            retired_intrusive_list,
+            &mut pass.notifications,
+        ) + self.reclaim_unguarded(
+            &pass.guarded_ptrs,
+            retired_by,
+            retired_list,
+            &mut pass.notifications,
+        );
+        self.finish_bulk_reclaim(pass)
+    }
+
+    /// Takes the retired lists and gathers the protected pointers, returning `None` if nothing
+    /// has been retired.
+    fn begin_bulk_reclaim(&self) -> Option<ReclaimPass> {
+        defer::run_ready(&self.deferred);
+        // Values retired from here on are not part of this reclamation. Those which remain
+        // retired afterwards are tracked again when they are put back.
+        #[cfg(feature = "std")]
+        self.oldest_retired_at.store(u64::MAX, Ordering::Release);
+        let retired_list = self
+            .retired
+            .head
+            .swap(core::ptr::null_mut(), Ordering::Acquire);
+        let retired_intrusive_list = self
+            .retired_intrusive
+            .head
+            .swap(core::ptr::null_mut(), Ordering::Acquire);
+        let reclaiming = !retired_list.is_null() || !retired_intrusive_list.is_null();
+        if reclaiming {
+            self.begin_reclaim_pass();
+        }
+
+        crate::sync::fence(Ordering::SeqCst);
+
+        let retired_count = self.retired.count.swap(0, Ordering::AcqRel);
+        if !reclaiming {
+            return None;
+        }
+        // # Safety
+        //
+        // We have exclusive access to the list of retired pointers.
+        #[cfg(feature = "fault-inject")]
+        let retired_list = unsafe { shuffle_retired(retired_list) };
+        #[cfg(feature = "log")]
+        log::debug!(
+            "Reclaiming {} retired values in {}",
+            retired_count,
+            self.display_name()
+        );
+        #[cfg(feature = "defmt")]
+        defmt::debug!(
+            "Reclaiming {} retired values in {}",
+            retired_count,
+            self.name
+        );
+        // Notifications are registered before their value is retired, so every notification for
+        // a value in the lists taken above is visible here.
+        let notifications = Notifications::take(&self.notifications);
+        let guarded_ptrs = self.get_guarded_ptrs();
+        Some(ReclaimPass {
+            retired_list,
+            retired_intrusive_list,
+            guarded_ptrs,
+            notifications,
+            retired_count,
+            reclaimed: 0,
+        })
+    }
+
+    /// Completes a reclamation pass whose retired lists have been fully processed, returning the
+    /// number of values reclaimed.
+    fn finish_bulk_reclaim(&self, pass: ReclaimPass) -> usize {
+        let ReclaimPass {
+            notifications,
+            retired_count: _retired_count,
+            reclaimed,
+            ..
+        } = pass;
+        #[cfg(feature = "log")]
+        log::debug!(
+            "Reclaimed {} of {} retired values in {}",
+            reclaimed,
+            _retired_count,
+            self.display_name()
+        );
+        #[cfg(feature = "defmt")]
+        defmt::debug!(
+            "Reclaimed {} of {} retired values in {}",
+            reclaimed,
+            _retired_count,
+            self.name
+        );
+        notifications.finish(&self.notifications);
+        reclaimed
+    }
+
+    #[cfg(feature = "log")]
+    fn display_name(&self) -> crate::DomainName {
+        crate::DomainName(self.name)
+    }
+
+    fn reclaim_unguarded_intrusive(
+        &self,
+        guarded_ptrs: &Set<*const usize>,
+        retired_by: RetiredAt,
+        retired_list: *mut RetireLink,
+        notifications: &mut Notifications,
+    ) -> usize {
+        let mut link_ptr = retired_list;
+        let mut still_retired: *mut RetireLink = core::ptr::null_mut();
+        let mut tail = None;
+        let mut reclaimable: *mut RetireLink = core::ptr::null_mut();
+        let mut reclaimed = 0;
+        let mut number_remaining = 0;
+        let mut oldest_remaining = u64::MAX;
+        while !link_ptr.is_null() {
+            // # Safety
+            //
+            // We have exclusive access to the list of retired values, and the value owning the
+            // link has not yet been reclaimed.
+            let link = unsafe { &*link_ptr };
+            let (retired, next) = unsafe { intrusive::retired(link) };
+            if is_guarded(guarded_ptrs, retired.ptr) || retired.retired_at.is_later_than(retired_by)
+            {
+                // The value is still guarded keep in the retired list
+                if let Some(nanos) = retired.retired_at.nanos() {
+                    oldest_remaining = oldest_remaining.min(nanos);
+                }
+                link.next.store(still_retired, Ordering::Relaxed);
+                if tail.is_none() {
+                    tail = Some(link);
+                }
+                still_retired = link_ptr;
+                number_remaining += 1;
+            } else {
+                // The value is reclaimed once the retired list has been rebuilt.
+                link.next.store(reclaimable, Ordering::Relaxed);
+                reclaimable = link_ptr;
+                reclaimed += 1;
+            }
+            link_ptr = next;
+        }
+
+        if let Some(tail) = tail {
+            crate::sync::fence(Ordering::SeqCst);
+
+            // # Safety
+            //
+            // All of the values in this list were originally owned by the retired list. We are
+            // putting them back in.
+            unsafe { self.retired_intrusive.push_all(still_retired, tail) };
+            self.retired
+                .count
+                .fetch_add(number_remaining, Ordering::Release);
+            self.track_retired_at(oldest_remaining);
+        }
+
+        // # Safety
+        //
+        // The values were taken from the retired list, and no hazard pointer protects them.
+        unsafe { self.reclaim_intrusive(reclaimable, notifications) };
+        reclaimed
+    }
+
+    /// Reclaims the values linked from `link_ptr`.
+    ///
+    /// This runs user destructors, which may themselves retire values into this domain, so it is
+    /// only called once the retired list has been rebuilt.
+    ///
+    /// # Safety
+    ///
+    /// The values must have been taken from the intrusive retired list and must no longer be
+    /// protected by any hazard pointer.
+    unsafe fn reclaim_intrusive(
+        &self,
+        mut link_ptr: *mut RetireLink,
+        notifications: &mut Notifications,
+    ) {
+        while !link_ptr.is_null() {
+            // # Safety
+            //
+            // We have exclusive access to the queued values, and the value owning the link has not
+            // yet been reclaimed.
+            let link = unsafe { &*link_ptr };
+            let (retired, _) = unsafe { intrusive::retired(link) };
+            // The link is part of the value, so is copied out before the value is reclaimed.
+            let (ptr, reclaim, retired_at, retired_type) = (
+                retired.ptr,
+                retired.reclaim,
+                retired.retired_at,
+                retired.retired_type,
+            );
+            link_ptr = link.next.load(Ordering::Relaxed);
+            #[cfg(feature = "leak-audit")]
+            crate::leak_audit::untrack(
+                crate::leak_audit::AllocationKind::Retired,
+                retired_type.size,
+            );
+            // # Safety
+            //
+            // According to the safety requirements of `retire_intrusive`, the value was allocated
+            // via box, has not been dropped and has only been retired once. It is no longer
+            // protected by any of the hazard pointers.
+            unsafe { reclaim(ptr) };
+            self.record_reclaimed(retired_type, retired_at);
+            notifications.reclaimed(ptr);
+        }
+    }
+
+    fn reclaim_unguarded(
+        &self,
+        guarded_ptrs: &Set<*const usize>,
+        retired_by: RetiredAt,
+        retired_list: *mut Node<Retire>,
+        notifications: &mut Notifications,
+    ) -> usize {
+        let mut node_ptr = retired_list;
+        let mut still_retired = core::ptr::null_mut();
+        let mut tail_ptr = None;
+        let mut reclaimable = core::ptr::null_mut();
+        let mut reclaimed = 0;
+        let mut number_remaining = 0;
+        let mut oldest_remaining = u64::MAX;
         while !node_ptr.is_null() {
             // # Safety
             //
             // We have exclusive access to the list of retired pointers.
             let node = unsafe { &*node_ptr };
             let next = node.next.load(Ordering::Relaxed);
-            if guarded_ptrs.contains(&(node.value.ptr as *const usize)) {
+            if is_guarded(guarded_ptrs, node.value.ptr)
+                || node.value.retired_at.is_later_than(retired_by)
+            {
                 // The pointer is still guarded keep in the retired list
+                if let Some(nanos) = node.value.retired_at.nanos() {
+                    oldest_remaining = oldest_remaining.min(nanos);
+                }
                 node.next.store(still_retired, Ordering::Relaxed);
                 still_retired = node_ptr;
                 if tail_ptr.is_none() {
@@ -259,47 +2062,152 @@ On nightly this will panic if the domain id is equal to the shared domain's id (
                 }
                 number_remaining += 1;
             } else {
-                // Deallocate the retired item
-                //
-                // # Safety
-                //
-                // The value was originally allocated via a box. Therefore all the safety
-                // requirement of box are met. According to the safety requirements of retire,
-                // the pointer has not yet been dropped and has only been placed in the retired
-                // list once. There are currently no other threads looking at the value since it is
-                // no longer protected by any of the hazard pointers.
-                unsafe { core::ptr::drop_in_place(node.value.retirable) };
-
-                // # Safety
-                //
-                // The node was originally allocated via box, therefore, all the safety
-                // requirements of box are met. We have exclusive access to the node so can
-                // therefore safely drop it.
-                let _node = unsafe { Box::from_raw(node_ptr) };
-
+                // The value is reclaimed once the retired list has been rebuilt.
+                node.next.store(reclaimable, Ordering::Relaxed);
+                reclaimable = node_ptr;
                 reclaimed += 1;
             }
             node_ptr = next;
         }
 
         if let Some(tail) = tail_ptr {
-            core::sync::atomic::fence(Ordering::SeqCst);
+            crate::sync::fence(Ordering::SeqCst);
 
             // # Safety
             //
             // All of the nodes in this list were originally owned by the retired list. We are
             // putting them back in.
             unsafe { self.retired.push_all(still_retired, tail, number_remaining) };
+            self.track_retired_at(oldest_remaining);
         }
 
+        // # Safety
+        //
+        // The nodes were taken from the retired list, and no hazard pointer protects their values.
+        unsafe { self.reclaim_nodes(reclaimable, notifications) };
         reclaimed
     }
 
+    /// Reclaims the values of the retired nodes linked from `node_ptr`, and the nodes themselves.
+    ///
+    /// This runs user destructors, which may themselves retire values into this domain, so it is
+    /// only called once the retired list has been rebuilt.
+    ///
+    /// # Safety
+    ///
+    /// The nodes must have been taken from the retired list and their values must no longer be
+    /// protected by any hazard pointer.
+    unsafe fn reclaim_nodes(
+        &self,
+        mut node_ptr: *mut Node<Retire>,
+        notifications: &mut Notifications,
+    ) {
+        while !node_ptr.is_null() {
+            // # Safety
+            //
+            // The node was originally allocated via box, therefore, all the safety requirements of
+            // box are met. We have exclusive access to the queued nodes so can therefore safely
+            // drop it.
+            let node = unsafe { Box::from_raw(node_ptr) };
+            #[cfg(feature = "leak-audit")]
+            crate::leak_audit::untrack(
+                crate::leak_audit::AllocationKind::ListNode,
+                core::mem::size_of::<Node<Retire>>(),
+            );
+            node_ptr = node.next.load(Ordering::Relaxed);
+            // Deallocate the retired item
+            //
+            // # Safety
+            //
+            // The value was originally allocated via a box. Therefore all the safety requirement
+            // of box are met. According to the safety requirements of retire, the pointer has not
+            // yet been dropped and has only been placed in the retired list once. There are
+            // currently no other threads looking at the value since it is no longer protected by
+            // any of the hazard pointers.
+            unsafe { (node.value.reclaim)(node.value.ptr) };
+            self.record_reclaimed(node.value.retired_type, node.value.retired_at);
+            #[cfg(feature = "leak-audit")]
+            crate::leak_audit::untrack(
+                crate::leak_audit::AllocationKind::Retired,
+                node.value.retired_type.size,
+            );
+            notifications.reclaimed(node.value.ptr);
+        }
+    }
+
+    /// Returns whether any hazard pointer currently protects `ptr`.
+    ///
+    /// Callers may free or take ownership of `ptr` if it is not protected, so this begins a
+    /// reclaim pass.
+    pub(crate) fn is_protected<T>(&self, ptr: *mut T) -> bool {
+        self.begin_reclaim_pass();
+        crate::sync::fence(Ordering::SeqCst);
+        self.hazard_ptrs().iter().any(|haz_ptr| {
+            let guarded_ptr = self.guarded_ptr(haz_ptr);
+            guarded_ptr == ptr as *mut usize || guarded_ptr == PINNED
+        })
+    }
+
+    /// Records that retired values may be reclaimed once the hazard pointers have been scanned.
+    ///
+    /// Must be called before the hazard pointers are scanned.
+    pub(crate) fn begin_reclaim_pass(&self) {
+        self.reclaim_passes.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns the number of reclaim passes which have begun.
+    pub(crate) fn reclaim_passes(&self) -> usize {
+        self.reclaim_passes.load(Ordering::SeqCst)
+    }
+
+    /// The pointer protected by `haz_ptr`, without its tag.
+    fn guarded_ptr(&self, haz_ptr: &AtomicPtr<usize>) -> *mut usize {
+        self.untagged(haz_ptr.load(Ordering::Acquire))
+    }
+
+    /// `guarded_ptr` without its tag, unless it pins the domain.
+    fn untagged(&self, guarded_ptr: *mut usize) -> *mut usize {
+        if guarded_ptr == PINNED {
+            guarded_ptr
+        } else {
+            crate::protection::untagged(guarded_ptr, self.tag_mask)
+        }
+    }
+
+    /// Gathers the protected pointers eight hazard pointers at a time.
+    ///
+    /// Released hazard pointers are always reset, so every hazard pointer of a chunk with any in
+    /// use can be read without consulting which are in use. The pointers of each group are
+    /// combined with a bitwise or, which compiles to vector instructions, so that groups which
+    /// protect nothing, as most do in a domain with many hazard pointers, are skipped without
+    /// branching on each pointer.
+    #[cfg(feature = "vectorized-scan")]
+    fn get_guarded_ptrs(&self) -> Set<*const usize> {
+        const GROUP: usize = 8;
+        let mut guarded_ptrs = Set::default();
+        for haz_ptrs in self.hazard_ptrs().chunks_in_use() {
+            for group in haz_ptrs.chunks_exact(GROUP) {
+                let ptrs: [*mut usize; GROUP] =
+                    core::array::from_fn(|index| group[index].load(Ordering::Acquire));
+                if ptrs.iter().fold(0, |any, ptr| any | *ptr as usize) == 0 {
+                    continue;
+                }
+                guarded_ptrs.extend(
+                    ptrs.iter()
+                        .filter(|ptr| !ptr.is_null())
+                        .map(|ptr| self.untagged(*ptr) as *const usize),
+                );
+            }
+        }
+        guarded_ptrs
+    }
+
+    #[cfg(not(feature = "vectorized-scan"))]
     fn get_guarded_ptrs(&self) -> Set<*const usize> {
-        self.hazard_ptrs
+        self.hazard_ptrs()
             .iter()
             .filter_map(|haz_ptr| {
-                let guarded_ptr = haz_ptr.load(Ordering::Acquire);
+                let guarded_ptr = self.guarded_ptr(haz_ptr);
                 if guarded_ptr.is_null() {
                     None
                 } else {
@@ -310,9 +2218,837 @@ On nightly this will panic if the domain id is equal to the shared domain's id (
     }
 }
 
+// # Safety
+//
+// Values are only reclaimed once a scan finds no hazard pointer protecting them.
+unsafe impl<const DOMAIN_ID: usize> Protection for Domain<DOMAIN_ID> {
+    type Guard<'a> = HazardPointer<'a>;
+
+    fn acquire(&self) -> Self::Guard<'_> {
+        self.acquire_haz_ptr()
+    }
+
+    fn release<'a>(&'a self, guard: Self::Guard<'a>) {
+        self.release_hazard_ptr(guard);
+    }
+
+    fn protect<'a, T>(&'a self, guard: &Self::Guard<'a>, ptr: *mut T) {
+        guard.protect(ptr as *mut usize);
+    }
+
+    unsafe fn retire<T>(&self, ptr: *mut T) {
+        unsafe { Domain::retire(self, ptr) };
+    }
+
+    unsafe fn retire_with_policy<T>(&self, ptr: *mut T, retire_policy: RetirePolicy) {
+        unsafe { Domain::retire_with_policy(self, ptr, retire_policy) };
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    fn protect_ptr<'a, T>(&'a self, guard: &Self::Guard<'a>, source: &AtomicPtr<T>) -> *mut T {
+        guard.protect_ptr(source)
+    }
+}
+
+// Retired values and hazard pointers are only ever updated atomically. If a destructor panics
+// while values are being reclaimed, the values which had not yet been visited are leaked rather
+// than freed, so a domain observed after a panic is still sound to use.
+impl<const DOMAIN_ID: usize> core::panic::UnwindSafe for Domain<DOMAIN_ID> {}
+impl<const DOMAIN_ID: usize> core::panic::RefUnwindSafe for Domain<DOMAIN_ID> {}
+
 impl<const DOMAIN_ID: usize> Drop for Domain<DOMAIN_ID> {
     fn drop(&mut self) {
-        self.bulk_reclaim();
+        // Dropping a retired value can release its protection of another retired value, so keep
+        // reclaiming until a pass reclaims nothing.
+        while self.bulk_reclaim_retired_by(RetiredAt::Untracked) > 0 {}
+        // No guard to a value of this domain can outlive it, so whatever deferred callbacks remain can be invoked.
+        for deferred in self.deferred.take_all() {
+            deferred.run();
+        }
         assert!(self.retired.head.load(Ordering::Relaxed).is_null());
+        assert!(self
+            .retired_intrusive
+            .head
+            .load(Ordering::Relaxed)
+            .is_null());
+        let configured = self.configured_strategy.load(Ordering::Relaxed);
+        if !configured.is_null() && configured != strategy_frozen() {
+            // # Safety
+            //
+            // We have exclusive access to the domain, so nothing references the strategy.
+            drop(unsafe { Box::from_raw(configured) });
+        }
+    }
+}
+
+/// Whether `ptr` is protected by one of `guarded_ptrs`, or the domain is pinned.
+fn is_guarded(guarded_ptrs: &Set<*const usize>, ptr: *mut usize) -> bool {
+    guarded_ptrs.contains(&(ptr as *const usize))
+        || guarded_ptrs.contains(&(PINNED as *const usize))
+}
+
+/// Whether a reclamation pass should be forced, even though the strategy did not call for one.
+#[cfg(feature = "fault-inject")]
+fn forced_reclaim() -> bool {
+    crate::fault_inject::force_reclaim()
+}
+
+#[cfg(not(feature = "fault-inject"))]
+#[inline(always)]
+fn forced_reclaim() -> bool {
+    false
+}
+
+/// Relinks the nodes of `retired_list` in a shuffled order, returning the new head.
+///
+/// # Safety
+///
+/// The caller must have exclusive access to the list.
+#[cfg(feature = "fault-inject")]
+unsafe fn shuffle_retired(retired_list: *mut Node<Retire>) -> *mut Node<Retire> {
+    let mut nodes = alloc::vec::Vec::new();
+    let mut node_ptr = retired_list;
+    while !node_ptr.is_null() {
+        nodes.push(node_ptr);
+        node_ptr = (*node_ptr).next.load(Ordering::Relaxed);
+    }
+    crate::fault_inject::shuffle(&mut nodes);
+    let mut head = core::ptr::null_mut();
+    for node_ptr in nodes {
+        (*node_ptr).next.store(head, Ordering::Relaxed);
+        head = node_ptr;
+    }
+    head
+}
+
+#[cfg(feature = "std")]
+fn backoff() {
+    std::thread::yield_now();
+}
+
+#[cfg(not(feature = "std"))]
+fn backoff() {
+    core::hint::spin_loop();
+}
+
+#[cfg(feature = "defmt")]
+impl<const DOMAIN_ID: usize> defmt::Format for Domain<DOMAIN_ID> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "Domain {{ id: {}, name: {}, .. }}", DOMAIN_ID, self.name)
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use crate::test_util::DropCounter;
+    #[cfg(feature = "introspection")]
+    use alloc::vec::Vec;
+    use core::time::Duration;
+
+    #[test]
+    #[cfg_attr(miri, ignore = "reclamation is triggered at random under Miri")]
+    fn stale_retired_values_are_reclaimed_when_guards_are_released() {
+        let domain: Domain<1> = Domain::new(ReclaimStrategy::TimedCapped(
+            TimedCappedSettings::default().with_max_retired_age(Duration::from_millis(10)),
+        ));
+        let drop_counter = DropCounter::new();
+        // Reach the first periodic reclamation, so that only the age can trigger the next one.
+        unsafe { domain.retire(Box::into_raw(Box::new(drop_counter.track(1)))) };
+        unsafe { domain.retire(Box::into_raw(Box::new(drop_counter.track(2)))) };
+        let haz_ptr = domain.acquire_haz_ptr();
+        drop_counter.assert_drops(1);
+
+        std::thread::sleep(Duration::from_millis(20));
+        domain.release_hazard_ptr(haz_ptr);
+
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "reclamation is triggered at random under Miri")]
+    fn recent_retired_values_are_not_forced_out() {
+        let domain: Domain<2> = Domain::new(ReclaimStrategy::TimedCapped(
+            TimedCappedSettings::default().with_max_retired_age(Duration::from_secs(3600)),
+        ));
+        let drop_counter = DropCounter::new();
+        // Reach the first periodic reclamation, so that only the age can trigger the next one.
+        unsafe { domain.retire(Box::into_raw(Box::new(drop_counter.track(1)))) };
+        unsafe { domain.retire(Box::into_raw(Box::new(drop_counter.track(2)))) };
+
+        domain.release_hazard_ptr(domain.acquire_haz_ptr());
+
+        drop_counter.assert_drops(1);
+        assert_eq!(domain.reclaim(), 1, "The recent value is still retired");
+    }
+
+    #[test]
+    fn protecting_another_pointer_forgets_the_validated_pointer() {
+        let domain: Domain<4> = Domain::new(ReclaimStrategy::Manual);
+        let mut first = 1_usize;
+        let mut second = 2_usize;
+        let source = AtomicPtr::new(&mut first as *mut usize);
+        let haz_ptr = domain.acquire_haz_ptr();
+
+        let protected = haz_ptr.protect_ptr(&source);
+        let reprotected = haz_ptr.protect_ptr(&source);
+        haz_ptr.protect(&mut second);
+        let after_other = haz_ptr.protect_ptr(&source);
+
+        assert_eq!(
+            protected, reprotected,
+            "The same pointer is protected again"
+        );
+        assert_eq!(after_other, protected);
+        assert_eq!(
+            haz_ptr.0.load(Ordering::Acquire),
+            protected,
+            "The pointer is published again after protecting another"
+        );
+        domain.release_hazard_ptr(haz_ptr);
+    }
+
+    #[test]
+    fn child_domains_share_hazard_pointers_but_not_retired_values() {
+        static PARENT: Domain<5> = Domain::new(ReclaimStrategy::Eager);
+        let child: Domain<6> = Domain::new_child(&PARENT, ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let value = Box::into_raw(Box::new(drop_counter.track(1)));
+        let haz_ptr = PARENT.acquire_haz_ptr();
+        haz_ptr.protect(value as *mut usize);
+
+        unsafe { child.retire(value) };
+        let reclaimed_while_protected = child.reclaim();
+        PARENT.release_hazard_ptr(haz_ptr);
+        let reclaimed_by_parent = PARENT.reclaim();
+
+        assert_eq!(
+            reclaimed_while_protected, 0,
+            "The parent's hazard pointer protects the child's value"
+        );
+        assert_eq!(
+            reclaimed_by_parent, 0,
+            "The value is retired in the child domain"
+        );
+        assert_eq!(child.reclaim(), 1, "The child reclaims its own value");
+        drop_counter.assert_drops(1);
+        assert!(
+            core::ptr::eq(child.hazard_ptrs(), PARENT.hazard_ptrs()),
+            "The child allocates hazard pointers from the parent's pool"
+        );
+        drop(child);
+    }
+
+    #[test]
+    fn transferred_values_are_reclaimed_by_the_receiving_domain() {
+        static PARENT: Domain<7> = Domain::new(ReclaimStrategy::Manual);
+        let child: Domain<8> = Domain::new_child(&PARENT, ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let guarded = Box::into_raw(Box::new(drop_counter.track(1)));
+        let haz_ptr = child.acquire_haz_ptr();
+        haz_ptr.protect(guarded as *mut usize);
+        unsafe { child.retire(guarded) };
+        unsafe { child.retire(Box::into_raw(Box::new(drop_counter.track(2)))) };
+
+        let transferred = child.transfer_retired(&PARENT);
+        child.release_hazard_ptr(haz_ptr);
+        drop(child);
+
+        assert_eq!(transferred, 2, "Both retired values are transferred");
+        drop_counter.assert_drops(0);
+        assert_eq!(
+            PARENT.reclaim(),
+            2,
+            "The parent reclaims the values once unguarded"
+        );
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    fn weak_handles_fail_to_upgrade_values_transferred_concurrently() {
+        const ALIVE: usize = 0xA11CE;
+        struct Checked(usize);
+        impl Drop for Checked {
+            fn drop(&mut self) {
+                self.0 = 0;
+            }
+        }
+
+        static PARENT: Domain<30> = Domain::new(ReclaimStrategy::Manual);
+        let child: Domain<31> = Domain::new_child(&PARENT, ReclaimStrategy::Manual);
+        let atom_box = crate::AtomBoxIn::new_with_domain(Checked(ALIVE), &child);
+        let done = core::sync::atomic::AtomicBool::new(false);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..200 {
+                    let weak = atom_box.downgrade();
+                    atom_box.store(Checked(ALIVE));
+                    if let Some(guard) = weak.try_upgrade() {
+                        assert_eq!(guard.0, ALIVE, "Upgraded values have not been reclaimed");
+                    }
+                }
+                done.store(true, Ordering::SeqCst);
+            });
+            scope.spawn(|| {
+                while !done.load(Ordering::SeqCst) {
+                    child.transfer_retired(&PARENT);
+                    PARENT.reclaim();
+                }
+            });
+        });
+
+        drop(atom_box);
+        child.transfer_retired(&PARENT);
+        PARENT.reclaim();
+    }
+
+    #[test]
+    #[should_panic(expected = "which does not share its hazard pointers")]
+    fn transferring_between_unrelated_domains_panics() {
+        let from: Domain<9> = Domain::new(ReclaimStrategy::Manual);
+        let to: Domain<10> = Domain::new(ReclaimStrategy::Manual);
+
+        from.transfer_retired(&to);
+    }
+
+    #[test]
+    fn reclaim_notifications_wait_for_the_last_guard() {
+        let domain: Domain<11> = Domain::new(ReclaimStrategy::Manual);
+        let notified = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let value = Box::into_raw(Box::new(1_usize));
+        let other = Box::into_raw(Box::new(2_usize));
+        let haz_ptr = domain.acquire_haz_ptr();
+        haz_ptr.protect(value);
+        let counter = notified.clone();
+        domain.notify_on_reclaim(value, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        unsafe { domain.retire(value) };
+        unsafe { domain.retire(other) };
+
+        domain.reclaim();
+        let notified_while_guarded = notified.load(Ordering::SeqCst);
+        domain.release_hazard_ptr(haz_ptr);
+        domain.reclaim();
+
+        assert_eq!(
+            notified_while_guarded, 0,
+            "Reclaiming other values does not notify"
+        );
+        assert_eq!(
+            notified.load(Ordering::SeqCst),
+            1,
+            "The callback is invoked once the value is reclaimed"
+        );
+    }
+
+    #[test]
+    fn deferred_callbacks_wait_for_existing_hazard_pointers() {
+        let domain: Domain<22> = Domain::new(ReclaimStrategy::Manual);
+        let invoked = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let value = Box::into_raw(Box::new(1_usize));
+        let haz_ptr = domain.acquire_haz_ptr();
+        haz_ptr.protect(value);
+        let counter = invoked.clone();
+        domain.defer(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let later_haz_ptr = domain.acquire_haz_ptr();
+        later_haz_ptr.protect(value);
+
+        domain.reclaim();
+        let invoked_while_protected = invoked.load(Ordering::SeqCst);
+        domain.release_hazard_ptr(haz_ptr);
+        domain.reclaim();
+
+        assert_eq!(
+            invoked_while_protected, 0,
+            "The callback waits for the hazard pointer"
+        );
+        assert_eq!(
+            invoked.load(Ordering::SeqCst),
+            1,
+            "Hazard pointers acquired after deferring are not waited for"
+        );
+        domain.release_hazard_ptr(later_haz_ptr);
+        drop(unsafe { Box::from_raw(value) });
+    }
+
+    #[test]
+    fn deferred_callbacks_are_invoked_when_the_domain_is_dropped() {
+        let domain: Domain<23> = Domain::new(ReclaimStrategy::Manual);
+        let invoked = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut value = 1_usize;
+        let haz_ptr = domain.acquire_haz_ptr();
+        haz_ptr.protect(&mut value);
+        let counter = invoked.clone();
+        domain.defer(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        // The hazard pointer is never released.
+        drop(domain);
+
+        assert_eq!(invoked.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn leak_all_never_reclaims_retired_values() {
+        let domain: Domain<12> = Domain::new(ReclaimStrategy::LeakAll);
+        let drop_counter = DropCounter::new();
+        let value = Box::into_raw(Box::new(drop_counter.track(1)));
+
+        unsafe { domain.retire(value) };
+        let reclaimed = domain.reclaim();
+        drop(domain);
+
+        assert_eq!(reclaimed, 0, "Nothing was retired");
+        drop_counter.assert_drops(0);
+        // # Safety
+        //
+        // The value was leaked rather than retired, so it is still valid.
+        drop(unsafe { Box::from_raw(value) });
+        drop_counter.assert_drops(1);
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    #[cfg_attr(miri, ignore = "reclamation is triggered at random under Miri")]
+    fn reclaim_stats_attribute_each_decision() {
+        let domain: Domain<3> = Domain::new(ReclaimStrategy::TimedCapped(
+            TimedCappedSettings::default().with_retired_threshold(isize::MAX),
+        ));
+        let drop_counter = DropCounter::new();
+
+        for value in 0..3 {
+            unsafe { domain.retire(Box::into_raw(Box::new(drop_counter.track(value)))) };
+        }
+        domain.reclaim();
+
+        let stats = domain.reclaim_stats();
+        assert_eq!(stats.timer, 1, "The first retire finds the timer expired");
+        assert_eq!(
+            stats.declined, 2,
+            "Later retires are within the sync timeout"
+        );
+        assert_eq!(stats.manual, 1, "Calling reclaim is recorded");
+        assert_eq!(stats.reclaimed, 3, "Every value is counted once reclaimed");
+        assert_eq!(stats.threshold + stats.eager + stats.max_age, 0);
+    }
+
+    #[test]
+    fn synchronize_waits_for_existing_protections() {
+        let domain: Domain<13> = Domain::new(ReclaimStrategy::Manual);
+        let value = Box::into_raw(Box::new(1_usize)) as usize;
+        let released = std::sync::atomic::AtomicBool::new(false);
+        let (protected_tx, protected_rx) = std::sync::mpsc::channel();
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let haz_ptr = domain.acquire_haz_ptr();
+                haz_ptr.protect(value as *mut usize);
+                protected_tx.send(()).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                released.store(true, Ordering::SeqCst);
+                domain.release_hazard_ptr(haz_ptr);
+            });
+            protected_rx.recv().unwrap();
+            unsafe { domain.retire(value as *mut usize) };
+
+            domain.synchronize();
+
+            assert!(
+                released.load(Ordering::SeqCst),
+                "Synchronize returns only once the hazard pointer is released"
+            );
+        });
+        assert_eq!(domain.reclaim(), 1, "The retired value is still retired");
+    }
+
+    #[test]
+    fn synchronize_ignores_guards_to_values_which_are_not_retired() {
+        let domain: Domain<32> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box = crate::AtomBoxIn::new_with_domain(1, &domain);
+        drop(atom_box.swap(2));
+        let guard = atom_box.load();
+
+        domain.synchronize();
+
+        assert_eq!(
+            *guard, 2,
+            "A guard to the stored value does not hold up synchronize"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    #[cfg_attr(miri, ignore = "reclamation is triggered at random under Miri")]
+    fn switching_strategy_keeps_the_unit_of_retired_values() {
+        let domain: Domain<33> = Domain::new(ReclaimStrategy::TimedCapped(
+            TimedCappedSettings::default()
+                .with_timeout(Duration::from_secs(3600))
+                .with_retired_threshold(isize::MAX)
+                .with_max_retired_age(Duration::from_secs(3600)),
+        ));
+        let drop_counter = DropCounter::new();
+        let atom_box = crate::AtomBoxIn::new_with_domain(drop_counter.track(1), &domain);
+        atom_box.store(drop_counter.track(2));
+        atom_box.store(drop_counter.track(3));
+        let timed_reclaimed = drop_counter.count();
+
+        domain.set_reclaim_strategy(ReclaimStrategy::FrameBased(2));
+        atom_box.store(drop_counter.track(4));
+
+        assert_eq!(
+            domain.advance_frame(),
+            2 - timed_reclaimed,
+            "Values timed before the switch are not held back by their timestamp"
+        );
+        assert_eq!(
+            domain.advance_frame(),
+            1,
+            "The value retired after the switch is kept for two frames"
+        );
+
+        domain.set_reclaim_strategy(ReclaimStrategy::TimedCapped(
+            TimedCappedSettings::default()
+                .with_timeout(Duration::from_secs(3600))
+                .with_retired_threshold(isize::MAX)
+                .with_max_retired_age(Duration::from_secs(3600)),
+        ));
+        atom_box.store(drop_counter.track(5));
+        assert!(
+            !domain.retired_too_long(),
+            "Frame numbers are not mistaken for timestamps"
+        );
+    }
+
+    #[test]
+    fn frame_based_domains_reclaim_values_after_the_delay() {
+        let domain: Domain<14> = Domain::new(ReclaimStrategy::FrameBased(2));
+        let drop_counter = DropCounter::new();
+        let atom_box = crate::AtomBoxIn::new_with_domain(drop_counter.track(1), &domain);
+        atom_box.store(drop_counter.track(2));
+        let guard = atom_box.load();
+        atom_box.store(drop_counter.track(3));
+
+        let first_frame = domain.advance_frame();
+        let second_frame = domain.advance_frame();
+        drop(guard);
+        let third_frame = domain.advance_frame();
+
+        assert_eq!(first_frame, 0, "Values are kept for two frames");
+        assert_eq!(second_frame, 1, "The guarded value is kept");
+        assert_eq!(third_frame, 1);
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    fn immediate_free_only_retires_protected_values() {
+        let domain: Domain<15> = Domain::new(ReclaimStrategy::Manual).with_immediate_free();
+        let drop_counter = DropCounter::new();
+        let atom_box = crate::AtomBoxIn::new_with_domain(drop_counter.track(1), &domain);
+        let guard = atom_box.load();
+
+        atom_box.store(drop_counter.track(2));
+        atom_box.store(drop_counter.track(3));
+        let drops_while_guarded = drop_counter.count();
+        drop(guard);
+
+        assert_eq!(drops_while_guarded, 1, "The unprotected value is freed");
+        assert_eq!(domain.reclaim(), 1, "The protected value was retired");
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    fn tagged_protections_match_untagged_retired_values() {
+        let domain: Domain<16> = Domain::new(ReclaimStrategy::Manual).with_tag_mask(0b1);
+        let value = Box::into_raw(Box::new(1_u64));
+        let haz_ptr = domain.acquire_haz_ptr();
+        haz_ptr.protect((value as usize | 0b1) as *mut usize);
+        unsafe { domain.retire(value) };
+
+        let reclaimed_while_protected = domain.reclaim();
+        domain.release_hazard_ptr(haz_ptr);
+
+        assert_eq!(
+            reclaimed_while_protected, 0,
+            "The tagged pointer protects the value"
+        );
+        assert_eq!(domain.reclaim(), 1);
+    }
+
+    #[test]
+    fn strategy_can_only_be_configured_before_use() {
+        let domain: Domain<17> = Domain::new(ReclaimStrategy::Manual);
+        let used_domain: Domain<17> = Domain::new(ReclaimStrategy::Manual);
+        used_domain.reclaim_if_needed();
+
+        let configured = domain.configure_reclaim_strategy(ReclaimStrategy::Eager);
+        let reconfigured = domain.configure_reclaim_strategy(ReclaimStrategy::Manual);
+        let configured_after_use = used_domain.configure_reclaim_strategy(ReclaimStrategy::Eager);
+        unsafe { domain.retire(Box::into_raw(Box::new(1_u64))) };
+
+        assert!(configured.is_ok());
+        assert!(
+            reconfigured.is_err(),
+            "A domain can only be configured once"
+        );
+        assert!(
+            configured_after_use.is_err(),
+            "A used domain cannot be configured"
+        );
+        assert_eq!(
+            domain.retired.count.load(Ordering::Acquire),
+            0,
+            "The configured strategy reclaims eagerly"
+        );
+    }
+
+    #[test]
+    fn strategy_can_be_replaced_while_in_use() {
+        let domain: Domain<18> = Domain::new(ReclaimStrategy::Manual);
+        unsafe { domain.retire(Box::into_raw(Box::new(1_u64))) };
+        let retired_before = domain.retired.count.load(Ordering::Acquire);
+
+        domain.set_reclaim_strategy(ReclaimStrategy::LeakAll);
+        domain.set_reclaim_strategy(ReclaimStrategy::Eager);
+        unsafe { domain.retire(Box::into_raw(Box::new(2_u64))) };
+
+        assert_eq!(
+            retired_before, 1,
+            "The manual strategy keeps retired values"
+        );
+        assert_eq!(
+            domain.retired.count.load(Ordering::Acquire),
+            0,
+            "The eager strategy reclaims every retired value"
+        );
+    }
+
+    #[test]
+    fn strategies_can_be_replaced_while_values_are_retired() {
+        static DOMAIN: Domain<34> = Domain::new(ReclaimStrategy::Eager);
+        DOMAIN.set_reclaim_strategy(ReclaimStrategy::Eager);
+        let done = std::sync::atomic::AtomicBool::new(false);
+
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    while !done.load(Ordering::Acquire) {
+                        unsafe { DOMAIN.retire(Box::into_raw(Box::new(1_u64))) };
+                    }
+                });
+            }
+            for replacement in 0..20 {
+                DOMAIN.set_reclaim_strategy(if replacement % 2 == 0 {
+                    ReclaimStrategy::Manual
+                } else {
+                    ReclaimStrategy::Eager
+                });
+            }
+            done.store(true, Ordering::Release);
+        });
+
+        DOMAIN.reclaim();
+        assert_eq!(
+            DOMAIN.retired.count.load(Ordering::Acquire),
+            0,
+            "Every retired value is reclaimed"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn quarantined_values_are_kept_for_the_grace_period() {
+        let domain: Domain<19> = Domain::new(ReclaimStrategy::Manual)
+            .with_immediate_free()
+            .with_quarantine(Duration::from_millis(20));
+        unsafe { domain.retire(Box::into_raw(Box::new(1_u64))) };
+
+        let reclaimed_in_quarantine = domain.reclaim();
+        std::thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(
+            reclaimed_in_quarantine, 0,
+            "Unprotected values are kept during the quarantine"
+        );
+        assert_eq!(domain.reclaim(), 1);
+    }
+
+    #[cfg(feature = "reclaim-history")]
+    #[test]
+    fn reclaim_history_keeps_the_most_recent_reclaims() {
+        let domain: Domain<20> = Domain::new(ReclaimStrategy::Manual).with_reclaim_history_len(2);
+        unsafe { domain.retire(Box::into_raw(Box::new(1_u8))) };
+        unsafe { domain.retire(Box::into_raw(Box::new(2_u16))) };
+        unsafe { domain.retire(Box::into_raw(Box::new(3_u32))) };
+
+        domain.reclaim();
+        let reclaims = domain.recent_reclaims();
+
+        assert_eq!(reclaims.len(), 2, "Only the configured number is kept");
+        for record in reclaims {
+            let expected_size = match record.type_name {
+                "u8" => 1,
+                "u16" => 2,
+                "u32" => 4,
+                type_name => panic!("Unexpected type {}", type_name),
+            };
+            assert_eq!(record.size, expected_size);
+            assert!(record.retired_at.expect("Retirement is timed") <= record.reclaimed_at);
+        }
+    }
+
+    #[cfg(feature = "introspection")]
+    #[test]
+    fn protected_ptrs_reports_published_protections() {
+        let domain: Domain<21> = Domain::new(ReclaimStrategy::Manual);
+        let value = Box::into_raw(Box::new(1_u64));
+        let haz_ptr = domain.acquire_haz_ptr();
+        let pin = domain.acquire_haz_ptr();
+
+        haz_ptr.protect(value as *mut usize);
+        let protecting_value: Vec<_> = domain.protected_ptrs().collect();
+        pin.protect(PINNED);
+        let pinned = domain.protected_ptrs().any(|ptr| ptr == ProtectedPtr::All);
+        domain.release_hazard_ptr(haz_ptr);
+        domain.release_hazard_ptr(pin);
+
+        assert_eq!(protecting_value, [ProtectedPtr::Ptr(value as *const ())]);
+        assert!(pinned, "Pins are reported as protecting everything");
+        assert_eq!(
+            domain.protected_ptrs().count(),
+            0,
+            "Released protections are not reported"
+        );
+        drop(unsafe { Box::from_raw(value) });
+    }
+
+    #[cfg(feature = "thread-attribution")]
+    #[test]
+    fn hazard_owners_attribute_hazard_pointers_to_threads() {
+        let domain: Domain<24> = Domain::new(ReclaimStrategy::Manual);
+        let mut value = 1_usize;
+        let unlabelled = domain.acquire_haz_ptr();
+        let previous = set_guard_label(Some("labelled"));
+        let labelled = domain.acquire_haz_ptr();
+        set_guard_label(previous);
+
+        labelled.protect(&mut value);
+        let owners = domain.hazard_owners();
+        domain.release_hazard_ptr(unlabelled);
+        domain.release_hazard_ptr(labelled);
+
+        let current = std::thread::current().id();
+        assert_eq!(owners.len(), 2);
+        assert!(owners.iter().all(|owner| owner.thread_id == current));
+        let labelled = owners.iter().find(|owner| owner.label.is_some()).unwrap();
+        assert_eq!(labelled.label, Some("labelled"));
+        assert_eq!(
+            labelled.protected,
+            Some(ProtectedPtr::Ptr(&value as *const usize as *const ()))
+        );
+        assert!(
+            owners.iter().any(|owner| owner.protected.is_none()),
+            "Hazard pointers protecting nothing are reported"
+        );
+        assert!(
+            domain.hazard_owners().is_empty(),
+            "Released owners are cleared"
+        );
+        assert_eq!(previous, None);
+    }
+
+    #[cfg(feature = "thread-attribution")]
+    #[test]
+    fn hazard_owners_skip_threads_which_have_exited() {
+        static DOMAIN: Domain<24> = Domain::new(ReclaimStrategy::Manual);
+        // Joining waits for the thread's local storage to be destroyed, unlike a scoped thread.
+        std::thread::spawn(|| {
+            let _leaked = DOMAIN.acquire_haz_ptr();
+        })
+        .join()
+        .unwrap();
+        let current = DOMAIN.acquire_haz_ptr();
+
+        let owners = DOMAIN.hazard_owners();
+        DOMAIN.release_hazard_ptr(current);
+
+        assert_eq!(owners.len(), 1, "Only the running thread is reported");
+        assert_eq!(owners[0].thread_id, std::thread::current().id());
+    }
+
+    #[test]
+    fn guarded_ptrs_are_gathered_from_every_chunk() {
+        let domain: Domain<25> = Domain::new(ReclaimStrategy::Manual);
+        let mut values = [0_usize; 4];
+        let haz_ptrs: alloc::vec::Vec<_> = (0..150).map(|_| domain.acquire_haz_ptr()).collect();
+        let expected: alloc::vec::Vec<*const usize> =
+            values.iter().map(|value| value as *const _).collect();
+        let protecting = [3, 64, 65, 149];
+
+        for (index, value) in protecting.iter().zip(values.iter_mut()) {
+            haz_ptrs[*index].protect(value);
+        }
+        let guarded_ptrs = domain.get_guarded_ptrs();
+        for haz_ptr in haz_ptrs {
+            domain.release_hazard_ptr(haz_ptr);
+        }
+
+        assert_eq!(guarded_ptrs.len(), expected.len());
+        assert!(expected.iter().all(|ptr| guarded_ptrs.contains(ptr)));
+        assert!(domain.get_guarded_ptrs().is_empty());
+    }
+
+    #[test]
+    fn destructors_can_retire_into_the_reclaiming_domain() {
+        struct RetiresOnDrop<'a> {
+            domain: &'a Domain<26>,
+            child: Option<crate::test_util::TrackedValue<usize>>,
+        }
+
+        impl Drop for RetiresOnDrop<'_> {
+            fn drop(&mut self) {
+                let child = Box::into_raw(Box::new(self.child.take()));
+                unsafe { self.domain.retire(child) };
+                self.domain.reclaim();
+                // The guarded value was put back on the retired list before this ran.
+                assert_eq!(self.domain.retired.count.load(Ordering::Acquire), 1);
+            }
+        }
+
+        let domain: Domain<26> = Domain::new(ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let guarded = Box::into_raw(Box::new(drop_counter.track(1)));
+        let haz_ptr = domain.acquire_haz_ptr();
+        haz_ptr.protect(guarded as *mut usize);
+        unsafe { domain.retire(guarded) };
+        unsafe {
+            domain.retire(Box::into_raw(Box::new(RetiresOnDrop {
+                domain: &domain,
+                child: Some(drop_counter.track(2)),
+            })))
+        };
+
+        let reclaimed = domain.reclaim();
+        let drops_while_guarded = drop_counter.count();
+        domain.release_hazard_ptr(haz_ptr);
+        domain.reclaim();
+
+        assert_eq!(
+            reclaimed, 1,
+            "Only the unguarded value was reclaimed by the outer pass"
+        );
+        assert_eq!(
+            drops_while_guarded, 1,
+            "The child was reclaimed by the inner pass"
+        );
+        drop_counter.assert_drops(2);
     }
 }
@@ -0,0 +1,65 @@
+use super::list::LockFreeList;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+pub(super) type Callback = Box<dyn FnOnce() + Send>;
+
+/// A callback to invoke once the value at `ptr` has been reclaimed.
+pub(super) struct Notification {
+    ptr: *const usize,
+    callback: Callback,
+}
+
+impl Notification {
+    pub(super) fn new(ptr: *const usize, callback: Callback) -> Self {
+        Self { ptr, callback }
+    }
+}
+
+impl core::fmt::Debug for Notification {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Notification")
+            .field("ptr", &self.ptr)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The notifications taken from a domain for the duration of a reclamation.
+pub(super) struct Notifications {
+    pending: Vec<Notification>,
+    ready: Vec<Callback>,
+}
+
+impl Notifications {
+    /// Takes every notification registered in `list`.
+    pub(super) fn take(list: &LockFreeList<Notification>) -> Self {
+        let pending = list.take_all();
+        Self {
+            pending,
+            ready: Vec::new(),
+        }
+    }
+
+    /// Records that the value at `ptr` has been reclaimed.
+    pub(super) fn reclaimed(&mut self, ptr: *const usize) {
+        let mut index = 0;
+        while index < self.pending.len() {
+            if self.pending[index].ptr == ptr {
+                self.ready.push(self.pending.swap_remove(index).callback);
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    /// Returns the notifications whose values were not reclaimed to `list`, then invokes the
+    /// callbacks of those which were.
+    pub(super) fn finish(self, list: &LockFreeList<Notification>) {
+        for notification in self.pending {
+            list.push(notification);
+        }
+        for callback in self.ready {
+            callback();
+        }
+    }
+}
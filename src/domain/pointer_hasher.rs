@@ -0,0 +1,65 @@
+use core::hash::Hasher;
+
+// The multiplier used by FxHash, chosen to spread the bits of the input across the output.
+const SEED: u64 = 0xf135_7aea_2e62_a9c5;
+
+/// A fast, non-cryptographic hasher for the sets of guarded pointers built during reclamation.
+///
+/// The default hasher of `HashSet` is resistant to collision attacks, which is unnecessary for
+/// pointers and makes building the set of guarded pointers dominate the cost of a reclamation
+/// sweep. This follows the design of FxHash, with a final rotation so that the low bits of the
+/// hash, which select the bucket, depend on the high bits of the pointer rather than the always
+/// zero alignment bits.
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct PointerHasher {
+    hash: u64,
+}
+
+impl PointerHasher {
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for PointerHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.add(value as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash.rotate_left(26)
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::hash::{BuildHasher, BuildHasherDefault};
+    use std::collections::HashSet;
+
+    #[test]
+    fn aligned_pointers_use_the_low_bits_of_the_hash() {
+        let build_hasher = BuildHasherDefault::<PointerHasher>::default();
+        let values = [0_u64; 64];
+
+        let buckets: HashSet<_> = values
+            .iter()
+            .map(|value| build_hasher.hash_one(value as *const u64) & 0xff)
+            .collect();
+
+        assert!(
+            buckets.len() > 32,
+            "Adjacent pointers should hash to different buckets, got {}",
+            buckets.len()
+        );
+    }
+}
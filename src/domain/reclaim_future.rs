@@ -0,0 +1,400 @@
+use super::list::Node;
+use super::notify::Notifications;
+use super::{intrusive, Domain, ReclaimTrigger, Retire, RetireLink, RetiredAt, Set};
+use crate::sync::Ordering;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// The number of retired values a [`ReclaimFuture`] processes each time it is polled, unless
+/// configured otherwise.
+const DEFAULT_CHUNK_SIZE: usize = 64;
+
+/// A reclamation pass which has taken a domain's retired lists and gathered the protected
+/// pointers.
+///
+/// The values left in the lists were retired before the protected pointers were gathered, so
+/// those which were unprotected cannot have been protected since. The lists can therefore be
+/// processed in one go, or a chunk at a time.
+pub(super) struct ReclaimPass {
+    pub(super) retired_list: *mut Node<Retire>,
+    pub(super) retired_intrusive_list: *mut RetireLink,
+    pub(super) guarded_ptrs: Set<*const usize>,
+    pub(super) notifications: Notifications,
+    pub(super) retired_count: isize,
+    pub(super) reclaimed: usize,
+}
+
+enum State {
+    NotStarted,
+    Reclaiming {
+        pass: ReclaimPass,
+        retired_by: RetiredAt,
+    },
+    Finished,
+}
+
+/// A future which reclaims the unprotected retired values of a [`Domain`], a chunk at a time.
+///
+/// Created by [`Domain::reclaim_async`]. Resolves to the number of values reclaimed.
+///
+/// Each poll does a bounded amount of work, either gathering the protected pointers or
+/// reclaiming one chunk of retired values, before waking itself and yielding to the executor.
+/// If the future is dropped before completing, the values it has not yet processed are returned
+/// to the domain's retired lists.
+#[must_use = "futures do nothing unless polled"]
+pub struct ReclaimFuture<'domain, const DOMAIN_ID: usize> {
+    domain: &'domain Domain<DOMAIN_ID>,
+    chunk_size: usize,
+    state: State,
+}
+
+// # Safety
+//
+// The retired values and notifications held by the pass were taken from the domain, which is
+// `Sync`, and are only accessed through `&mut self`.
+unsafe impl<const DOMAIN_ID: usize> Send for ReclaimFuture<'_, DOMAIN_ID> {}
+
+impl<const DOMAIN_ID: usize> core::fmt::Debug for ReclaimFuture<'_, DOMAIN_ID> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let reclaimed = match &self.state {
+            State::Reclaiming { pass, .. } => pass.reclaimed,
+            _ => 0,
+        };
+        f.debug_struct("ReclaimFuture")
+            .field("chunk_size", &self.chunk_size)
+            .field("reclaimed", &reclaimed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'domain, const DOMAIN_ID: usize> ReclaimFuture<'domain, DOMAIN_ID> {
+    pub(super) fn new(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            domain,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            state: State::NotStarted,
+        }
+    }
+
+    /// Sets the maximum number of retired values processed each time the future is polled.
+    ///
+    /// Smaller chunks yield to the executor more often, at the cost of more polls. Destructors
+    /// are run as values are reclaimed, so the time taken by a chunk also depends on the values
+    /// being reclaimed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "ReclaimFuture chunk size must be non-zero");
+        self.chunk_size = chunk_size;
+        self
+    }
+}
+
+impl<const DOMAIN_ID: usize> Future for ReclaimFuture<'_, DOMAIN_ID> {
+    type Output = usize;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<usize> {
+        let this = self.get_mut();
+        let domain = this.domain;
+        match &mut this.state {
+            State::NotStarted => {
+                domain.record_reclaim_decision(Some(ReclaimTrigger::Manual));
+                let retired_by = domain.reclaimable_retired_by();
+                match domain.begin_bulk_reclaim() {
+                    Some(pass) => this.state = State::Reclaiming { pass, retired_by },
+                    None => {
+                        this.state = State::Finished;
+                        return Poll::Ready(0);
+                    }
+                }
+            }
+            State::Reclaiming { pass, retired_by } => {
+                let retired_by = *retired_by;
+                // Intrusive values are processed first, as they are in the list built by
+                // `bulk_reclaim`.
+                if !pass.retired_intrusive_list.is_null() {
+                    // # Safety
+                    //
+                    // The pass has exclusive access to its retired lists.
+                    let chunk =
+                        unsafe { split_links(&mut pass.retired_intrusive_list, this.chunk_size) };
+                    pass.reclaimed += domain.reclaim_unguarded_intrusive(
+                        &pass.guarded_ptrs,
+                        retired_by,
+                        chunk,
+                        &mut pass.notifications,
+                    );
+                } else {
+                    // # Safety
+                    //
+                    // The pass has exclusive access to its retired lists.
+                    let chunk = unsafe { split_nodes(&mut pass.retired_list, this.chunk_size) };
+                    pass.reclaimed += domain.reclaim_unguarded(
+                        &pass.guarded_ptrs,
+                        retired_by,
+                        chunk,
+                        &mut pass.notifications,
+                    );
+                }
+                if pass.retired_list.is_null() && pass.retired_intrusive_list.is_null() {
+                    if let State::Reclaiming { pass, .. } =
+                        core::mem::replace(&mut this.state, State::Finished)
+                    {
+                        return Poll::Ready(domain.finish_bulk_reclaim(pass));
+                    }
+                }
+            }
+            State::Finished => panic!("ReclaimFuture polled after completion"),
+        }
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+impl<const DOMAIN_ID: usize> Drop for ReclaimFuture<'_, DOMAIN_ID> {
+    fn drop(&mut self) {
+        if let State::Reclaiming { pass, .. } = core::mem::replace(&mut self.state, State::Finished)
+        {
+            // # Safety
+            //
+            // The values remaining in the pass's lists were taken from the domain's retired lists
+            // and have not been reclaimed.
+            unsafe {
+                self.domain
+                    .restore_retired(pass.retired_list, pass.retired_intrusive_list)
+            };
+            self.domain.finish_bulk_reclaim(pass);
+        }
+    }
+}
+
+impl<const DOMAIN_ID: usize> Domain<DOMAIN_ID> {
+    /// Returns values taken from the retired lists, without reclaiming them.
+    ///
+    /// # Safety
+    ///
+    /// The lists must have been taken from this domain's retired lists, and none of their values
+    /// may have been reclaimed.
+    unsafe fn restore_retired(
+        &self,
+        retired_list: *mut Node<Retire>,
+        retired_intrusive_list: *mut RetireLink,
+    ) {
+        let mut oldest_remaining = u64::MAX;
+        if !retired_list.is_null() {
+            let mut tail_ptr = retired_list;
+            let mut number_remaining = 1;
+            loop {
+                // # Safety
+                //
+                // We have exclusive access to the list of retired nodes.
+                let node = unsafe { &*tail_ptr };
+                if let Some(nanos) = node.value.retired_at.nanos() {
+                    oldest_remaining = oldest_remaining.min(nanos);
+                }
+                let next = node.next.load(Ordering::Relaxed);
+                if next.is_null() {
+                    break;
+                }
+                tail_ptr = next;
+                number_remaining += 1;
+            }
+            // # Safety
+            //
+            // The nodes were originally owned by the retired list. We are putting them back in.
+            unsafe {
+                self.retired
+                    .push_all(retired_list, &(*tail_ptr).next, number_remaining)
+            };
+        }
+        if !retired_intrusive_list.is_null() {
+            let mut tail_ptr = retired_intrusive_list;
+            let mut number_remaining = 1;
+            loop {
+                // # Safety
+                //
+                // We have exclusive access to the list of retired values, none of which have been
+                // reclaimed.
+                let (retired, next) = unsafe { intrusive::retired(&*tail_ptr) };
+                if let Some(nanos) = retired.retired_at.nanos() {
+                    oldest_remaining = oldest_remaining.min(nanos);
+                }
+                if next.is_null() {
+                    break;
+                }
+                tail_ptr = next;
+                number_remaining += 1;
+            }
+            // # Safety
+            //
+            // The values were originally owned by the retired list. We are putting them back in.
+            unsafe {
+                self.retired_intrusive
+                    .push_all(retired_intrusive_list, &*tail_ptr)
+            };
+            self.retired
+                .count
+                .fetch_add(number_remaining, Ordering::Release);
+        }
+        self.track_retired_at(oldest_remaining);
+    }
+}
+
+/// Detaches up to `chunk_size` nodes from the front of `list`, returning them.
+///
+/// # Safety
+///
+/// The caller must have exclusive access to the nodes in the list.
+unsafe fn split_nodes(list: &mut *mut Node<Retire>, chunk_size: usize) -> *mut Node<Retire> {
+    let chunk = *list;
+    let mut last_ptr = chunk;
+    for _ in 1..chunk_size {
+        // # Safety
+        //
+        // According to the safety contract we have exclusive access to the nodes.
+        let next = unsafe { &*last_ptr }.next.load(Ordering::Relaxed);
+        if next.is_null() {
+            break;
+        }
+        last_ptr = next;
+    }
+    // # Safety
+    //
+    // As above.
+    let last = unsafe { &*last_ptr };
+    *list = last.next.swap(core::ptr::null_mut(), Ordering::Relaxed);
+    chunk
+}
+
+/// Detaches up to `chunk_size` links from the front of `list`, returning them.
+///
+/// # Safety
+///
+/// The caller must have exclusive access to the values in the list.
+unsafe fn split_links(list: &mut *mut RetireLink, chunk_size: usize) -> *mut RetireLink {
+    let chunk = *list;
+    let mut last_ptr = chunk;
+    for _ in 1..chunk_size {
+        // # Safety
+        //
+        // According to the safety contract we have exclusive access to the values owning the
+        // links, none of which have been reclaimed.
+        let next = unsafe { &*last_ptr }.next.load(Ordering::Relaxed);
+        if next.is_null() {
+            break;
+        }
+        last_ptr = next;
+    }
+    // # Safety
+    //
+    // As above.
+    let last = unsafe { &*last_ptr };
+    *list = last.next.swap(core::ptr::null_mut(), Ordering::Relaxed);
+    chunk
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::super::ReclaimStrategy;
+    use super::*;
+    use crate::test_util::DropCounter;
+    use crate::AtomBoxIn;
+    use alloc::vec::Vec;
+    use core::task::{RawWaker, RawWakerVTable, Waker};
+
+    fn noop_waker() -> Waker {
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(core::ptr::null(), &VTABLE)
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        // # Safety
+        //
+        // The vtable's functions do nothing, so uphold the contract of `RawWaker`.
+        unsafe { Waker::from_raw(clone(core::ptr::null())) }
+    }
+
+    /// Polls `future` to completion, returning its output and the number of polls taken.
+    fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+        let waker = noop_waker();
+        let mut context = Context::from_waker(&waker);
+        let mut future = core::pin::pin!(future);
+        let mut polls = 0;
+        loop {
+            polls += 1;
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return (output, polls);
+            }
+        }
+    }
+
+    #[test]
+    fn reclaims_in_chunks() {
+        let domain: Domain<27> = Domain::new(ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(0), &domain);
+        for value in 1..=10 {
+            atom_box.store(drop_counter.track(value));
+        }
+        let guard = atom_box.load();
+
+        let (reclaimed, polls) = block_on(domain.reclaim_async().chunk_size(3));
+
+        assert_eq!(reclaimed, 10, "Every replaced value should be reclaimed");
+        assert_eq!(polls, 5, "One poll to scan and one for each chunk");
+        drop_counter.assert_drops(10);
+        assert_eq!(**guard, 10, "The stored value is untouched");
+    }
+
+    #[test]
+    fn protected_values_remain_retired() {
+        let domain: Domain<28> = Domain::new(ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(0), &domain);
+        let mut guards = Vec::new();
+        for value in 1..=4 {
+            guards.push(atom_box.load());
+            atom_box.store(drop_counter.track(value));
+        }
+
+        let (reclaimed, _) = block_on(domain.reclaim_async().chunk_size(1));
+
+        assert_eq!(reclaimed, 0, "Every replaced value is protected");
+        drop(guards);
+        assert_eq!(domain.reclaim(), 4, "Protected values are still retired");
+        drop_counter.assert_drops(4);
+    }
+
+    #[test]
+    fn dropping_the_future_restores_the_retired_values() {
+        let domain: Domain<29> = Domain::new(ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(0), &domain);
+        for value in 1..=10 {
+            atom_box.store(drop_counter.track(value));
+        }
+        let waker = noop_waker();
+        let mut context = Context::from_waker(&waker);
+        let mut future = domain.reclaim_async().chunk_size(4);
+
+        for _ in 0..2 {
+            assert!(
+                Pin::new(&mut future).poll(&mut context).is_pending(),
+                "Future should yield between chunks"
+            );
+        }
+        drop(future);
+
+        drop_counter.assert_drops(4);
+        assert_eq!(
+            domain.reclaim(),
+            6,
+            "Unprocessed values are returned to the domain"
+        );
+        drop_counter.assert_drops(10);
+    }
+}
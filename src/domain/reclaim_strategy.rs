@@ -13,7 +13,33 @@ const DEFAULT_HAZARD_POINTER_MULTIPLIER: isize = 2;
 ///
 /// A `default` const constructor function is defined for this enum. It cannot implement `Default`
 /// since we would like the `default` constructor to be a const function.
+///
+/// With the `serde` feature, strategies can be deserialized, so that they can be loaded from
+/// configuration rather than hard-coded. Variants are named in snake case, and any settings of
+/// the `timed_capped` strategy which are left out take their default values. A deserialized
+/// strategy can be given to a domain created at runtime with [`Domain::new`], or applied to a
+/// static domain with [`Domain::set_reclaim_strategy`].
+///
+/// [`Domain::new`]: crate::domain::Domain::new
+/// [`Domain::set_reclaim_strategy`]: crate::domain::Domain::set_reclaim_strategy
+///
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// use atom_box::domain::{Domain, ReclaimStrategy};
+///
+/// let config = r#"{ "timed_capped": { "retired_threshold": 64, "sync_timeout": [1, 0] } }"#;
+/// let reclaim_strategy: ReclaimStrategy = serde_json::from_str(config).unwrap();
+///
+/// let domain: Domain<42> = Domain::new(reclaim_strategy);
+/// # }
+/// ```
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 #[non_exhaustive]
 pub enum ReclaimStrategy {
     /// Every time an item is retired the domain will try to reclaim any items which are not
@@ -27,16 +53,86 @@ pub enum ReclaimStrategy {
     /// Memory reclamation will only happen when the `reclaim` method on [`crate::domain::Domain`]
     /// is called.
     Manual,
+
+    /// Retired items are never reclaimed, and retiring an item does no work at all.
+    ///
+    /// Values are still protected when they are loaded, so the API remains safe to use, but every
+    /// value which is replaced is leaked. This is intended for short-lived programs, such as
+    /// command line tools and fuzz targets, which would rather leak memory than pay for its
+    /// reclamation.
+    LeakAll,
+
+    /// Retired items are reclaimed when [`Domain::advance_frame`](crate::domain::Domain::advance_frame)
+    /// has been called the given number of times since they were retired.
+    ///
+    /// This suits programs built around a frame loop, such as games and simulations, in which no
+    /// guard outlives the frame in which it was loaded. Retiring an item does no work, and
+    /// reclamation happens at a predictable point in each frame. Items which are still protected
+    /// are kept until a later frame.
+    FrameBased(usize),
+}
+
+/// How the values replaced in an individual `AtomBox` are retired.
+///
+/// Set with [`AtomBoxIn::with_retire_policy`](crate::AtomBoxIn::with_retire_policy), this lets
+/// a box whose values are expensive to keep around be reclaimed more aggressively than the rest
+/// of its domain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum RetirePolicy {
+    /// Values are retired to the domain, and reclaimed according to its [`ReclaimStrategy`].
+    #[default]
+    Domain,
+
+    /// Values which are not protected when they are retired are freed straight away, as if the
+    /// domain were created with [`Domain::with_immediate_free`](crate::domain::Domain::with_immediate_free).
+    ///
+    /// Values which are still protected are retired to the domain as usual.
+    Immediate,
+}
+
+/// The condition which triggered a reclamation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum ReclaimTrigger {
+    Eager,
+    Threshold,
+    Timer,
+    #[cfg(feature = "std")]
+    MaxAge,
+    Manual,
+    Frame,
 }
 
 impl ReclaimStrategy {
-    pub(super) fn should_reclaim(&self, hazard_pointer_count: isize, retired_count: isize) -> bool {
+    pub(super) fn should_reclaim(
+        &self,
+        hazard_pointer_count: isize,
+        retired_count: isize,
+    ) -> Option<ReclaimTrigger> {
         match self {
-            Self::Eager => true,
+            Self::Eager => Some(ReclaimTrigger::Eager),
             Self::TimedCapped(settings) => {
                 settings.should_reclaim(hazard_pointer_count, retired_count)
             }
-            Self::Manual => false,
+            Self::Manual | Self::LeakAll | Self::FrameBased(_) => None,
+        }
+    }
+
+    /// The number of frames for which retired items are kept, if reclamation is frame based.
+    pub(super) fn frame_delay(&self) -> Option<usize> {
+        match self {
+            Self::FrameBased(delay) => Some(*delay),
+            _ => None,
+        }
+    }
+
+    /// The age after which retired items force a reclamation, if any.
+    #[cfg(feature = "std")]
+    pub(super) fn max_retired_age(&self) -> Option<Duration> {
+        match self {
+            Self::TimedCapped(settings) => settings.max_retired_age,
+            Self::Eager | Self::Manual | Self::LeakAll | Self::FrameBased(_) => None,
         }
     }
 
@@ -49,6 +145,19 @@ impl ReclaimStrategy {
     );
 }
 
+/// The current time in nanoseconds since the unix epoch.
+#[cfg(feature = "std")]
+pub(super) fn now_nanos() -> u64 {
+    use core::convert::TryFrom;
+    u64::try_from(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("system time is set to before the epoch")
+            .as_nanos(),
+    )
+    .expect("system time is too far into the future")
+}
+
 /// The particulate settings of the `TimedCapped` reclamation strategy.
 ///
 /// # Example
@@ -69,15 +178,34 @@ impl ReclaimStrategy {
 ///         .with_hazard_pointer_multiplier(3),
 /// );
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(from = "TimedCappedConfig")
+)]
 pub struct TimedCappedSettings {
     #[cfg(feature = "std")]
     last_sync_time: AtomicU64,
     #[cfg(feature = "std")]
     sync_timeout: Duration,
+    #[cfg(feature = "std")]
+    max_retired_age: Option<Duration>,
     hazard_pointer_multiplier: isize,
     retired_threshold: isize,
 }
 
+#[cfg(feature = "defmt")]
+impl defmt::Format for TimedCappedSettings {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "TimedCappedSettings {{ hazard_pointer_multiplier: {}, retired_threshold: {}, .. }}",
+            self.hazard_pointer_multiplier,
+            self.retired_threshold
+        )
+    }
+}
+
 impl TimedCappedSettings {
     #[cfg(feature = "std")]
     conditional_const!(
@@ -114,6 +242,8 @@ const RECLAIM_STRATEGY: ReclaimStrategy = ReclaimStrategy::TimedCapped(TimedCapp
                 last_sync_time: AtomicU64::new(0),
                 #[cfg(feature = "std")]
                 sync_timeout,
+                #[cfg(feature = "std")]
+                max_retired_age: None,
                 retired_threshold,
                 hazard_pointer_multiplier,
             }
@@ -148,31 +278,54 @@ const RECLAIM_STRATEGY: ReclaimStrategy = ReclaimStrategy::TimedCapped(TimedCapp
                 last_sync_time: AtomicU64::new(0),
                 #[cfg(feature = "std")]
                 sync_timeout: DEFAULT_SYNC_THRESHOLD,
+                #[cfg(feature = "std")]
+                max_retired_age: None,
                 retired_threshold,
                 hazard_pointer_multiplier,
             }
         }
     );
 
-    fn should_reclaim(&self, hazard_pointer_count: isize, retired_count: isize) -> bool {
+    #[cfg(not(miri))]
+    fn should_reclaim(
+        &self,
+        hazard_pointer_count: isize,
+        retired_count: isize,
+    ) -> Option<ReclaimTrigger> {
         if retired_count >= self.retired_threshold
             && retired_count >= hazard_pointer_count * self.hazard_pointer_multiplier
         {
-            return true;
+            return Some(ReclaimTrigger::Threshold);
+        }
+        if self.check_sync_time() {
+            return Some(ReclaimTrigger::Timer);
         }
-        self.check_sync_time()
+        None
+    }
+
+    /// Reclaims at random once a single item is retired, whatever the settings.
+    ///
+    /// Programs run under Miri retire too few items to reach the usual thresholds, so would
+    /// otherwise rarely exercise reclamation, or the paths which put still protected items back
+    /// into the retired list.
+    #[cfg(miri)]
+    fn should_reclaim(
+        &self,
+        _hazard_pointer_count: isize,
+        retired_count: isize,
+    ) -> Option<ReclaimTrigger> {
+        if retired_count >= 1 && miri_rng::coin_flip() {
+            return Some(ReclaimTrigger::Threshold);
+        }
+        if self.check_sync_time() {
+            return Some(ReclaimTrigger::Timer);
+        }
+        None
     }
 
     #[cfg(feature = "std")]
     fn check_sync_time(&self) -> bool {
-        use core::convert::TryFrom;
-        let time = u64::try_from(
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .expect("system time is set to before the epoch")
-                .as_nanos(),
-        )
-        .expect("system time is too far into the future");
+        let time = now_nanos();
         let last_sync_time = self.last_sync_time.load(Ordering::Relaxed);
 
         // If it's not time to clean yet, or someone else just started cleaning, don't clean.
@@ -219,6 +372,32 @@ This is not an implementation of `Default` since it is a const function.",
         }
     }
 
+    #[cfg(feature = "std")]
+    /// Set the maximum age of a retired item, after which a reclamation is forced.
+    ///
+    /// The age of the oldest retired item is checked whenever an item is retired or a hazard
+    /// pointer is released. Once it exceeds `max_retired_age`, an attempt is made to reclaim the
+    /// retired items regardless of the other thresholds. This bounds how long memory can linger
+    /// when values are rarely retired and the retired thresholds are never reached.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::domain::{Domain, ReclaimStrategy, TimedCappedSettings};
+    /// use core::time::Duration;
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::TimedCapped(
+    ///     TimedCappedSettings::default().with_max_retired_age(Duration::from_millis(100)),
+    /// ));
+    /// ```
+    pub const fn with_max_retired_age(self, max_retired_age: Duration) -> Self {
+        Self {
+            max_retired_age: Some(max_retired_age),
+            ..self
+        }
+    }
+
     /// Set the hazard pointer multiplier.
     ///
     /// If the number of retired items exceeds the number of hazard pointers multiplied by
@@ -241,3 +420,142 @@ This is not an implementation of `Default` since it is a const function.",
         }
     }
 }
+
+/// The settings of the `TimedCapped` strategy as they are deserialized, every one of them optional.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(default)]
+struct TimedCappedConfig {
+    #[cfg(feature = "std")]
+    sync_timeout: Duration,
+    #[cfg(feature = "std")]
+    max_retired_age: Option<Duration>,
+    retired_threshold: isize,
+    hazard_pointer_multiplier: isize,
+}
+
+#[cfg(feature = "serde")]
+impl Default for TimedCappedConfig {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            sync_timeout: DEFAULT_SYNC_THRESHOLD,
+            #[cfg(feature = "std")]
+            max_retired_age: None,
+            retired_threshold: DEFAULT_RETIERED_THRESHOLD,
+            hazard_pointer_multiplier: DEFAULT_HAZARD_POINTER_MULTIPLIER,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<TimedCappedConfig> for TimedCappedSettings {
+    fn from(config: TimedCappedConfig) -> Self {
+        let settings = Self::new(config.retired_threshold, config.hazard_pointer_multiplier);
+        #[cfg(feature = "std")]
+        let settings = settings.with_timeout(config.sync_timeout);
+        #[cfg(feature = "std")]
+        let settings = Self {
+            max_retired_age: config.max_retired_age,
+            ..settings
+        };
+        settings
+    }
+}
+
+/// A seeded random number generator for deciding when to reclaim under Miri.
+///
+/// The seed can be set at compile time with the `ATOM_BOX_MIRI_SEED` environment variable, so
+/// that a failing run can be reproduced, as long as its threads are scheduled the same way.
+#[cfg(miri)]
+mod miri_rng {
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+    static STATE: AtomicU64 = AtomicU64::new(seed(option_env!("ATOM_BOX_MIRI_SEED")));
+
+    const fn seed(seed: Option<&str>) -> u64 {
+        let digits = match seed {
+            Some(seed) => seed.as_bytes(),
+            None => return 0,
+        };
+        let mut seed = 0_u64;
+        let mut index = 0;
+        while index < digits.len() {
+            assert!(
+                digits[index].is_ascii_digit(),
+                "ATOM_BOX_MIRI_SEED must be a number"
+            );
+            seed = seed
+                .wrapping_mul(10)
+                .wrapping_add((digits[index] - b'0') as u64);
+            index += 1;
+        }
+        seed
+    }
+
+    /// Returns `true` or `false` with equal probability.
+    pub(super) fn coin_flip() -> bool {
+        // SplitMix64
+        let mut value = STATE
+            .fetch_add(GOLDEN_GAMMA, Ordering::Relaxed)
+            .wrapping_add(GOLDEN_GAMMA);
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        (value ^ (value >> 31)) & 1 == 1
+    }
+}
+
+#[cfg(all(feature = "serde", not(loom)))]
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strategies_deserialize_by_name() {
+        let strategies: alloc::vec::Vec<ReclaimStrategy> =
+            serde_json::from_str(r#"["eager", "manual", "leak_all", { "frame_based": 3 }]"#)
+                .unwrap();
+
+        assert!(matches!(
+            strategies[..],
+            [
+                ReclaimStrategy::Eager,
+                ReclaimStrategy::Manual,
+                ReclaimStrategy::LeakAll,
+                ReclaimStrategy::FrameBased(3)
+            ]
+        ));
+    }
+
+    #[test]
+    fn missing_timed_capped_settings_take_their_defaults() {
+        let strategy: ReclaimStrategy =
+            serde_json::from_str(r#"{ "timed_capped": { "hazard_pointer_multiplier": 4 } }"#)
+                .unwrap();
+
+        let settings = match strategy {
+            ReclaimStrategy::TimedCapped(settings) => settings,
+            other => panic!("Expected a timed capped strategy, got {:?}", other),
+        };
+        assert_eq!(settings.hazard_pointer_multiplier, 4);
+        assert_eq!(settings.retired_threshold, DEFAULT_RETIERED_THRESHOLD);
+        #[cfg(feature = "std")]
+        assert_eq!(settings.sync_timeout, DEFAULT_SYNC_THRESHOLD);
+        #[cfg(feature = "std")]
+        assert_eq!(settings.max_retired_age, None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn durations_deserialize_as_seconds_and_nanoseconds() {
+        let settings: TimedCappedSettings = serde_json::from_str(
+            r#"{ "sync_timeout": [5, 0], "max_retired_age": { "secs": 0, "nanos": 1000000 } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(settings.sync_timeout, Duration::from_secs(5));
+        assert_eq!(settings.max_retired_age, Some(Duration::from_millis(1)));
+    }
+}
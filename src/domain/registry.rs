@@ -0,0 +1,122 @@
+use super::Domain;
+use alloc::vec::Vec;
+use std::sync::Mutex;
+
+/// The state of a registered [`Domain`] at the time the registry was read.
+///
+/// Returned by [`domains`](crate::domains).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DomainInfo {
+    /// The name of the domain, if it was given one.
+    pub name: Option<&'static str>,
+    /// The ID of the domain.
+    pub id: usize,
+    /// The number of retired values waiting to be reclaimed.
+    pub retired: usize,
+    /// The number of hazard pointers the domain has allocated.
+    ///
+    /// Child domains report the hazard pointers they share with their parent.
+    pub hazard_pointers: usize,
+    /// The number of hazard pointers currently acquired.
+    pub hazard_pointers_in_use: usize,
+    /// Why the domain has reclaimed its retired values.
+    #[cfg(feature = "stats")]
+    pub reclaim_stats: super::ReclaimStats,
+    /// The hazard pointers currently acquired, and the threads which acquired them.
+    #[cfg(feature = "thread-attribution")]
+    pub hazard_owners: Vec<super::HazardOwner>,
+}
+
+/// The operations of a registered [`Domain`] which do not depend on its ID.
+trait Registered: Sync {
+    #[cfg(not(loom))]
+    fn info(&self) -> DomainInfo;
+}
+
+impl<const DOMAIN_ID: usize> Registered for Domain<DOMAIN_ID> {
+    #[cfg(not(loom))]
+    fn info(&self) -> DomainInfo {
+        let hazard_ptrs = self.hazard_ptrs();
+        DomainInfo {
+            name: self.name,
+            id: DOMAIN_ID,
+            // The count can briefly be negative while values are being reclaimed.
+            retired: self
+                .retired
+                .count
+                .load(crate::sync::Ordering::Acquire)
+                .max(0) as usize,
+            hazard_pointers: hazard_ptrs.capacity(),
+            hazard_pointers_in_use: hazard_ptrs.iter().count(),
+            #[cfg(feature = "stats")]
+            reclaim_stats: self.reclaim_stats(),
+            #[cfg(feature = "thread-attribution")]
+            hazard_owners: self.hazard_owners(),
+        }
+    }
+}
+
+// Only domains which live for the rest of the process can be registered, so entries never dangle.
+static REGISTRY: Mutex<Vec<&'static dyn Registered>> = Mutex::new(Vec::new());
+
+/// Adds `domain` to the registry, unless it has already been registered.
+pub(super) fn register<const DOMAIN_ID: usize>(domain: &'static Domain<DOMAIN_ID>) {
+    // The registry cannot be left inconsistent by a panic, so poisoning is ignored.
+    let mut registry = REGISTRY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let address = domain as *const Domain<DOMAIN_ID> as *const ();
+    if !registry
+        .iter()
+        .any(|registered| *registered as *const dyn Registered as *const () == address)
+    {
+        registry.push(domain);
+    }
+}
+
+/// Returns the state of every registered domain, in the order they were registered.
+#[cfg(not(loom))]
+pub(crate) fn snapshot() -> Vec<DomainInfo> {
+    let registry = REGISTRY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    registry.iter().map(|domain| domain.info()).collect()
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::AtomBoxIn;
+
+    static TEST_DOMAIN: Domain<21> = Domain::new_named("registered", ReclaimStrategy::Manual);
+
+    fn registered_info() -> Vec<DomainInfo> {
+        snapshot()
+            .into_iter()
+            .filter(|info| info.name == Some("registered"))
+            .collect()
+    }
+
+    #[test]
+    fn registered_domains_report_their_backlog_and_slot_usage() {
+        TEST_DOMAIN.register();
+        TEST_DOMAIN.register();
+        let atom_box = AtomBoxIn::new_with_domain(1, &TEST_DOMAIN);
+        let guard = atom_box.load();
+        atom_box.store(2);
+
+        let info = registered_info();
+        drop(guard);
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(info.len(), 1, "Registering twice adds the domain once");
+        assert_eq!(info[0].id, 21);
+        assert_eq!(info[0].retired, 1, "The replaced value is still protected");
+        assert_eq!(info[0].hazard_pointers_in_use, 1);
+        assert!(info[0].hazard_pointers >= 1);
+        assert_eq!(registered_info()[0].retired, 0);
+    }
+}
@@ -0,0 +1,221 @@
+use super::{Domain, ReclaimStats};
+use core::time::Duration;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+/// A snapshot of a [`Domain`], taken periodically by a [`StatsReporter`].
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+/// use core::time::Duration;
+///
+/// const CUSTOM_DOMAIN_ID: usize = 42;
+/// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+///
+/// let atom_box = AtomBoxIn::new_with_domain("Hello", &CUSTOM_DOMAIN);
+/// atom_box.store("World");
+///
+/// let (reports, received) = std::sync::mpsc::channel();
+/// let reporter = CUSTOM_DOMAIN.spawn_stats_reporter(Duration::from_millis(10), move |report| {
+///     let _ = reports.send(report.clone());
+/// });
+///
+/// let report = received.recv().unwrap();
+/// assert_eq!(report.retired, 1);
+/// println!("{report}");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct StatsReport {
+    /// The name of the domain, if it was given one.
+    pub name: Option<&'static str>,
+    /// The ID of the domain.
+    pub id: usize,
+    /// The number of retired values waiting to be reclaimed.
+    pub retired: usize,
+    /// The number of retired values reclaimed since the previous report.
+    pub reclaimed: usize,
+    /// The time since the previous report, or since the reporter was spawned.
+    pub elapsed: Duration,
+    /// The number of hazard pointers the domain has allocated.
+    ///
+    /// Child domains report the hazard pointers they share with their parent.
+    pub hazard_pointers: usize,
+    /// The number of hazard pointers currently acquired.
+    pub hazard_pointers_in_use: usize,
+    /// Why the domain has reclaimed its retired values.
+    pub reclaim_stats: ReclaimStats,
+}
+
+impl StatsReport {
+    /// The number of retired values reclaimed per second since the previous report.
+    pub fn reclaimed_per_second(&self) -> f64 {
+        self.reclaimed as f64 / self.elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+impl core::fmt::Display for StatsReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "domain `{}`", name)?,
+            None => write!(f, "domain {}", self.id)?,
+        }
+        write!(
+            f,
+            ": {} retired, {:.1} reclaimed/s, {} of {} hazard pointers in use",
+            self.retired,
+            self.reclaimed_per_second(),
+            self.hazard_pointers_in_use,
+            self.hazard_pointers
+        )
+    }
+}
+
+/// A background thread which periodically reports the stats of a [`Domain`].
+///
+/// The thread is stopped, and joined, when the reporter is dropped.
+///
+/// Created by [`Domain::spawn_stats_reporter`].
+#[must_use = "The reporter stops as soon as it is dropped"]
+#[derive(Debug)]
+pub struct StatsReporter {
+    // Set to `true` to stop the thread.
+    stopped: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for StatsReporter {
+    fn drop(&mut self) {
+        let (stopped, wake) = &*self.stopped;
+        *stopped
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = true;
+        wake.notify_one();
+        if let Some(thread) = self.thread.take() {
+            // A panic in the callback has already been reported by the thread.
+            let _ = thread.join();
+        }
+    }
+}
+
+pub(super) fn spawn<const DOMAIN_ID: usize>(
+    domain: &'static Domain<DOMAIN_ID>,
+    interval: Duration,
+    mut callback: impl FnMut(&StatsReport) + Send + 'static,
+) -> StatsReporter {
+    let stopped = Arc::new((Mutex::new(false), Condvar::new()));
+    let thread_stopped = stopped.clone();
+    let thread = std::thread::Builder::new()
+        .name(alloc::format!(
+            "{}-stats",
+            domain.name.unwrap_or("atom-box")
+        ))
+        .spawn(move || {
+            let (stopped, wake) = &*thread_stopped;
+            let mut previous_reclaimed = domain.reclaim_stats().reclaimed;
+            let mut previous_report = Instant::now();
+            loop {
+                let (stopped, _) = wake
+                    .wait_timeout_while(
+                        stopped
+                            .lock()
+                            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+                        interval,
+                        |stopped| !*stopped,
+                    )
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                if *stopped {
+                    return;
+                }
+                drop(stopped);
+                let reclaim_stats = domain.reclaim_stats();
+                let hazard_ptrs = domain.hazard_ptrs();
+                let now = Instant::now();
+                callback(&StatsReport {
+                    name: domain.name,
+                    id: DOMAIN_ID,
+                    // The count can briefly be negative while values are being reclaimed.
+                    retired: domain
+                        .retired
+                        .count
+                        .load(crate::sync::Ordering::Acquire)
+                        .max(0) as usize,
+                    reclaimed: reclaim_stats.reclaimed - previous_reclaimed,
+                    elapsed: now - previous_report,
+                    hazard_pointers: hazard_ptrs.capacity(),
+                    hazard_pointers_in_use: hazard_ptrs.iter().count(),
+                    reclaim_stats,
+                });
+                previous_reclaimed = reclaim_stats.reclaimed;
+                previous_report = now;
+            }
+        })
+        .expect("Failed to spawn the stats reporter thread");
+    StatsReporter {
+        stopped,
+        thread: Some(thread),
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::AtomBoxIn;
+    use alloc::boxed::Box;
+
+    #[test]
+    fn reports_the_backlog_throughput_and_slot_usage() {
+        let domain: &'static Domain<1> = Box::leak(Box::new(Domain::new(ReclaimStrategy::Manual)));
+        let atom_box = AtomBoxIn::new_with_domain(1, domain);
+        atom_box.store(2);
+        atom_box.store(3);
+        let guard = atom_box.load();
+        let (reports, received) = std::sync::mpsc::channel();
+
+        let reporter = domain.spawn_stats_reporter(Duration::from_millis(1), move |report| {
+            let _ = reports.send(report.clone());
+        });
+        let first = received.recv().unwrap();
+        drop(guard);
+        domain.reclaim();
+        let reclaimed: usize = received
+            .iter()
+            .map(|report| report.reclaimed)
+            .scan(0, |total, reclaimed| {
+                *total += reclaimed;
+                Some(*total)
+            })
+            .find(|total| *total >= 2)
+            .unwrap();
+        drop(reporter);
+
+        assert_eq!(first.retired, 2);
+        assert_eq!(first.reclaimed, 0);
+        assert_eq!(first.hazard_pointers_in_use, 1, "The guard is counted");
+        assert!(first.hazard_pointers >= 1);
+        assert_eq!(reclaimed, 2, "Each value is reported as reclaimed once");
+    }
+
+    #[test]
+    fn dropping_the_reporter_stops_it() {
+        let domain: &'static Domain<2> = Box::leak(Box::new(Domain::new(ReclaimStrategy::Manual)));
+        let (reports, received) = std::sync::mpsc::channel();
+        let reporter = domain.spawn_stats_reporter(Duration::from_secs(3600), move |report| {
+            let _ = reports.send(report.clone());
+        });
+
+        let started = Instant::now();
+        drop(reporter);
+
+        assert!(
+            started.elapsed() < Duration::from_secs(60),
+            "The reporter does not wait out its interval"
+        );
+        assert!(received.recv().is_err(), "No report was made");
+    }
+}
@@ -0,0 +1,157 @@
+use super::{Domain, ReclaimStrategy};
+use crate::AtomBoxIn;
+use core::marker::PhantomData;
+
+/// A domain which only lives for the duration of [`Domain::scope`], whose boxes may hold values
+/// borrowing from outside the scope.
+///
+/// Values retired to a domain can outlive the box they were stored in, so values stored in boxes
+/// associated with other domains should not hold borrows which may end before the domain is
+/// dropped. Every value retired to a `DomainScope` is reclaimed before [`Domain::scope`] returns,
+/// so its boxes can hold any value which outlives the scope.
+#[derive(Debug)]
+pub struct DomainScope<'scope, 'env: 'scope, const DOMAIN_ID: usize> {
+    domain: Domain<DOMAIN_ID>,
+    // Invariant, so that the lifetimes cannot be shortened or extended.
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<const DOMAIN_ID: usize> Domain<DOMAIN_ID> {
+    /// Creates a domain which lives for the duration of `f`, reclaiming every value retired to it
+    /// before returning.
+    ///
+    /// Like [`std::thread::scope`], boxes created from the [`DomainScope`] can hold values
+    /// borrowing from outside of the scope, as the values are guaranteed to be reclaimed while
+    /// the borrow is still live.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::domain::{Domain, ReclaimStrategy};
+    ///
+    /// let first = String::from("Hello");
+    /// let second = String::from("World");
+    ///
+    /// let loaded = Domain::<42>::scope(ReclaimStrategy::Manual, |scope| {
+    ///     let atom_box = scope.new_box(first.as_str());
+    ///     atom_box.store(second.as_str());
+    ///     atom_box.load().to_uppercase()
+    /// });
+    ///
+    /// assert_eq!(loaded, "WORLD");
+    /// ```
+    ///
+    /// Values borrowing from inside the scope cannot be stored, as they may be dropped before the
+    /// domain reclaims them.
+    ///
+    /// ```compile_fail
+    /// use atom_box::domain::{Domain, ReclaimStrategy};
+    ///
+    /// Domain::<42>::scope(ReclaimStrategy::Manual, |scope| {
+    ///     let local = String::from("Hello");
+    ///     let atom_box = scope.new_box(local.as_str());
+    /// });
+    /// ```
+    pub fn scope<'env, F, R>(reclaim_strategy: ReclaimStrategy, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope DomainScope<'scope, 'env, DOMAIN_ID>) -> R,
+    {
+        let scope = DomainScope {
+            domain: Domain::new(reclaim_strategy),
+            scope: PhantomData,
+            env: PhantomData,
+        };
+        // Boxes created from the scope borrow it, so cannot be used after `f` returns. Dropping
+        // the domain reclaims every value retired to it.
+        f(&scope)
+    }
+}
+
+impl<'scope, 'env, const DOMAIN_ID: usize> DomainScope<'scope, 'env, DOMAIN_ID> {
+    /// Creates a new `AtomBox` associated with the scope's domain.
+    ///
+    /// The value can borrow from outside the scope. It is reclaimed before [`Domain::scope`]
+    /// returns, if not before.
+    pub fn new_box<T: 'scope>(&'scope self, value: T) -> AtomBoxIn<'scope, T, DOMAIN_ID> {
+        AtomBoxIn::new_with_domain(value, &self.domain)
+    }
+
+    /// Reclaims all unprotected retired values, returning the number reclaimed.
+    pub fn reclaim(&self) -> usize {
+        self.domain.reclaim()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec::Vec;
+    use std::sync::Mutex;
+
+    struct Logged<'a> {
+        value: usize,
+        log: &'a Mutex<Vec<usize>>,
+    }
+
+    impl Drop for Logged<'_> {
+        fn drop(&mut self) {
+            self.log.lock().unwrap().push(self.value);
+        }
+    }
+
+    #[test]
+    fn retired_values_are_reclaimed_before_the_scope_ends() {
+        let log = Mutex::new(Vec::new());
+
+        let reclaimed_within_scope = Domain::<1>::scope(ReclaimStrategy::Manual, |scope| {
+            let atom_box = scope.new_box(Logged {
+                value: 1,
+                log: &log,
+            });
+            let guard = atom_box.load();
+            atom_box.store(Logged {
+                value: 2,
+                log: &log,
+            });
+            core::mem::forget(atom_box);
+            drop(guard);
+            log.lock().unwrap().len()
+        });
+
+        assert_eq!(
+            reclaimed_within_scope, 0,
+            "Nothing is reclaimed within the scope"
+        );
+        assert_eq!(
+            *log.lock().unwrap(),
+            [1],
+            "The retired value is reclaimed, the value of the forgotten box is leaked"
+        );
+    }
+
+    #[test]
+    fn scoped_boxes_can_be_shared_between_scoped_threads() {
+        let values: Vec<_> = (0..100).collect();
+
+        let last = Domain::<2>::scope(ReclaimStrategy::Eager, |scope| {
+            let atom_box = scope.new_box(&values[0]);
+            std::thread::scope(|threads| {
+                threads.spawn(|| {
+                    for value in &values {
+                        atom_box.store(value);
+                    }
+                });
+                threads.spawn(|| {
+                    for _ in 0..100 {
+                        assert!(**atom_box.load() < 100);
+                    }
+                });
+            });
+            **atom_box.load()
+        });
+
+        assert_eq!(last, 99);
+    }
+}
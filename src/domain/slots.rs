@@ -0,0 +1,456 @@
+#![deny(unsafe_op_in_unsafe_fn)]
+use crate::macros::conditional_const;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use alloc::boxed::Box;
+use core::iter::Iterator;
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+/// The number of slots in each chunk, one per bit of the availability bitmap.
+const SLOTS_PER_CHUNK: usize = usize::BITS as usize;
+
+/// A growable collection of slots which can be acquired and released.
+///
+/// Slots are allocated in chunks. Each chunk maintains a bitmap of which of its slots are in use,
+/// so acquiring a slot is a `trailing_zeros` followed by a compare and swap, rather than a walk
+/// of a list of available slots. Chunks are never deallocated until the collection is dropped.
+///
+/// [`Slots::try_acquire`] never allocates and makes a bounded number of attempts on each chunk,
+/// so slots can be acquired from contexts which cannot allocate or block, provided enough slots
+/// have been [reserved](Slots::reserve) up front.
+#[derive(Debug)]
+pub(super) struct Slots<T> {
+    head: AtomicPtr<Chunk<T>>,
+    // The total number of slots in all chunks.
+    capacity: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Chunk<T> {
+    slots: [Slot<T>; SLOTS_PER_CHUNK],
+    // A set bit marks the slot with that index as in use.
+    in_use: AtomicUsize,
+    next: AtomicPtr<Chunk<T>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Slot<T> {
+    value: T,
+    index: usize,
+    // Set before the chunk is published and never changed afterwards.
+    chunk: AtomicPtr<Chunk<T>>,
+    // The key of the thread which acquired the slot while it is in use, or zero.
+    #[cfg(feature = "thread-attribution")]
+    owner: crate::sync::AtomicU64,
+    // The label of the thread which acquired the slot, as recorded in `SlotOwner`.
+    #[cfg(feature = "thread-attribution")]
+    owner_label: AtomicUsize,
+}
+
+#[cfg(feature = "thread-attribution")]
+impl<T> Slot<T> {
+    /// Records the thread which acquired the slot.
+    ///
+    /// The owner is only ever reported as a snapshot, so relaxed stores suffice, and a reader
+    /// racing with an acquire may pair the thread of one owner with the label of another.
+    pub(super) fn set_owner(&self, owner: Option<super::attribution::SlotOwner>) {
+        let (thread, label) = owner.map_or((0, 0), |owner| (owner.thread, owner.label));
+        self.owner_label.store(label, Ordering::Relaxed);
+        self.owner.store(thread, Ordering::Relaxed);
+    }
+
+    /// The thread which acquired the slot, if it is in use and the owner has been recorded.
+    pub(super) fn owner(&self) -> Option<super::attribution::SlotOwner> {
+        let thread = self.owner.load(Ordering::Relaxed);
+        (thread != 0).then(|| super::attribution::SlotOwner {
+            thread,
+            label: self.owner_label.load(Ordering::Relaxed),
+        })
+    }
+}
+
+impl<T> Deref for Slot<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T: Default> Chunk<T> {
+    /// Allocates a new chunk with the slots in the `in_use` bitmap already in use.
+    fn new(in_use: usize) -> *mut Self {
+        #[cfg(feature = "leak-audit")]
+        crate::leak_audit::track(
+            crate::leak_audit::AllocationKind::HazardPointers,
+            core::mem::size_of::<Self>(),
+        );
+        let chunk = Box::into_raw(Box::new(Self {
+            slots: core::array::from_fn(|index| Slot {
+                value: T::default(),
+                index,
+                chunk: AtomicPtr::new(core::ptr::null_mut()),
+                #[cfg(feature = "thread-attribution")]
+                owner: crate::sync::AtomicU64::new(0),
+                #[cfg(feature = "thread-attribution")]
+                owner_label: AtomicUsize::new(0),
+            }),
+            in_use: AtomicUsize::new(in_use),
+            next: AtomicPtr::new(core::ptr::null_mut()),
+        }));
+        // # Safety
+        //
+        // We have just created the chunk and have not yet shared it with any other threads.
+        for slot in unsafe { &*chunk }.slots.iter() {
+            slot.chunk.store(chunk, Ordering::Relaxed);
+        }
+        chunk
+    }
+}
+
+impl<T> Chunk<T> {
+    /// Attempts to acquire a slot, giving up after one attempt per slot in the chunk.
+    fn try_acquire(&self) -> Option<&Slot<T>> {
+        let mut in_use = self.in_use.load(Ordering::Acquire);
+        for _ in 0..SLOTS_PER_CHUNK {
+            let index = (!in_use).trailing_zeros() as usize;
+            if index == SLOTS_PER_CHUNK {
+                return None;
+            }
+            let bit = 1 << index;
+            in_use = self.in_use.fetch_or(bit, Ordering::AcqRel);
+            if in_use & bit == 0 {
+                return Some(&self.slots[index]);
+            }
+        }
+        None
+    }
+}
+
+impl<T> Slots<T> {
+    conditional_const!(
+        "Creates a new `Slots`",
+        pub,
+        fn new() -> Self {
+            Self {
+                head: AtomicPtr::new(core::ptr::null_mut()),
+                capacity: AtomicUsize::new(0),
+            }
+        }
+    );
+
+    /// Acquires a slot which is not in use, allocating a new chunk if all slots are in use.
+    pub(super) fn acquire(&self) -> &Slot<T>
+    where
+        T: Default,
+    {
+        self.try_acquire()
+            .unwrap_or_else(|| self.acquire_from_new_chunk())
+    }
+
+    /// Acquires a slot which is not in use, allocating a new chunk if all slots are in use and
+    /// there are fewer than `limit` slots.
+    ///
+    /// Concurrent callers may each allocate a chunk, so the limit may be exceeded by a chunk per
+    /// caller.
+    pub(super) fn acquire_within(&self, limit: usize) -> Option<&Slot<T>>
+    where
+        T: Default,
+    {
+        self.try_acquire().or_else(|| {
+            if self.capacity.load(Ordering::Acquire) < limit {
+                Some(self.acquire_from_new_chunk())
+            } else {
+                None
+            }
+        })
+    }
+
+    fn acquire_from_new_chunk(&self) -> &Slot<T>
+    where
+        T: Default,
+    {
+        let new_chunk_ptr = Chunk::new(1);
+        // # Safety
+        //
+        // We have just created the chunk, it will only be deallocated when the `Slots` is dropped.
+        let new_chunk = unsafe { &*new_chunk_ptr };
+        self.push_chunk(new_chunk_ptr);
+        &new_chunk.slots[0]
+    }
+
+    /// Acquires a slot which is not in use without allocating.
+    ///
+    /// Returns `None` if no slot could be acquired from the existing chunks.
+    pub(super) fn try_acquire(&self) -> Option<&Slot<T>> {
+        self.chunks().find_map(Chunk::try_acquire)
+    }
+
+    /// Returns the total number of slots, whether or not they are in use.
+    #[cfg(any(feature = "domain-registry", feature = "stats-reporter"))]
+    pub(super) fn capacity(&self) -> usize {
+        self.capacity.load(Ordering::Acquire)
+    }
+
+    /// Allocates chunks until there are at least `count` slots.
+    pub(super) fn reserve(&self, count: usize)
+    where
+        T: Default,
+    {
+        while self.capacity.load(Ordering::Acquire) < count {
+            self.push_chunk(Chunk::new(0));
+        }
+    }
+
+    fn push_chunk(&self, chunk_ptr: *mut Chunk<T>) {
+        // # Safety
+        //
+        // The chunk has just been created by the caller and not yet published.
+        let chunk = unsafe { &*chunk_ptr };
+        let mut head_ptr = self.head.load(Ordering::Acquire);
+        loop {
+            chunk.next.store(head_ptr, Ordering::Release);
+            match self.head.compare_exchange_weak(
+                head_ptr,
+                chunk_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(new_head_ptr) => head_ptr = new_head_ptr,
+            }
+        }
+        let _capacity = self.capacity.fetch_add(SLOTS_PER_CHUNK, Ordering::Release);
+        #[cfg(feature = "log")]
+        log::debug!(
+            "Allocated {} hazard pointer slots, {} in total",
+            SLOTS_PER_CHUNK,
+            _capacity + SLOTS_PER_CHUNK
+        );
+        #[cfg(feature = "defmt")]
+        defmt::debug!(
+            "Allocated {} hazard pointer slots, {} in total",
+            SLOTS_PER_CHUNK,
+            _capacity + SLOTS_PER_CHUNK
+        );
+    }
+
+    fn chunks(&self) -> impl Iterator<Item = &Chunk<T>> {
+        // # Safety
+        //
+        // Chunks are only deallocated when the `Slots` is dropped.
+        let head = unsafe { self.head.load(Ordering::Acquire).as_ref() };
+        core::iter::successors(head, |chunk| unsafe {
+            chunk.next.load(Ordering::Acquire).as_ref()
+        })
+    }
+
+    /// Marks a slot acquired from this `Slots` as no longer in use.
+    pub(super) fn release(&self, slot: &Slot<T>) {
+        // # Safety
+        //
+        // The chunk pointer was set before the chunk was published and chunks are only
+        // deallocated when the `Slots` is dropped.
+        let chunk = unsafe { &*slot.chunk.load(Ordering::Relaxed) };
+        #[cfg(feature = "thread-attribution")]
+        slot.set_owner(None);
+        chunk
+            .in_use
+            .fetch_and(!(1 << slot.index), Ordering::Release);
+    }
+
+    /// Iterates over the values of the slots which are currently in use.
+    pub(super) fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots().map(|slot| &slot.value)
+    }
+
+    /// Iterates over the slots of every chunk with at least one slot in use, whether or not each
+    /// slot is in use.
+    #[cfg(feature = "vectorized-scan")]
+    pub(super) fn chunks_in_use(&self) -> impl Iterator<Item = &[Slot<T>]> {
+        self.chunks()
+            .filter(|chunk| chunk.in_use.load(Ordering::Acquire) != 0)
+            .map(|chunk| &chunk.slots[..])
+    }
+
+    /// Iterates over the slots which are currently in use.
+    pub(super) fn slots(&self) -> SlotsIterator<'_, T> {
+        SlotsIterator {
+            chunk: self.head.load(Ordering::Acquire),
+            in_use: None,
+            _slots: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for Slots<T> {
+    fn drop(&mut self) {
+        let mut chunk_ptr = self.head.load(Ordering::Relaxed);
+        while !chunk_ptr.is_null() {
+            // # Safety
+            //
+            // We are the only ones capable of creating chunks. Chunks are created with
+            // `Box::into_raw`. Therefore, we know that the safety guarantees of `Box` have been
+            // met and we have a non null pointer.
+            let chunk: Box<Chunk<T>> = unsafe { Box::from_raw(chunk_ptr) };
+            #[cfg(feature = "leak-audit")]
+            crate::leak_audit::untrack(
+                crate::leak_audit::AllocationKind::HazardPointers,
+                core::mem::size_of::<Chunk<T>>(),
+            );
+            chunk_ptr = chunk.next.load(Ordering::Relaxed);
+        }
+    }
+}
+
+pub(super) struct SlotsIterator<'a, T> {
+    chunk: *const Chunk<T>,
+    // The slots of the current chunk which are yet to be visited, loaded on entering the chunk.
+    in_use: Option<usize>,
+    _slots: PhantomData<&'a Slots<T>>,
+}
+
+impl<'a, T> Iterator for SlotsIterator<'a, T> {
+    type Item = &'a Slot<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // # Safety
+            //
+            // Chunks are only deallocated when the `Slots` is dropped. Chunks are allocated via box
+            // so maintain all the safety guarantees associated with Box.
+            let chunk: &'a Chunk<T> = unsafe { self.chunk.as_ref() }?;
+            let in_use = *self
+                .in_use
+                .get_or_insert_with(|| chunk.in_use.load(Ordering::Acquire));
+            if in_use == 0 {
+                self.chunk = chunk.next.load(Ordering::Acquire);
+                self.in_use = None;
+                continue;
+            }
+            let index = in_use.trailing_zeros() as usize;
+            self.in_use = Some(in_use & (in_use - 1));
+            break Some(&chunk.slots[index]);
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sync::AtomicUsize;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn acquired_slots_are_distinct() {
+        let slots: Slots<usize> = Slots::new();
+
+        let acquired: Vec<_> = (0..SLOTS_PER_CHUNK + 1)
+            .map(|_| slots.acquire() as *const Slot<usize>)
+            .collect();
+
+        for (index, slot) in acquired.iter().enumerate() {
+            assert!(
+                !acquired[index + 1..].contains(slot),
+                "Slot {} was acquired twice",
+                index
+            );
+        }
+        assert_eq!(
+            slots.iter().count(),
+            SLOTS_PER_CHUNK + 1,
+            "Every acquired slot should be iterated over"
+        );
+        assert!(
+            !unsafe { &*slots.head.load(Ordering::Acquire) }
+                .next
+                .load(Ordering::Acquire)
+                .is_null(),
+            "A second chunk should have been allocated"
+        );
+    }
+
+    #[test]
+    fn released_slots_are_reused() {
+        let slots: Slots<usize> = Slots::new();
+        let first = slots.acquire();
+        let _second = slots.acquire();
+
+        slots.release(first);
+        let reacquired = slots.acquire();
+
+        assert!(
+            core::ptr::eq(first, reacquired),
+            "The released slot should be acquired again"
+        );
+        assert_eq!(
+            slots.iter().count(),
+            2,
+            "Only the slots in use should be iterated over"
+        );
+    }
+
+    #[test]
+    fn try_acquire_fails_once_reserved_slots_are_exhausted() {
+        let slots: Slots<usize> = Slots::new();
+        slots.reserve(SLOTS_PER_CHUNK + 1);
+
+        let acquired = (0..2 * SLOTS_PER_CHUNK)
+            .map_while(|_| slots.try_acquire())
+            .count();
+
+        assert_eq!(
+            acquired,
+            2 * SLOTS_PER_CHUNK,
+            "Reserved slots are rounded up to whole chunks"
+        );
+        assert!(
+            slots.try_acquire().is_none(),
+            "Acquisition fails rather than allocating"
+        );
+    }
+
+    #[test]
+    fn acquire_within_does_not_allocate_past_limit() {
+        let slots: Slots<usize> = Slots::new();
+
+        let acquired = (0..2 * SLOTS_PER_CHUNK)
+            .map_while(|_| slots.acquire_within(1))
+            .count();
+
+        assert_eq!(
+            acquired, SLOTS_PER_CHUNK,
+            "Only the first chunk should have been allocated"
+        );
+    }
+
+    #[test]
+    fn concurrent_acquisition_is_exclusive() {
+        let slots: Slots<AtomicUsize> = Slots::new();
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        let slot = slots.acquire();
+
+                        assert_eq!(
+                            slot.swap(1, Ordering::AcqRel),
+                            0,
+                            "Slot should not be in use by another thread"
+                        );
+                        slot.store(0, Ordering::Release);
+                        slots.release(slot);
+                    }
+                });
+            }
+        });
+        let chunk = unsafe { &*slots.head.load(Ordering::Acquire) };
+        assert_eq!(
+            chunk.in_use.load(Ordering::Acquire),
+            0,
+            "Every slot should have been released"
+        );
+    }
+}
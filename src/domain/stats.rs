@@ -0,0 +1,258 @@
+use super::reclaim_strategy::ReclaimTrigger;
+use crate::macros::conditional_const;
+use crate::sync::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+// Hazard pointers held for `2^(DWELL_BUCKETS - 2)` nanoseconds (about nine minutes) or longer are
+// counted in the last bucket.
+#[cfg(feature = "std")]
+const DWELL_BUCKETS: usize = 41;
+
+/// A snapshot of why a [`Domain`](super::Domain) has reclaimed its retired items.
+///
+/// Each reclamation is attributed to the condition which triggered it. Comparing these counts
+/// shows which of the [`TimedCappedSettings`](super::TimedCappedSettings) is actually firing.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+///
+/// const CUSTOM_DOMAIN_ID: usize = 42;
+/// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+///
+/// let atom_box = AtomBoxIn::new_with_domain("Hello", &CUSTOM_DOMAIN);
+/// atom_box.store("World");
+/// CUSTOM_DOMAIN.reclaim();
+///
+/// let stats = CUSTOM_DOMAIN.reclaim_stats();
+/// assert_eq!(stats.declined, 1);
+/// assert_eq!(stats.manual, 1);
+/// assert_eq!(stats.reclaimed, 1);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct ReclaimStats {
+    /// Reclamations run because the domain uses the `Eager` strategy.
+    pub eager: usize,
+    /// Reclamations run because the number of retired items reached both the retired threshold
+    /// and the hazard pointer multiple.
+    pub threshold: usize,
+    /// Reclamations run because the sync timeout had elapsed.
+    pub timer: usize,
+    /// Reclamations run because the oldest retired item exceeded the maximum retired age.
+    pub max_age: usize,
+    /// Reclamations run by calling [`Domain::reclaim`](super::Domain::reclaim).
+    pub manual: usize,
+    /// Reclamations run by calling [`Domain::advance_frame`](super::Domain::advance_frame).
+    pub frame: usize,
+    /// The number of times an item was retired without triggering a reclamation.
+    pub declined: usize,
+    /// The number of retired items which have been reclaimed.
+    pub reclaimed: usize,
+}
+
+/// The counters behind [`ReclaimStats`].
+#[derive(Debug)]
+pub(super) struct ReclaimCounters {
+    eager: AtomicUsize,
+    threshold: AtomicUsize,
+    timer: AtomicUsize,
+    max_age: AtomicUsize,
+    manual: AtomicUsize,
+    frame: AtomicUsize,
+    declined: AtomicUsize,
+    reclaimed: AtomicUsize,
+}
+
+impl ReclaimCounters {
+    conditional_const!(
+        "Creates a new `ReclaimCounters` with every count at zero.",
+        pub(super),
+        fn new() -> Self {
+            Self {
+                eager: AtomicUsize::new(0),
+                threshold: AtomicUsize::new(0),
+                timer: AtomicUsize::new(0),
+                max_age: AtomicUsize::new(0),
+                manual: AtomicUsize::new(0),
+                frame: AtomicUsize::new(0),
+                declined: AtomicUsize::new(0),
+                reclaimed: AtomicUsize::new(0),
+            }
+        }
+    );
+
+    /// Records the outcome of deciding whether to reclaim, `None` if reclamation was declined.
+    pub(super) fn record(&self, trigger: Option<ReclaimTrigger>) {
+        let counter = match trigger {
+            Some(ReclaimTrigger::Eager) => &self.eager,
+            Some(ReclaimTrigger::Threshold) => &self.threshold,
+            Some(ReclaimTrigger::Timer) => &self.timer,
+            #[cfg(feature = "std")]
+            Some(ReclaimTrigger::MaxAge) => &self.max_age,
+            Some(ReclaimTrigger::Manual) => &self.manual,
+            Some(ReclaimTrigger::Frame) => &self.frame,
+            None => &self.declined,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a retired item has been reclaimed.
+    pub(super) fn record_reclaimed(&self) {
+        self.reclaimed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn snapshot(&self) -> ReclaimStats {
+        ReclaimStats {
+            eager: self.eager.load(Ordering::Relaxed),
+            threshold: self.threshold.load(Ordering::Relaxed),
+            timer: self.timer.load(Ordering::Relaxed),
+            max_age: self.max_age.load(Ordering::Relaxed),
+            manual: self.manual.load(Ordering::Relaxed),
+            frame: self.frame.load(Ordering::Relaxed),
+            declined: self.declined.load(Ordering::Relaxed),
+            reclaimed: self.reclaimed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A histogram of how long the hazard pointers of a [`Domain`](super::Domain) were held.
+///
+/// A value cannot be reclaimed while a hazard pointer protects it, so hazard pointers which are
+/// held for a long time, typically by long-lived guards, are the usual cause of retired values
+/// accumulating. Each hazard pointer is counted when it is released, in a bucket covering a range
+/// of durations between consecutive powers of two nanoseconds.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+/// use core::time::Duration;
+///
+/// const CUSTOM_DOMAIN_ID: usize = 42;
+/// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+///
+/// let atom_box = AtomBoxIn::new_with_domain("Hello", &CUSTOM_DOMAIN);
+/// let guard = atom_box.load();
+/// std::thread::sleep(Duration::from_millis(2));
+/// drop(guard);
+///
+/// let histogram = CUSTOM_DOMAIN.hazard_dwell_histogram();
+/// let held_for_a_millisecond: usize = histogram
+///     .buckets()
+///     .filter(|(at_least, _)| *at_least >= Duration::from_millis(1))
+///     .map(|(_, count)| count)
+///     .sum();
+/// assert_eq!(held_for_a_millisecond, 1);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DwellHistogram {
+    counts: [usize; DWELL_BUCKETS],
+}
+
+#[cfg(feature = "std")]
+impl DwellHistogram {
+    /// The number of hazard pointers which have been released.
+    pub fn count(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// Iterates over the buckets of the histogram, in order of increasing duration.
+    ///
+    /// Yields the shortest duration counted in each bucket along with the number of hazard
+    /// pointers counted in it. A bucket counts hazard pointers held for at least its duration and
+    /// less than the duration of the next bucket.
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, usize)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(index, count)| (bucket_start(index), *count))
+    }
+}
+
+/// The shortest duration counted in the bucket at `index`.
+#[cfg(feature = "std")]
+fn bucket_start(index: usize) -> Duration {
+    match index {
+        0 => Duration::ZERO,
+        index => Duration::from_nanos(1 << (index - 1)),
+    }
+}
+
+/// The counters behind [`DwellHistogram`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub(super) struct DwellCounters {
+    counts: [AtomicUsize; DWELL_BUCKETS],
+}
+
+#[cfg(feature = "std")]
+impl DwellCounters {
+    #[cfg(not(loom))]
+    pub(super) const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const ZERO: AtomicUsize = AtomicUsize::new(0);
+        Self {
+            counts: [ZERO; DWELL_BUCKETS],
+        }
+    }
+
+    #[cfg(loom)]
+    pub(super) fn new() -> Self {
+        Self {
+            counts: core::array::from_fn(|_| AtomicUsize::new(0)),
+        }
+    }
+
+    /// Records that a hazard pointer was held for `dwell`.
+    pub(super) fn record(&self, dwell: Duration) {
+        use core::convert::TryFrom;
+        let nanos = u64::try_from(dwell.as_nanos()).unwrap_or(u64::MAX);
+        let index = (64 - nanos.leading_zeros() as usize).min(DWELL_BUCKETS - 1);
+        self.counts[index].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn snapshot(&self) -> DwellHistogram {
+        let mut counts = [0; DWELL_BUCKETS];
+        for (count, counter) in counts.iter_mut().zip(&self.counts) {
+            *count = counter.load(Ordering::Relaxed);
+        }
+        DwellHistogram { counts }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dwell_times_are_counted_in_power_of_two_buckets() {
+        let counters = DwellCounters::new();
+
+        counters.record(Duration::ZERO);
+        counters.record(Duration::from_nanos(5));
+        counters.record(Duration::from_nanos(7));
+        counters.record(Duration::from_secs(u64::MAX));
+
+        let histogram = counters.snapshot();
+        let buckets: std::vec::Vec<_> = histogram
+            .buckets()
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        assert_eq!(
+            buckets,
+            [
+                (Duration::ZERO, 1),
+                (Duration::from_nanos(4), 2),
+                (Duration::from_nanos(1 << 39), 1)
+            ],
+            "Each dwell time is counted in the bucket covering it"
+        );
+        assert_eq!(histogram.count(), 4);
+    }
+}
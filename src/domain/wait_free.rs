@@ -0,0 +1,458 @@
+use super::{needs_reclaim, Domain, HazardPointer, Retire, PINNED};
+use crate::sync::Ordering;
+use crate::AtomBoxIn;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+/// The number of times a load publishes and validates a pointer before pinning the domain.
+const VALIDATION_ATTEMPTS: usize = 2;
+
+/// The size of the retire stack of the handles created by [`Domain::with_thread_handle`].
+#[cfg(all(feature = "std", not(loom)))]
+const THREAD_RETIRE_CAPACITY: usize = 64;
+
+/// A thread's registration with a [`Domain`], owning the resources it needs to load, store and
+/// retire values in a bounded number of steps.
+///
+/// Created by [`Domain::register_thread`]. The handle owns a hazard pointer and a preallocated
+/// stack of retired values, so none of its operations allocate or contend on the domain's shared
+/// lists:
+///
+/// * [`load`](WaitFreeHandle::load) validates the loaded pointer at most twice. If the box is
+///   still being changed, it briefly pins the domain, which stops any values being reclaimed, so
+///   that the current pointer can be protected without validation.
+/// * [`store`](WaitFreeHandle::store) stores a value which has already been boxed, and pushes the
+///   replaced value onto the handle's retire stack.
+/// * Once the retire stack is full, it is scanned against the domain's hazard pointers. No
+///   allocation is needed to scan, and at most one value per hazard pointer can still be
+///   protected, so a stack larger than the number of hazard pointers always has room after a
+///   scan.
+///
+/// The bounds hold provided the box is only stored to from one thread at a time. Otherwise,
+/// stores retry like those of [`AtomBoxIn`]. If the retire stack is still full after a scan, or a callback has been
+/// registered with [`Domain::notify_on_reclaim`], retired values are handed to the domain, which
+/// may allocate.
+///
+/// Values remaining on the retire stack when the handle is dropped are handed to the domain, and
+/// its hazard pointer is released. A thread which exits without dropping its handle leaks them
+/// until the process exits. With the `std` feature, [`Domain::with_thread_handle`] keeps a handle
+/// per thread which is dropped when the thread exits. Otherwise, the handle must be dropped, or
+/// [flushed](WaitFreeHandle::flush), before the thread exits.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+///
+/// const AUDIO_DOMAIN_ID: usize = 42;
+/// static AUDIO_DOMAIN: Domain<AUDIO_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+///
+/// let gain = AtomBoxIn::new_with_domain(0.5_f32, &AUDIO_DOMAIN);
+/// let mut handle = AUDIO_DOMAIN.register_thread(16);
+///
+/// // Allocate ahead of time, outside of the real-time thread.
+/// let next_gain = Box::new(0.75);
+///
+/// handle.store(&gain, next_gain);
+/// assert_eq!(*handle.load(&gain), 0.75);
+/// ```
+#[derive(Debug)]
+pub struct WaitFreeHandle<'domain, const DOMAIN_ID: usize> {
+    domain: &'domain Domain<DOMAIN_ID>,
+    haz_ptr: HazardPointer<'domain>,
+    retired: Vec<Retire>,
+}
+
+impl<const DOMAIN_ID: usize> Domain<DOMAIN_ID> {
+    /// Registers the calling thread for wait-free operation, preallocating a hazard pointer and
+    /// a stack for up to `retire_capacity` retired values.
+    ///
+    /// For retiring to be bounded, `retire_capacity` should exceed the number of hazard pointers
+    /// the domain will ever allocate, including those of every registered thread. Hazard pointers
+    /// can be preallocated with [`Domain::reserve_hazard_pointers`].
+    ///
+    /// See [`WaitFreeHandle`].
+    pub fn register_thread(&self, retire_capacity: usize) -> WaitFreeHandle<'_, DOMAIN_ID> {
+        WaitFreeHandle {
+            domain: self,
+            haz_ptr: self.acquire_haz_ptr(),
+            retired: Vec::with_capacity(retire_capacity.max(1)),
+        }
+    }
+
+    /// Calls `f` with the calling thread's handle for this domain, registering the thread the
+    /// first time it is called.
+    ///
+    /// The handle is kept in thread local storage and dropped when the thread exits, handing its
+    /// retired values to the domain and releasing its hazard pointer. Calls nested within `f` for
+    /// the same domain are given a separate handle.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let gain: &'static _ = Box::leak(Box::new(AtomBoxIn::new_with_domain(0.5, &CUSTOM_DOMAIN)));
+    ///
+    /// std::thread::spawn(move || {
+    ///     CUSTOM_DOMAIN.with_thread_handle(|handle| handle.store(gain, Box::new(0.75)));
+    /// })
+    /// .join()
+    /// .unwrap();
+    ///
+    /// // The thread's retired values were handed to the domain when it exited.
+    /// assert_eq!(CUSTOM_DOMAIN.reclaim(), 1);
+    /// ```
+    #[cfg(all(feature = "std", not(loom)))]
+    pub fn with_thread_handle<R>(
+        &'static self,
+        f: impl FnOnce(&mut WaitFreeHandle<'static, DOMAIN_ID>) -> R,
+    ) -> R {
+        thread_exit::with_handle(self, f)
+    }
+}
+
+#[cfg(all(feature = "std", not(loom)))]
+mod thread_exit {
+    use super::{Domain, WaitFreeHandle, THREAD_RETIRE_CAPACITY};
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use core::any::Any;
+    use core::cell::RefCell;
+
+    // The handles of the current thread, keyed by the address of their domain. They are dropped
+    // by the thread local destructor when the thread exits.
+    std::thread_local! {
+        static HANDLES: RefCell<Vec<(usize, Box<dyn Any>)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub(super) fn with_handle<const DOMAIN_ID: usize, R>(
+        domain: &'static Domain<DOMAIN_ID>,
+        f: impl FnOnce(&mut WaitFreeHandle<'static, DOMAIN_ID>) -> R,
+    ) -> R {
+        let key = domain as *const Domain<DOMAIN_ID> as usize;
+        // The handle is taken out while `f` runs, so that `f` can use the handles of other
+        // domains.
+        let handle = HANDLES
+            .try_with(|handles| {
+                let mut handles = handles.borrow_mut();
+                let index = handles.iter().position(|(domain, _)| *domain == key)?;
+                Some(handles.swap_remove(index).1)
+            })
+            .ok()
+            .flatten();
+        let mut handle = match handle {
+            Some(handle) => handle
+                .downcast::<WaitFreeHandle<'static, DOMAIN_ID>>()
+                .expect("Handles are keyed by their domain"),
+            None => Box::new(domain.register_thread(THREAD_RETIRE_CAPACITY)),
+        };
+        let result = f(&mut handle);
+        // If the thread is exiting, or a nested call has put back its own handle, this handle is
+        // dropped, handing its retired values to the domain.
+        let _ = HANDLES.try_with(|handles| {
+            let mut handles = handles.borrow_mut();
+            if handles.iter().all(|(domain, _)| *domain != key) {
+                handles.push((key, handle));
+            }
+        });
+        result
+    }
+}
+
+impl<'domain, const DOMAIN_ID: usize> WaitFreeHandle<'domain, DOMAIN_ID> {
+    /// Loads the value stored in `atom_box`, protecting it with this handle's hazard pointer.
+    ///
+    /// Only one value can be protected by a handle at a time, so the handle is borrowed until the
+    /// guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `atom_box` is associated with a different domain.
+    pub fn load<'h, T>(
+        &'h mut self,
+        atom_box: &AtomBoxIn<'domain, T, DOMAIN_ID>,
+    ) -> WaitFreeGuard<'h, 'domain, T> {
+        crate::assert_same_domain(self.domain, atom_box.domain);
+        let ptr = if needs_reclaim::<T>() {
+            self.protect(atom_box)
+        } else {
+            core::ptr::NonNull::dangling().as_ptr()
+        };
+        WaitFreeGuard {
+            ptr,
+            haz_ptr: &self.haz_ptr,
+        }
+    }
+
+    fn protect<T>(&self, atom_box: &AtomBoxIn<'domain, T, DOMAIN_ID>) -> *mut T {
+        let mut ptr = atom_box.ptr.load(Ordering::Acquire);
+        for _ in 0..VALIDATION_ATTEMPTS {
+            self.haz_ptr.protect(ptr as *mut usize);
+            crate::sync::fence(Ordering::SeqCst);
+            let current_ptr = atom_box.ptr.load(Ordering::Acquire);
+            if current_ptr == ptr {
+                return ptr;
+            }
+            ptr = current_ptr;
+        }
+        // No value can be reclaimed while the domain is pinned, so the pointer loaded after
+        // pinning remains valid until it replaces the pin, without being validated.
+        self.haz_ptr.protect(PINNED);
+        crate::sync::fence(Ordering::SeqCst);
+        let ptr = atom_box.ptr.load(Ordering::Acquire);
+        self.haz_ptr.protect(ptr as *mut usize);
+        ptr
+    }
+
+    /// Stores `value` in `atom_box`, retiring the replaced value on this handle's retire stack.
+    ///
+    /// The value is already boxed, so storing it does not allocate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `atom_box` is associated with a different domain.
+    pub fn store<T>(&mut self, atom_box: &AtomBoxIn<'domain, T, DOMAIN_ID>, value: Box<T>) {
+        crate::assert_same_domain(self.domain, atom_box.domain);
+        let old_ptr = atom_box.ptr.swap(Box::into_raw(value), Ordering::AcqRel);
+        // # Safety
+        //
+        // The value has been swapped out of the box, so this is the only place it will be
+        // retired.
+        unsafe { self.retire(old_ptr) };
+    }
+
+    /// Places a value on this handle's retire stack, reclaiming unprotected values if it is full.
+    ///
+    /// # Safety
+    ///
+    /// As for [`Domain::retire`].
+    pub(crate) unsafe fn retire<T>(&mut self, value: *mut T) {
+        if !needs_reclaim::<T>() || self.domain.leaks_retired() {
+            return;
+        }
+        #[cfg(feature = "leak-audit")]
+        crate::leak_audit::track(
+            crate::leak_audit::AllocationKind::Retired,
+            core::mem::size_of::<T>(),
+        );
+        if self.retired.len() == self.retired.capacity() {
+            self.reclaim();
+        }
+        if self.retired.len() == self.retired.capacity() {
+            self.hand_over();
+        }
+        self.retired
+            .push(Retire::new(value, super::RetiredAt::Untracked));
+    }
+
+    /// Reclaims the values on the retire stack which are not protected by any hazard pointer,
+    /// returning the number reclaimed.
+    pub fn reclaim(&mut self) -> usize {
+        if self.domain.notifications.count.load(Ordering::Acquire) != 0
+            || self.domain.quarantines_retired()
+        {
+            // Callbacks are only invoked, and quarantines only applied, for values reclaimed by
+            // the domain.
+            self.hand_over();
+            return 0;
+        }
+        self.domain.begin_reclaim_pass();
+        crate::sync::fence(Ordering::SeqCst);
+        let domain = self.domain;
+        let hazard_ptrs = domain.hazard_ptrs();
+        if hazard_ptrs
+            .iter()
+            .any(|haz_ptr| domain.guarded_ptr(haz_ptr) == PINNED)
+        {
+            return 0;
+        }
+        let before = self.retired.len();
+        self.retired.retain(|retired| {
+            let guarded = hazard_ptrs
+                .iter()
+                .any(|haz_ptr| domain.guarded_ptr(haz_ptr) == retired.ptr);
+            if !guarded {
+                #[cfg(feature = "leak-audit")]
+                crate::leak_audit::untrack(
+                    crate::leak_audit::AllocationKind::Retired,
+                    retired.retired_type.size,
+                );
+                // # Safety
+                //
+                // The value was allocated via a box and retired once, and is no longer protected
+                // by any of the hazard pointers.
+                unsafe { (retired.reclaim)(retired.ptr) };
+                domain.record_reclaimed(retired.retired_type, retired.retired_at);
+            }
+            guarded
+        });
+        before - self.retired.len()
+    }
+
+    /// Hands every value on the retire stack to the domain, to be reclaimed according to its
+    /// strategy.
+    ///
+    /// This may allocate. Without the `std` feature, a thread which keeps its handle for its
+    /// whole lifetime should call this, or drop the handle, before it exits.
+    pub fn flush(&mut self) {
+        self.hand_over();
+    }
+
+    /// The number of values on the retire stack.
+    pub fn retired(&self) -> usize {
+        self.retired.len()
+    }
+
+    /// Moves every value on the retire stack onto the domain's retired list.
+    fn hand_over(&mut self) {
+        for retired in self.retired.drain(..) {
+            #[cfg(feature = "leak-audit")]
+            crate::leak_audit::untrack(
+                crate::leak_audit::AllocationKind::Retired,
+                retired.retired_type.size,
+            );
+            // # Safety
+            //
+            // The value was retired on this handle according to the contract of `retire`, and is
+            // removed from the stack so is only retired once.
+            unsafe {
+                self.domain
+                    .retire_erased(retired.ptr, retired.reclaim, retired.retired_type)
+            };
+        }
+    }
+}
+
+impl<const DOMAIN_ID: usize> Drop for WaitFreeHandle<'_, DOMAIN_ID> {
+    fn drop(&mut self) {
+        self.hand_over();
+        self.haz_ptr.reset();
+        self.domain.hazard_ptrs().release(self.haz_ptr.0);
+    }
+}
+
+/// Contains a reference to a value loaded through a [`WaitFreeHandle`].
+///
+/// The value is protected by the handle's hazard pointer until this guard is dropped.
+///
+/// Dereferences to the value.
+#[derive(Debug)]
+pub struct WaitFreeGuard<'h, 'domain, T> {
+    ptr: *const T,
+    haz_ptr: &'h HazardPointer<'domain>,
+}
+
+impl<T> Deref for WaitFreeGuard<'_, '_, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        // # Safety
+        //
+        // The pointer is protected by the hazard pointer so will not have been dropped. The
+        // pointer was created via a Box so is aligned and there are no mutable references since
+        // we do not give any out.
+        unsafe { self.ptr.as_ref().expect("Non null") }
+    }
+}
+
+impl<T> Drop for WaitFreeGuard<'_, '_, T> {
+    fn drop(&mut self) {
+        self.haz_ptr.reset();
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    #[test]
+    fn full_retire_stack_reclaims_unprotected_values() {
+        let domain: Domain<1> = Domain::new(ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(0), &domain);
+        let mut handle = domain.register_thread(2);
+
+        for value in 1..=3 {
+            handle.store(&atom_box, Box::new(drop_counter.track(value)));
+        }
+
+        drop_counter.assert_drops(2);
+        assert_eq!(handle.retired(), 1);
+        assert_eq!(**handle.load(&atom_box), 3);
+    }
+
+    #[test]
+    fn protected_values_stay_on_the_retire_stack() {
+        let domain: Domain<2> = Domain::new(ReclaimStrategy::Manual);
+        let drop_counter = DropCounter::new();
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &domain);
+        let mut handle = domain.register_thread(4);
+        let guard = atom_box.load();
+
+        handle.store(&atom_box, Box::new(drop_counter.track(2)));
+        let reclaimed_while_guarded = handle.reclaim();
+        drop(guard);
+
+        assert_eq!(reclaimed_while_guarded, 0, "The value is still protected");
+        assert_eq!(handle.reclaim(), 1);
+        drop_counter.assert_drops(1);
+    }
+
+    #[test]
+    fn pinned_domains_reclaim_nothing() {
+        let domain: Domain<3> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box = AtomBoxIn::new_with_domain(1, &domain);
+        let mut handle = domain.register_thread(4);
+        let pin = domain.acquire_haz_ptr();
+        pin.protect(PINNED);
+        atom_box.store(2);
+
+        let reclaimed_while_pinned = domain.reclaim();
+        handle.store(&atom_box, Box::new(3));
+        let handle_reclaimed_while_pinned = handle.reclaim();
+        domain.release_hazard_ptr(pin);
+
+        assert_eq!(reclaimed_while_pinned, 0);
+        assert_eq!(handle_reclaimed_while_pinned, 0);
+        assert_eq!(domain.reclaim(), 1);
+        assert_eq!(handle.reclaim(), 1);
+    }
+
+    #[test]
+    fn dropping_the_handle_hands_retired_values_to_the_domain() {
+        let domain: Domain<4> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box = AtomBoxIn::new_with_domain(1, &domain);
+        let mut handle = domain.register_thread(4);
+        handle.store(&atom_box, Box::new(2));
+
+        drop(handle);
+
+        assert_eq!(domain.reclaim(), 1);
+    }
+
+    #[test]
+    fn thread_handles_are_flushed_when_the_thread_exits() {
+        static DOMAIN: Domain<5> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box: &'static _ = Box::leak(Box::new(AtomBoxIn::new_with_domain(1, &DOMAIN)));
+
+        // Unlike scoped threads, joining waits for the thread local destructors to run.
+        let retired = std::thread::spawn(move || {
+            DOMAIN.with_thread_handle(|handle| handle.store(atom_box, Box::new(2)));
+            DOMAIN.with_thread_handle(|handle| handle.store(atom_box, Box::new(3)));
+            DOMAIN.with_thread_handle(|handle| handle.retired())
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(retired, 2, "The thread keeps its handle between calls");
+        assert_eq!(DOMAIN.reclaim(), 2);
+        assert_eq!(*atom_box.load(), 3);
+    }
+}
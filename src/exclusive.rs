@@ -0,0 +1,352 @@
+//! Exclusive
+//!
+//! An `AtomBox` whose value can be mutated in place while it is not referenced by any other
+//! guard.
+//!
+//! Mutating a value in place means no reader may load it until the mutation is complete, so
+//! unlike `AtomBox` this is lock-based. While an [`ExclusiveGuard`] is held the box is locked: its
+//! pointer carries a tag in its low bit, and loads and stores wait for the tag to be removed.
+
+use crate::domain::{self, Domain};
+use crate::sync::Ordering;
+use crate::{AtomBoxIn, LoadGuard, StoreGuard};
+use alloc::boxed::Box;
+use core::ops::{Deref, DerefMut};
+
+// The low bit marks a box which is locked by an `ExclusiveGuard`.
+const LOCKED: usize = 1;
+
+/// Whether a box of `T` can be locked by tagging its pointer.
+///
+/// Values aligned to a single byte may have odd addresses, which would be mistaken for locked
+/// pointers, so these are never upgraded.
+const fn supports_locking<T>() -> bool {
+    core::mem::align_of::<T>() > LOCKED
+}
+
+fn is_locked<T>(ptr: *mut T) -> bool {
+    supports_locking::<T>() && ptr as usize & LOCKED == LOCKED
+}
+
+fn lock<T>(ptr: *mut T) -> *mut T {
+    (ptr as usize | LOCKED) as *mut T
+}
+
+fn unlock<T>(ptr: *mut T) -> *mut T {
+    if supports_locking::<T>() {
+        (ptr as usize & !LOCKED) as *mut T
+    } else {
+        ptr
+    }
+}
+
+/// An `AtomBox` whose value can be mutated in place through [`LoadGuard::try_upgrade`], rather
+/// than cloned and swapped.
+///
+/// An upgrade only succeeds while no other guard references the value, and locks the box until
+/// the returned [`ExclusiveGuard`] is dropped. Loads and stores wait while the box is locked, so
+/// unlike `AtomBox` this type is not lock-free: a thread which holds an `ExclusiveGuard` for a
+/// long time, or is descheduled while holding one, delays every other thread using the box.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::UpgradableAtomBox;
+///
+/// let atom_box = UpgradableAtomBox::new(vec![1, 2]);
+///
+/// match atom_box.load().try_upgrade(&atom_box) {
+///     Ok(mut value) => value.push(3),
+///     Err(_) => panic!("The value is not referenced elsewhere"),
+/// }
+/// assert_eq!(*atom_box.load(), [1, 2, 3]);
+///
+/// let other_reader = atom_box.load();
+/// assert!(atom_box.load().try_upgrade(&atom_box).is_err());
+/// # drop(other_reader);
+/// ```
+pub struct UpgradableAtomBox<'domain, T, const DOMAIN_ID: usize> {
+    atom_box: AtomBoxIn<'domain, T, DOMAIN_ID>,
+}
+
+impl<T, const DOMAIN_ID: usize> core::fmt::Debug for UpgradableAtomBox<'_, T, DOMAIN_ID> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("UpgradableAtomBox")
+            .field("domain", &self.atom_box.domain)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> UpgradableAtomBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `UpgradableAtomBox` associated with the shared (global) domain.
+    pub fn new(value: T) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> UpgradableAtomBox<'domain, T, DOMAIN_ID> {
+    /// Creates a new `UpgradableAtomBox` and associates it with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{UpgradableAtomBox, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let atom_box = UpgradableAtomBox::new_with_domain(5, &CUSTOM_DOMAIN);
+    /// assert_eq!(*atom_box.load(), 5);
+    /// ```
+    pub fn new_with_domain(value: T, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            atom_box: AtomBoxIn::new_with_domain(value, domain),
+        }
+    }
+
+    /// Loads the value stored in the `UpgradableAtomBox`, waiting while it is locked by an
+    /// [`ExclusiveGuard`].
+    ///
+    /// See [`AtomBoxIn::load`].
+    pub fn load(&self) -> LoadGuard<'domain, T, DOMAIN_ID> {
+        if !domain::needs_reclaim::<T>() {
+            return self.atom_box.unprotected_load();
+        }
+        let haz_ptr = self.atom_box.domain.acquire_haz_ptr();
+        let ptr = loop {
+            let ptr = haz_ptr.protect_ptr(&self.atom_box.ptr);
+            if !is_locked(ptr) {
+                break ptr;
+            }
+            core::hint::spin_loop();
+        };
+        LoadGuard {
+            ptr,
+            domain: self.atom_box.domain,
+            haz_ptr: Some(haz_ptr),
+        }
+    }
+
+    /// Stores a new value, waiting while the box is locked by an [`ExclusiveGuard`].
+    ///
+    /// See [`AtomBoxIn::store`].
+    pub fn store(&self, value: T) {
+        let _ = self.swap(value);
+    }
+
+    /// Stores a new value and returns a `StoreGuard` which dereferences into the previous value,
+    /// waiting while the box is locked by an [`ExclusiveGuard`].
+    ///
+    /// See [`AtomBoxIn::swap`].
+    pub fn swap(&self, new_value: T) -> StoreGuard<'domain, T, DOMAIN_ID> {
+        let new_ptr = Box::into_raw(Box::new(new_value));
+        let mut current_ptr = self.atom_box.ptr.load(Ordering::Acquire);
+        let old_ptr = loop {
+            if is_locked(current_ptr) {
+                core::hint::spin_loop();
+                current_ptr = self.atom_box.ptr.load(Ordering::Acquire);
+                continue;
+            }
+            match self.atom_box.ptr.compare_exchange_weak(
+                current_ptr,
+                new_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(old_ptr) => break old_ptr,
+                Err(actual_ptr) => current_ptr = actual_ptr,
+            }
+        };
+        StoreGuard {
+            ptr: old_ptr,
+            domain: self.atom_box.domain,
+            retire_policy: self.atom_box.retire_policy,
+        }
+    }
+}
+
+impl<T, const DOMAIN_ID: usize> Drop for UpgradableAtomBox<'_, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        // Removes the lock left behind by a leaked `ExclusiveGuard`, so the inner box retires the
+        // value rather than the tagged pointer.
+        let ptr = self.atom_box.ptr.load(Ordering::Acquire);
+        self.atom_box.ptr.store(unlock(ptr), Ordering::Relaxed);
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> LoadGuard<'domain, T, DOMAIN_ID> {
+    /// Attempts to upgrade the guard to exclusive access to the value, so it can be mutated in
+    /// place rather than cloned and swapped.
+    ///
+    /// Succeeds if `atom_box` still holds the value and no other guard references it. While the
+    /// returned `ExclusiveGuard` is held, other operations on `atom_box` wait for it to be
+    /// dropped. If the upgrade fails, the `Err` hands back this guard.
+    ///
+    /// Values aligned to a single byte cannot be upgraded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `UpgradableAtomBox` is associated with a different domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::UpgradableAtomBox;
+    ///
+    /// let counters = UpgradableAtomBox::new([0_u64; 4]);
+    ///
+    /// if let Ok(mut counters) = counters.load().try_upgrade(&counters) {
+    ///     counters[2] += 1;
+    /// }
+    /// assert_eq!(*counters.load(), [0, 0, 1, 0]);
+    /// ```
+    pub fn try_upgrade<'a>(
+        mut self,
+        atom_box: &'a UpgradableAtomBox<'domain, T, DOMAIN_ID>,
+    ) -> Result<ExclusiveGuard<'domain, 'a, T, DOMAIN_ID>, Self> {
+        crate::assert_same_domain(self.domain, atom_box.atom_box.domain);
+        if !supports_locking::<T>() {
+            return Err(self);
+        }
+        let haz_ptr = match &self.haz_ptr {
+            Some(haz_ptr) => haz_ptr,
+            None => return Err(self),
+        };
+        let ptr = self.ptr as *mut T;
+        let target = &atom_box.atom_box.ptr;
+        if target
+            .compare_exchange(ptr, lock(ptr), Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(self);
+        }
+        // No new reader can protect the value while the box is locked, and it cannot be retired,
+        // so it is safe to stop protecting it while checking for other readers.
+        haz_ptr.reset();
+        if self.domain.is_protected(ptr) {
+            haz_ptr.protect(ptr as *mut usize);
+            target.store(ptr, Ordering::SeqCst);
+            return Err(self);
+        }
+        if let Some(haz_ptr) = self.haz_ptr.take() {
+            self.domain.release_hazard_ptr(haz_ptr);
+        }
+        Ok(ExclusiveGuard { ptr, atom_box })
+    }
+}
+
+/// Exclusive access to the value stored in an [`UpgradableAtomBox`].
+///
+/// Returned from [`LoadGuard::try_upgrade`]. Other operations on the box wait until the guard is
+/// dropped. Leaking the guard leaves the box locked, but the value is still retired when the box
+/// is dropped.
+///
+/// Dereferences mutably to the value.
+pub struct ExclusiveGuard<'domain, 'a, T, const DOMAIN_ID: usize> {
+    ptr: *mut T,
+    atom_box: &'a UpgradableAtomBox<'domain, T, DOMAIN_ID>,
+}
+
+impl<T, const DOMAIN_ID: usize> Deref for ExclusiveGuard<'_, '_, T, DOMAIN_ID> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // # Safety
+        //
+        // The box is locked and no other guard references the value.
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<T, const DOMAIN_ID: usize> DerefMut for ExclusiveGuard<'_, '_, T, DOMAIN_ID> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // # Safety
+        //
+        // The box is locked and no other guard references the value.
+        unsafe { &mut *self.ptr }
+    }
+}
+
+impl<T, const DOMAIN_ID: usize> Drop for ExclusiveGuard<'_, '_, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        self.atom_box.atom_box.ptr.store(self.ptr, Ordering::SeqCst);
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::UpgradableAtomBox;
+    use crate::domain::{Domain, ReclaimStrategy};
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn upgrade_fails_while_value_is_referenced_or_replaced() {
+        let atom_box = UpgradableAtomBox::new_with_domain(1_u32, &TEST_DOMAIN);
+        let other_reader = atom_box.load();
+
+        let referenced = atom_box.load().try_upgrade(&atom_box);
+        let stale = atom_box.load();
+        drop(other_reader);
+        atom_box.store(2);
+        let replaced = stale.try_upgrade(&atom_box);
+
+        match (referenced, replaced) {
+            (Err(referenced), Err(replaced)) => {
+                assert_eq!(*referenced, 1, "The guard is handed back intact");
+                assert_eq!(*replaced, 1, "The guard is handed back intact");
+            }
+            _ => panic!("Neither upgrade should succeed"),
+        }
+        assert_eq!(*atom_box.load(), 2, "The box is unlocked");
+    }
+
+    #[test]
+    fn dropping_a_box_after_leaking_its_exclusive_guard_retires_the_value() {
+        let drop_counter = DropCounter::new();
+        let atom_box = UpgradableAtomBox::new_with_domain(drop_counter.track(1_u32), &TEST_DOMAIN);
+
+        match atom_box.load().try_upgrade(&atom_box) {
+            Ok(mut value) => {
+                **value = 2;
+                core::mem::forget(value);
+            }
+            Err(_) => panic!("The value is not referenced elsewhere"),
+        }
+        drop(atom_box);
+        TEST_DOMAIN.reclaim();
+
+        drop_counter.assert_drops(1);
+    }
+
+    #[test]
+    fn concurrent_readers_wait_for_exclusive_mutation() {
+        let atom_box = UpgradableAtomBox::new_with_domain((0_u32, 0_u32), &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let mut upgrades = 0;
+                while upgrades < 500 {
+                    if let Ok(mut value) = atom_box.load().try_upgrade(&atom_box) {
+                        value.0 += 1;
+                        value.1 += 1;
+                        upgrades += 1;
+                    }
+                }
+            });
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        let value = atom_box.load();
+                        assert_eq!(value.0, value.1, "Readers never see a partial update");
+                    }
+                });
+            }
+        });
+
+        assert_eq!(*atom_box.load(), (500, 500));
+    }
+}
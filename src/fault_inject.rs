@@ -0,0 +1,216 @@
+//! Fault Inject
+//!
+//! Randomised perturbation of the reclamation machinery, enabled by the `fault-inject` feature.
+//!
+//! Bugs in code built on hazard pointers usually hide in the window between loading a pointer and
+//! publishing its protection, or depend on the order in which retired values are freed. Such
+//! windows are rarely hit by ordinary tests, and are often too large for loom to model. Once
+//! [enabled](enable), faults widen these windows at random:
+//!
+//! * publishing a protection is delayed, so that values are more likely to be retired while they
+//!   are being protected;
+//! * reclamation passes are forced when values are retired, so that unprotected values are freed
+//!   as early as possible;
+//! * retired values are reclaimed in a shuffled order.
+//!
+//! Faults are driven by a seed. Each thread draws from its own generator, derived from the seed
+//! and the order in which threads first inject a fault, so a failing seed is likely, though not
+//! guaranteed, to reproduce a failure.
+//!
+//! # Example
+//!
+//! ```
+//! use atom_box::{AtomBox, fault_inject::{self, Faults}};
+//!
+//! fault_inject::enable(
+//!     Faults::new(42)
+//!         .with_protection_delay(0.5)
+//!         .with_forced_reclaim(0.5)
+//!         .with_shuffled_reclaim(),
+//! );
+//!
+//! let atom_box = AtomBox::new(1);
+//! let value = atom_box.load();
+//! atom_box.store(2);
+//! assert_eq!(*value, 1);
+//!
+//! fault_inject::disable();
+//! ```
+
+use crate::sync::Ordering;
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize};
+
+/// The most times the publication of a protection is delayed by yielding the thread.
+const MAX_DELAY_YIELDS: u64 = 16;
+
+/// The faults to inject, and the seed which drives them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Faults {
+    seed: u64,
+    protection_delay: f64,
+    forced_reclaim: f64,
+    shuffled_reclaim: bool,
+}
+
+impl Faults {
+    /// Creates a new `Faults` with the given seed, injecting no faults.
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            protection_delay: 0.0,
+            forced_reclaim: 0.0,
+            shuffled_reclaim: false,
+        }
+    }
+
+    /// Sets the probability that publishing a protection is delayed.
+    pub const fn with_protection_delay(self, probability: f64) -> Self {
+        Self {
+            protection_delay: probability,
+            ..self
+        }
+    }
+
+    /// Sets the probability that retiring a value forces a reclamation pass, whatever the domain's
+    /// strategy.
+    pub const fn with_forced_reclaim(self, probability: f64) -> Self {
+        Self {
+            forced_reclaim: probability,
+            ..self
+        }
+    }
+
+    /// Reclaims retired values in a shuffled order.
+    pub const fn with_shuffled_reclaim(self) -> Self {
+        Self {
+            shuffled_reclaim: true,
+            ..self
+        }
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SEED: AtomicU64 = AtomicU64::new(0);
+// Incremented each time faults are enabled, so that threads reseed their generators.
+static EPOCH: AtomicUsize = AtomicUsize::new(0);
+// The number of threads which have seeded their generator in the current epoch.
+static THREADS: AtomicU64 = AtomicU64::new(0);
+static PROTECTION_DELAY: AtomicU32 = AtomicU32::new(0);
+static FORCED_RECLAIM: AtomicU32 = AtomicU32::new(0);
+static SHUFFLED_RECLAIM: AtomicBool = AtomicBool::new(false);
+
+std::thread_local! {
+    // The epoch the generator was seeded in, and its state.
+    static GENERATOR: Cell<(usize, u64)> = const { Cell::new((usize::MAX, 0)) };
+}
+
+/// Starts injecting `faults` in every domain.
+///
+/// Replaces any faults previously enabled.
+pub fn enable(faults: Faults) {
+    SEED.store(faults.seed, Ordering::Relaxed);
+    THREADS.store(0, Ordering::Relaxed);
+    PROTECTION_DELAY.store(threshold(faults.protection_delay), Ordering::Relaxed);
+    FORCED_RECLAIM.store(threshold(faults.forced_reclaim), Ordering::Relaxed);
+    SHUFFLED_RECLAIM.store(faults.shuffled_reclaim, Ordering::Relaxed);
+    EPOCH.fetch_add(1, Ordering::Release);
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// Stops injecting faults.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Release);
+}
+
+/// Converts a probability into the threshold below which a random `u32` injects the fault.
+fn threshold(probability: f64) -> u32 {
+    (probability.clamp(0.0, 1.0) * f64::from(u32::MAX)) as u32
+}
+
+/// Draws the next random number from the calling thread's generator.
+fn next_random() -> u64 {
+    let epoch = EPOCH.load(Ordering::Acquire);
+    GENERATOR
+        .try_with(|generator| {
+            let (seeded_epoch, mut state) = generator.get();
+            if seeded_epoch != epoch {
+                let thread = THREADS.fetch_add(1, Ordering::Relaxed);
+                state = SEED.load(Ordering::Relaxed) ^ thread.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            }
+            // SplitMix64
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            generator.set((epoch, state));
+            let mut value = state;
+            value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            value ^ (value >> 31)
+        })
+        .unwrap_or(0)
+}
+
+/// Whether a fault whose threshold is stored in `probability` should be injected.
+fn inject(probability: &AtomicU32) -> bool {
+    if !ENABLED.load(Ordering::Acquire) {
+        return false;
+    }
+    let threshold = probability.load(Ordering::Relaxed);
+    threshold != 0 && (next_random() as u32) <= threshold
+}
+
+/// Possibly delays the publication of a protection.
+pub(crate) fn delay_protection() {
+    if inject(&PROTECTION_DELAY) {
+        for _ in 0..=next_random() % MAX_DELAY_YIELDS {
+            std::thread::yield_now();
+        }
+    }
+}
+
+/// Whether retiring a value should force a reclamation pass.
+pub(crate) fn force_reclaim() -> bool {
+    inject(&FORCED_RECLAIM)
+}
+
+/// Shuffles `values` if retired values are reclaimed in a shuffled order.
+pub(crate) fn shuffle<T>(values: &mut [T]) {
+    if !ENABLED.load(Ordering::Acquire) || !SHUFFLED_RECLAIM.load(Ordering::Relaxed) {
+        return;
+    }
+    // Fisher-Yates
+    for index in (1..values.len()).rev() {
+        values.swap(index, (next_random() % (index as u64 + 1)) as usize);
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn thresholds_cover_the_probability_range() {
+        assert_eq!(threshold(0.0), 0);
+        assert_eq!(threshold(-1.0), 0, "Probabilities are clamped");
+        assert_eq!(threshold(1.0), u32::MAX);
+        assert_eq!(threshold(2.0), u32::MAX, "Probabilities are clamped");
+    }
+
+    #[test]
+    fn generators_repeat_for_the_same_seed() {
+        let draw = || {
+            GENERATOR.with(|generator| generator.set((EPOCH.load(Ordering::Acquire), 42)));
+            (0..8).map(|_| next_random()).collect::<Vec<_>>()
+        };
+
+        let first = draw();
+        let second = draw();
+
+        assert_eq!(first, second, "The same state draws the same numbers");
+        assert!(
+            first.windows(2).all(|pair| pair[0] != pair[1]),
+            "Successive draws differ"
+        );
+    }
+}
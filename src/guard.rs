@@ -0,0 +1,108 @@
+//! Guard
+//!
+//! A trait implemented by the guards returned from loading and replacing values.
+
+use crate::protection::Protection;
+use crate::{LoadGuard, StoreGuard};
+use core::ops::Deref;
+
+/// A guard which keeps a value from an `AtomBox` alive, whether it was loaded or replaced.
+///
+/// Implemented by [`LoadGuard`] and [`StoreGuard`], so helpers which only read the value can
+/// accept either, generically or as a `Box<dyn Guard<T>>`.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBox, Guard};
+///
+/// fn describe(guard: &dyn Guard<String>) -> String {
+///     format!("{} from domain {}", &**guard, guard.domain_id())
+/// }
+///
+/// let config = AtomBox::new(String::from("v1"));
+/// let replaced = config.swap(String::from("v2"));
+///
+/// assert_eq!(describe(&replaced), "v1 from domain 0");
+/// assert_eq!(describe(&config.load()), "v2 from domain 0");
+/// ```
+pub trait Guard<T>: Deref<Target = T> {
+    /// Returns a pointer to the guarded value.
+    ///
+    /// The pointer is only valid to dereference while the guard is alive.
+    fn as_ptr(&self) -> *const T;
+
+    /// Returns the id of the domain the value belongs to.
+    fn domain_id(&self) -> usize;
+}
+
+impl<T, const DOMAIN_ID: usize, P: Protection> Guard<T> for LoadGuard<'_, T, DOMAIN_ID, P> {
+    fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    fn domain_id(&self) -> usize {
+        DOMAIN_ID
+    }
+}
+
+impl<T, const DOMAIN_ID: usize, P: Protection> Guard<T> for StoreGuard<'_, T, DOMAIN_ID, P> {
+    fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    fn domain_id(&self) -> usize {
+        DOMAIN_ID
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::{Domain, ReclaimStrategy};
+    use crate::test_util::DropCounter;
+    use crate::AtomBoxIn;
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+
+    fn total<G: Guard<u32>>(guards: &[G]) -> u32 {
+        guards.iter().map(|guard| **guard).sum()
+    }
+
+    #[test]
+    fn load_and_store_guards_can_be_used_interchangeably() {
+        let domain: Domain<7> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box = AtomBoxIn::new_with_domain(1, &domain);
+
+        let replaced = atom_box.swap(2);
+        let loaded = atom_box.load();
+        let guards: Vec<Box<dyn Guard<u32> + '_>> =
+            alloc::vec![Box::new(replaced), Box::new(loaded)];
+
+        assert_eq!(
+            guards.iter().map(|guard| ***guard).collect::<Vec<_>>(),
+            [1, 2]
+        );
+        assert!(guards.iter().all(|guard| guard.domain_id() == 7));
+        assert_eq!(guards[1].as_ptr(), &*atom_box.load() as *const u32);
+        assert_eq!(total(&[atom_box.load(), atom_box.load()]), 4);
+    }
+
+    #[test]
+    fn boxed_guards_still_protect_their_values() {
+        let drop_counter = DropCounter::new();
+        let domain: Domain<8> = Domain::new(ReclaimStrategy::Eager);
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &domain);
+        let guard: Box<dyn Guard<_> + '_> = Box::new(atom_box.load());
+
+        atom_box.store(drop_counter.track(2));
+        domain.reclaim();
+        let drops_while_guarded = drop_counter.count();
+        drop(guard);
+        domain.reclaim();
+
+        assert_eq!(drops_while_guarded, 0, "The boxed guard protects the value");
+        drop_counter.assert_drops(1);
+    }
+}
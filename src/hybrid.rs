@@ -0,0 +1,355 @@
+//! Hybrid
+//!
+//! An `AtomBox` which protects reads with hazard pointers while they are available, and falls
+//! back to reference counted snapshots once the domain's hazard pointer limit has been reached.
+
+use crate::domain::Domain;
+use crate::sync::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use crate::LoadGuard;
+use alloc::boxed::Box;
+use core::ops::Deref;
+
+// The reference counted pointer used for snapshots.
+#[cfg(not(feature = "triomphe"))]
+pub(crate) use alloc::sync::Arc;
+#[cfg(feature = "triomphe")]
+pub(crate) use triomphe::Arc;
+
+/// How the value referenced by a [`HybridGuard`] is kept alive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GuardMode {
+    /// The value is protected by a hazard pointer.
+    Hazard,
+    /// The guard holds a reference counted snapshot of the value.
+    RefCounted,
+}
+
+/// An `AtomBox` which degrades to reference counted snapshots rather than allocating hazard
+/// pointers without bound.
+///
+/// Loads are protected by hazard pointers until the domain's
+/// [hazard pointer limit](Domain::with_hazard_pointer_limit) is reached. Beyond that, loads
+/// return a reference counted snapshot of the value, which is slower but never fails. Readers
+/// which need to hold a value indefinitely can take a snapshot directly with
+/// [`HybridAtomBox::load_arc`], rather than tying up a hazard pointer.
+///
+/// Snapshots are `std::sync::Arc`s by default. With the `triomphe` feature enabled they are
+/// `triomphe::Arc`s instead, which have no weak count and a layout which is stable across FFI.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{GuardMode, HybridAtomBox};
+///
+/// let atom_box = HybridAtomBox::new("Hello");
+///
+/// let value = atom_box.load();
+/// assert_eq!(*value, "Hello");
+/// assert_eq!(value.mode(), GuardMode::Hazard);
+///
+/// let snapshot = atom_box.load_arc();
+/// atom_box.store("World");
+/// assert_eq!(*snapshot, "Hello");
+/// assert_eq!(*atom_box.load(), "World");
+/// ```
+#[derive(Debug)]
+pub struct HybridAtomBox<'domain, T, const DOMAIN_ID: usize> {
+    ptr: AtomicPtr<Arc<T>>,
+    // The number of readers taking a snapshot without hazard protection, counted separately for
+    // each parity of `epoch`. Before retiring the value it replaced, a writer advances the epoch,
+    // so that later readers are counted separately, and waits only for the readers counted
+    // before it did.
+    snapshot_readers: [AtomicUsize; 2],
+    epoch: AtomicUsize,
+    // Held by a writer while it waits for snapshot readers, so that no other writer can advance
+    // the epoch back to the parity it is waiting on.
+    writing: AtomicBool,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+#[cfg(not(loom))]
+impl<T> HybridAtomBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `HybridAtomBox` associated with the shared (global) domain.
+    pub fn new(value: T) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> HybridAtomBox<'domain, T, DOMAIN_ID> {
+    /// Creates a new `HybridAtomBox` and associates it with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{GuardMode, HybridAtomBox, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> =
+    ///     Domain::new(ReclaimStrategy::Eager).with_hazard_pointer_limit(0);
+    ///
+    /// let atom_box = HybridAtomBox::new_with_domain(5, &CUSTOM_DOMAIN);
+    /// let value = atom_box.load();
+    /// assert_eq!(*value, 5);
+    /// assert_eq!(value.mode(), GuardMode::RefCounted);
+    /// ```
+    pub fn new_with_domain(value: T, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            ptr: AtomicPtr::new(Box::into_raw(Box::new(Arc::new(value)))),
+            snapshot_readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            epoch: AtomicUsize::new(0),
+            writing: AtomicBool::new(false),
+            domain,
+        }
+    }
+
+    /// Loads the value stored in the `HybridAtomBox`.
+    ///
+    /// The value is protected by a hazard pointer if one is available within the domain's limit,
+    /// and is otherwise a reference counted snapshot.
+    pub fn load(&self) -> HybridGuard<'domain, T, DOMAIN_ID> {
+        match self.domain.acquire_haz_ptr_within_limit() {
+            Some(haz_ptr) => {
+                let ptr = haz_ptr.protect_ptr(&self.ptr);
+                HybridGuard::Protected(LoadGuard {
+                    ptr,
+                    domain: self.domain,
+                    haz_ptr: Some(haz_ptr),
+                })
+            }
+            None => HybridGuard::Shared(self.load_arc()),
+        }
+    }
+
+    /// Takes a reference counted snapshot of the value stored in the `HybridAtomBox`.
+    ///
+    /// Unlike a hazard protected guard, the snapshot can be held indefinitely without preventing
+    /// the domain from reclaiming other values.
+    pub fn load_arc(&self) -> Arc<T> {
+        let readers = loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            let readers = &self.snapshot_readers[epoch & 1];
+            readers.fetch_add(1, Ordering::SeqCst);
+            crate::sync::fence(Ordering::SeqCst);
+            // A writer which advanced the epoch in the meantime may not be waiting for this count.
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                break readers;
+            }
+            readers.fetch_sub(1, Ordering::Release);
+        };
+        let ptr = self.ptr.load(Ordering::SeqCst);
+        // # Safety
+        //
+        // Writers do not retire a value while there are snapshot readers which may have loaded
+        // it.
+        let snapshot = Arc::clone(unsafe { &*ptr });
+        readers.fetch_sub(1, Ordering::Release);
+        snapshot
+    }
+
+    /// Stores a new value in the `HybridAtomBox`.
+    pub fn store(&self, value: T) {
+        let _ = self.swap(value);
+    }
+
+    /// Stores a new value in the `HybridAtomBox`, returning a snapshot of the previous value.
+    pub fn swap(&self, value: T) -> Arc<T> {
+        let new_ptr = Box::into_raw(Box::new(Arc::new(value)));
+        let old_ptr = self.ptr.swap(new_ptr, Ordering::SeqCst);
+        // # Safety
+        //
+        // The old value has not been retired yet, and only this thread can retire it.
+        let previous = Arc::clone(unsafe { &*old_ptr });
+        while self
+            .writing
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        // Readers counted from here on load the new value, so only those already counted may still
+        // be cloning the old value. Readers counted before an earlier writer advanced the epoch
+        // have already been waited for.
+        let epoch = self.epoch.fetch_add(1, Ordering::SeqCst);
+        crate::sync::fence(Ordering::SeqCst);
+        while self.snapshot_readers[epoch & 1].load(Ordering::SeqCst) != 0 {
+            core::hint::spin_loop();
+        }
+        self.writing.store(false, Ordering::Release);
+        // # Safety
+        //
+        // The old value was created via `Box::into_raw` and is no longer reachable from the box.
+        // It is retired exactly once, by the thread which replaced it.
+        unsafe { self.domain.retire(old_ptr) };
+        previous
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Drop for HybridAtomBox<'domain, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        // # Safety
+        //
+        // We have exclusive access to the box, so there are no snapshot readers. Hazard protected
+        // readers may still reference the value, so it is retired rather than dropped.
+        unsafe { self.domain.retire(self.ptr.load(Ordering::Relaxed)) };
+    }
+}
+
+/// A guard to a value loaded from a [`HybridAtomBox`].
+///
+/// Dereferences to the value.
+pub enum HybridGuard<'domain, T, const DOMAIN_ID: usize> {
+    /// The value is protected by a hazard pointer.
+    Protected(LoadGuard<'domain, Arc<T>, DOMAIN_ID>),
+    /// The guard holds a reference counted snapshot of the value.
+    Shared(Arc<T>),
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> HybridGuard<'domain, T, DOMAIN_ID> {
+    /// Returns how the value is being kept alive.
+    pub fn mode(&self) -> GuardMode {
+        match self {
+            Self::Protected(_) => GuardMode::Hazard,
+            Self::Shared(_) => GuardMode::RefCounted,
+        }
+    }
+
+    /// Converts the guard into a reference counted snapshot, releasing any hazard pointer.
+    pub fn into_arc(self) -> Arc<T> {
+        match self {
+            Self::Protected(guard) => Arc::clone(&guard),
+            Self::Shared(snapshot) => snapshot,
+        }
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Deref for HybridGuard<'domain, T, DOMAIN_ID> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Protected(guard) => guard,
+            Self::Shared(snapshot) => snapshot,
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn falls_back_to_snapshots_once_limit_is_reached() {
+        let domain: Domain<1> = Domain::new(ReclaimStrategy::Eager).with_hazard_pointer_limit(1);
+        let atom_box = HybridAtomBox::new_with_domain(1, &domain);
+
+        let guards: Vec<_> = (0..100).map(|_| atom_box.load()).collect();
+
+        assert!(
+            guards.iter().all(|guard| **guard == 1),
+            "Every guard should load the value"
+        );
+        assert_eq!(
+            guards[0].mode(),
+            GuardMode::Hazard,
+            "Hazard pointers are used while available"
+        );
+        assert_eq!(
+            guards[99].mode(),
+            GuardMode::RefCounted,
+            "Snapshots are used once the limit is reached"
+        );
+    }
+
+    #[test]
+    fn replaced_values_outlive_guards_in_either_mode() {
+        let domain: Domain<2> = Domain::new(ReclaimStrategy::Eager);
+        let drop_counter = DropCounter::new();
+        let atom_box = HybridAtomBox::new_with_domain(drop_counter.track(1), &domain);
+        let protected = atom_box.load();
+        let snapshot = atom_box.load_arc();
+
+        let previous = atom_box.swap(drop_counter.track(2));
+
+        assert_eq!(protected.mode(), GuardMode::Hazard);
+        assert_eq!(**protected, 1, "The protected value is still accessible");
+        assert_eq!(**snapshot, 1, "The snapshot is still accessible");
+        drop((protected, previous));
+        domain.reclaim();
+        drop_counter.assert_drops(0);
+        drop(snapshot);
+        drop_counter.assert_drops(1);
+    }
+
+    #[test]
+    fn concurrent_snapshots_and_swaps() {
+        let domain: Domain<3> = Domain::new(ReclaimStrategy::Eager).with_hazard_pointer_limit(0);
+        let atom_box = HybridAtomBox::new_with_domain(0_usize, &domain);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for value in 1..=1000 {
+                    atom_box.store(value);
+                }
+            });
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    let mut last = 0;
+                    for _ in 0..1000 {
+                        let value = *atom_box.load();
+                        assert!(value >= last, "Values should not go backwards");
+                        last = value;
+                    }
+                });
+            }
+        });
+
+        assert_eq!(*atom_box.load_arc(), 1000);
+    }
+
+    #[test]
+    fn writers_progress_while_snapshots_are_taken_continuously() {
+        let domain: Domain<5> = Domain::new(ReclaimStrategy::Eager).with_hazard_pointer_limit(0);
+        let atom_box = HybridAtomBox::new_with_domain(0_usize, &domain);
+        let done = AtomicBool::new(false);
+
+        std::thread::scope(|scope| {
+            for _ in 0..3 {
+                scope.spawn(|| {
+                    while !done.load(Ordering::Relaxed) {
+                        drop(atom_box.load_arc());
+                    }
+                });
+            }
+            for value in 1..=200 {
+                atom_box.store(value);
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+
+        assert_eq!(*atom_box.load_arc(), 200);
+    }
+
+    #[cfg(feature = "triomphe")]
+    #[test]
+    fn snapshots_share_the_protected_allocation() {
+        let domain: Domain<4> = Domain::new(ReclaimStrategy::Eager);
+        let atom_box = HybridAtomBox::new_with_domain(1, &domain);
+        let protected = atom_box.load();
+
+        let raw = triomphe::Arc::into_raw(atom_box.load_arc());
+
+        assert!(
+            core::ptr::eq(raw, &*protected),
+            "The snapshot points to the value"
+        );
+        // # Safety
+        //
+        // The pointer was returned by `triomphe::Arc::into_raw`.
+        drop(unsafe { triomphe::Arc::from_raw(raw) });
+    }
+}
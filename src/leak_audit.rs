@@ -0,0 +1,179 @@
+//! Leak Audit
+//!
+//! A registry of the allocations currently owned by this crate, enabled by the `leak-audit`
+//! feature.
+//!
+//! Every allocation is recorded with its kind and size, so that a report can show whether memory
+//! is held by values stored in `AtomBox`s, by retired values awaiting reclamation (which are kept
+//! alive by guards), or by the domains' own bookkeeping.
+//!
+//! # Example
+//!
+//! ```
+//! use atom_box::{AtomBox, leak_audit::{self, AllocationKind}};
+//!
+//! let atom_box = AtomBox::new([0_u8; 1021]);
+//! let report = leak_audit::report();
+//! assert!(report.count(AllocationKind::Stored, 1021) >= 1);
+//! println!("{}", report);
+//! ```
+
+use alloc::collections::BTreeMap;
+use core::fmt;
+use std::sync::Mutex;
+
+/// What an allocation owned by this crate is used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum AllocationKind {
+    /// A value stored in an `AtomBox`.
+    Stored,
+    /// A value which has been retired but not yet reclaimed, usually because a guard still
+    /// protects it.
+    Retired,
+    /// A node of one of a domain's internal lists.
+    ListNode,
+    /// A block of hazard pointers.
+    HazardPointers,
+}
+
+impl fmt::Display for AllocationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Stored => "stored",
+            Self::Retired => "retired",
+            Self::ListNode => "list node",
+            Self::HazardPointers => "hazard pointers",
+        })
+    }
+}
+
+static REGISTRY: Mutex<BTreeMap<(AllocationKind, usize), usize>> = Mutex::new(BTreeMap::new());
+
+/// Records an allocation of `size` bytes.
+pub(crate) fn track(kind: AllocationKind, size: usize) {
+    if size == 0 {
+        return;
+    }
+    let mut registry = REGISTRY.lock().unwrap_or_else(|error| error.into_inner());
+    *registry.entry((kind, size)).or_insert(0) += 1;
+}
+
+/// Records that an allocation of `size` bytes is no longer owned as `kind`.
+pub(crate) fn untrack(kind: AllocationKind, size: usize) {
+    if size == 0 {
+        return;
+    }
+    let mut registry = REGISTRY.lock().unwrap_or_else(|error| error.into_inner());
+    if let Some(count) = registry.get_mut(&(kind, size)) {
+        *count -= 1;
+        if *count == 0 {
+            registry.remove(&(kind, size));
+        }
+    }
+}
+
+/// Returns a snapshot of the allocations currently owned by this crate.
+pub fn report() -> LeakReport {
+    LeakReport {
+        counts: REGISTRY
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .clone(),
+    }
+}
+
+/// A snapshot of the allocations owned by this crate, grouped by kind and size.
+///
+/// The `Display` implementation lists every group, which is suitable for dumping to a log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeakReport {
+    counts: BTreeMap<(AllocationKind, usize), usize>,
+}
+
+impl LeakReport {
+    /// The number of allocations of `kind` which are `size` bytes.
+    pub fn count(&self, kind: AllocationKind, size: usize) -> usize {
+        self.counts.get(&(kind, size)).copied().unwrap_or(0)
+    }
+
+    /// The total number of bytes allocated for `kind`.
+    pub fn bytes(&self, kind: AllocationKind) -> usize {
+        self.entries()
+            .filter(|(entry_kind, _, _)| *entry_kind == kind)
+            .map(|(_, size, count)| size * count)
+            .sum()
+    }
+
+    /// Iterates over the groups of allocations as `(kind, size, count)`, ordered by kind then
+    /// size.
+    pub fn entries(&self) -> impl Iterator<Item = (AllocationKind, usize, usize)> + '_ {
+        self.counts
+            .iter()
+            .map(|(&(kind, size), &count)| (kind, size, count))
+    }
+}
+
+impl fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (kind, size, count) in self.entries() {
+            writeln!(f, "{}: {} x {} bytes", kind, count, size)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::{Domain, ReclaimStrategy};
+    use crate::AtomBoxIn;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Manual);
+
+    #[test]
+    fn report_counts_tracked_allocations_by_kind_and_size() {
+        const SIZE: usize = 1999;
+
+        track(AllocationKind::Retired, SIZE);
+        track(AllocationKind::Retired, SIZE);
+        track(AllocationKind::Retired, SIZE);
+        untrack(AllocationKind::Retired, SIZE);
+        let report = report();
+        untrack(AllocationKind::Retired, SIZE);
+        untrack(AllocationKind::Retired, SIZE);
+
+        assert_eq!(report.count(AllocationKind::Retired, SIZE), 2);
+        assert_eq!(report.count(AllocationKind::Stored, SIZE), 0);
+        assert_eq!(
+            super::report().count(AllocationKind::Retired, SIZE),
+            0,
+            "Untracked allocations are removed"
+        );
+    }
+
+    #[test]
+    fn values_are_tracked_until_reclaimed() {
+        const SIZE: usize = 1997;
+        let atom_box = AtomBoxIn::new_with_domain([0_u8; SIZE], &TEST_DOMAIN);
+        let stored = report().count(AllocationKind::Stored, SIZE);
+
+        drop(atom_box);
+        let retired = report();
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(stored, 1, "The value is stored in a box");
+        assert_eq!(retired.count(AllocationKind::Stored, SIZE), 0);
+        assert_eq!(
+            retired.count(AllocationKind::Retired, SIZE),
+            1,
+            "Dropping the box retires the value"
+        );
+        assert_eq!(
+            report().count(AllocationKind::Retired, SIZE),
+            0,
+            "The value has been reclaimed"
+        );
+    }
+}
@@ -50,23 +50,173 @@
 #![no_std]
 #![warn(missing_docs)]
 extern crate alloc;
-#[cfg(feature = "std")]
+#[cfg(any(test, feature = "std"))]
 extern crate std;
 use crate::sync::{AtomicPtr, Ordering};
 use core::ops::Deref;
 
+mod alloc_error;
+mod any;
+mod atom;
+#[cfg(feature = "bytes")]
+mod atom_bytes;
+mod atom_str;
+pub mod broadcast;
+mod builder;
+mod cache;
+mod callback;
+pub mod collections;
+mod derived;
 pub mod domain;
+mod exclusive;
+#[cfg(feature = "fault-inject")]
+pub mod fault_inject;
+mod guard;
+mod hybrid;
+#[cfg(feature = "leak-audit")]
+pub mod leak_audit;
+mod local;
+mod mcas;
+mod option;
+mod poison;
+pub mod protection;
+mod rollback;
+mod scope;
+mod seal;
+mod seqlock;
+mod sharded;
+#[cfg(feature = "shared-memory")]
+pub mod shared_memory;
+mod single_writer;
 mod sync;
+#[cfg(any(test, loom, feature = "test-util"))]
+pub mod test_util;
+mod versioned;
+mod weak;
 
-use crate::domain::{Domain, HazardPointer};
+#[cfg(not(loom))]
+use crate::domain::ReclaimStrategy;
+use crate::domain::{Domain, RetirePolicy};
+use crate::protection::Protection;
 use alloc::boxed::Box;
+pub use alloc_error::AllocError;
+pub use any::AtomAny;
+pub use atom::Atom;
+#[cfg(feature = "derive")]
+pub use atom_box_derive::AtomFields;
+#[cfg(feature = "bytes")]
+pub use atom_bytes::AtomBytes;
+pub use atom_str::{AtomStr, AtomStrGuard};
+pub use broadcast::BroadcastBox;
+pub use builder::AtomBoxBuilder;
+pub use cache::AtomCache;
+pub use callback::AtomCallback;
+pub use derived::DerivedAtomBox;
+pub use exclusive::{ExclusiveGuard, UpgradableAtomBox};
+pub use guard::Guard;
+pub use hybrid::{GuardMode, HybridAtomBox, HybridGuard};
+pub use local::{LocalAtomBox, LocalGuard};
+pub use mcas::{mcas, McasAtomBox};
+pub use option::AtomOptionBox;
+pub use poison::{PoisonAtomBox, Poisoned};
+pub use rollback::{NoHistory, RollbackAtomBox};
+pub use scope::GuardScope;
+pub use seal::{SealableAtomBox, SealedError};
+pub use seqlock::{NoUninit, SeqLockAtomBox};
+pub use sharded::ShardedAtomBox;
+pub use single_writer::{SingleWriter, SingleWriterAtomBox};
+pub use versioned::AtomVersioned;
+pub use weak::AtomWeak;
 
 #[cfg(not(loom))]
 const SHARED_DOMAIN_ID: usize = 0;
 
+// Used by the code generated by the derive macros.
+#[doc(hidden)]
+#[cfg(all(feature = "derive", not(loom)))]
+pub mod __private {
+    pub const SHARED_DOMAIN_ID: usize = crate::SHARED_DOMAIN_ID;
+}
+
+// The number of times `try_load` attempts to protect a value which is being concurrently replaced.
+const TRY_LOAD_ATTEMPTS: usize = 4;
+
 #[cfg(not(loom))]
 static SHARED_DOMAIN: Domain<SHARED_DOMAIN_ID> = Domain::default();
 
+/// Sets the reclamation strategy of the shared domain, used by [`AtomBox`] and the other types
+/// created with `new`.
+///
+/// The shared domain can only be configured once, and only before it is used. It is used from
+/// the first time a value is retired or a guard is dropped. If it is too late to configure the
+/// shared domain, `reclaim_strategy` is returned as the error. This allows an application to
+/// choose how the values of every library it depends on are reclaimed.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{configure_shared_domain, domain::ReclaimStrategy, AtomBox};
+///
+/// configure_shared_domain(ReclaimStrategy::Eager).expect("The shared domain is not yet used");
+///
+/// let atom_box = AtomBox::new("Hello");
+/// atom_box.store("World");
+///
+/// assert!(configure_shared_domain(ReclaimStrategy::Manual).is_err());
+/// ```
+#[cfg(not(loom))]
+pub fn configure_shared_domain(reclaim_strategy: ReclaimStrategy) -> Result<(), ReclaimStrategy> {
+    SHARED_DOMAIN.configure_reclaim_strategy(reclaim_strategy)
+}
+
+/// Returns the state of the shared domain and every [registered](Domain::register) domain.
+///
+/// This lets a diagnostics endpoint report the backlog of retired values and the hazard pointer
+/// usage of every domain in the process in one place.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::AtomBox;
+///
+/// let atom_box = AtomBox::new("Hello");
+/// let guard = atom_box.load();
+///
+/// let shared = &atom_box::domains()[0];
+/// assert_eq!(shared.id, 0);
+/// assert!(shared.hazard_pointers_in_use >= 1);
+/// # drop(guard);
+/// ```
+#[cfg(all(feature = "domain-registry", not(loom)))]
+pub fn domains() -> alloc::vec::Vec<domain::DomainInfo> {
+    SHARED_DOMAIN.register();
+    domain::registered_domains()
+}
+
+/// Panics if a guard from `guard_domain` is used with a box associated with `box_domain`.
+#[track_caller]
+pub(crate) fn assert_same_domain<P: Protection>(guard_domain: &P, box_domain: &P) {
+    if !guard_domain.is_same(box_domain) {
+        panic!(
+            "Cannot use guarded value from different domain (guard from {}, box in {})",
+            DomainName(guard_domain.name()),
+            DomainName(box_domain.name())
+        );
+    }
+}
+
+/// Formats an optional domain name for panic messages.
+struct DomainName(Option<&'static str>);
+
+impl core::fmt::Display for DomainName {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Some(name) => write!(f, "domain `{}`", name),
+            None => f.write_str("an unnamed domain"),
+        }
+    }
+}
+
 mod macros {
     // The loom atomics do not have const constructors. So we cannot use them in const functions.
     // This macro enables us to create a const function in normal compilation and a non const
@@ -76,6 +226,7 @@ mod macros {
             #[doc = $doc_comment]
             #[cfg(not(loom))]
             $visibility const $( $token )*
+            #[doc = $doc_comment]
             #[cfg(loom)]
             $visibility $( $token )*
         };
@@ -123,14 +274,80 @@ mod macros {
 /// handle1.join().unwrap();
 /// handle2.join().unwrap();
 /// ```
+///
+/// Loading gives shared access to the value, so an `AtomBox` is only unwind safe if its values
+/// are [`RefUnwindSafe`](core::panic::RefUnwindSafe). The following example will fail to compile.
+///
+/// ```compile_fail
+/// use atom_box::AtomBox;
+/// use std::cell::RefCell;
+///
+/// let atom_box = AtomBox::new(RefCell::new(0));
+/// let _ = std::panic::catch_unwind(|| *atom_box.load().borrow_mut() += 1);
+/// ```
 #[derive(Debug)]
-pub struct AtomBox<'domain, T, const DOMAIN_ID: usize> {
+pub struct AtomBoxIn<
+    'domain,
+    T,
+    const DOMAIN_ID: usize,
+    P: Protection + 'domain = Domain<DOMAIN_ID>,
+> {
     ptr: AtomicPtr<T>,
-    domain: &'domain Domain<DOMAIN_ID>,
+    domain: &'domain P,
+    retire_policy: RetirePolicy,
+}
+
+// The stored pointer is only replaced atomically, by a fully constructed value, so a panicking
+// update leaves the previous value in place. The value itself is shared with every reader.
+impl<T: core::panic::RefUnwindSafe, const DOMAIN_ID: usize, P> core::panic::UnwindSafe
+    for AtomBoxIn<'_, T, DOMAIN_ID, P>
+where
+    P: Protection + core::panic::RefUnwindSafe,
+{
 }
+impl<T: core::panic::RefUnwindSafe, const DOMAIN_ID: usize, P> core::panic::RefUnwindSafe
+    for AtomBoxIn<'_, T, DOMAIN_ID, P>
+where
+    P: Protection + core::panic::RefUnwindSafe,
+{
+}
+
+/// The domain ID of boxes associated with an [`AnyDomain`](domain::AnyDomain).
+pub const ANY_DOMAIN_ID: usize = usize::MAX;
+
+/// An [`AtomBoxIn`] associated with a domain whose ID has been erased.
+///
+/// See [`AnyDomain`](domain::AnyDomain).
+pub type AnyAtomBox<'domain, T> = AtomBoxIn<'domain, T, ANY_DOMAIN_ID, domain::AnyDomain<'domain>>;
+
+/// An [`AtomBoxIn`] associated with the shared (global) domain.
+///
+/// Most users only need the shared domain, and this alias keeps the domain's lifetime and ID out
+/// of their type signatures. Boxes associated with a custom domain are created with
+/// [`AtomBoxIn::new_with_domain`].
+///
+/// # Example
+///
+/// ```
+/// use atom_box::AtomBox;
+///
+/// struct Config {
+///     name: AtomBox<String>,
+/// }
+///
+/// fn rename(config: &Config, name: &str) {
+///     config.name.store(name.to_owned());
+/// }
+///
+/// let config = Config { name: AtomBox::new("Hello".to_owned()) };
+/// rename(&config, "World");
+/// assert_eq!(*config.name.load(), "World");
+/// ```
+#[cfg(not(loom))]
+pub type AtomBox<T> = AtomBoxIn<'static, T, SHARED_DOMAIN_ID>;
 
 #[cfg(not(loom))]
-impl<T> AtomBox<'static, T, SHARED_DOMAIN_ID> {
+impl<T> AtomBox<T> {
     /// Creates a new `AtomBox` associated with the shared (global) domain.
     ///
     /// # Example
@@ -149,9 +366,15 @@ impl<T> AtomBox<'static, T, SHARED_DOMAIN_ID> {
     /// ```
     pub fn new(value: T) -> Self {
         let ptr = AtomicPtr::new(Box::into_raw(Box::new(value)));
+        #[cfg(feature = "leak-audit")]
+        leak_audit::track(
+            leak_audit::AllocationKind::Stored,
+            core::mem::size_of::<T>(),
+        );
         Self {
             ptr,
             domain: &SHARED_DOMAIN,
+            retire_policy: RetirePolicy::Domain,
         }
     }
 
@@ -192,25 +415,105 @@ impl<T> AtomBox<'static, T, SHARED_DOMAIN_ID> {
     pub fn new_static(value: T) -> &'static mut Self {
         Box::leak(Box::new(Self::new(value)))
     }
+
+    /// Creates a new `AtomBox` associated with the shared (global) domain, returning the value
+    /// in an [`AllocError`] rather than aborting if it cannot be allocated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let atom_box = AtomBox::try_new("Hello").expect("Out of memory");
+    /// assert_eq!(*atom_box.load(), "Hello");
+    /// ```
+    pub fn try_new(value: T) -> Result<Self, AllocError<T>> {
+        let ptr = AtomicPtr::new(alloc_error::try_box(value)?);
+        #[cfg(feature = "leak-audit")]
+        leak_audit::track(
+            leak_audit::AllocationKind::Stored,
+            core::mem::size_of::<T>(),
+        );
+        Ok(Self {
+            ptr,
+            domain: &SHARED_DOMAIN,
+            retire_policy: RetirePolicy::Domain,
+        })
+    }
 }
 
-impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
-    /// Creates a new `AtomBox` and assoicates it with the given domain.
+// The result of exchanging a value from a `StoreGuard`, which is handed back on failure.
+type CompareExchangeFromGuardResult<'domain, T, const DOMAIN_ID: usize, P> = Result<
+    StoreGuard<'domain, T, DOMAIN_ID, P>,
+    (
+        LoadGuard<'domain, T, DOMAIN_ID, P>,
+        StoreGuard<'domain, T, DOMAIN_ID, P>,
+    ),
+>;
+
+// The result of exchanging a value, which also returns a guard to the installed value on success.
+type CompareExchangeAndLoadResult<'domain, T, const DOMAIN_ID: usize, P> = Result<
+    (
+        StoreGuard<'domain, T, DOMAIN_ID, P>,
+        LoadGuard<'domain, T, DOMAIN_ID, P>,
+    ),
+    LoadGuard<'domain, T, DOMAIN_ID, P>,
+>;
+
+impl<'domain, T, const DOMAIN_ID: usize, P: Protection> AtomBoxIn<'domain, T, DOMAIN_ID, P> {
+    /// Creates a new `AtomBox` protected by the given reclamation scheme.
+    ///
+    /// Boxes using a [`Domain`] are usually created with [`AtomBoxIn::new_with_domain`], which
+    /// infers the domain ID.
     ///
     /// # Example
     ///
     /// ```
-    /// use atom_box::{AtomBox, domain::Domain, domain::ReclaimStrategy};
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
     ///
-    /// const CUSTOM_DOMAIN_ID: usize = 42;
-    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    /// static DOMAIN: Domain<42> = Domain::new(ReclaimStrategy::Eager);
     ///
-    /// let atom_box = AtomBox::new_with_domain("Hello World", &CUSTOM_DOMAIN);
-    /// assert_eq!(*atom_box.load(), "Hello World");
+    /// let atom_box: AtomBoxIn<'_, _, 42, _> = AtomBoxIn::new_with_protection(5, &DOMAIN);
+    /// assert_eq!(*atom_box.load(), 5);
     /// ```
-    pub fn new_with_domain(value: T, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+    pub fn new_with_protection(value: T, domain: &'domain P) -> Self {
         let ptr = AtomicPtr::new(Box::into_raw(Box::new(value)));
-        Self { ptr, domain }
+        #[cfg(feature = "leak-audit")]
+        leak_audit::track(
+            leak_audit::AllocationKind::Stored,
+            core::mem::size_of::<T>(),
+        );
+        Self {
+            ptr,
+            domain,
+            retire_policy: RetirePolicy::Domain,
+        }
+    }
+
+    /// Sets how the values replaced in this `AtomBox` are retired, overriding its domain's
+    /// strategy for this box only.
+    ///
+    /// The policy applies to the `StoreGuard`s returned by this box, and to the value held when
+    /// the box is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy, RetirePolicy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let blob = AtomBoxIn::new_with_domain(vec![0_u8; 1 << 20], &CUSTOM_DOMAIN)
+    ///     .with_retire_policy(RetirePolicy::Immediate);
+    ///
+    /// // The replaced blob is freed straight away, since nothing protects it.
+    /// blob.store(vec![1_u8; 1 << 20]);
+    /// assert_eq!(CUSTOM_DOMAIN.reclaim(), 0);
+    /// ```
+    pub fn with_retire_policy(mut self, retire_policy: RetirePolicy) -> Self {
+        self.retire_policy = retire_policy;
+        self
     }
 
     /// Loads the value stored in the `AtomBox`.
@@ -227,26 +530,12 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     /// let value = atom_box.load();
     /// assert_eq!(*value, "Hello World");
     /// ```
-    pub fn load(&self) -> LoadGuard<'domain, T, DOMAIN_ID> {
-        let haz_ptr = self.domain.acquire_haz_ptr();
-        // load pointer
-        let mut original_ptr = self.ptr.load(Ordering::Relaxed);
-
-        let ptr = loop {
-            // protect pointer
-            haz_ptr.protect(original_ptr as *mut usize);
-
-            core::sync::atomic::fence(Ordering::SeqCst);
-
-            // check pointer
-            let current_ptr = self.ptr.load(Ordering::Acquire);
-            if current_ptr == original_ptr {
-                // The pointer is the same, we have successfully protected its value.
-                break current_ptr;
-            }
-            haz_ptr.reset();
-            original_ptr = current_ptr;
-        };
+    pub fn load(&self) -> LoadGuard<'domain, T, DOMAIN_ID, P> {
+        if !domain::needs_reclaim::<T>() {
+            return self.unprotected_load();
+        }
+        let haz_ptr = self.domain.acquire();
+        let ptr = self.protect(&haz_ptr);
         LoadGuard {
             ptr,
             domain: self.domain,
@@ -254,6 +543,106 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
         }
     }
 
+    /// Loads the value currently stored in the `AtomBox` into an existing guard.
+    ///
+    /// The guard's hazard pointer is reused to protect the current value, rather than being
+    /// released and another acquired, so loops which repeatedly refresh their snapshot of a box
+    /// avoid churning through the domain's hazard pointers. The value previously referenced by
+    /// the guard is no longer protected by it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was loaded from a box associated with a different domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let atom_box = AtomBox::new(1);
+    /// let mut value = atom_box.load();
+    ///
+    /// atom_box.store(2);
+    /// atom_box.reload(&mut value);
+    /// assert_eq!(*value, 2);
+    /// ```
+    pub fn reload(&self, guard: &mut LoadGuard<'domain, T, DOMAIN_ID, P>) {
+        assert_same_domain(guard.domain, self.domain);
+        match &guard.haz_ptr {
+            Some(haz_ptr) => guard.ptr = self.protect(haz_ptr),
+            None => *guard = self.load(),
+        }
+    }
+
+    /// Returns `true` if this `AtomBox` and `other` currently hold the same value.
+    ///
+    /// Only the pointers are compared, so no protection is acquired and the values themselves are
+    /// not inspected. A value is only ever held by one box at a time, so distinct boxes hold the
+    /// same value only if it is zero-sized.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let first = AtomBox::new("Hello");
+    /// let second = AtomBox::new("Hello");
+    ///
+    /// assert!(first.ptr_eq(&first));
+    /// assert!(!first.ptr_eq(&second));
+    /// ```
+    pub fn ptr_eq(&self, other: &AtomBoxIn<'_, T, DOMAIN_ID, P>) -> bool {
+        self.ptr.load(Ordering::Acquire) == other.ptr.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if this `AtomBox` still holds the value referenced by `guard`.
+    ///
+    /// Only the pointers are compared, so no protection is acquired. See [`is_current`](AtomBoxIn::is_current) for when a replaced value may compare equal.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let atom_box = AtomBox::new("Hello");
+    /// let value = atom_box.load();
+    /// assert!(atom_box.current_ptr_eq(&value));
+    ///
+    /// atom_box.store("Hello");
+    /// assert!(!atom_box.current_ptr_eq(&value));
+    /// ```
+    pub fn current_ptr_eq(&self, guard: &LoadGuard<'_, T, DOMAIN_ID, P>) -> bool {
+        core::ptr::eq(self.ptr.load(Ordering::Acquire), guard.ptr)
+    }
+
+    /// Returns `true` if the value referenced by `guard` has not been replaced since it was
+    /// loaded.
+    ///
+    /// Long-lived readers can use this to decide whether their snapshot needs refreshing, without
+    /// acquiring a hazard pointer to load the value again. While `guard` is held its value is not
+    /// reclaimed, so no newer value can be allocated at its address. This does not hold for
+    /// zero-sized types, which all share one dangling address: a replaced zero-sized value is
+    /// reported as current, although its contents cannot differ.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let config = AtomBox::new(String::from("v1"));
+    /// let mut snapshot = config.load();
+    /// assert!(config.is_current(&snapshot));
+    ///
+    /// config.store(String::from("v2"));
+    /// if !config.is_current(&snapshot) {
+    ///     snapshot = config.load();
+    /// }
+    /// assert_eq!(*snapshot, "v2");
+    /// ```
+    pub fn is_current(&self, guard: &LoadGuard<'_, T, DOMAIN_ID, P>) -> bool {
+        self.current_ptr_eq(guard)
+    }
+
     /// Stores a new value in the `AtomBox`
     ///
     /// # Example
@@ -291,10 +680,28 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     /// let value = atom_box2.load();
     /// assert_eq!(*value, "Hello");
     /// ```
-    pub fn store_from_guard(&self, value: StoreGuard<'domain, T, DOMAIN_ID>) {
+    pub fn store_from_guard(&self, value: StoreGuard<'domain, T, DOMAIN_ID, P>) {
         let _ = self.swap_from_guard(value);
     }
 
+    /// Stores a new value in the `AtomBox`, returning the value in an [`AllocError`] rather than
+    /// aborting if it cannot be allocated.
+    ///
+    /// Retiring the replaced value may still allocate, unless the domain reclaims it immediately.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let atom_box = AtomBox::new("Hello");
+    /// atom_box.try_store("World").expect("Out of memory");
+    /// assert_eq!(*atom_box.load(), "World");
+    /// ```
+    pub fn try_store(&self, value: T) -> Result<(), AllocError<T>> {
+        self.try_swap(value).map(drop)
+    }
+
     /// Stores the value into the `AtomBox` and returns a `StoreGuard` which dereferences into the
     /// previous value.
     ///
@@ -311,15 +718,45 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     /// let guard = atom_box.swap("Bye Bye");
     /// assert_eq!(*guard, "Hello World");
     /// ```
-    pub fn swap(&self, new_value: T) -> StoreGuard<'domain, T, DOMAIN_ID> {
+    pub fn swap(&self, new_value: T) -> StoreGuard<'domain, T, DOMAIN_ID, P> {
         let new_ptr = Box::into_raw(Box::new(new_value));
         let old_ptr = self.ptr.swap(new_ptr, Ordering::AcqRel);
         StoreGuard {
             ptr: old_ptr,
             domain: self.domain,
+            retire_policy: self.retire_policy,
         }
     }
 
+    /// Stores the value into the `AtomBox` and returns a `StoreGuard` which dereferences into the
+    /// previous value, or returns the value in an [`AllocError`] rather than aborting if it cannot
+    /// be allocated.
+    ///
+    /// Dropping the `StoreGuard` retires the previous value, which may still allocate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let atom_box = AtomBox::new("Hello World");
+    ///
+    /// let guard = atom_box.try_swap("Bye Bye").expect("Out of memory");
+    /// assert_eq!(*guard, "Hello World");
+    /// ```
+    pub fn try_swap(
+        &self,
+        new_value: T,
+    ) -> Result<StoreGuard<'domain, T, DOMAIN_ID, P>, AllocError<T>> {
+        let new_ptr = alloc_error::try_box(new_value)?;
+        let old_ptr = self.ptr.swap(new_ptr, Ordering::AcqRel);
+        Ok(StoreGuard {
+            ptr: old_ptr,
+            domain: self.domain,
+            retire_policy: self.retire_policy,
+        })
+    }
+
     /// Stores the value into the `AtomBox` and returns a `StoreGuard` which dereferences into the
     /// previous value.
     ///
@@ -347,12 +784,12 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     /// The following example will fail to compile.
     ///
     /// ```compile_fail
-    /// use atom_box::{AtomBox, domain::{Domain, ReclaimStrategy}};
+    /// use atom_box::{AtomBox, AtomBoxIn, domain::{Domain, ReclaimStrategy}};
     ///
     /// const CUSTOM_DOMAIN_ID: usize = 42;
     /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
     ///
-    /// let atom_box1 = AtomBox::new_with_domain("Hello", &CUSTOM_DOMAIN);
+    /// let atom_box1 = AtomBoxIn::new_with_domain("Hello", &CUSTOM_DOMAIN);
     /// let atom_box2 = AtomBox::new("World");
     ///
     /// let guard = atom_box1.swap("Bye bye");
@@ -360,12 +797,9 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     /// ```
     pub fn swap_from_guard(
         &self,
-        new_value: StoreGuard<'domain, T, DOMAIN_ID>,
-    ) -> StoreGuard<'domain, T, DOMAIN_ID> {
-        assert!(
-            core::ptr::eq(new_value.domain, self.domain),
-            "Cannot use guarded value from different domain"
-        );
+        new_value: StoreGuard<'domain, T, DOMAIN_ID, P>,
+    ) -> StoreGuard<'domain, T, DOMAIN_ID, P> {
+        assert_same_domain(new_value.domain, self.domain);
 
         let new_ptr = new_value.ptr;
         core::mem::forget(new_value);
@@ -373,6 +807,183 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
         StoreGuard {
             ptr: old_ptr,
             domain: self.domain,
+            retire_policy: self.retire_policy,
+        }
+    }
+
+    /// Atomically replaces the value in the `AtomBox` with the result of applying `f` to it.
+    ///
+    /// If another thread updates the value while `f` is running, `f` is called again with the
+    /// updated value. Returns a `StoreGuard` which dereferences into the value which was replaced
+    /// and a `LoadGuard` which dereferences into the value which was installed, so that the
+    /// transition can be observed without loading the value again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let atom_box = AtomBox::new(1);
+    ///
+    /// let (old_value, new_value) = atom_box.modify(|value| value * 10);
+    /// assert_eq!(*old_value, 1);
+    /// assert_eq!(*new_value, 10);
+    /// ```
+    pub fn modify(
+        &self,
+        mut f: impl FnMut(&T) -> T,
+    ) -> (
+        StoreGuard<'domain, T, DOMAIN_ID, P>,
+        LoadGuard<'domain, T, DOMAIN_ID, P>,
+    ) {
+        let current_haz_ptr = self.domain.acquire();
+        let new_haz_ptr = self.domain.acquire();
+        let mut current_ptr = self.protect(&current_haz_ptr);
+        loop {
+            // # Safety
+            //
+            // The current value is protected by the hazard pointer.
+            let new_ptr = Box::into_raw(Box::new(f(unsafe { &*current_ptr })));
+            // The new value is not shared until the exchange succeeds, so it cannot have been
+            // retired before it is protected.
+            self.domain.protect(&new_haz_ptr, new_ptr);
+            match self.ptr.compare_exchange(
+                current_ptr,
+                new_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(old_ptr) => {
+                    self.domain.release(current_haz_ptr);
+                    return (
+                        StoreGuard {
+                            ptr: old_ptr,
+                            domain: self.domain,
+                            retire_policy: self.retire_policy,
+                        },
+                        LoadGuard {
+                            ptr: new_ptr,
+                            domain: self.domain,
+                            haz_ptr: Some(new_haz_ptr),
+                        },
+                    );
+                }
+                Err(_) => {
+                    self.domain.reset(&new_haz_ptr);
+                    // # Safety
+                    //
+                    // The new value was never shared so we still have exclusive ownership.
+                    drop(unsafe { Box::from_raw(new_ptr) });
+                    current_ptr = self.protect(&current_haz_ptr);
+                }
+            }
+        }
+    }
+
+    /// Replaces the value in the `AtomBox` with a modified clone of it.
+    ///
+    /// The current value is cloned and `f` applies its changes to the clone, which is then stored
+    /// if the `AtomBox` still holds the value it was cloned from. If another thread updates the
+    /// value in the meantime, the updated value is cloned and `f` is called again. Returns a
+    /// `StoreGuard` which dereferences into the value which was replaced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let atom_box = AtomBox::new(vec![1, 2]);
+    ///
+    /// let old_value = atom_box.make_mut(|value| value.push(3));
+    /// assert_eq!(*old_value, [1, 2]);
+    /// assert_eq!(*atom_box.load(), [1, 2, 3]);
+    /// ```
+    pub fn make_mut(&self, mut f: impl FnMut(&mut T)) -> StoreGuard<'domain, T, DOMAIN_ID, P>
+    where
+        T: Clone,
+    {
+        let (old_value, _) = self.modify(|value| {
+            let mut value = value.clone();
+            f(&mut value);
+            value
+        });
+        old_value
+    }
+
+    /// Stores `new_value` into the `AtomBox` if `predicate` holds for its current value.
+    ///
+    /// The predicate is checked against a protected load of the current value, and the new value
+    /// is only stored if the `AtomBox` still holds that value. If another thread updates the value
+    /// in the meantime, the predicate is checked again against the updated value.
+    ///
+    /// On success, the return value is a `StoreGuard` which dereferences to the old value. If the
+    /// predicate does not hold, the `Err` contains a `LoadGuard` which dereferences to the value
+    /// it was rejected for, along with `new_value`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let atom_box = AtomBox::new(5);
+    ///
+    /// // Only replace the value with a larger one.
+    /// match atom_box.compare_exchange_if(|value| *value < 10, 10) {
+    ///     Ok(old_value) => assert_eq!(*old_value, 5),
+    ///     Err(_) => panic!("5 is less than 10"),
+    /// }
+    ///
+    /// match atom_box.compare_exchange_if(|value| *value < 7, 7) {
+    ///     Ok(_) => panic!("10 is not less than 7"),
+    ///     Err((current_value, rejected)) => {
+    ///         assert_eq!(*current_value, 10);
+    ///         assert_eq!(rejected, 7);
+    ///     }
+    /// }
+    /// ```
+    pub fn compare_exchange_if(
+        &self,
+        predicate: impl Fn(&T) -> bool,
+        new_value: T,
+    ) -> Result<StoreGuard<'domain, T, DOMAIN_ID, P>, (LoadGuard<'domain, T, DOMAIN_ID, P>, T)>
+    {
+        let haz_ptr = self.domain.acquire();
+        let new_ptr = Box::into_raw(Box::new(new_value));
+        let mut current_ptr = self.protect(&haz_ptr);
+        loop {
+            // # Safety
+            //
+            // The current value is protected by the hazard pointer.
+            if !predicate(unsafe { &*current_ptr }) {
+                // # Safety
+                //
+                // The new value was never shared so we still have exclusive ownership.
+                let new_value = *unsafe { Box::from_raw(new_ptr) };
+                return Err((
+                    LoadGuard {
+                        ptr: current_ptr,
+                        domain: self.domain,
+                        haz_ptr: Some(haz_ptr),
+                    },
+                    new_value,
+                ));
+            }
+            match self.ptr.compare_exchange(
+                current_ptr,
+                new_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(old_ptr) => {
+                    self.domain.release(haz_ptr);
+                    return Ok(StoreGuard {
+                        ptr: old_ptr,
+                        domain: self.domain,
+                        retire_policy: self.retire_policy,
+                    });
+                }
+                Err(_) => current_ptr = self.protect(&haz_ptr),
+            }
         }
     }
 
@@ -412,9 +1023,9 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     /// ```
     pub fn compare_exchange(
         &self,
-        current_value: LoadGuard<'domain, T, DOMAIN_ID>,
+        current_value: LoadGuard<'domain, T, DOMAIN_ID, P>,
         new_value: T,
-    ) -> Result<StoreGuard<'domain, T, DOMAIN_ID>, LoadGuard<'domain, T, DOMAIN_ID>> {
+    ) -> Result<StoreGuard<'domain, T, DOMAIN_ID, P>, LoadGuard<'domain, T, DOMAIN_ID, P>> {
         let new_ptr = Box::into_raw(Box::new(new_value));
         match self.ptr.compare_exchange(
             current_value.ptr as *mut T,
@@ -425,6 +1036,7 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
             Ok(ptr) => Ok(StoreGuard {
                 ptr,
                 domain: self.domain,
+                retire_policy: self.retire_policy,
             }),
             Err(ptr) => Err(LoadGuard {
                 ptr,
@@ -434,35 +1046,106 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
         }
     }
 
-    /// Stores a value into the `AtomBox` if its current value equals `current_value`.
-    ///
-    /// The return value is a result indicating whether the new value was written.
-    /// On success, this value is guaranteed to be equal to `current_value` and the return value is
-    /// a StoreGuard which dereferences to the old value.
-    /// On failure, the `Err` contains a LoadGaurd which dereferences to the `current_value`.
-    ///
-    /// **Note:** This method is only available on platforms that support atomic operations on
-    /// pointers.
+    /// Stores a value into the `AtomBox` if its current value equals `current_value`, returning a
+    /// guard to the value which was installed.
     ///
-    /// # Panics
-    ///
-    /// Panics if the guard is associated with a different domain.
+    /// Behaves like [`AtomBoxIn::compare_exchange`], except that on success a `LoadGuard` which
+    /// dereferences to the new value is returned alongside the `StoreGuard` to the old value. The
+    /// new value is protected before it is published, so the guard always refers to the value this
+    /// call wrote, even if another thread has replaced it since. Loading the box again instead
+    /// might observe an even newer value.
+    /// On failure, the `Err` contains a LoadGuard which dereferences to the current value.
     ///
-    /// # example
+    /// # Example
     /// ```
     /// use atom_box::AtomBox;
     ///
-    /// let atom_box1 = AtomBox::new(0);
-    /// let atom_box2 = AtomBox::new(1);
-    ///
-    /// let mut guard = atom_box2.swap(2);
-    /// let mut current_value = atom_box1.load();
-    /// let _ = loop {
-    ///     match atom_box1.compare_exchange_from_guard(current_value, guard) {
-    ///         Ok(value) => {
-    ///             break value;
-    ///         }
-    ///         Err((value, returned_guard)) => {
+    /// let atom_box = AtomBox::new(0);
+    /// let mut current_value = atom_box.load();
+    /// let (old_value, new_value) = loop {
+    ///     let new_value = *current_value + 1;
+    ///     match atom_box.compare_exchange_and_load(current_value, new_value) {
+    ///         Ok(values) => break values,
+    ///         Err(value) => current_value = value,
+    ///     }
+    /// };
+    /// assert_eq!(*old_value, 0);
+    /// assert_eq!(*new_value, 1);
+    /// ```
+    pub fn compare_exchange_and_load(
+        &self,
+        current_value: LoadGuard<'domain, T, DOMAIN_ID, P>,
+        new_value: T,
+    ) -> CompareExchangeAndLoadResult<'domain, T, DOMAIN_ID, P> {
+        let new_ptr = Box::into_raw(Box::new(new_value));
+        let new_haz_ptr = self.domain.acquire();
+        // The new value is not shared until the exchange succeeds, so it cannot have been retired
+        // before it is protected.
+        self.domain.protect(&new_haz_ptr, new_ptr);
+        match self.ptr.compare_exchange(
+            current_value.ptr as *mut T,
+            new_ptr,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(ptr) => Ok((
+                StoreGuard {
+                    ptr,
+                    domain: self.domain,
+                    retire_policy: self.retire_policy,
+                },
+                LoadGuard {
+                    ptr: new_ptr,
+                    domain: self.domain,
+                    haz_ptr: Some(new_haz_ptr),
+                },
+            )),
+            Err(_) => {
+                // # Safety
+                //
+                // The new value was never shared so we still have exclusive ownership.
+                drop(unsafe { Box::from_raw(new_ptr) });
+                // The hazard pointer is reused to protect the value which caused the failure, or
+                // one which has replaced it since.
+                let ptr = self.protect(&new_haz_ptr);
+                Err(LoadGuard {
+                    ptr,
+                    domain: self.domain,
+                    haz_ptr: Some(new_haz_ptr),
+                })
+            }
+        }
+    }
+
+    /// Stores a value into the `AtomBox` if its current value equals `current_value`.
+    ///
+    /// The return value is a result indicating whether the new value was written.
+    /// On success, this value is guaranteed to be equal to `current_value` and the return value is
+    /// a StoreGuard which dereferences to the old value.
+    /// On failure, the `Err` contains a LoadGaurd which dereferences to the `current_value`.
+    ///
+    /// **Note:** This method is only available on platforms that support atomic operations on
+    /// pointers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the guard is associated with a different domain.
+    ///
+    /// # example
+    /// ```
+    /// use atom_box::AtomBox;
+    ///
+    /// let atom_box1 = AtomBox::new(0);
+    /// let atom_box2 = AtomBox::new(1);
+    ///
+    /// let mut guard = atom_box2.swap(2);
+    /// let mut current_value = atom_box1.load();
+    /// let _ = loop {
+    ///     match atom_box1.compare_exchange_from_guard(current_value, guard) {
+    ///         Ok(value) => {
+    ///             break value;
+    ///         }
+    ///         Err((value, returned_guard)) => {
     ///             current_value = value;
     ///             guard = returned_guard;
     ///         }
@@ -478,12 +1161,12 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     /// The following example will fail to compile.
     ///
     /// ```compile_fail
-    /// use atom_box::{AtomBox, domain::{Domain, Reclaimstrategy}};
+    /// use atom_box::{AtomBox, AtomBoxIn, domain::{Domain, Reclaimstrategy}};
     ///
     /// const custom_domain_id: usize = 42;
     /// static custom_domain: domain<custom_domain_id> = domain::new(reclaimstrategy::eager);
     ///
-    /// let atom_box1 = AtomBox::new_with_domain("hello", &custom_domain);
+    /// let atom_box1 = AtomBoxIn::new_with_domain("hello", &custom_domain);
     /// let atom_box2 = AtomBox::new("world");
     ///
     /// let guard = atom_box1.swap("bye bye");
@@ -492,19 +1175,10 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     /// ```
     pub fn compare_exchange_from_guard(
         &self,
-        current_value: LoadGuard<'domain, T, DOMAIN_ID>,
-        new_value: StoreGuard<'domain, T, DOMAIN_ID>,
-    ) -> Result<
-        StoreGuard<'domain, T, DOMAIN_ID>,
-        (
-            LoadGuard<'domain, T, DOMAIN_ID>,
-            StoreGuard<'domain, T, DOMAIN_ID>,
-        ),
-    > {
-        assert!(
-            core::ptr::eq(new_value.domain, self.domain),
-            "Cannot use guarded value from different domain"
-        );
+        current_value: LoadGuard<'domain, T, DOMAIN_ID, P>,
+        new_value: StoreGuard<'domain, T, DOMAIN_ID, P>,
+    ) -> CompareExchangeFromGuardResult<'domain, T, DOMAIN_ID, P> {
+        assert_same_domain(new_value.domain, self.domain);
 
         let new_ptr = new_value.ptr;
         match self.ptr.compare_exchange(
@@ -518,6 +1192,7 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
                 Ok(StoreGuard {
                     ptr,
                     domain: self.domain,
+                    retire_policy: self.retire_policy,
                 })
             }
             Err(ptr) => Err((
@@ -567,9 +1242,9 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     /// ```
     pub fn compare_exchange_weak(
         &self,
-        current_value: LoadGuard<'domain, T, DOMAIN_ID>,
+        current_value: LoadGuard<'domain, T, DOMAIN_ID, P>,
         new_value: T,
-    ) -> Result<StoreGuard<'domain, T, DOMAIN_ID>, LoadGuard<'domain, T, DOMAIN_ID>> {
+    ) -> Result<StoreGuard<'domain, T, DOMAIN_ID, P>, LoadGuard<'domain, T, DOMAIN_ID, P>> {
         let new_ptr = Box::into_raw(Box::new(new_value));
         match self.ptr.compare_exchange_weak(
             current_value.ptr as *mut T,
@@ -580,6 +1255,7 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
             Ok(ptr) => Ok(StoreGuard {
                 ptr,
                 domain: self.domain,
+                retire_policy: self.retire_policy,
             }),
             Err(ptr) => Err(LoadGuard {
                 ptr,
@@ -633,12 +1309,12 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     /// The following example will fail to compile.
     ///
     /// ```compile_fail
-    /// use atom_box::{AtomBox, domain::{Domain, ReclaimStrategy}};
+    /// use atom_box::{AtomBox, AtomBoxIn, domain::{Domain, ReclaimStrategy}};
     ///
     /// const custom_domain_id: usize = 42;
     /// static custom_domain: Domain<custom_domain_id> = Domain::new(ReclaimStrategy::Eager);
     ///
-    /// let atom_box1 = AtomBox::new_with_domain("hello", &custom_domain);
+    /// let atom_box1 = AtomBoxIn::new_with_domain("hello", &custom_domain);
     /// let atom_box2 = AtomBox::new("world");
     ///
     /// let guard = atom_box1.swap("bye bye");
@@ -647,19 +1323,10 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     /// ```
     pub fn compare_exchange_weak_from_guard(
         &self,
-        current_value: LoadGuard<'domain, T, DOMAIN_ID>,
-        new_value: StoreGuard<'domain, T, DOMAIN_ID>,
-    ) -> Result<
-        StoreGuard<'domain, T, DOMAIN_ID>,
-        (
-            LoadGuard<'domain, T, DOMAIN_ID>,
-            StoreGuard<'domain, T, DOMAIN_ID>,
-        ),
-    > {
-        assert!(
-            core::ptr::eq(new_value.domain, self.domain),
-            "Cannot use guarded value from different domain"
-        );
+        current_value: LoadGuard<'domain, T, DOMAIN_ID, P>,
+        new_value: StoreGuard<'domain, T, DOMAIN_ID, P>,
+    ) -> CompareExchangeFromGuardResult<'domain, T, DOMAIN_ID, P> {
+        assert_same_domain(new_value.domain, self.domain);
 
         let new_ptr = new_value.ptr;
         match self.ptr.compare_exchange_weak(
@@ -673,6 +1340,7 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
                 Ok(StoreGuard {
                     ptr,
                     domain: self.domain,
+                    retire_policy: self.retire_policy,
                 })
             }
             Err(ptr) => Err((
@@ -687,7 +1355,233 @@ impl<'domain, T, const DOMAIN_ID: usize> AtomBox<'domain, T, DOMAIN_ID> {
     }
 }
 
-impl<'domain, T, const DOMAIN_ID: usize> Drop for AtomBox<'domain, T, DOMAIN_ID> {
+impl<'domain, T, const DOMAIN_ID: usize> AtomBoxIn<'domain, T, DOMAIN_ID> {
+    /// Creates a new `AtomBox` and assoicates it with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::Domain, domain::ReclaimStrategy};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain("Hello World", &CUSTOM_DOMAIN);
+    /// assert_eq!(*atom_box.load(), "Hello World");
+    /// ```
+    pub fn new_with_domain(value: T, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self::new_with_protection(value, domain)
+    }
+
+    /// Creates a new `AtomBox` and associates it with the given domain, returning the value in an
+    /// [`AllocError`] rather than aborting if it cannot be allocated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let atom_box = AtomBoxIn::try_new_with_domain("Hello", &CUSTOM_DOMAIN).expect("Out of memory");
+    /// assert_eq!(*atom_box.load(), "Hello");
+    /// ```
+    pub fn try_new_with_domain(
+        value: T,
+        domain: &'domain Domain<DOMAIN_ID>,
+    ) -> Result<Self, AllocError<T>> {
+        let ptr = AtomicPtr::new(alloc_error::try_box(value)?);
+        #[cfg(feature = "leak-audit")]
+        leak_audit::track(
+            leak_audit::AllocationKind::Stored,
+            core::mem::size_of::<T>(),
+        );
+        Ok(Self {
+            ptr,
+            domain,
+            retire_policy: RetirePolicy::Domain,
+        })
+    }
+
+    /// Attempts to load the value stored in the `AtomBox` without allocating or blocking.
+    ///
+    /// Unlike [`AtomBox::load`], this never allocates a hazard pointer and makes a bounded number
+    /// of attempts to protect the value. Returns `None` if every hazard pointer reserved in the
+    /// domain is in use, or if the value was replaced during every attempt to protect it.
+    ///
+    /// # Interrupt safety
+    ///
+    /// Together with dropping the returned `LoadGuard`, this is the only operation on an `AtomBox`
+    /// which is safe to call from an interrupt handler. Every other operation may allocate, either
+    /// to store a new value or to retire an old one. Hazard pointers should be reserved up front
+    /// with [`Domain::reserve_hazard_pointers`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain("Hello World", &CUSTOM_DOMAIN);
+    /// assert!(atom_box.try_load().is_none(), "No hazard pointers have been reserved");
+    ///
+    /// CUSTOM_DOMAIN.reserve_hazard_pointers(1);
+    /// assert_eq!(*atom_box.try_load().unwrap(), "Hello World");
+    /// ```
+    pub fn try_load(&self) -> Option<LoadGuard<'domain, T, DOMAIN_ID>> {
+        if !domain::needs_reclaim::<T>() {
+            return Some(self.unprotected_load());
+        }
+        let haz_ptr = self.domain.try_acquire_haz_ptr()?;
+        match haz_ptr.try_protect_ptr(&self.ptr, TRY_LOAD_ATTEMPTS) {
+            Some(ptr) => Some(LoadGuard {
+                ptr,
+                domain: self.domain,
+                haz_ptr: Some(haz_ptr),
+            }),
+            None => {
+                self.domain.release_hazard_ptr(haz_ptr);
+                None
+            }
+        }
+    }
+
+    /// Stores a new value in the `AtomBox`, then waits for the previous value to stop being
+    /// protected by any hazard pointer and returns it by value, rather than retiring it.
+    ///
+    /// The calling thread yields while it waits. If the previous value is still protected once
+    /// `timeout` has elapsed, the `Err` hands back a `StoreGuard` to it, which retires it as
+    /// usual when dropped.
+    ///
+    /// Callbacks registered for the previous value with
+    /// [`notify_on_reclaim`](domain::Domain::notify_on_reclaim) are invoked before it is returned,
+    /// as no reader can access it any more.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::AtomBox;
+    /// use std::time::Duration;
+    ///
+    /// let connection = AtomBox::new(String::from("primary"));
+    ///
+    /// let previous = connection.replace_owned(String::from("replica"), Duration::from_secs(1));
+    /// assert_eq!(previous.ok().as_deref(), Some("primary"));
+    ///
+    /// let reader = connection.load();
+    /// let still_read = connection.replace_owned(String::from("primary"), Duration::ZERO);
+    /// assert_eq!(*still_read.unwrap_err(), "replica");
+    /// # drop(reader);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn replace_owned(
+        &self,
+        value: T,
+        timeout: core::time::Duration,
+    ) -> Result<T, StoreGuard<'domain, T, DOMAIN_ID>> {
+        let previous = self.swap(value);
+        let deadline = std::time::Instant::now() + timeout;
+        while self.domain.is_protected(previous.ptr as *mut T) {
+            if std::time::Instant::now() >= deadline {
+                return Err(previous);
+            }
+            std::thread::yield_now();
+        }
+        let previous = core::mem::ManuallyDrop::new(previous);
+        // # Safety
+        //
+        // The value was created via `Box::into_raw`, and is no longer reachable from the box. It
+        // is not protected by any hazard pointer, and a reader which protects it now would find
+        // it has been replaced before using it. The store guard is not dropped, so the value is
+        // never retired.
+        let value = *unsafe { Box::from_raw(previous.ptr as *mut T) };
+        self.domain.notify_released(previous.ptr);
+        Ok(value)
+    }
+
+    /// Moves the `AtomBox` to another domain, keeping its current value.
+    ///
+    /// Values this `AtomBox` has already retired are reclaimed by its current domain, and values
+    /// it retires after the move are reclaimed by `domain`. Readers which loaded the current value
+    /// from the current domain may still be using it, so the calling thread yields until the
+    /// value is no longer protected by the current domain. If it is still protected once
+    /// `timeout` has elapsed, the `Err` hands back the `AtomBox` unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    /// use std::time::Duration;
+    ///
+    /// const BUSY_TENANT_ID: usize = 42;
+    /// static BUSY_TENANT: Domain<BUSY_TENANT_ID> = Domain::new(ReclaimStrategy::Manual);
+    /// const QUIET_TENANT_ID: usize = 43;
+    /// static QUIET_TENANT: Domain<QUIET_TENANT_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let settings = AtomBoxIn::new_with_domain(String::from("v1"), &BUSY_TENANT);
+    /// settings.store(String::from("v2"));
+    ///
+    /// let settings = settings
+    ///     .migrate_to(&QUIET_TENANT, Duration::from_secs(1))
+    ///     .expect("No reader holds the current value");
+    /// settings.store(String::from("v3"));
+    ///
+    /// assert_eq!(*settings.load(), "v3");
+    /// assert_eq!(BUSY_TENANT.reclaim(), 1, "\"v1\" was retired before the move");
+    /// assert_eq!(QUIET_TENANT.reclaim(), 1, "\"v2\" was retired after the move");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn migrate_to<'new_domain, const NEW_DOMAIN_ID: usize>(
+        self,
+        domain: &'new_domain Domain<NEW_DOMAIN_ID>,
+        timeout: core::time::Duration,
+    ) -> Result<AtomBoxIn<'new_domain, T, NEW_DOMAIN_ID>, Self> {
+        // The box is owned, so no load or update can be in flight, and the pointer cannot be a
+        // descriptor.
+        let ptr = self.ptr.load(Ordering::Acquire);
+        let deadline = std::time::Instant::now() + timeout;
+        while domain::needs_reclaim::<T>() && self.domain.is_protected(ptr) {
+            if std::time::Instant::now() >= deadline {
+                return Err(self);
+            }
+            std::thread::yield_now();
+        }
+        // The value is no longer protected by the old domain, and it can no longer be loaded from
+        // it, so only the new domain's hazard pointers can protect it from now on. The old box is
+        // not dropped, so the value is only retired once, by the new box.
+        let atom_box = core::mem::ManuallyDrop::new(self);
+        Ok(AtomBoxIn {
+            ptr: AtomicPtr::new(ptr),
+            domain,
+            retire_policy: atom_box.retire_policy,
+        })
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize, P: Protection> AtomBoxIn<'domain, T, DOMAIN_ID, P> {
+    /// Loads a zero sized value which needs no protection.
+    ///
+    /// Every such value is equivalent, and is never reclaimed.
+    fn unprotected_load(&self) -> LoadGuard<'domain, T, DOMAIN_ID, P> {
+        LoadGuard {
+            ptr: core::ptr::NonNull::dangling().as_ptr(),
+            domain: self.domain,
+            haz_ptr: None,
+        }
+    }
+
+    /// Protects the current value with `haz_ptr`.
+    fn protect(&self, haz_ptr: &P::Guard<'domain>) -> *mut T {
+        self.domain.protect_ptr(haz_ptr, &self.ptr)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize, P: Protection> Drop
+    for AtomBoxIn<'domain, T, DOMAIN_ID, P>
+{
     fn drop(&mut self) {
         // # Safety
         //
@@ -700,7 +1594,12 @@ impl<'domain, T, const DOMAIN_ID: usize> Drop for AtomBox<'domain, T, DOMAIN_ID>
         // We are safe to flag it for retire, where it will be reclaimed when it is no longer
         // protected by any hazard pointers.
         let ptr = self.ptr.load(Ordering::Relaxed);
-        unsafe { self.domain.retire(ptr) };
+        #[cfg(feature = "leak-audit")]
+        leak_audit::untrack(
+            leak_audit::AllocationKind::Stored,
+            core::mem::size_of::<T>(),
+        );
+        unsafe { self.domain.retire_with_policy(ptr, self.retire_policy) };
     }
 }
 
@@ -710,12 +1609,58 @@ impl<'domain, T, const DOMAIN_ID: usize> Drop for AtomBox<'domain, T, DOMAIN_ID>
 /// `from_guard` methods to store this value in an `AtomBox` associated with the same domain.
 ///
 /// Dereferences to the value.
-pub struct StoreGuard<'domain, T, const DOMAIN_ID: usize> {
+pub struct StoreGuard<
+    'domain,
+    T,
+    const DOMAIN_ID: usize,
+    P: Protection + 'domain = Domain<DOMAIN_ID>,
+> {
     ptr: *const T,
-    domain: &'domain Domain<DOMAIN_ID>,
+    domain: &'domain P,
+    retire_policy: RetirePolicy,
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> StoreGuard<'domain, T, DOMAIN_ID> {
+    /// Registers `callback` to be invoked once the value has been reclaimed, and its memory
+    /// freed.
+    ///
+    /// The callback is invoked exactly once, by whichever thread reclaims the value, after the
+    /// last guard referencing it has been dropped. If the value is stored again, for example with
+    /// [`AtomBoxIn::store_from_guard`], the callback waits until it is replaced and reclaimed
+    /// once more. See [`Domain::notify_on_reclaim`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let quota_used = Arc::new(AtomicUsize::new(1024));
+    /// let buffer = AtomBoxIn::new_with_domain(vec![0_u8; 1024], &CUSTOM_DOMAIN);
+    /// let reader = buffer.load();
+    ///
+    /// let released = quota_used.clone();
+    /// buffer.swap(vec![0_u8; 512]).on_reclaim(move || {
+    ///     released.fetch_sub(1024, Ordering::SeqCst);
+    /// });
+    /// CUSTOM_DOMAIN.reclaim();
+    /// assert_eq!(quota_used.load(Ordering::SeqCst), 1024, "Still being read");
+    ///
+    /// drop(reader);
+    /// CUSTOM_DOMAIN.reclaim();
+    /// assert_eq!(quota_used.load(Ordering::SeqCst), 0);
+    /// ```
+    pub fn on_reclaim(self, callback: impl FnOnce() + Send + 'static) -> Self {
+        self.domain.notify_on_reclaim(self.ptr, callback);
+        self
+    }
 }
 
-impl<T, const DOMAIN_ID: usize> Deref for StoreGuard<'_, T, DOMAIN_ID> {
+impl<T, const DOMAIN_ID: usize, P: Protection> Deref for StoreGuard<'_, T, DOMAIN_ID, P> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
         // # Safety
@@ -727,7 +1672,7 @@ impl<T, const DOMAIN_ID: usize> Deref for StoreGuard<'_, T, DOMAIN_ID> {
     }
 }
 
-impl<T, const DOMAIN_ID: usize> Drop for StoreGuard<'_, T, DOMAIN_ID> {
+impl<T, const DOMAIN_ID: usize, P: Protection> Drop for StoreGuard<'_, T, DOMAIN_ID, P> {
     fn drop(&mut self) {
         // # Safety
         //
@@ -738,7 +1683,10 @@ impl<T, const DOMAIN_ID: usize> Drop for StoreGuard<'_, T, DOMAIN_ID> {
         // via hazard pointers.
         // We are safe to flag it for retire, where it will be reclaimed when it is no longer
         // protected by any hazard pointers.
-        unsafe { self.domain.retire(self.ptr as *mut T) };
+        unsafe {
+            self.domain
+                .retire_with_policy(self.ptr as *mut T, self.retire_policy)
+        };
     }
 }
 
@@ -749,21 +1697,26 @@ impl<T, const DOMAIN_ID: usize> Drop for StoreGuard<'_, T, DOMAIN_ID> {
 /// The value is guaranteed not to be dropped before this guard is dropped.
 ///
 /// Dereferences to the value.
-pub struct LoadGuard<'domain, T, const DOMAIN_ID: usize> {
+pub struct LoadGuard<
+    'domain,
+    T,
+    const DOMAIN_ID: usize,
+    P: Protection + 'domain = Domain<DOMAIN_ID>,
+> {
     ptr: *const T,
-    domain: &'domain Domain<DOMAIN_ID>,
-    haz_ptr: Option<HazardPointer<'domain>>,
+    domain: &'domain P,
+    haz_ptr: Option<P::Guard<'domain>>,
 }
 
-impl<T, const DOMAIN_ID: usize> Drop for LoadGuard<'_, T, DOMAIN_ID> {
+impl<T, const DOMAIN_ID: usize, P: Protection> Drop for LoadGuard<'_, T, DOMAIN_ID, P> {
     fn drop(&mut self) {
         if let Some(haz_ptr) = self.haz_ptr.take() {
-            self.domain.release_hazard_ptr(haz_ptr);
+            self.domain.release(haz_ptr);
         }
     }
 }
 
-impl<T, const DOMAIN_ID: usize> Deref for LoadGuard<'_, T, DOMAIN_ID> {
+impl<T, const DOMAIN_ID: usize, P: Protection> Deref for LoadGuard<'_, T, DOMAIN_ID, P> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
         // # Safety
@@ -775,31 +1728,73 @@ impl<T, const DOMAIN_ID: usize> Deref for LoadGuard<'_, T, DOMAIN_ID> {
     }
 }
 
+#[cfg(feature = "defmt")]
+impl<T: defmt::Format, const DOMAIN_ID: usize, P: Protection> defmt::Format
+    for LoadGuard<'_, T, DOMAIN_ID, P>
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::Format::format(&**self, f)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: defmt::Format, const DOMAIN_ID: usize, P: Protection> defmt::Format
+    for StoreGuard<'_, T, DOMAIN_ID, P>
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::Format::format(&**self, f)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: defmt::Format, const DOMAIN_ID: usize, P: Protection> defmt::Format
+    for AtomBoxIn<'_, T, DOMAIN_ID, P>
+{
+    /// Formats the current value.
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "AtomBox({})", *self.load())
+    }
+}
+
 #[cfg(not(loom))]
 #[cfg(test)]
 mod test {
     use super::*;
-
-    pub(crate) use core::sync::atomic::AtomicUsize;
+    use crate::test_util::DropCounter;
+    use alloc::vec::Vec;
 
     static TEST_DOMAIN: domain::Domain<1> = Domain::new(domain::ReclaimStrategy::Eager);
 
-    struct DropTester<'a, T> {
-        drop_count: &'a AtomicUsize,
-        value: T,
-    }
+    fn assert_unwind_safe<T: core::panic::UnwindSafe + core::panic::RefUnwindSafe>() {}
 
-    impl<'a, T> Drop for DropTester<'a, T> {
-        fn drop(&mut self) {
-            self.drop_count.fetch_add(1, Ordering::AcqRel);
-        }
+    #[test]
+    fn boxes_guards_and_domains_are_unwind_safe() {
+        // An `AtomBox` is only updated by atomically publishing a fully constructed value, so a
+        // panicking update leaves the previous value in place.
+        assert_unwind_safe::<AtomBox<usize>>();
+        // Guards only give shared access to their value, and release their hazard pointer when
+        // dropped during unwinding.
+        assert_unwind_safe::<LoadGuard<'static, usize, SHARED_DOMAIN_ID>>();
+        assert_unwind_safe::<StoreGuard<'static, usize, SHARED_DOMAIN_ID>>();
+        // A domain which panics while reclaiming leaks the values it had not yet reclaimed.
+        assert_unwind_safe::<Domain<1>>();
+        assert_unwind_safe::<domain::RetireLink>();
     }
 
-    impl<'a, T> Deref for DropTester<'a, T> {
-        type Target = T;
-        fn deref(&self) -> &Self::Target {
-            &self.value
-        }
+    #[test]
+    fn box_is_usable_after_panicking_while_holding_a_guard() {
+        let drop_counter = DropCounter::new();
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &TEST_DOMAIN);
+
+        let result = std::panic::catch_unwind(|| {
+            let _guard = atom_box.load();
+            panic!("Panicked while holding a guard");
+        });
+        atom_box.store(drop_counter.track(2));
+
+        assert!(result.is_err(), "The closure panicked");
+        drop_counter.assert_drops(1);
+        assert_eq!(**atom_box.load(), 2, "The box can still be updated");
     }
 
     #[test]
@@ -842,19 +1837,12 @@ mod test {
 
     #[test]
     fn drop_test() {
-        let drop_count = AtomicUsize::new(0);
-        let value = DropTester {
-            drop_count: &drop_count,
-            value: 20,
-        };
-        let atom_box = AtomBox::new_with_domain(value, &TEST_DOMAIN);
+        let drop_counter = DropCounter::new();
+        let value = drop_counter.track(20);
+        let atom_box = AtomBoxIn::new_with_domain(value, &TEST_DOMAIN);
 
         let value = atom_box.load();
-        assert_eq!(
-            drop_count.load(Ordering::Acquire),
-            0,
-            "No values have been dropped yet"
-        );
+        assert_eq!(drop_counter.count(), 0, "No values have been dropped yet");
         assert_eq!(**value, 20, "The correct value is returned via load");
         assert_eq!(
             value.ptr as *mut usize,
@@ -864,10 +1852,7 @@ mod test {
 
         {
             // Immediately retire the original value
-            let guard = atom_box.swap(DropTester {
-                drop_count: &drop_count,
-                value: 30,
-            });
+            let guard = atom_box.swap(drop_counter.track(30));
             assert_eq!(guard.ptr, value.ptr, "When we swap the value we get back a guard that contains a pointer to the old value");
             let new_value = atom_box.load();
             assert_eq!(
@@ -877,46 +1862,237 @@ mod test {
             drop(guard);
         }
         assert_eq!(
-            drop_count.load(Ordering::SeqCst),
+            drop_counter.count(),
             0,
             "Value should not be dropped while there is an active reference to it"
         );
         assert_eq!(**value, 20, "We are still able to access the original value since we have been holding a load guard");
         drop(value);
-        let _ = atom_box.swap(DropTester {
-            drop_count: &drop_count,
-            value: 40,
-        });
+        let _ = atom_box.swap(drop_counter.track(40));
         let final_value = atom_box.load();
         assert_eq!(**final_value, 40, "The value has been updated");
         assert_eq!(
-            drop_count.load(Ordering::SeqCst),
+            drop_counter.count(),
             2,
             "Both of the old values should now be dropped"
         );
     }
 
+    #[test]
+    fn modify_returns_old_and_new_values() {
+        let drop_counter = DropCounter::new();
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &TEST_DOMAIN);
+
+        let (old_value, new_value) = atom_box.modify(|value| drop_counter.track(**value + 1));
+
+        assert_eq!(
+            **old_value, 1,
+            "The store guard contains the replaced value"
+        );
+        assert_eq!(
+            **new_value, 2,
+            "The load guard contains the installed value"
+        );
+        drop(old_value);
+        atom_box.store(drop_counter.track(3));
+        assert_eq!(
+            drop_counter.count(),
+            1,
+            "The installed value is protected by the load guard"
+        );
+        assert_eq!(**new_value, 2, "The installed value is still accessible");
+    }
+
+    #[test]
+    fn concurrent_modify_applies_every_update() {
+        let atom_box = AtomBoxIn::new_with_domain(0, &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        let (old_value, new_value) = atom_box.modify(|value| value + 1);
+                        assert_eq!(*new_value, *old_value + 1, "Each transition is observed");
+                    }
+                });
+            }
+        });
+
+        assert_eq!(*atom_box.load(), 400, "No updates are lost");
+    }
+
+    #[test]
+    fn concurrent_make_mut_applies_every_update() {
+        let atom_box = AtomBoxIn::new_with_domain(Vec::new(), &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let atom_box = &atom_box;
+                scope.spawn(move || {
+                    for i in 0..100 {
+                        let old_value = atom_box.make_mut(|value| value.push(thread * 100 + i));
+                        assert!(!old_value.contains(&(thread * 100 + i)));
+                    }
+                });
+            }
+        });
+
+        let mut values = atom_box.load().clone();
+        values.sort_unstable();
+        assert_eq!(values, (0..400).collect::<Vec<_>>(), "No updates are lost");
+    }
+
+    #[test]
+    fn reload_moves_the_guards_protection_to_the_current_value() {
+        let drop_counter = DropCounter::new();
+        let domain: Domain<31> = Domain::new(domain::ReclaimStrategy::Eager);
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &domain);
+        let other_box = AtomBoxIn::new_with_domain(drop_counter.track(10), &domain);
+        let mut guard = atom_box.load();
+
+        atom_box.store(drop_counter.track(2));
+        let drops_before_reload = drop_counter.count();
+        atom_box.reload(&mut guard);
+        domain.reclaim();
+        let drops_after_reload = drop_counter.count();
+        other_box.reload(&mut guard);
+        other_box.store(drop_counter.track(11));
+        domain.reclaim();
+
+        assert_eq!(drops_before_reload, 0, "The guard protects the old value");
+        assert_eq!(
+            drops_after_reload, 1,
+            "The old value is no longer protected"
+        );
+        assert_eq!(**guard, 10, "The guard protects the other box's value");
+        assert_eq!(
+            drop_counter.count(),
+            1,
+            "The other box's value is protected"
+        );
+    }
+
+    #[test]
+    fn is_current_reports_whether_a_snapshot_is_stale() {
+        let atom_box = AtomBoxIn::new_with_domain(1, &TEST_DOMAIN);
+        let zero_sized = AtomBoxIn::new_with_domain((), &TEST_DOMAIN);
+        let value = atom_box.load();
+        let unit = zero_sized.load();
+
+        assert!(atom_box.is_current(&value));
+        atom_box.store(2);
+        zero_sized.store(());
+
+        assert!(!atom_box.is_current(&value), "The value has been replaced");
+        assert!(
+            zero_sized.is_current(&unit),
+            "Zero-sized values share one address"
+        );
+    }
+
+    #[test]
+    fn current_ptr_eq_detects_replaced_values() {
+        let atom_box = AtomBoxIn::new_with_domain(1, &TEST_DOMAIN);
+        let other_box = AtomBoxIn::new_with_domain(1, &TEST_DOMAIN);
+        let value = atom_box.load();
+
+        let unchanged = atom_box.current_ptr_eq(&value);
+        atom_box.store(1);
+        let replaced = atom_box.current_ptr_eq(&value);
+
+        assert!(unchanged, "The box still holds the loaded value");
+        assert!(
+            !replaced,
+            "An equal value stored in its place is a different value"
+        );
+        assert!(
+            atom_box.ptr_eq(&atom_box),
+            "A box holds the same value as itself"
+        );
+        assert!(
+            !atom_box.ptr_eq(&other_box),
+            "Distinct boxes hold distinct values"
+        );
+    }
+
+    #[test]
+    fn compare_exchange_if_only_stores_when_predicate_holds() {
+        let atom_box = AtomBoxIn::new_with_domain(5, &TEST_DOMAIN);
+
+        let rejected = atom_box.compare_exchange_if(|value| *value > 5, 1);
+        let accepted = atom_box.compare_exchange_if(|value| *value == 5, 6);
+
+        match rejected {
+            Ok(_) => panic!("Predicate should not hold"),
+            Err((current_value, new_value)) => {
+                assert_eq!(*current_value, 5, "Err contains the rejected value");
+                assert_eq!(new_value, 1, "Err hands back the new value");
+            }
+        }
+        match accepted {
+            Ok(old_value) => assert_eq!(*old_value, 5, "Ok contains the replaced value"),
+            Err(_) => panic!("Predicate should hold"),
+        }
+        assert_eq!(*atom_box.load(), 6, "The new value is stored");
+    }
+
+    #[test]
+    fn compare_exchange_and_load_protects_the_installed_value() {
+        let drop_counter = DropCounter::new();
+        let domain: Domain<7> = Domain::new(domain::ReclaimStrategy::Manual);
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &domain);
+        let stale_value = atom_box.load();
+        let current_value = atom_box.load();
+
+        let (old_value, new_value) = atom_box
+            .compare_exchange_and_load(current_value, drop_counter.track(2))
+            .unwrap_or_else(|_| panic!("Exchange should succeed"));
+        let rejected = atom_box.compare_exchange_and_load(stale_value, drop_counter.track(3));
+        drop(old_value);
+        atom_box.store(drop_counter.track(4));
+        domain.reclaim();
+
+        match rejected {
+            Ok(_) => panic!("Exchange against a replaced value should fail"),
+            Err(current_value) => assert_eq!(**current_value, 2, "Err contains the current value"),
+        }
+        drop_counter.assert_drops(2);
+        assert_eq!(**new_value, 2, "Guard still references the installed value");
+        drop(new_value);
+        domain.reclaim();
+        drop_counter.assert_drops(3);
+    }
+
+    #[test]
+    fn concurrent_compare_exchange_if_keeps_maximum() {
+        let atom_box = AtomBoxIn::new_with_domain(0, &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let atom_box = &atom_box;
+                scope.spawn(move || {
+                    for value in (thread..1000).step_by(4) {
+                        let _ = atom_box.compare_exchange_if(|current| *current < value, value);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(*atom_box.load(), 999, "The largest value wins");
+    }
+
     #[test]
     fn swap_from_gaurd_test() {
-        let drop_count = AtomicUsize::new(0);
-        let drop_count_for_placeholder = AtomicUsize::new(0);
-        let value1 = DropTester {
-            drop_count: &drop_count,
-            value: 10,
-        };
-        let value2 = DropTester {
-            drop_count: &drop_count,
-            value: 20,
-        };
-        let atom_box1 = AtomBox::new_with_domain(value1, &TEST_DOMAIN);
-        let atom_box2 = AtomBox::new_with_domain(value2, &TEST_DOMAIN);
+        let drop_counter = DropCounter::new();
+        let placeholder_drop_counter = DropCounter::new();
+        let value1 = drop_counter.track(10);
+        let value2 = drop_counter.track(20);
+        let atom_box1 = AtomBoxIn::new_with_domain(value1, &TEST_DOMAIN);
+        let atom_box2 = AtomBoxIn::new_with_domain(value2, &TEST_DOMAIN);
 
         {
             // Immediately retire the original value
-            let guard1 = atom_box1.swap(DropTester {
-                drop_count: &drop_count_for_placeholder,
-                value: 30,
-            });
+            let guard1 = atom_box1.swap(placeholder_drop_counter.track(30));
             let guard2 = atom_box2.swap_from_guard(guard1);
             let _ = atom_box1.swap_from_guard(guard2);
             let new_value1 = atom_box1.load();
@@ -931,14 +2107,151 @@ mod test {
             );
         }
         assert_eq!(
-            drop_count_for_placeholder.load(Ordering::Acquire),
+            placeholder_drop_counter.count(),
             1,
             "The placeholder value should have been dropped"
         );
         assert_eq!(
-            drop_count.load(Ordering::Acquire),
+            drop_counter.count(),
             0,
             "Neither of the initial values should have been dropped"
         );
     }
+
+    #[test]
+    #[should_panic(
+        expected = "Cannot use guarded value from different domain (guard from domain `cache`, box in an unnamed domain)"
+    )]
+    fn cross_domain_panic_names_the_domains() {
+        let named_domain: Domain<1> = Domain::new_named("cache", domain::ReclaimStrategy::Eager);
+        let atom_box1 = AtomBoxIn::new_with_domain(1, &named_domain);
+        let atom_box2 = AtomBoxIn::new_with_domain(2, &TEST_DOMAIN);
+
+        atom_box2.store_from_guard(atom_box1.swap(3));
+    }
+
+    #[test]
+    fn immediate_retire_policy_frees_unprotected_values() {
+        let drop_counter = DropCounter::new();
+        let manual_domain: Domain<2> = Domain::new(domain::ReclaimStrategy::Manual);
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &manual_domain)
+            .with_retire_policy(RetirePolicy::Immediate);
+        let guard = atom_box.load();
+
+        atom_box.store(drop_counter.track(2));
+        let drops_while_protected = drop_counter.count();
+        drop(guard);
+        atom_box.store(drop_counter.track(3));
+
+        assert_eq!(drops_while_protected, 0, "A protected value is retired");
+        assert_eq!(drop_counter.count(), 1, "An unprotected value is freed");
+        assert_eq!(manual_domain.reclaim(), 1);
+        drop_counter.assert_drops(2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn replace_owned_waits_for_readers() {
+        let drop_counter = DropCounter::new();
+        let manual_domain: Domain<3> = Domain::new(domain::ReclaimStrategy::Manual);
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &manual_domain);
+        let loaded = std::sync::Barrier::new(2);
+
+        let previous = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let guard = atom_box.load();
+                loaded.wait();
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                drop(guard);
+            });
+            loaded.wait();
+            atom_box.replace_owned(drop_counter.track(2), std::time::Duration::from_secs(60))
+        });
+
+        let previous = previous.ok().expect("The reader released the value");
+        assert_eq!(*previous, 1);
+        assert_eq!(manual_domain.reclaim(), 0, "The value is not retired");
+        drop_counter.assert_drops(0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn replace_owned_invokes_reclaim_callbacks() {
+        static MANUAL_DOMAIN: Domain<8> = Domain::new(domain::ReclaimStrategy::Manual);
+        let atom_box = AtomBoxIn::new_with_domain(1, &MANUAL_DOMAIN);
+        let notified = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let notify = notified.clone();
+        let guard = atom_box.load();
+        MANUAL_DOMAIN.notify_on_reclaim(&*guard, move || notify.store(true, Ordering::SeqCst));
+        drop(guard);
+
+        let previous = atom_box.replace_owned(2, std::time::Duration::from_secs(60));
+
+        assert_eq!(previous.ok(), Some(1));
+        assert!(
+            notified.load(Ordering::SeqCst),
+            "The callback is invoked once no reader can access the value"
+        );
+        assert_eq!(MANUAL_DOMAIN.reclaim(), 0, "The value is not retired");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn migrate_to_waits_for_readers_of_the_old_domain() {
+        let drop_counter = DropCounter::new();
+        let old_domain: Domain<5> = Domain::new(domain::ReclaimStrategy::Manual);
+        let new_domain: Domain<6> = Domain::new(domain::ReclaimStrategy::Manual);
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &old_domain);
+        let guard = atom_box.load();
+
+        let atom_box = atom_box
+            .migrate_to(&new_domain, std::time::Duration::ZERO)
+            .expect_err("The current value is still being read");
+        drop(guard);
+        let atom_box = atom_box
+            .migrate_to(&new_domain, std::time::Duration::ZERO)
+            .expect("The reader released the value");
+        atom_box.store(drop_counter.track(2));
+
+        assert_eq!(
+            old_domain.reclaim(),
+            0,
+            "Nothing was retired before the move"
+        );
+        assert_eq!(
+            new_domain.reclaim(),
+            1,
+            "The replaced value is retired to the new domain"
+        );
+        drop_counter.assert_drops(1);
+        drop(atom_box);
+        new_domain.reclaim();
+        drop_counter.assert_drops(2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn on_reclaim_waits_for_values_stored_again() {
+        let manual_domain: Domain<4> = Domain::new(domain::ReclaimStrategy::Manual);
+        let atom_box1 = AtomBoxIn::new_with_domain(1, &manual_domain);
+        let atom_box2 = AtomBoxIn::new_with_domain(2, &manual_domain);
+        let reclaimed = std::sync::Arc::new(core::sync::atomic::AtomicUsize::new(0));
+
+        let counter = reclaimed.clone();
+        let guard = atom_box1.swap(3).on_reclaim(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        atom_box2.store_from_guard(guard);
+        manual_domain.reclaim();
+        let reclaimed_while_stored = reclaimed.load(Ordering::SeqCst);
+        atom_box2.store(4);
+        manual_domain.reclaim();
+        manual_domain.reclaim();
+
+        assert_eq!(
+            reclaimed_while_stored, 0,
+            "The value is stored in another box"
+        );
+        assert_eq!(reclaimed.load(Ordering::SeqCst), 1, "Invoked exactly once");
+    }
 }
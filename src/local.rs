@@ -0,0 +1,144 @@
+//! Local
+//!
+//! A single-threaded counterpart to `AtomBox`, which needs neither atomics nor hazard pointers.
+
+use alloc::rc::Rc;
+use core::cell::UnsafeCell;
+use core::ops::Deref;
+
+/// A single-threaded box whose value can be replaced while it is being read.
+///
+/// `LocalAtomBox` has the same methods as [`AtomBox`](crate::AtomBox), and implements
+/// [`Atom`](crate::Atom), so code which is generic over a swappable box can use it where values
+/// are never shared between threads, such as in tests, on wasm or for per-thread state. Values
+/// are reference counted without atomics instead of being protected by hazard pointers, and a
+/// replaced value is dropped as soon as its last guard is dropped.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::LocalAtomBox;
+///
+/// let local_box = LocalAtomBox::new("Hello");
+/// let value = local_box.load();
+///
+/// let old_value = local_box.swap("World");
+/// assert_eq!(*value, "Hello");
+/// assert_eq!(*old_value, "Hello");
+/// assert_eq!(*local_box.load(), "World");
+/// ```
+pub struct LocalAtomBox<T> {
+    value: UnsafeCell<Rc<T>>,
+}
+
+impl<T> LocalAtomBox<T> {
+    /// Creates a new `LocalAtomBox` holding `value`.
+    pub fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(Rc::new(value)),
+        }
+    }
+
+    /// Loads the current value.
+    ///
+    /// Returns a `LocalGuard` which can be dereferenced into the value.
+    pub fn load(&self) -> LocalGuard<T> {
+        // # Safety
+        //
+        // The box is not `Sync`, and no reference to the cell's contents outlives a method call,
+        // so nothing else is accessing it. Cloning an `Rc` runs no user code.
+        LocalGuard(Rc::clone(unsafe { &*self.value.get() }))
+    }
+
+    /// Stores a new value, dropping the previous value unless it is still guarded.
+    pub fn store(&self, value: T) {
+        let _ = self.swap(value);
+    }
+
+    /// Stores a new value, returning a `LocalGuard` which dereferences into the previous value.
+    pub fn swap(&self, new_value: T) -> LocalGuard<T> {
+        LocalGuard(self.replace(Rc::new(new_value)))
+    }
+
+    /// Stores `new_value` if the box still holds the value `current_value` was loaded from.
+    ///
+    /// On success, returns a `LocalGuard` to the previous value. Otherwise, returns a `LocalGuard`
+    /// to the value the box holds and `new_value` is dropped.
+    pub fn compare_exchange(
+        &self,
+        current_value: LocalGuard<T>,
+        new_value: T,
+    ) -> Result<LocalGuard<T>, LocalGuard<T>> {
+        let actual_value = self.load();
+        if Rc::ptr_eq(&current_value.0, &actual_value.0) {
+            Ok(self.swap(new_value))
+        } else {
+            Err(actual_value)
+        }
+    }
+
+    fn replace(&self, new_value: Rc<T>) -> Rc<T> {
+        // # Safety
+        //
+        // The box is not `Sync`, and no reference to the cell's contents outlives a method call,
+        // so nothing else is accessing it. The previous value is returned rather than dropped
+        // here, so no user code runs while the contents are borrowed.
+        core::mem::replace(unsafe { &mut *self.value.get() }, new_value)
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for LocalAtomBox<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("LocalAtomBox").field(&*self.load()).finish()
+    }
+}
+
+/// Contains a reference to a value that was stored in a [`LocalAtomBox`].
+///
+/// The value is not dropped before this guard is dropped. Dereferences to the value.
+#[derive(Debug)]
+pub struct LocalGuard<T>(Rc<T>);
+
+impl<T> Deref for LocalGuard<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::DropCounter;
+
+    #[test]
+    fn replaced_values_are_dropped_with_their_last_guard() {
+        let drop_counter = DropCounter::new();
+        let local_box = LocalAtomBox::new(drop_counter.track(1));
+        let guard = local_box.load();
+
+        local_box.store(drop_counter.track(2));
+        let drops_while_guarded = drop_counter.count();
+        drop(guard);
+
+        assert_eq!(drops_while_guarded, 0, "The guard keeps the value alive");
+        drop_counter.assert_drops(1);
+        assert_eq!(**local_box.load(), 2);
+    }
+
+    #[test]
+    fn compare_exchange_fails_if_the_value_was_replaced() {
+        let local_box = LocalAtomBox::new(1);
+        let stale_value = local_box.load();
+        local_box.store(2);
+
+        let result = local_box.compare_exchange(stale_value, 3);
+        let current_value = local_box.load();
+        let retried = local_box.compare_exchange(current_value, 4);
+
+        assert_eq!(result.err().map(|value| *value), Some(2));
+        assert_eq!(retried.ok().map(|value| *value), Some(2));
+        assert_eq!(*local_box.load(), 4);
+    }
+}
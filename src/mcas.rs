@@ -0,0 +1,609 @@
+//! MCAS
+//!
+//! A descriptor based multi-word compare and swap, used to update several boxes atomically.
+//!
+//! Only [`McasAtomBox`]es take part in an update, so that the cost of recognising descriptors is
+//! only paid by boxes which opt in to it. A plain `AtomBox` never holds a descriptor.
+//!
+//! While an update is in progress, each box holds a tagged pointer to a shared descriptor in place
+//! of its value. The descriptor records the value each box held when it was installed, the value
+//! it should hold afterwards, and whether the update has succeeded. Readers which find a
+//! descriptor read through it, while writers which find one abort the update if it is undecided
+//! and replace the descriptor in their own box with its resolved value before retrying.
+//!
+//! Only the thread performing the update installs its descriptor, and other threads only ever
+//! remove it from the box they are operating on. This means no thread touches a box it does not
+//! hold a reference to.
+
+use crate::domain;
+use crate::domain::{Domain, HazardPointer};
+use crate::protection::Protection;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use crate::{AtomBoxIn, LoadGuard, StoreGuard};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+// The low bit marks a pointer to a descriptor rather than a value.
+const TAG: usize = 1;
+
+const UNDECIDED: usize = 0;
+const SUCCEEDED: usize = 1;
+const FAILED: usize = 2;
+
+struct Entry<T> {
+    target: *const AtomicPtr<T>,
+    // Only written by the owning thread before the descriptor is installed in the target.
+    expected: AtomicPtr<T>,
+    new: *mut T,
+}
+
+impl<T> Entry<T> {
+    fn resolve(&self, succeeded: bool) -> *mut T {
+        if succeeded {
+            self.new
+        } else {
+            self.expected.load(Ordering::SeqCst)
+        }
+    }
+}
+
+struct Descriptor<T> {
+    entries: Vec<Entry<T>>,
+    status: AtomicUsize,
+}
+
+impl<T> Descriptor<T> {
+    fn entry(&self, target: &AtomicPtr<T>) -> &Entry<T> {
+        self.entries
+            .iter()
+            .find(|entry| core::ptr::eq(entry.target, target))
+            .expect("Descriptor is installed in the target")
+    }
+
+    /// Aborts the update if it is still undecided, returning whether it succeeded.
+    fn decide(&self) -> bool {
+        match self
+            .status
+            .compare_exchange(UNDECIDED, FAILED, Ordering::SeqCst, Ordering::SeqCst)
+        {
+            Ok(_) => false,
+            Err(status) => status == SUCCEEDED,
+        }
+    }
+}
+
+/// Whether a box of `T` can hold a tagged descriptor pointer.
+///
+/// Values aligned to a single byte may have odd addresses, which would be mistaken for tagged
+/// pointers, so these never take part in an update.
+const fn supports_descriptors<T>() -> bool {
+    core::mem::align_of::<T>() > TAG
+}
+
+/// Returns whether `ptr`, loaded from an `AtomBox`, is a tagged descriptor pointer.
+fn is_descriptor<T>(ptr: *mut T) -> bool {
+    supports_descriptors::<T>() && ptr as usize & TAG == TAG
+}
+
+fn tag<T>(descriptor: *mut Descriptor<T>) -> *mut T {
+    (descriptor as *mut u8).wrapping_add(TAG) as *mut T
+}
+
+fn untag<T>(ptr: *mut T) -> *mut Descriptor<T> {
+    (ptr as *mut u8).wrapping_sub(TAG) as *mut Descriptor<T>
+}
+
+/// Protects the descriptor `tagged` points to, returning `None` if it is no longer installed in
+/// `target`.
+fn protect_descriptor<'a, 'h, P: Protection, T>(
+    domain: &'a P,
+    target: &AtomicPtr<T>,
+    tagged: *mut T,
+    guard: &'h P::Guard<'a>,
+) -> Option<&'h Descriptor<T>> {
+    domain.protect(guard, untag(tagged));
+    crate::sync::fence(Ordering::SeqCst);
+    if target.load(Ordering::Acquire) == tagged {
+        // # Safety
+        //
+        // The descriptor was still installed after it was protected, so it has not been retired.
+        Some(unsafe { &*untag(tagged) })
+    } else {
+        domain.reset(guard);
+        None
+    }
+}
+
+/// Replaces the descriptor `tagged` installed in `target` with its resolved value, aborting the
+/// update if it is undecided.
+///
+/// Leaves `guard` reset.
+fn help<'a, P: Protection, T>(
+    domain: &'a P,
+    target: &AtomicPtr<T>,
+    tagged: *mut T,
+    guard: &P::Guard<'a>,
+) {
+    if let Some(descriptor) = protect_descriptor(domain, target, tagged, guard) {
+        let succeeded = descriptor.decide();
+        let _ = target.compare_exchange(
+            tagged,
+            descriptor.entry(target).resolve(succeeded),
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
+    }
+    domain.reset(guard);
+}
+
+/// Reads the value of `target` through the descriptor `tagged`, without disturbing the update.
+///
+/// On success the returned value is protected by `guard`. Returns `None` if the descriptor was
+/// removed in the meantime, in which case the caller should load `target` again.
+fn read<'a, P: Protection, T>(
+    domain: &'a P,
+    target: &AtomicPtr<T>,
+    tagged: *mut T,
+    guard: &P::Guard<'a>,
+) -> Option<*mut T> {
+    let descriptor = protect_descriptor(domain, target, tagged, guard)?;
+    let value = descriptor
+        .entry(target)
+        .resolve(descriptor.status.load(Ordering::SeqCst) == SUCCEEDED);
+    domain.protect(guard, value);
+    crate::sync::fence(Ordering::SeqCst);
+    // Neither value recorded in the descriptor is retired while it is installed.
+    let current = target.load(Ordering::Acquire);
+    if current == tagged || current == value {
+        Some(value)
+    } else {
+        domain.reset(guard);
+        None
+    }
+}
+
+/// An `AtomBox` which can be updated atomically together with other boxes by [`mcas`].
+///
+/// While an update is in progress, the box holds a descriptor in place of its value. Loads read
+/// through it, and stores abort the update if it is undecided, so neither waits for the thread
+/// performing it.
+///
+/// Values aligned to a single byte cannot be stored in an `McasAtomBox`, as their addresses could
+/// be mistaken for descriptors. The following example will fail to compile.
+///
+/// ```compile_fail
+/// use atom_box::McasAtomBox;
+///
+/// let flag = McasAtomBox::new(true);
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{mcas, McasAtomBox};
+///
+/// let primary = McasAtomBox::new(String::from("eu-west"));
+/// let fallback = McasAtomBox::new(String::from("us-east"));
+///
+/// let previous = mcas([
+///     (&primary, String::from("us-east")),
+///     (&fallback, String::from("eu-west")),
+/// ]);
+/// assert_eq!(*previous[0], "eu-west");
+/// assert_eq!(*primary.load(), "us-east");
+/// ```
+pub struct McasAtomBox<'domain, T, const DOMAIN_ID: usize> {
+    atom_box: AtomBoxIn<'domain, T, DOMAIN_ID>,
+}
+
+impl<T, const DOMAIN_ID: usize> core::fmt::Debug for McasAtomBox<'_, T, DOMAIN_ID> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("McasAtomBox")
+            .field("domain", &self.atom_box.domain)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> McasAtomBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `McasAtomBox` associated with the shared (global) domain.
+    pub fn new(value: T) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> McasAtomBox<'domain, T, DOMAIN_ID> {
+    const ALIGNED: () = assert!(
+        supports_descriptors::<T>(),
+        "McasAtomBox values must be aligned to more than one byte"
+    );
+
+    /// Creates a new `McasAtomBox` and associates it with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{McasAtomBox, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let atom_box = McasAtomBox::new_with_domain(5_u32, &CUSTOM_DOMAIN);
+    /// assert_eq!(*atom_box.load(), 5);
+    /// ```
+    pub fn new_with_domain(value: T, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::ALIGNED;
+        Self {
+            atom_box: AtomBoxIn::new_with_domain(value, domain),
+        }
+    }
+
+    /// Loads the value stored in the `McasAtomBox`, reading through any update in progress.
+    ///
+    /// See [`AtomBoxIn::load`].
+    pub fn load(&self) -> LoadGuard<'domain, T, DOMAIN_ID> {
+        if !domain::needs_reclaim::<T>() {
+            return self.atom_box.unprotected_load();
+        }
+        let haz_ptr = self.atom_box.domain.acquire_haz_ptr();
+        let ptr = self.protect(&haz_ptr);
+        LoadGuard {
+            ptr,
+            domain: self.atom_box.domain,
+            haz_ptr: Some(haz_ptr),
+        }
+    }
+
+    /// Stores a new value, aborting any undecided update in progress.
+    ///
+    /// See [`AtomBoxIn::store`].
+    pub fn store(&self, value: T) {
+        let _ = self.swap(value);
+    }
+
+    /// Stores a new value and returns a `StoreGuard` which dereferences into the previous value,
+    /// aborting any undecided update in progress.
+    ///
+    /// See [`AtomBoxIn::swap`].
+    pub fn swap(&self, new_value: T) -> StoreGuard<'domain, T, DOMAIN_ID> {
+        let new_ptr = Box::into_raw(Box::new(new_value));
+        let target = &self.atom_box.ptr;
+        let haz_ptr = self.atom_box.domain.acquire_haz_ptr();
+        let mut current_ptr = target.load(Ordering::Acquire);
+        let old_ptr = loop {
+            if is_descriptor(current_ptr) {
+                help(self.atom_box.domain, target, current_ptr, &haz_ptr);
+                current_ptr = target.load(Ordering::Acquire);
+                continue;
+            }
+            match target.compare_exchange_weak(
+                current_ptr,
+                new_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(old_ptr) => break old_ptr,
+                Err(actual_ptr) => current_ptr = actual_ptr,
+            }
+        };
+        self.atom_box.domain.release_hazard_ptr(haz_ptr);
+        StoreGuard {
+            ptr: old_ptr,
+            domain: self.atom_box.domain,
+            retire_policy: self.atom_box.retire_policy,
+        }
+    }
+
+    /// Replaces the value with the result of `f`, retrying with the updated value if it changes
+    /// in the meantime.
+    ///
+    /// See [`AtomBoxIn::modify`].
+    pub fn modify(
+        &self,
+        mut f: impl FnMut(&T) -> T,
+    ) -> (
+        StoreGuard<'domain, T, DOMAIN_ID>,
+        LoadGuard<'domain, T, DOMAIN_ID>,
+    ) {
+        let domain = self.atom_box.domain;
+        let target = &self.atom_box.ptr;
+        let current_haz_ptr = domain.acquire_haz_ptr();
+        let new_haz_ptr = domain.acquire_haz_ptr();
+        let mut current_ptr = self.protect(&current_haz_ptr);
+        loop {
+            // # Safety
+            //
+            // The current value is protected by the hazard pointer.
+            let new_ptr = Box::into_raw(Box::new(f(unsafe { &*current_ptr })));
+            // The new value is not shared until the exchange succeeds, so it cannot have been
+            // retired before it is protected.
+            new_haz_ptr.protect(new_ptr as *mut usize);
+            match target.compare_exchange(current_ptr, new_ptr, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(old_ptr) => {
+                    domain.release_hazard_ptr(current_haz_ptr);
+                    return (
+                        StoreGuard {
+                            ptr: old_ptr,
+                            domain,
+                            retire_policy: self.atom_box.retire_policy,
+                        },
+                        LoadGuard {
+                            ptr: new_ptr,
+                            domain,
+                            haz_ptr: Some(new_haz_ptr),
+                        },
+                    );
+                }
+                Err(actual_ptr) => {
+                    new_haz_ptr.reset();
+                    // # Safety
+                    //
+                    // The new value was never shared so we still have exclusive ownership.
+                    drop(unsafe { Box::from_raw(new_ptr) });
+                    if is_descriptor(actual_ptr) {
+                        help(domain, target, actual_ptr, &current_haz_ptr);
+                    }
+                    current_ptr = self.protect(&current_haz_ptr);
+                }
+            }
+        }
+    }
+
+    /// Protects the current value with `haz_ptr`, reading through any descriptor installed by an
+    /// update in progress.
+    fn protect(&self, haz_ptr: &HazardPointer<'domain>) -> *mut T {
+        let target = &self.atom_box.ptr;
+        loop {
+            let ptr = haz_ptr.protect_ptr(target);
+            if !is_descriptor(ptr) {
+                break ptr;
+            }
+            if let Some(ptr) = read(self.atom_box.domain, target, ptr, haz_ptr) {
+                break ptr;
+            }
+        }
+    }
+}
+
+/// Atomically stores a new value in each of several [`McasAtomBox`]es.
+///
+/// Every box is updated at the same instant, so no thread can observe some of the new values
+/// alongside some of the old ones. Returns a `StoreGuard` to the previous value of each box, in
+/// the order the boxes were given.
+///
+/// Concurrent writers to the same boxes abort an update which has not yet taken effect, in which
+/// case it is retried. Heavily contended boxes can therefore delay an update indefinitely.
+///
+/// # Panics
+///
+/// Panics if the boxes are associated with different domains, or if the same box appears more than
+/// once.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{mcas, McasAtomBox};
+///
+/// let from = McasAtomBox::new(100_u32);
+/// let to = McasAtomBox::new(0_u32);
+///
+/// let previous = mcas([(&from, 50), (&to, 50)]);
+/// assert_eq!(*previous[0], 100);
+/// assert_eq!(*previous[1], 0);
+/// assert_eq!((*from.load(), *to.load()), (50, 50));
+/// ```
+pub fn mcas<'a, 'domain: 'a, T: 'a, const DOMAIN_ID: usize>(
+    updates: impl IntoIterator<Item = (&'a McasAtomBox<'domain, T, DOMAIN_ID>, T)>,
+) -> Vec<StoreGuard<'domain, T, DOMAIN_ID>> {
+    let mut updates: Vec<_> = updates
+        .into_iter()
+        .enumerate()
+        .map(|(index, (atom_box, value))| (index, &atom_box.atom_box, value))
+        .collect();
+    let domain = match updates.first() {
+        Some((_, atom_box, _)) => atom_box.domain,
+        None => return Vec::new(),
+    };
+    if let Some((_, atom_box, _)) = updates
+        .iter()
+        .find(|(_, atom_box, _)| !core::ptr::eq(atom_box.domain, domain))
+    {
+        panic!(
+            "Cannot update boxes from different domains ({} and {})",
+            crate::DomainName(domain.name()),
+            crate::DomainName(atom_box.domain.name())
+        );
+    }
+    // Installing descriptors in address order stops concurrent updates from repeatedly aborting
+    // each other.
+    updates.sort_by_key(|(_, atom_box, _)| &atom_box.ptr as *const AtomicPtr<T>);
+    assert!(
+        updates
+            .windows(2)
+            .all(|pair| !core::ptr::eq(pair[0].1, pair[1].1)),
+        "Cannot update the same box more than once"
+    );
+    let updates: Vec<_> = updates
+        .into_iter()
+        .map(|(index, atom_box, value)| (index, atom_box, Box::into_raw(Box::new(value))))
+        .collect();
+
+    let haz_ptr = domain.acquire_haz_ptr();
+    loop {
+        let descriptor = Box::into_raw(Box::new(Descriptor {
+            entries: updates
+                .iter()
+                .map(|(_, atom_box, new)| Entry {
+                    target: &atom_box.ptr,
+                    expected: AtomicPtr::new(core::ptr::null_mut()),
+                    new: *new,
+                })
+                .collect(),
+            status: AtomicUsize::new(UNDECIDED),
+        }));
+        // # Safety
+        //
+        // The descriptor is only retired by this thread, below.
+        let entries = unsafe { &(*descriptor).entries };
+        let status = unsafe { &(*descriptor).status };
+        let tagged = tag(descriptor);
+
+        let installed = install(domain, entries, status, tagged, &haz_ptr);
+        if installed == entries.len() {
+            let _ =
+                status.compare_exchange(UNDECIDED, SUCCEEDED, Ordering::SeqCst, Ordering::SeqCst);
+        }
+        let succeeded = status.load(Ordering::SeqCst) == SUCCEEDED;
+        for entry in &entries[..installed] {
+            // # Safety
+            //
+            // The targets are borrowed for the duration of this call.
+            let target = unsafe { &*entry.target };
+            // Another thread may already have replaced the descriptor in this box.
+            let _ = target.compare_exchange(
+                tagged,
+                entry.resolve(succeeded),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            );
+        }
+
+        if succeeded {
+            let mut previous: Vec<_> = updates
+                .iter()
+                .zip(entries)
+                .map(|((index, atom_box, _), entry)| {
+                    (
+                        *index,
+                        StoreGuard {
+                            ptr: entry.expected.load(Ordering::SeqCst),
+                            domain,
+                            retire_policy: atom_box.retire_policy,
+                        },
+                    )
+                })
+                .collect();
+            // # Safety
+            //
+            // The descriptor has been removed from every box and was created via `Box::into_raw`.
+            unsafe { domain.retire(descriptor) };
+            domain.release_hazard_ptr(haz_ptr);
+            previous.sort_by_key(|(index, _)| *index);
+            return previous.into_iter().map(|(_, guard)| guard).collect();
+        }
+        // The new values are only ever read through a descriptor which has succeeded, so they can
+        // be reused in the next attempt.
+        //
+        // # Safety
+        //
+        // The descriptor has been removed from every box and was created via `Box::into_raw`.
+        unsafe { domain.retire(descriptor) };
+        core::hint::spin_loop();
+    }
+}
+
+/// Installs the descriptor in each target in turn, returning how many were installed before the
+/// update was aborted.
+fn install<T, const DOMAIN_ID: usize>(
+    domain: &Domain<DOMAIN_ID>,
+    entries: &[Entry<T>],
+    status: &AtomicUsize,
+    tagged: *mut T,
+    haz_ptr: &HazardPointer<'_>,
+) -> usize {
+    for (installed, entry) in entries.iter().enumerate() {
+        // # Safety
+        //
+        // The targets are borrowed for the duration of the update.
+        let target = unsafe { &*entry.target };
+        loop {
+            if status.load(Ordering::SeqCst) != UNDECIDED {
+                return installed;
+            }
+            let current = target.load(Ordering::Acquire);
+            if is_descriptor(current) {
+                help(domain, target, current, haz_ptr);
+                continue;
+            }
+            entry.expected.store(current, Ordering::SeqCst);
+            if target
+                .compare_exchange(current, tagged, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                break;
+            }
+        }
+    }
+    entries.len()
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::{Domain, ReclaimStrategy};
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn mcas_returns_previous_values_in_order() {
+        let drop_counter = DropCounter::new();
+        let atom_box1 = McasAtomBox::new_with_domain(drop_counter.track(1), &TEST_DOMAIN);
+        let atom_box2 = McasAtomBox::new_with_domain(drop_counter.track(2), &TEST_DOMAIN);
+
+        let previous = mcas([
+            (&atom_box2, drop_counter.track(20)),
+            (&atom_box1, drop_counter.track(10)),
+        ]);
+
+        assert_eq!(**previous[0], 2, "Previous values are in the given order");
+        assert_eq!(**previous[1], 1, "Previous values are in the given order");
+        assert_eq!(**atom_box1.load(), 10, "The first box was updated");
+        assert_eq!(**atom_box2.load(), 20, "The second box was updated");
+        drop_counter.assert_drops(0);
+        drop(previous);
+        TEST_DOMAIN.reclaim();
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot update the same box more than once")]
+    fn mcas_rejects_duplicate_boxes() {
+        let atom_box = McasAtomBox::new_with_domain(1_u32, &TEST_DOMAIN);
+        let _ = mcas([(&atom_box, 2), (&atom_box, 3)]);
+    }
+
+    #[test]
+    fn concurrent_mcas_updates_are_never_torn() {
+        let atom_box1 = McasAtomBox::new_with_domain(0_usize, &TEST_DOMAIN);
+        let atom_box2 = McasAtomBox::new_with_domain(0_usize, &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for thread in 0..3 {
+                let (atom_box1, atom_box2) = (&atom_box1, &atom_box2);
+                scope.spawn(move || {
+                    for i in 0..500 {
+                        let value = thread * 1000 + i;
+                        let previous = mcas([(atom_box1, value), (atom_box2, value)]);
+                        assert_eq!(
+                            *previous[0], *previous[1],
+                            "Both boxes should always hold the same value"
+                        );
+                    }
+                });
+            }
+            // Writers which do not change the value abort in-flight updates and read through them.
+            scope.spawn(|| {
+                for _ in 0..500 {
+                    atom_box2.modify(|value| *value);
+                    let _ = atom_box1.load();
+                }
+            });
+        });
+
+        assert_eq!(*atom_box1.load(), *atom_box2.load());
+    }
+}
@@ -0,0 +1,242 @@
+//! Option
+//!
+//! A nullable `AtomBox` which supports racing to initialise its value.
+
+use crate::domain::{Domain, RetirePolicy};
+use crate::sync::{AtomicPtr, Ordering};
+use crate::{LoadGuard, StoreGuard};
+use alloc::boxed::Box;
+
+/// An `AtomBox` which may be empty.
+///
+/// Concurrent initialisers can race to set the value with [`AtomOptionBox::store_if_none`] or
+/// [`AtomOptionBox::get_or_init`]. Exactly one of them succeeds, and the others are handed back
+/// their value rather than having it silently retired.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::AtomOptionBox;
+///
+/// let atom_box = AtomOptionBox::new();
+/// assert!(atom_box.load().is_none());
+///
+/// let value = atom_box.get_or_init(|| "Hello");
+/// assert_eq!(*value, "Hello");
+///
+/// match atom_box.store_if_none("World") {
+///     Ok(_) => panic!("The box has already been initialised"),
+///     Err((current_value, rejected)) => {
+///         assert_eq!(*current_value, "Hello");
+///         assert_eq!(rejected, "World");
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AtomOptionBox<'domain, T, const DOMAIN_ID: usize> {
+    ptr: AtomicPtr<T>,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+#[cfg(not(loom))]
+impl<T> AtomOptionBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new, empty `AtomOptionBox` associated with the shared (global) domain.
+    pub fn new() -> Self {
+        Self::new_with_domain(&crate::SHARED_DOMAIN)
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> Default for AtomOptionBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> AtomOptionBox<'domain, T, DOMAIN_ID> {
+    /// Creates a new, empty `AtomOptionBox` and associates it with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomOptionBox, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let atom_box = AtomOptionBox::new_with_domain(&CUSTOM_DOMAIN);
+    /// assert_eq!(*atom_box.get_or_init(|| 5), 5);
+    /// ```
+    pub fn new_with_domain(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            ptr: AtomicPtr::new(core::ptr::null_mut()),
+            domain,
+        }
+    }
+
+    /// Loads the value stored in the `AtomOptionBox`, if there is one.
+    pub fn load(&self) -> Option<LoadGuard<'domain, T, DOMAIN_ID>> {
+        let haz_ptr = self.domain.acquire_haz_ptr();
+        let ptr = haz_ptr.protect_ptr(&self.ptr);
+        if ptr.is_null() {
+            self.domain.release_hazard_ptr(haz_ptr);
+            None
+        } else {
+            Some(LoadGuard {
+                ptr,
+                domain: self.domain,
+                haz_ptr: Some(haz_ptr),
+            })
+        }
+    }
+
+    /// Stores `value` if the `AtomOptionBox` is empty.
+    ///
+    /// On success, returns a `LoadGuard` to the stored value. If the `AtomOptionBox` already holds
+    /// a value, the `Err` contains a `LoadGuard` to that value along with `value`.
+    pub fn store_if_none(
+        &self,
+        value: T,
+    ) -> Result<LoadGuard<'domain, T, DOMAIN_ID>, (LoadGuard<'domain, T, DOMAIN_ID>, T)> {
+        let haz_ptr = self.domain.acquire_haz_ptr();
+        let new_ptr = Box::into_raw(Box::new(value));
+        loop {
+            // The new value is not shared until the exchange succeeds, so it cannot have been
+            // retired before it is protected.
+            haz_ptr.protect(new_ptr as *mut usize);
+            if self
+                .ptr
+                .compare_exchange(
+                    core::ptr::null_mut(),
+                    new_ptr,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            {
+                return Ok(LoadGuard {
+                    ptr: new_ptr,
+                    domain: self.domain,
+                    haz_ptr: Some(haz_ptr),
+                });
+            }
+            let current_ptr = haz_ptr.protect_ptr(&self.ptr);
+            // The value may have been taken since the exchange failed, in which case try again.
+            if !current_ptr.is_null() {
+                // # Safety
+                //
+                // The new value was never shared so we still have exclusive ownership.
+                let value = *unsafe { Box::from_raw(new_ptr) };
+                return Err((
+                    LoadGuard {
+                        ptr: current_ptr,
+                        domain: self.domain,
+                        haz_ptr: Some(haz_ptr),
+                    },
+                    value,
+                ));
+            }
+        }
+    }
+
+    /// Returns the stored value, initialising it with `f` if the `AtomOptionBox` is empty.
+    ///
+    /// If several threads initialise the value concurrently, `f` may be called by each of them,
+    /// but only one value is stored and every thread receives a guard to that value.
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> LoadGuard<'domain, T, DOMAIN_ID> {
+        if let Some(value) = self.load() {
+            return value;
+        }
+        match self.store_if_none(f()) {
+            Ok(value) | Err((value, _)) => value,
+        }
+    }
+
+    /// Takes the value out of the `AtomOptionBox`, leaving it empty.
+    ///
+    /// Returns a `StoreGuard` to the value, which is retired when the guard is dropped.
+    pub fn take(&self) -> Option<StoreGuard<'domain, T, DOMAIN_ID>> {
+        let ptr = self.ptr.swap(core::ptr::null_mut(), Ordering::AcqRel);
+        if ptr.is_null() {
+            None
+        } else {
+            Some(StoreGuard {
+                ptr,
+                domain: self.domain,
+                retire_policy: RetirePolicy::Domain,
+            })
+        }
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Drop for AtomOptionBox<'domain, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        let ptr = self.ptr.load(Ordering::Relaxed);
+        if !ptr.is_null() {
+            // # Safety
+            //
+            // We have exclusive access to the box, and the value was created via `Box::into_raw`.
+            // Other threads may still be reading it under hazard protection, so it is retired.
+            unsafe { self.domain.retire(ptr) };
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn losing_initialiser_gets_value_back() {
+        let drop_counter = DropCounter::new();
+        let atom_box = AtomOptionBox::new_with_domain(&TEST_DOMAIN);
+        let winner = atom_box
+            .store_if_none(drop_counter.track(1))
+            .unwrap_or_else(|_| panic!("The box is empty"));
+
+        let loser = atom_box.store_if_none(drop_counter.track(2));
+
+        match loser {
+            Ok(_) => panic!("The box has already been initialised"),
+            Err((current_value, value)) => {
+                assert_eq!(**current_value, 1, "Err contains the stored value");
+                assert_eq!(*value, 2, "Err hands back the rejected value");
+            }
+        }
+        drop_counter.assert_drops(1);
+        assert_eq!(**winner, 1, "Ok contains the stored value");
+        drop(winner);
+        drop(atom_box.take());
+        drop_counter.assert_drops(2);
+        assert!(atom_box.load().is_none(), "The value has been taken");
+    }
+
+    #[test]
+    fn concurrent_get_or_init_agrees_on_one_value() {
+        let atom_box = AtomOptionBox::new_with_domain(&TEST_DOMAIN);
+
+        let values: std::vec::Vec<usize> = std::thread::scope(|scope| {
+            let handles: std::vec::Vec<_> = (0..4)
+                .map(|thread| {
+                    let atom_box = &atom_box;
+                    scope.spawn(move || *atom_box.get_or_init(|| thread))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        assert!(
+            values.iter().all(|value| *value == values[0]),
+            "Every thread should see the same value, got {:?}",
+            values
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Poison
+//!
+//! An `AtomBox` which is poisoned when an update closure panics, in the manner of `Mutex`.
+
+use crate::domain::Domain;
+use crate::sync::{AtomicBool, Ordering};
+use crate::{AtomBoxIn, LoadGuard, StoreGuard};
+use core::fmt;
+
+/// The error returned by operations on a poisoned [`PoisonAtomBox`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Poisoned;
+
+impl fmt::Display for Poisoned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an update to the AtomBox panicked")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Poisoned {}
+
+/// An `AtomBox` which is poisoned if a closure passed to one of its update methods panics.
+///
+/// Updates built from closures, such as [`PoisonAtomBox::modify`] and
+/// [`PoisonAtomBox::make_mut`], only store the new value once the closure has returned, so a
+/// panicking closure never publishes a partially constructed value. However, the update the caller
+/// intended has been abandoned part way through, which may leave related state elsewhere in the
+/// program inconsistent with the value in the box. Like a `Mutex`, a `PoisonAtomBox` records this:
+/// every subsequent operation returns `Err(Poisoned)` until [`PoisonAtomBox::clear_poison`] is
+/// called.
+///
+/// Poisoning is advisory. An update which had already checked the flag when another update
+/// panicked may still complete.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{PoisonAtomBox, Poisoned};
+/// use std::panic::{catch_unwind, AssertUnwindSafe};
+///
+/// let atom_box = PoisonAtomBox::new(vec![1, 2]);
+///
+/// let result = catch_unwind(AssertUnwindSafe(|| {
+///     atom_box.make_mut(|value| {
+///         value.push(3);
+///         panic!("Failed part way through an update");
+///     })
+/// }));
+/// assert!(result.is_err());
+///
+/// assert!(atom_box.is_poisoned());
+/// assert_eq!(atom_box.load().err(), Some(Poisoned));
+///
+/// atom_box.clear_poison();
+/// assert_eq!(*atom_box.load().unwrap(), [1, 2]);
+/// ```
+#[derive(Debug)]
+pub struct PoisonAtomBox<'domain, T, const DOMAIN_ID: usize> {
+    atom_box: AtomBoxIn<'domain, T, DOMAIN_ID>,
+    poisoned: AtomicBool,
+}
+
+#[cfg(not(loom))]
+impl<T> PoisonAtomBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `PoisonAtomBox` associated with the shared (global) domain.
+    pub fn new(value: T) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> PoisonAtomBox<'domain, T, DOMAIN_ID> {
+    /// Creates a new `PoisonAtomBox` and associates it with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{PoisonAtomBox, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let atom_box = PoisonAtomBox::new_with_domain(5, &CUSTOM_DOMAIN);
+    /// assert_eq!(*atom_box.load().unwrap(), 5);
+    /// ```
+    pub fn new_with_domain(value: T, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            atom_box: AtomBoxIn::new_with_domain(value, domain),
+            poisoned: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns `true` if an update closure has panicked since the poison was last cleared.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+
+    /// Clears the poisoned state, allowing the `PoisonAtomBox` to be used again.
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Release);
+    }
+
+    fn check(&self) -> Result<(), Poisoned> {
+        if self.is_poisoned() {
+            Err(Poisoned)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Loads the value stored in the `PoisonAtomBox`.
+    ///
+    /// See [`AtomBoxIn::load`].
+    pub fn load(&self) -> Result<LoadGuard<'domain, T, DOMAIN_ID>, Poisoned> {
+        self.check()?;
+        Ok(self.atom_box.load())
+    }
+
+    /// Stores a new value in the `PoisonAtomBox`.
+    ///
+    /// See [`AtomBoxIn::store`].
+    pub fn store(&self, value: T) -> Result<(), Poisoned> {
+        self.check()?;
+        self.atom_box.store(value);
+        Ok(())
+    }
+
+    /// Stores a new value in the `PoisonAtomBox`, returning the value which was replaced.
+    ///
+    /// See [`AtomBoxIn::swap`].
+    pub fn swap(&self, new_value: T) -> Result<StoreGuard<'domain, T, DOMAIN_ID>, Poisoned> {
+        self.check()?;
+        Ok(self.atom_box.swap(new_value))
+    }
+
+    /// Replaces the value with the result of `f`, poisoning the `PoisonAtomBox` if `f` panics.
+    ///
+    /// See [`AtomBoxIn::modify`].
+    #[allow(clippy::type_complexity)]
+    pub fn modify(
+        &self,
+        f: impl FnMut(&T) -> T,
+    ) -> Result<
+        (
+            StoreGuard<'domain, T, DOMAIN_ID>,
+            LoadGuard<'domain, T, DOMAIN_ID>,
+        ),
+        Poisoned,
+    > {
+        self.check()?;
+        let poison_on_unwind = PoisonOnUnwind(&self.poisoned);
+        let result = self.atom_box.modify(f);
+        core::mem::forget(poison_on_unwind);
+        Ok(result)
+    }
+
+    /// Replaces the value with a modified clone of it, poisoning the `PoisonAtomBox` if `f`
+    /// panics.
+    ///
+    /// See [`AtomBoxIn::make_mut`].
+    pub fn make_mut(
+        &self,
+        f: impl FnMut(&mut T),
+    ) -> Result<StoreGuard<'domain, T, DOMAIN_ID>, Poisoned>
+    where
+        T: Clone,
+    {
+        self.check()?;
+        let poison_on_unwind = PoisonOnUnwind(&self.poisoned);
+        let result = self.atom_box.make_mut(f);
+        core::mem::forget(poison_on_unwind);
+        Ok(result)
+    }
+}
+
+/// Poisons the flag when dropped, which only happens if the update it guards unwinds.
+struct PoisonOnUnwind<'a>(&'a AtomicBool);
+
+impl Drop for PoisonOnUnwind<'_> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn panicking_update_poisons_the_box_until_cleared() {
+        let atom_box = PoisonAtomBox::new_with_domain(1, &TEST_DOMAIN);
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            atom_box.modify(|_| panic!("Failed part way through an update"))
+        }));
+
+        assert!(result.is_err(), "The update panicked");
+        assert!(atom_box.is_poisoned(), "The box is poisoned");
+        assert!(atom_box.load().is_err(), "Loading a poisoned box fails");
+        assert_eq!(atom_box.store(2), Err(Poisoned), "Storing fails");
+        assert!(atom_box.swap(3).is_err(), "Swapping fails");
+        assert!(
+            atom_box.modify(|value| value + 1).is_err(),
+            "Modifying fails"
+        );
+        atom_box.clear_poison();
+        assert!(!atom_box.is_poisoned(), "The poison has been cleared");
+        assert_eq!(
+            *atom_box.load().unwrap(),
+            1,
+            "The panicking update did not store a value"
+        );
+    }
+
+    #[test]
+    fn successful_updates_do_not_poison_the_box() {
+        let atom_box = PoisonAtomBox::new_with_domain(std::vec![1], &TEST_DOMAIN);
+
+        let old_value = atom_box
+            .make_mut(|value| value.push(2))
+            .expect("The box is not poisoned");
+
+        assert_eq!(*old_value, [1], "The old value is returned");
+        assert!(!atom_box.is_poisoned(), "The box is not poisoned");
+        assert_eq!(*atom_box.load().unwrap(), [1, 2], "The value is updated");
+    }
+}
@@ -0,0 +1,231 @@
+//! Protection
+//!
+//! The interface between an `AtomBox` and the memory reclamation scheme which keeps loaded values
+//! alive.
+//!
+//! [`Domain`](crate::domain::Domain) implements this trait with hazard pointers, and is the
+//! scheme used unless another is chosen. Data structures written against [`Protection`] rather
+//! than a concrete domain let applications choose the reclamation scheme, for example an epoch
+//! or quiescent state based scheme, by constructing their boxes with a different backend.
+//!
+//! [`Hyaline`] is provided as an alternative backend, which reclaims retired values by counting
+//! references to them rather than scanning for hazard pointers.
+
+use crate::domain::RetirePolicy;
+use crate::sync::{AtomicPtr, Ordering};
+
+mod hyaline;
+
+pub use hyaline::{Hyaline, HyalineGuard};
+
+/// A memory reclamation scheme protecting values loaded from an `AtomBox`.
+///
+/// A reader acquires a guard, protects a pointer with it, and then checks that the pointer is
+/// still current. Once that check succeeds, the value must not be reclaimed until the guard is
+/// released or used to protect another pointer.
+///
+/// # Safety
+///
+/// Implementations must not reclaim a retired value while it is protected by a guard which
+/// protected it before it was retired.
+pub unsafe trait Protection {
+    /// A guard which protects a single value at a time.
+    type Guard<'a>
+    where
+        Self: 'a;
+
+    /// Acquires a guard which does not yet protect any value.
+    fn acquire(&self) -> Self::Guard<'_>;
+
+    /// Releases a guard, ending the protection of its value.
+    fn release<'a>(&'a self, guard: Self::Guard<'a>);
+
+    /// Protects `ptr` with `guard`, replacing any value it previously protected.
+    ///
+    /// Protecting a null pointer ends the protection of the previous value.
+    fn protect<'a, T>(&'a self, guard: &Self::Guard<'a>, ptr: *mut T);
+
+    /// Protects the allocation `ptr` points to, ignoring the tag bits in `tag_mask`.
+    ///
+    /// Lock-free algorithms often keep marks in the low bits of their pointers. Values are retired
+    /// by their untagged address, so the tag must be removed for the protection to match.
+    fn protect_tagged<'a, T>(&'a self, guard: &Self::Guard<'a>, ptr: *mut T, tag_mask: usize) {
+        self.protect(guard, untagged(ptr, tag_mask));
+    }
+
+    /// Ends the protection of the value protected by `guard`, without releasing it.
+    fn reset<'a>(&'a self, guard: &Self::Guard<'a>) {
+        self.protect(guard, core::ptr::null_mut::<usize>());
+    }
+
+    /// Places a value on the retire list, to be reclaimed once no guard protects it.
+    ///
+    /// # Safety
+    ///
+    /// The value must have been created via `Box::into_raw`, must no longer be reachable by
+    /// threads which have not already protected it, and must not be retired more than once.
+    unsafe fn retire<T>(&self, ptr: *mut T);
+
+    /// Places a value on the retire list, or reclaims it straight away if `retire_policy` allows
+    /// and no guard protects it.
+    ///
+    /// Backends which cannot tell whether a value is protected retire it as usual.
+    ///
+    /// # Safety
+    ///
+    /// As for [`Protection::retire`].
+    unsafe fn retire_with_policy<T>(&self, ptr: *mut T, retire_policy: RetirePolicy) {
+        let _ = retire_policy;
+        unsafe { self.retire(ptr) };
+    }
+
+    /// A name identifying this backend in diagnostics, such as panic messages.
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether `other` refers to the same backend as this, so that values can be moved between
+    /// boxes associated with either.
+    fn is_same(&self, other: &Self) -> bool {
+        core::ptr::eq(self, other)
+    }
+
+    /// Protects the pointer currently stored in `source`, returning the protected pointer.
+    ///
+    /// Retries until the protected pointer is confirmed to still be the one stored in `source`.
+    fn protect_ptr<'a, T>(&'a self, guard: &Self::Guard<'a>, source: &AtomicPtr<T>) -> *mut T {
+        let mut original_ptr = source.load(Ordering::Relaxed);
+        loop {
+            self.protect(guard, original_ptr);
+
+            crate::sync::fence(Ordering::SeqCst);
+
+            let current_ptr = source.load(Ordering::Acquire);
+            if current_ptr == original_ptr {
+                break current_ptr;
+            }
+            original_ptr = current_ptr;
+        }
+    }
+
+    /// Protects the allocation the tagged pointer currently stored in `source` points to,
+    /// returning the tagged pointer.
+    ///
+    /// The bits in `tag_mask` are removed before the pointer is protected, and are ignored when
+    /// checking that the pointer is still current, so the tag may change while the allocation
+    /// remains protected. The returned pointer carries the tag most recently loaded from `source`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::domain::{Domain, ReclaimStrategy};
+    /// use atom_box::protection::Protection;
+    /// use core::sync::atomic::AtomicPtr;
+    ///
+    /// const MARKED: usize = 1;
+    /// static DOMAIN: Domain<42> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let node = Box::into_raw(Box::new(5_u64));
+    /// let next = AtomicPtr::new((node as usize | MARKED) as *mut u64);
+    ///
+    /// let guard = DOMAIN.acquire();
+    /// let ptr = DOMAIN.protect_ptr_tagged(&guard, &next, MARKED);
+    /// assert_eq!(ptr as usize & MARKED, MARKED);
+    /// assert_eq!(unsafe { *((ptr as usize & !MARKED) as *const u64) }, 5);
+    /// DOMAIN.release(guard);
+    /// # drop(unsafe { Box::from_raw(node) });
+    /// ```
+    fn protect_ptr_tagged<'a, T>(
+        &'a self,
+        guard: &Self::Guard<'a>,
+        source: &AtomicPtr<T>,
+        tag_mask: usize,
+    ) -> *mut T {
+        let mut original_ptr = source.load(Ordering::Relaxed);
+        loop {
+            self.protect_tagged(guard, original_ptr, tag_mask);
+
+            crate::sync::fence(Ordering::SeqCst);
+
+            let current_ptr = source.load(Ordering::Acquire);
+            if untagged(current_ptr, tag_mask) == untagged(original_ptr, tag_mask) {
+                break current_ptr;
+            }
+            original_ptr = current_ptr;
+        }
+    }
+}
+
+/// Removes the bits in `tag_mask` from `ptr`.
+pub(crate) fn untagged<T>(ptr: *mut T, tag_mask: usize) -> *mut T {
+    (ptr as usize & !tag_mask) as *mut T
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::DropCounter;
+    use crate::AtomBoxIn;
+    use alloc::vec::Vec;
+    use std::sync::Mutex;
+
+    type Retired = (usize, unsafe fn(usize));
+
+    /// Defers reclaiming every retired value until the backend itself is dropped.
+    #[derive(Debug, Default)]
+    struct Quiescent {
+        retired: Mutex<Vec<Retired>>,
+    }
+
+    unsafe fn reclaim<T>(ptr: usize) {
+        drop(unsafe { alloc::boxed::Box::from_raw(ptr as *mut T) });
+    }
+
+    unsafe impl Protection for Quiescent {
+        type Guard<'a> = ();
+
+        fn acquire(&self) -> Self::Guard<'_> {}
+
+        fn release<'a>(&'a self, _guard: Self::Guard<'a>) {}
+
+        fn protect<'a, T>(&'a self, _guard: &Self::Guard<'a>, _ptr: *mut T) {}
+
+        unsafe fn retire<T>(&self, ptr: *mut T) {
+            self.retired
+                .lock()
+                .unwrap()
+                .push((ptr as usize, reclaim::<T>));
+        }
+    }
+
+    impl Drop for Quiescent {
+        fn drop(&mut self) {
+            for (ptr, reclaim) in self.retired.get_mut().unwrap().drain(..) {
+                // # Safety
+                //
+                // Every value was retired exactly once and no guards outlive the backend.
+                unsafe { reclaim(ptr) };
+            }
+        }
+    }
+
+    #[test]
+    fn atom_box_uses_custom_backend() {
+        let drop_counter = DropCounter::new();
+        let backend = Quiescent::default();
+        let atom_box: AtomBoxIn<'_, _, 0, _> =
+            AtomBoxIn::new_with_protection(drop_counter.track(1), &backend);
+
+        let value = atom_box.load();
+        atom_box.store(drop_counter.track(2));
+        let (_, new_value) = atom_box.modify(|value| drop_counter.track(**value * 10));
+
+        assert_eq!(**value, 1, "The loaded value is still accessible");
+        assert_eq!(**new_value, 20, "The box was updated");
+        drop((value, new_value, atom_box));
+        drop_counter.assert_drops(0);
+        drop(backend);
+        drop_counter.assert_drops(3);
+    }
+}
@@ -0,0 +1,445 @@
+//! Hyaline
+//!
+//! A reclamation backend which counts the references to each batch of retired values, rather than
+//! scanning for hazard pointers.
+
+use super::Protection;
+use crate::macros::conditional_const;
+use crate::sync::{fence, AtomicBool, AtomicIsize, AtomicPtr, AtomicUsize, Ordering};
+use alloc::boxed::Box;
+
+/// The number of retired values collected into a batch, unless configured otherwise.
+const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// A retired value waiting to be reclaimed.
+struct Retired {
+    ptr: *mut u8,
+    reclaim: unsafe fn(*mut u8),
+    next: *mut Retired,
+}
+
+unsafe fn reclaim<T>(ptr: *mut u8) {
+    drop(unsafe { Box::from_raw(ptr as *mut T) });
+}
+
+/// Retired values which are reclaimed together, once every guard which was active when they were
+/// retired has been released.
+struct Batch {
+    // The number of active guards still referencing the batch. Guards may release the batch
+    // before the retiring thread has counted them, so this can be negative until it has.
+    refs: AtomicIsize,
+    retired: *mut Retired,
+}
+
+/// An entry in the list of batches referenced by a guard.
+struct Node {
+    batch: *mut Batch,
+    next: *mut Node,
+}
+
+/// A slot held by a single guard at a time.
+struct Slot {
+    // `inactive()` while the guard is not active. Otherwise, the batches retired since it became
+    // active.
+    head: AtomicPtr<Node>,
+    in_use: AtomicBool,
+    next: *mut Slot,
+}
+
+/// The head of a slot which is not active.
+///
+/// Nodes are aligned, so a real node is never allocated at this address.
+fn inactive() -> *mut Node {
+    core::ptr::NonNull::dangling().as_ptr()
+}
+
+/// A [`Protection`] backend implementing a variant of the Hyaline reclamation scheme.
+///
+/// Retired values are collected into batches. When a batch is complete, it is handed to every
+/// guard active at the time, and counts how many it was handed to. Each guard releases the
+/// batches it was handed when it is released, and the guard which releases a batch last reclaims
+/// it. Reclaiming never scans for guards, and a batch is only visited once by each guard which
+/// holds it, however long the guards remain active. Guards which are not active are skipped when
+/// a batch is handed out, and cost nothing more.
+///
+/// A guard protects every value which had not been retired when it was acquired, not only the
+/// value it protects. Long lived guards, such as a [`LoadGuard`](crate::LoadGuard) held across a
+/// slow operation, therefore delay the reclamation of every batch retired while they are held.
+///
+/// Each `Hyaline` is independent of every other backend, so a subsystem can choose it by creating
+/// its boxes with [`AtomBoxIn::new_with_protection`](crate::AtomBoxIn::new_with_protection).
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBoxIn, protection::Hyaline};
+///
+/// static HYALINE: Hyaline = Hyaline::new().with_batch_size(2);
+///
+/// let atom_box: AtomBoxIn<'_, _, 0, _> = AtomBoxIn::new_with_protection("Hello", &HYALINE);
+/// let value = atom_box.load();
+///
+/// atom_box.store("World");
+/// assert_eq!(*value, "Hello");
+/// assert_eq!(*atom_box.load(), "World");
+/// ```
+pub struct Hyaline {
+    // Slots are never removed, and are only freed when the backend is dropped.
+    slots: AtomicPtr<Slot>,
+    // Retired values which have not yet been added to a batch.
+    pending: AtomicPtr<Retired>,
+    pending_count: AtomicUsize,
+    batch_size: usize,
+}
+
+// Retired values are only reclaimed by the thread which releases their batch last, having been
+// retired as `Send` values by the boxes using the backend.
+unsafe impl Send for Hyaline {}
+unsafe impl Sync for Hyaline {}
+
+impl core::fmt::Debug for Hyaline {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Hyaline")
+            .field("batch_size", &self.batch_size)
+            .field("pending", &self.pending_count.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(loom))]
+impl Default for Hyaline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hyaline {
+    conditional_const!(
+        "Creates a new `Hyaline` backend, collecting retired values into batches of 64.",
+        pub,
+        fn new() -> Self {
+            Self {
+                slots: AtomicPtr::new(core::ptr::null_mut()),
+                pending: AtomicPtr::new(core::ptr::null_mut()),
+                pending_count: AtomicUsize::new(0),
+                batch_size: DEFAULT_BATCH_SIZE,
+            }
+        }
+    );
+
+    conditional_const!(
+        "Sets the number of retired values collected into each batch.
+
+Larger batches are handed out less often, at the cost of keeping more retired values alive.
+
+# Panics
+
+Panics if `batch_size` is zero.",
+        pub,
+        fn with_batch_size(mut self, batch_size: usize) -> Self {
+            assert!(batch_size > 0, "Batch size must be greater than zero");
+            self.batch_size = batch_size;
+            self
+        }
+    );
+
+    /// Hands out the retired values which have not yet filled a batch, so that they are
+    /// reclaimed once the guards currently active have been released.
+    pub fn flush(&self) {
+        let mut retired = self.pending.swap(core::ptr::null_mut(), Ordering::Acquire);
+        if retired.is_null() {
+            return;
+        }
+        let batch = Box::into_raw(Box::new(Batch {
+            refs: AtomicIsize::new(0),
+            retired,
+        }));
+        let mut count = 0;
+        while !retired.is_null() {
+            count += 1;
+            // # Safety
+            //
+            // The pending values now belong to the batch, and are not reclaimed before it is
+            // handed out.
+            retired = unsafe { &*retired }.next;
+        }
+        self.pending_count.fetch_sub(count, Ordering::Relaxed);
+
+        // Orders the values being retired before checking which guards are active. A guard
+        // becoming active afterwards cannot load a value which was retired before it.
+        fence(Ordering::SeqCst);
+
+        let mut references = 0;
+        let mut node: *mut Node = core::ptr::null_mut();
+        let mut slot = self.slots.load(Ordering::Acquire);
+        // # Safety
+        //
+        // Slots are only freed when the backend is dropped.
+        while let Some(current) = unsafe { slot.as_ref() } {
+            slot = current.next;
+            let mut head = current.head.load(Ordering::Acquire);
+            while head != inactive() {
+                if node.is_null() {
+                    node = Box::into_raw(Box::new(Node {
+                        batch,
+                        next: core::ptr::null_mut(),
+                    }));
+                }
+                // # Safety
+                //
+                // The node has not been published yet.
+                unsafe { (*node).next = head };
+                match current
+                    .head
+                    .compare_exchange(head, node, Ordering::AcqRel, Ordering::Acquire)
+                {
+                    Ok(_) => {
+                        references += 1;
+                        node = core::ptr::null_mut();
+                        break;
+                    }
+                    Err(new_head) => head = new_head,
+                }
+            }
+        }
+        if !node.is_null() {
+            // # Safety
+            //
+            // The node was never published, so we still own it.
+            drop(unsafe { Box::from_raw(node) });
+        }
+        // # Safety
+        //
+        // The batch is not reclaimed before its references have been counted.
+        unsafe { release_batch(batch, -references) };
+    }
+
+    fn acquire_slot(&self) -> &Slot {
+        let mut slot = self.slots.load(Ordering::Acquire);
+        // # Safety
+        //
+        // Slots are only freed when the backend is dropped.
+        while let Some(current) = unsafe { slot.as_ref() } {
+            if !current.in_use.load(Ordering::Relaxed)
+                && current
+                    .in_use
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                return current;
+            }
+            slot = current.next;
+        }
+        let new_slot = Box::into_raw(Box::new(Slot {
+            head: AtomicPtr::new(inactive()),
+            in_use: AtomicBool::new(true),
+            next: core::ptr::null_mut(),
+        }));
+        let mut head = self.slots.load(Ordering::Acquire);
+        loop {
+            // # Safety
+            //
+            // The slot has not been published yet.
+            unsafe { (*new_slot).next = head };
+            match self
+                .slots
+                .compare_exchange(head, new_slot, Ordering::AcqRel, Ordering::Acquire)
+            {
+                // # Safety
+                //
+                // The slot is never freed before the backend.
+                Ok(_) => break unsafe { &*new_slot },
+                Err(new_head) => head = new_head,
+            }
+        }
+    }
+}
+
+/// Removes `count` references from `batch`, reclaiming it if none remain.
+///
+/// # Safety
+///
+/// The caller must hold the references it removes, or be the thread which handed out the batch
+/// removing the references it counted, negated.
+unsafe fn release_batch(batch: *mut Batch, count: isize) {
+    // # Safety
+    //
+    // The batch is not reclaimed while the caller holds a reference to it.
+    let refs = unsafe { &*batch }.refs.fetch_sub(count, Ordering::AcqRel);
+    if refs == count {
+        // # Safety
+        //
+        // No guard references the batch, and the thread which handed it out has counted every
+        // reference, so we are the only thread with access to it.
+        let batch = unsafe { Box::from_raw(batch) };
+        let mut retired = batch.retired;
+        while !retired.is_null() {
+            // # Safety
+            //
+            // Each retired value belongs to exactly one batch, and is reclaimed with it.
+            let current = unsafe { Box::from_raw(retired) };
+            retired = current.next;
+            unsafe { (current.reclaim)(current.ptr) };
+        }
+    }
+}
+
+/// A guard acquired from a [`Hyaline`] backend.
+#[derive(Debug)]
+pub struct HyalineGuard<'a> {
+    slot: &'a Slot,
+}
+
+impl core::fmt::Debug for Slot {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Slot").finish_non_exhaustive()
+    }
+}
+
+unsafe impl Protection for Hyaline {
+    type Guard<'a> = HyalineGuard<'a>;
+
+    fn acquire(&self) -> Self::Guard<'_> {
+        let slot = self.acquire_slot();
+        slot.head.store(core::ptr::null_mut(), Ordering::Relaxed);
+        // Orders the guard becoming active before any value it loads.
+        fence(Ordering::SeqCst);
+        HyalineGuard { slot }
+    }
+
+    fn release<'a>(&'a self, guard: Self::Guard<'a>) {
+        let mut node = guard.slot.head.swap(inactive(), Ordering::AcqRel);
+        while !node.is_null() {
+            // # Safety
+            //
+            // The nodes handed to the slot while it was active now belong to us.
+            let current = unsafe { Box::from_raw(node) };
+            node = current.next;
+            // # Safety
+            //
+            // The node held one reference to its batch.
+            unsafe { release_batch(current.batch, 1) };
+        }
+        guard.slot.in_use.store(false, Ordering::Release);
+    }
+
+    fn protect<'a, T>(&'a self, _guard: &Self::Guard<'a>, _ptr: *mut T) {
+        // Every value which had not been retired when the guard was acquired is protected.
+    }
+
+    unsafe fn retire<T>(&self, ptr: *mut T) {
+        let retired = Box::into_raw(Box::new(Retired {
+            ptr: ptr as *mut u8,
+            reclaim: reclaim::<T>,
+            next: core::ptr::null_mut(),
+        }));
+        let mut head = self.pending.load(Ordering::Relaxed);
+        loop {
+            // # Safety
+            //
+            // The retired value has not been published yet.
+            unsafe { (*retired).next = head };
+            match self.pending.compare_exchange_weak(
+                head,
+                retired,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(new_head) => head = new_head,
+            }
+        }
+        if self.pending_count.fetch_add(1, Ordering::Relaxed) + 1 >= self.batch_size {
+            self.flush();
+        }
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        Some("Hyaline")
+    }
+}
+
+impl Drop for Hyaline {
+    fn drop(&mut self) {
+        // No guards outlive the backend, so every batch which has been handed out has already been
+        // reclaimed, and a flushed batch is reclaimed straight away.
+        self.flush();
+        let mut slot = self.slots.load(Ordering::Relaxed);
+        while !slot.is_null() {
+            // # Safety
+            //
+            // We have exclusive access to the backend, so nothing else references its slots.
+            let current = unsafe { Box::from_raw(slot) };
+            slot = current.next;
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::DropCounter;
+    use crate::AtomBoxIn;
+
+    #[test]
+    fn batches_are_reclaimed_once_active_guards_are_released() {
+        let drop_counter = DropCounter::new();
+        let hyaline = Hyaline::new().with_batch_size(2);
+        let atom_box: AtomBoxIn<'_, _, 0, _> =
+            AtomBoxIn::new_with_protection(drop_counter.track(0), &hyaline);
+        let guard = atom_box.load();
+
+        atom_box.store(drop_counter.track(1));
+        atom_box.store(drop_counter.track(2));
+        let drops_while_guarded = drop_counter.count();
+        drop(guard);
+
+        assert_eq!(drops_while_guarded, 0, "The guard keeps the batch alive");
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    fn batches_retired_while_no_guard_is_active_are_reclaimed_straight_away() {
+        let drop_counter = DropCounter::new();
+        let hyaline = Hyaline::new();
+        let atom_box: AtomBoxIn<'_, _, 0, _> =
+            AtomBoxIn::new_with_protection(drop_counter.track(0), &hyaline);
+
+        atom_box.store(drop_counter.track(1));
+        let drops_before_flush = drop_counter.count();
+        hyaline.flush();
+
+        assert_eq!(drops_before_flush, 0, "The batch is not yet complete");
+        drop_counter.assert_drops(1);
+        drop(atom_box);
+        drop(hyaline);
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    fn concurrent_loads_and_stores() {
+        let hyaline = Hyaline::new().with_batch_size(8);
+        let atom_box: AtomBoxIn<'_, _, 0, _> = AtomBoxIn::new_with_protection(0_usize, &hyaline);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for value in 1..=1000 {
+                    atom_box.store(value);
+                }
+            });
+            for _ in 0..3 {
+                scope.spawn(|| {
+                    let mut last = 0;
+                    for _ in 0..1000 {
+                        let value = *atom_box.load();
+                        assert!(value >= last, "Values should not go backwards");
+                        last = value;
+                    }
+                });
+            }
+        });
+
+        assert_eq!(*atom_box.load(), 1000);
+    }
+}
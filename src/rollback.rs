@@ -0,0 +1,311 @@
+//! Rollback
+//!
+//! An `AtomBox` which remembers the value it held before the last store, so that the store can be
+//! undone.
+
+use crate::collections::Hazard;
+use crate::domain::{Domain, RetirePolicy};
+use crate::sync::{AtomicPtr, Ordering};
+use crate::{LoadGuard, StoreGuard};
+use alloc::boxed::Box;
+use core::fmt;
+
+/// The error returned by [`RollbackAtomBox::rollback`] when there is no previous value to restore.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NoHistory;
+
+impl fmt::Display for NoHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the AtomBox has no previous value to roll back to")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NoHistory {}
+
+/// The current and previous values of a [`RollbackAtomBox`].
+///
+/// States are never modified once published. Each store or rollback publishes a new state, so
+/// both values are always replaced together.
+struct State<T> {
+    current: *mut T,
+    // Null if there is no previous value.
+    previous: *mut T,
+}
+
+/// An `AtomBox` which keeps the value it held before the last store, so that the store can be
+/// rolled back.
+///
+/// The previous value is kept alive, rather than retired, until it is either restored by
+/// [`RollbackAtomBox::rollback`] or replaced as the previous value by the next store. Only one
+/// value of history is kept: rolling back clears it, so a second rollback fails until the next
+/// store.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{NoHistory, RollbackAtomBox};
+///
+/// let config = RollbackAtomBox::new("known good");
+/// assert_eq!(config.rollback().err(), Some(NoHistory));
+///
+/// config.store("broken");
+/// let reverted = config.rollback().unwrap();
+///
+/// assert_eq!(*reverted, "broken");
+/// assert_eq!(*config.load(), "known good");
+/// assert!(!config.can_rollback());
+/// ```
+pub struct RollbackAtomBox<'domain, T, const DOMAIN_ID: usize> {
+    state: AtomicPtr<State<T>>,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// Values stored on one thread may be read from, and dropped by, any other thread.
+unsafe impl<'domain, T: Send, const DOMAIN_ID: usize> Send
+    for RollbackAtomBox<'domain, T, DOMAIN_ID>
+{
+}
+unsafe impl<'domain, T: Send + Sync, const DOMAIN_ID: usize> Sync
+    for RollbackAtomBox<'domain, T, DOMAIN_ID>
+{
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> fmt::Debug for RollbackAtomBox<'domain, T, DOMAIN_ID> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RollbackAtomBox")
+            .field("can_rollback", &self.can_rollback())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> RollbackAtomBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `RollbackAtomBox` associated with the shared (global) domain.
+    pub fn new(value: T) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> RollbackAtomBox<'domain, T, DOMAIN_ID> {
+    /// Creates a new `RollbackAtomBox`, with no previous value, and associates it with the given
+    /// domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{RollbackAtomBox, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let atom_box = RollbackAtomBox::new_with_domain(5, &CUSTOM_DOMAIN);
+    /// assert_eq!(*atom_box.load(), 5);
+    /// ```
+    pub fn new_with_domain(value: T, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            state: AtomicPtr::new(Box::into_raw(Box::new(State {
+                current: Box::into_raw(Box::new(value)),
+                previous: core::ptr::null_mut(),
+            }))),
+            domain,
+        }
+    }
+
+    /// Loads the current value.
+    pub fn load(&self) -> LoadGuard<'domain, T, DOMAIN_ID> {
+        let state_hazard = Hazard::new(self.domain);
+        let value_hazard = Hazard::new(self.domain);
+        let mut state_ptr = state_hazard.protect_ptr(&self.state);
+        loop {
+            // # Safety
+            //
+            // The state is protected by its hazard.
+            let current = unsafe { &*state_ptr }.current;
+            value_hazard.protect(current);
+            crate::sync::fence(Ordering::SeqCst);
+            // A value is only retired once no published state refers to it, so if the state is
+            // still current, the value has not been retired and is now protected.
+            let latest_ptr = state_hazard.protect_ptr(&self.state);
+            if latest_ptr == state_ptr {
+                break value_hazard.into_load_guard(current);
+            }
+            state_ptr = latest_ptr;
+        }
+    }
+
+    /// Returns `true` if there is a previous value to roll back to.
+    pub fn can_rollback(&self) -> bool {
+        let hazard = Hazard::new(self.domain);
+        let state_ptr = hazard.protect_ptr(&self.state);
+        // # Safety
+        //
+        // The state is protected by the hazard.
+        !unsafe { &*state_ptr }.previous.is_null()
+    }
+
+    /// Stores a new value, keeping the current value as the previous value.
+    ///
+    /// The value which was previously kept is retired.
+    pub fn store(&self, value: T) {
+        let value = Box::into_raw(Box::new(value));
+        let replaced = self.replace(|state| {
+            Some(State {
+                current: value,
+                previous: state.current,
+            })
+        });
+        if let Some(replaced) = replaced {
+            if !replaced.previous.is_null() {
+                // # Safety
+                //
+                // The previous value is no longer referred to by the published state, and only
+                // the thread which replaced the state can retire it.
+                unsafe { self.domain.retire(replaced.previous) };
+            }
+        }
+    }
+
+    /// Restores the previous value, returning the value it replaced.
+    ///
+    /// Returns `Err(NoHistory)` if nothing has been stored since the box was created or last
+    /// rolled back.
+    pub fn rollback(&self) -> Result<StoreGuard<'domain, T, DOMAIN_ID>, NoHistory> {
+        let replaced = self
+            .replace(|state| {
+                (!state.previous.is_null()).then_some(State {
+                    current: state.previous,
+                    previous: core::ptr::null_mut(),
+                })
+            })
+            .ok_or(NoHistory)?;
+        Ok(StoreGuard {
+            ptr: replaced.current,
+            domain: self.domain,
+            retire_policy: RetirePolicy::Domain,
+        })
+    }
+
+    /// Publishes the state returned by `f` in place of the current state, returning a copy of the
+    /// state which was replaced, or `None` if `f` returns `None`.
+    fn replace(&self, mut f: impl FnMut(&State<T>) -> Option<State<T>>) -> Option<State<T>> {
+        let hazard = Hazard::new(self.domain);
+        loop {
+            let state_ptr = hazard.protect_ptr(&self.state);
+            // # Safety
+            //
+            // The state is protected by the hazard.
+            let state = unsafe { &*state_ptr };
+            let new_state = Box::into_raw(Box::new(f(state)?));
+            match self.state.compare_exchange(
+                state_ptr,
+                new_state,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    let replaced = State {
+                        current: state.current,
+                        previous: state.previous,
+                    };
+                    // # Safety
+                    //
+                    // The state was created via `Box::into_raw` and is no longer reachable from
+                    // the box. Only the thread which replaced it can retire it.
+                    unsafe { self.domain.retire(state_ptr) };
+                    break Some(replaced);
+                }
+                // # Safety
+                //
+                // The new state was never published, so we still own it.
+                Err(_) => drop(unsafe { Box::from_raw(new_state) }),
+            }
+        }
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Drop for RollbackAtomBox<'domain, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        let state_ptr = self.state.load(Ordering::Relaxed);
+        // # Safety
+        //
+        // We have exclusive access to the box, so the state and its values are retired exactly
+        // once. Guards may still reference the current value, so it is retired rather than
+        // dropped.
+        unsafe {
+            let state = &*state_ptr;
+            self.domain.retire(state.current);
+            if !state.previous.is_null() {
+                self.domain.retire(state.previous);
+            }
+            self.domain.retire(state_ptr);
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn only_the_last_store_can_be_rolled_back() {
+        let atom_box = RollbackAtomBox::new_with_domain(1, &TEST_DOMAIN);
+        atom_box.store(2);
+        atom_box.store(3);
+
+        let reverted = atom_box.rollback().map(|guard| *guard);
+        let second_rollback = atom_box.rollback().map(|guard| *guard);
+
+        assert_eq!(reverted, Ok(3));
+        assert_eq!(second_rollback, Err(NoHistory));
+        assert_eq!(*atom_box.load(), 2);
+    }
+
+    #[test]
+    fn previous_values_are_kept_until_replaced() {
+        let drop_counter = DropCounter::new();
+        let atom_box = RollbackAtomBox::new_with_domain(drop_counter.track(1), &TEST_DOMAIN);
+
+        atom_box.store(drop_counter.track(2));
+        TEST_DOMAIN.reclaim();
+        let drops_while_kept = drop_counter.count();
+        atom_box.store(drop_counter.track(3));
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(drops_while_kept, 0, "The previous value is kept");
+        drop_counter.assert_drops(1);
+        drop(atom_box);
+        TEST_DOMAIN.reclaim();
+        drop_counter.assert_drops(3);
+    }
+
+    #[test]
+    fn concurrent_stores_and_rollbacks() {
+        let atom_box = RollbackAtomBox::new_with_domain(0_usize, &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let atom_box = &atom_box;
+                scope.spawn(move || {
+                    for value in 0..500 {
+                        if value % 3 == 0 {
+                            let _ = atom_box.rollback();
+                        } else {
+                            atom_box.store(thread * 1000 + value);
+                        }
+                        assert!(*atom_box.load() < 4000);
+                    }
+                });
+            }
+        });
+
+        atom_box.store(4000);
+        assert_eq!(*atom_box.rollback().unwrap(), 4000);
+    }
+}
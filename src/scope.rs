@@ -0,0 +1,148 @@
+//! Scope
+//!
+//! A scope which keeps every value loaded through it protected until the scope ends, releasing
+//! the protection in one batch.
+
+use crate::domain::{Domain, HazardPointer};
+use crate::AtomBoxIn;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+/// A scope within which loaded values remain protected, releasing their hazard pointers together
+/// when the scope is dropped.
+///
+/// Values loaded through a `GuardScope` are borrowed from the scope rather than returned in a
+/// [`LoadGuard`](crate::LoadGuard), so they do not release their hazard pointers individually.
+/// Code which loads many boxes in a short-lived context, such as handling a request, trades
+/// slightly longer protection for fewer atomic operations. The scope can optionally reclaim the
+/// domain's retired values when it ends, see [`GuardScope::reclaim_on_drop`].
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBox, GuardScope};
+///
+/// let name = AtomBox::new("Ferris");
+/// let greeting = AtomBox::new("Hello");
+///
+/// let scope = GuardScope::new();
+/// let message = format!("{} {}", scope.load(&greeting), scope.load(&name));
+/// drop(scope);
+///
+/// assert_eq!(message, "Hello Ferris");
+/// ```
+#[derive(Debug)]
+pub struct GuardScope<'domain, const DOMAIN_ID: usize> {
+    domain: &'domain Domain<DOMAIN_ID>,
+    haz_ptrs: RefCell<Vec<HazardPointer<'domain>>>,
+    reclaim_on_drop: bool,
+}
+
+#[cfg(not(loom))]
+impl GuardScope<'static, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `GuardScope` for boxes associated with the shared (global) domain.
+    pub fn new() -> Self {
+        Self::new_with_domain(&crate::SHARED_DOMAIN)
+    }
+}
+
+#[cfg(not(loom))]
+impl Default for GuardScope<'static, { crate::SHARED_DOMAIN_ID }> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'domain, const DOMAIN_ID: usize> GuardScope<'domain, DOMAIN_ID> {
+    /// Creates a new `GuardScope` for boxes associated with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomBoxIn, GuardScope, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+    ///
+    /// let atom_box = AtomBoxIn::new_with_domain(5, &CUSTOM_DOMAIN);
+    /// let scope = GuardScope::new_with_domain(&CUSTOM_DOMAIN).reclaim_on_drop();
+    /// assert_eq!(*scope.load(&atom_box), 5);
+    /// ```
+    pub fn new_with_domain(domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            domain,
+            haz_ptrs: RefCell::new(Vec::new()),
+            reclaim_on_drop: false,
+        }
+    }
+
+    /// Reclaims the domain's unprotected retired values when the scope is dropped, after the
+    /// scope's own protection has been released.
+    pub fn reclaim_on_drop(mut self) -> Self {
+        self.reclaim_on_drop = true;
+        self
+    }
+
+    /// Loads the value stored in `atom_box`, keeping it protected until the scope is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `atom_box` is associated with a different domain.
+    pub fn load<'s, T>(&'s self, atom_box: &AtomBoxIn<'domain, T, DOMAIN_ID>) -> &'s T {
+        crate::assert_same_domain(self.domain, atom_box.domain);
+        let mut guard = atom_box.load();
+        if let Some(haz_ptr) = guard.haz_ptr.take() {
+            self.haz_ptrs.borrow_mut().push(haz_ptr);
+        }
+        // # Safety
+        //
+        // The value is protected by a hazard pointer which is only released when the scope is
+        // dropped, so it cannot be reclaimed while borrowed from the scope. The pointer was created
+        // via a Box so is aligned and there are no mutable references since we do not give any
+        // out.
+        unsafe { guard.ptr.as_ref().expect("Non null") }
+    }
+
+    /// The number of values this scope is protecting.
+    pub fn protected(&self) -> usize {
+        self.haz_ptrs.borrow().len()
+    }
+}
+
+impl<const DOMAIN_ID: usize> Drop for GuardScope<'_, DOMAIN_ID> {
+    fn drop(&mut self) {
+        self.domain
+            .release_hazard_ptrs(self.haz_ptrs.get_mut().drain(..));
+        if self.reclaim_on_drop {
+            self.domain.reclaim();
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Manual);
+
+    #[test]
+    fn values_stay_protected_until_the_scope_ends() {
+        let drop_counter = DropCounter::new();
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &TEST_DOMAIN);
+        let scope = GuardScope::new_with_domain(&TEST_DOMAIN).reclaim_on_drop();
+        let value = scope.load(&atom_box);
+
+        atom_box.store(drop_counter.track(2));
+        TEST_DOMAIN.reclaim();
+        let value_while_scoped = **value;
+        let protected = scope.protected();
+        drop(scope);
+
+        assert_eq!(value_while_scoped, 1);
+        assert_eq!(protected, 1);
+        drop_counter.assert_drops(1);
+    }
+}
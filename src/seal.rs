@@ -0,0 +1,397 @@
+//! Seal
+//!
+//! An `AtomBox` which can be sealed, making its current value permanent. The stored pointer is
+//! marked with a tag bit, so that writers see the box is sealed in the same atomic operation which
+//! would otherwise have replaced the value, and readers know the value can no longer be retired.
+
+use crate::domain::{self, Domain, HazardPointer};
+use crate::sync::Ordering;
+use crate::{AtomBoxIn, LoadGuard, StoreGuard};
+use alloc::boxed::Box;
+use core::fmt;
+
+// The low bit marks the pointer to the value of a sealed box.
+const SEALED: usize = 1;
+
+/// Returns whether `ptr`, loaded from a `SealableAtomBox`, is the tagged pointer of a sealed box.
+fn is_sealed<T>(ptr: *mut T) -> bool {
+    ptr as usize & SEALED == SEALED
+}
+
+fn seal<T>(ptr: *mut T) -> *mut T {
+    (ptr as usize | SEALED) as *mut T
+}
+
+fn unseal<T>(ptr: *mut T) -> *mut T {
+    (ptr as usize & !SEALED) as *mut T
+}
+
+/// The error returned when storing a value into a [`SealableAtomBox`] which has been
+/// [sealed](SealableAtomBox::seal).
+///
+/// Contains the value which was rejected, so that it is not lost.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{SealableAtomBox, SealedError};
+///
+/// let atom_box = SealableAtomBox::new(vec![1, 2, 3]);
+/// atom_box.seal();
+/// match atom_box.store(vec![4, 5, 6]) {
+///     Ok(()) => panic!("A sealed box cannot be updated"),
+///     Err(SealedError(value)) => assert_eq!(value, [4, 5, 6]),
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SealedError<T>(pub T);
+
+impl<T> SealedError<T> {
+    /// Returns the value which was rejected.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for SealedError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SealedError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SealedError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the AtomBox is sealed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> std::error::Error for SealedError<T> {}
+
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for SealedError<T> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "SealedError(..)")
+    }
+}
+
+/// An `AtomBox` which can be [sealed](SealableAtomBox::seal), after which its value can never be
+/// replaced.
+///
+/// Useful for configuration which becomes immutable once a program has started. Every operation
+/// which would store a value into a sealed box fails instead, handing back the rejected value in
+/// a [`SealedError`]. Loads from a sealed box still protect the value, since it is retired once
+/// the box is dropped, but never have to validate the protection.
+///
+/// Values aligned to a single byte cannot be stored in a `SealableAtomBox`, as their pointers
+/// cannot be marked as sealed. The following example will fail to compile.
+///
+/// ```compile_fail
+/// use atom_box::SealableAtomBox;
+///
+/// let flag = SealableAtomBox::new(true);
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use atom_box::SealableAtomBox;
+///
+/// let config = SealableAtomBox::new(String::from("initial"));
+/// config.store(String::from("loaded")).unwrap();
+/// config.seal();
+///
+/// assert!(config.is_sealed());
+/// assert_eq!(*config.load(), "loaded");
+/// assert!(config.store(String::from("changed")).is_err());
+/// ```
+pub struct SealableAtomBox<'domain, T, const DOMAIN_ID: usize> {
+    atom_box: AtomBoxIn<'domain, T, DOMAIN_ID>,
+}
+
+impl<T, const DOMAIN_ID: usize> fmt::Debug for SealableAtomBox<'_, T, DOMAIN_ID> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SealableAtomBox")
+            .field("sealed", &self.is_sealed())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> SealableAtomBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `SealableAtomBox` associated with the shared (global) domain.
+    pub fn new(value: T) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> SealableAtomBox<'domain, T, DOMAIN_ID> {
+    const ALIGNED: () = assert!(
+        core::mem::align_of::<T>() > SEALED,
+        "SealableAtomBox values must be aligned to more than one byte"
+    );
+
+    /// Creates a new `SealableAtomBox` and associates it with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{SealableAtomBox, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let atom_box = SealableAtomBox::new_with_domain(5_u32, &CUSTOM_DOMAIN);
+    /// assert_eq!(*atom_box.load(), 5);
+    /// ```
+    pub fn new_with_domain(value: T, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::ALIGNED;
+        Self {
+            atom_box: AtomBoxIn::new_with_domain(value, domain),
+        }
+    }
+
+    /// Makes the current value permanent.
+    ///
+    /// Sealing a box which is already sealed has no effect.
+    pub fn seal(&self) {
+        let ptr = &self.atom_box.ptr;
+        let mut current_ptr = ptr.load(Ordering::Acquire);
+        while !is_sealed(current_ptr) {
+            match ptr.compare_exchange_weak(
+                current_ptr,
+                seal(current_ptr),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return,
+                Err(actual_ptr) => current_ptr = actual_ptr,
+            }
+        }
+    }
+
+    /// Returns whether the box has been [sealed](SealableAtomBox::seal).
+    pub fn is_sealed(&self) -> bool {
+        is_sealed(self.atom_box.ptr.load(Ordering::Acquire))
+    }
+
+    /// Loads the value stored in the `SealableAtomBox`.
+    ///
+    /// See [`AtomBoxIn::load`].
+    pub fn load(&self) -> LoadGuard<'domain, T, DOMAIN_ID> {
+        if !domain::needs_reclaim::<T>() {
+            return self.atom_box.unprotected_load();
+        }
+        let haz_ptr = self.atom_box.domain.acquire_haz_ptr();
+        let ptr = self.protect(&haz_ptr);
+        LoadGuard {
+            ptr,
+            domain: self.atom_box.domain,
+            haz_ptr: Some(haz_ptr),
+        }
+    }
+
+    /// Stores a new value.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value in a [`SealedError`] if the box has been sealed.
+    pub fn store(&self, value: T) -> Result<(), SealedError<T>> {
+        self.swap(value).map(drop)
+    }
+
+    /// Stores a new value and returns a `StoreGuard` which dereferences into the previous value.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value in a [`SealedError`] if the box has been sealed.
+    pub fn swap(&self, new_value: T) -> Result<StoreGuard<'domain, T, DOMAIN_ID>, SealedError<T>> {
+        let new_ptr = Box::into_raw(Box::new(new_value));
+        let ptr = &self.atom_box.ptr;
+        let mut current_ptr = ptr.load(Ordering::Acquire);
+        loop {
+            if is_sealed(current_ptr) {
+                // # Safety
+                //
+                // The new value was never shared so we still have exclusive ownership.
+                return Err(SealedError(*unsafe { Box::from_raw(new_ptr) }));
+            }
+            match ptr.compare_exchange_weak(
+                current_ptr,
+                new_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(old_ptr) => {
+                    return Ok(StoreGuard {
+                        ptr: old_ptr,
+                        domain: self.atom_box.domain,
+                        retire_policy: self.atom_box.retire_policy,
+                    })
+                }
+                Err(actual_ptr) => current_ptr = actual_ptr,
+            }
+        }
+    }
+
+    /// Replaces the value with the result of `f`, retrying with the updated value if it changes
+    /// in the meantime.
+    ///
+    /// See [`AtomBoxIn::modify`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the new value in a [`SealedError`] if the box has been sealed.
+    #[allow(clippy::type_complexity)]
+    pub fn modify(
+        &self,
+        mut f: impl FnMut(&T) -> T,
+    ) -> Result<
+        (
+            StoreGuard<'domain, T, DOMAIN_ID>,
+            LoadGuard<'domain, T, DOMAIN_ID>,
+        ),
+        SealedError<T>,
+    > {
+        let domain = self.atom_box.domain;
+        let ptr = &self.atom_box.ptr;
+        let current_haz_ptr = domain.acquire_haz_ptr();
+        let new_haz_ptr = domain.acquire_haz_ptr();
+        let mut current_ptr = self.protect(&current_haz_ptr);
+        loop {
+            // # Safety
+            //
+            // The current value is protected by the hazard pointer.
+            let new_ptr = Box::into_raw(Box::new(f(unsafe { &*current_ptr })));
+            // The new value is not shared until the exchange succeeds, so it cannot have been
+            // retired before it is protected.
+            new_haz_ptr.protect(new_ptr as *mut usize);
+            match ptr.compare_exchange(current_ptr, new_ptr, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(old_ptr) => {
+                    domain.release_hazard_ptr(current_haz_ptr);
+                    return Ok((
+                        StoreGuard {
+                            ptr: old_ptr,
+                            domain,
+                            retire_policy: self.atom_box.retire_policy,
+                        },
+                        LoadGuard {
+                            ptr: new_ptr,
+                            domain,
+                            haz_ptr: Some(new_haz_ptr),
+                        },
+                    ));
+                }
+                Err(actual_ptr) => {
+                    new_haz_ptr.reset();
+                    // # Safety
+                    //
+                    // The new value was never shared so we still have exclusive ownership.
+                    let new_value = *unsafe { Box::from_raw(new_ptr) };
+                    if is_sealed(actual_ptr) {
+                        domain.release_hazard_ptr(current_haz_ptr);
+                        domain.release_hazard_ptr(new_haz_ptr);
+                        return Err(SealedError(new_value));
+                    }
+                    drop(new_value);
+                    current_ptr = self.protect(&current_haz_ptr);
+                }
+            }
+        }
+    }
+
+    /// Protects the current value with `haz_ptr`.
+    fn protect(&self, haz_ptr: &HazardPointer<'domain>) -> *mut T {
+        let mut ptr = self.atom_box.ptr.load(Ordering::Acquire);
+        if !is_sealed(ptr) {
+            ptr = haz_ptr.protect_ptr(&self.atom_box.ptr);
+            if !is_sealed(ptr) {
+                return ptr;
+            }
+        }
+        // The value of a sealed box is only retired once the box is dropped, which cannot happen
+        // while it is borrowed. The protection only has to be visible to the thread which drops
+        // the box, which must synchronise with this one first, so it needs no fence or
+        // validation. Values are retired by their untagged address, so that is what is published.
+        let value_ptr = unseal(ptr);
+        haz_ptr.protect(value_ptr as *mut usize);
+        value_ptr
+    }
+}
+
+impl<T, const DOMAIN_ID: usize> Drop for SealableAtomBox<'_, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        // Removes the sealed tag, so the inner box retires the value rather than the tagged
+        // pointer.
+        let ptr = self.atom_box.ptr.load(Ordering::Acquire);
+        self.atom_box.ptr.store(unseal(ptr), Ordering::Relaxed);
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::SealableAtomBox;
+    use crate::domain::{Domain, ReclaimStrategy};
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn sealed_boxes_reject_updates() {
+        let drop_counter = DropCounter::new();
+        let atom_box = SealableAtomBox::new_with_domain(drop_counter.track(1_u64), &TEST_DOMAIN);
+
+        atom_box.seal();
+        let swapped = atom_box.swap(drop_counter.track(2));
+
+        assert!(atom_box.is_sealed(), "The box is sealed");
+        match swapped {
+            Ok(_) => panic!("A sealed box cannot be updated"),
+            Err(rejected) => assert_eq!(*rejected.0, 2, "The rejected value is handed back"),
+        }
+        let value = atom_box.load();
+        assert_eq!(**value, 1, "The sealed value is loaded");
+        drop(value);
+        drop(atom_box);
+        TEST_DOMAIN.reclaim();
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    fn sealed_values_stay_protected_after_the_box_is_dropped() {
+        let drop_counter = DropCounter::new();
+        let atom_box = SealableAtomBox::new_with_domain(drop_counter.track(1_u64), &TEST_DOMAIN);
+        atom_box.seal();
+
+        let value = atom_box.load();
+        drop(atom_box);
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(**value, 1, "The value outlives the box");
+        drop_counter.assert_drops(0);
+        drop(value);
+        TEST_DOMAIN.reclaim();
+        drop_counter.assert_drops(1);
+    }
+
+    #[test]
+    fn storing_into_a_sealed_box_hands_back_the_value() {
+        let atom_box = SealableAtomBox::new_with_domain(1_u64, &TEST_DOMAIN);
+        atom_box.seal();
+
+        assert_eq!(atom_box.store(2).unwrap_err().into_inner(), 2);
+        assert_eq!(atom_box.swap(3).map(drop).unwrap_err().into_inner(), 3);
+        assert_eq!(
+            atom_box
+                .modify(|value| value + 1)
+                .map(drop)
+                .unwrap_err()
+                .into_inner(),
+            2,
+            "The modified value is handed back"
+        );
+        assert_eq!(*atom_box.load(), 1, "The sealed value is unchanged");
+    }
+}
@@ -0,0 +1,382 @@
+//! Seqlock
+//!
+//! An `AtomBox` for small values without padding which keeps an inline copy of the current value
+//! guarded by a sequence counter. Readers copy the inline value and validate the counter, only falling
+//! back to the hazard protected path if a write is in progress.
+
+use crate::domain::Domain;
+use crate::sync::{AtomicUsize, Ordering};
+use crate::{AtomBoxIn, LoadGuard, StoreGuard};
+use core::cell::UnsafeCell;
+use core::mem::{self, MaybeUninit};
+use core::sync::atomic::{AtomicU8, AtomicUsize as AtomicWord};
+
+/// The size in bytes of the largest value a `SeqLockAtomBox` keeps inline.
+const MAX_INLINE_SIZE: usize = 64;
+
+/// Types whose values have no uninitialised bytes.
+///
+/// [`SeqLockAtomBox`] copies its inline value as integers, so every byte of the value must be
+/// initialised.
+///
+/// # Safety
+///
+/// The type must not contain padding, or any other bytes which may be uninitialised, such as a
+/// `MaybeUninit` or a union. A `#[repr(C)]` or `#[repr(transparent)]` struct whose fields all
+/// implement `NoUninit` and which has no padding between or after them satisfies this.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::NoUninit;
+///
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// // # Safety
+/// //
+/// // Both fields are `i32`s, so there is no padding.
+/// unsafe impl NoUninit for Point {}
+/// ```
+pub unsafe trait NoUninit: Copy {}
+
+macro_rules! impl_no_uninit {
+    ($($type:ty),*) => {
+        $(unsafe impl NoUninit for $type {})*
+    };
+}
+
+impl_no_uninit!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64
+);
+
+unsafe impl<T: NoUninit, const N: usize> NoUninit for [T; N] {}
+
+/// An `AtomBox` for small values without padding with a seqlock optimised read path.
+///
+/// Values must implement [`NoUninit`] and be at most 64 bytes, which is checked when the box is
+/// created.
+///
+/// ```compile_fail
+/// use atom_box::SeqLockAtomBox;
+///
+/// let too_large = SeqLockAtomBox::new([0_u64; 16]);
+/// ```
+///
+/// [`SeqLockAtomBox::read`] copies the value without touching any hazard pointers. If a writer is
+/// concurrently updating the value, the read falls back to loading the value through the
+/// underlying [`AtomBoxIn`].
+///
+/// Writers are serialised with respect to each other by the sequence counter, so this is best
+/// suited to values which are read far more frequently than they are written.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{NoUninit, SeqLockAtomBox};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// #[repr(C)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// // # Safety
+/// //
+/// // Both fields are `i32`s, so there is no padding.
+/// unsafe impl NoUninit for Point {}
+///
+/// let point = SeqLockAtomBox::new(Point { x: 1, y: 2 });
+/// assert_eq!(point.read(), Point { x: 1, y: 2 });
+///
+/// let previous = point.swap(Point { x: 3, y: 4 });
+/// assert_eq!(*previous, Point { x: 1, y: 2 });
+/// assert_eq!(point.read(), Point { x: 3, y: 4 });
+/// ```
+#[derive(Debug)]
+pub struct SeqLockAtomBox<'domain, T, const DOMAIN_ID: usize> {
+    atom_box: AtomBoxIn<'domain, T, DOMAIN_ID>,
+    // Odd while a writer is updating the inline value.
+    sequence: AtomicUsize,
+    // Only accessed through atomic loads and stores, so a read racing with a write is not a data
+    // race. A torn copy is discarded before it is assumed to be initialised.
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+unsafe impl<'domain, T: NoUninit + Send + Sync, const DOMAIN_ID: usize> Sync
+    for SeqLockAtomBox<'domain, T, DOMAIN_ID>
+{
+}
+
+#[cfg(not(loom))]
+impl<T: NoUninit> SeqLockAtomBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `SeqLockAtomBox` associated with the shared (global) domain.
+    ///
+    /// Fails to compile if `T` is larger than 64 bytes.
+    pub fn new(value: T) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T: NoUninit, const DOMAIN_ID: usize> SeqLockAtomBox<'domain, T, DOMAIN_ID> {
+    const SMALL: () = assert!(
+        mem::size_of::<T>() <= MAX_INLINE_SIZE,
+        "SeqLockAtomBox values must be at most 64 bytes"
+    );
+
+    /// Creates a new `SeqLockAtomBox` and associates it with the given domain.
+    ///
+    /// Fails to compile if `T` is larger than 64 bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{SeqLockAtomBox, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let seq_lock_box = SeqLockAtomBox::new_with_domain(5_u64, &CUSTOM_DOMAIN);
+    /// assert_eq!(seq_lock_box.read(), 5);
+    /// ```
+    pub fn new_with_domain(value: T, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::SMALL;
+        Self {
+            atom_box: AtomBoxIn::new_with_domain(value, domain),
+            sequence: AtomicUsize::new(0),
+            value: UnsafeCell::new(MaybeUninit::new(value)),
+        }
+    }
+
+    /// Returns a copy of the current value.
+    ///
+    /// The value is read optimistically from the inline copy. Hazard pointers are only used if a
+    /// write is in progress.
+    pub fn read(&self) -> T {
+        match self.optimistic_read() {
+            Some(value) => value,
+            None => *self.atom_box.load(),
+        }
+    }
+
+    /// Loads the current value through the underlying `AtomBox`.
+    ///
+    /// See [`AtomBoxIn::load`].
+    pub fn load(&self) -> LoadGuard<'domain, T, DOMAIN_ID> {
+        self.atom_box.load()
+    }
+
+    /// Stores a new value.
+    pub fn store(&self, value: T) {
+        let _ = self.swap(value);
+    }
+
+    /// Stores a new value and returns a `StoreGuard` which dereferences into the previous value.
+    ///
+    /// See [`AtomBoxIn::swap`].
+    pub fn swap(&self, value: T) -> StoreGuard<'domain, T, DOMAIN_ID> {
+        let sequence = self.lock();
+        let previous = self.atom_box.swap(value);
+        // # Safety
+        //
+        // We hold the write lock so no other writer is accessing the inline value. Readers only
+        // use the inline value if the sequence did not change while they copied it.
+        unsafe { store_atomically(self.value.get() as *mut T, &value) };
+        self.sequence
+            .store(sequence.wrapping_add(2), Ordering::Release);
+        previous
+    }
+
+    /// Copies the inline value, returning `None` if a writer was active during the copy.
+    fn optimistic_read(&self) -> Option<T> {
+        let sequence = self.sequence.load(Ordering::Acquire);
+        if sequence & 1 == 1 {
+            return None;
+        }
+        // # Safety
+        //
+        // The pointer is valid and aligned. The copy may race with a writer, but both only use
+        // atomic accesses.
+        let value = unsafe { load_atomically(self.value.get() as *const T) };
+
+        crate::sync::fence(Ordering::Acquire);
+
+        if self.sequence.load(Ordering::Relaxed) == sequence {
+            // # Safety
+            //
+            // No writer was active during the copy, so it is a whole copy of an initialised value.
+            Some(unsafe { value.assume_init() })
+        } else {
+            None
+        }
+    }
+
+    /// Acquires the write lock by making the sequence odd, returning the even sequence it replaced.
+    fn lock(&self) -> usize {
+        loop {
+            let sequence = self.sequence.load(Ordering::Relaxed);
+            if sequence & 1 == 0
+                && self
+                    .sequence
+                    .compare_exchange_weak(
+                        sequence,
+                        sequence.wrapping_add(1),
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+            {
+                crate::sync::fence(Ordering::Release);
+                return sequence;
+            }
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// The number of leading words of a `T` which are copied a word at a time, the remainder being
+/// copied a byte at a time.
+const fn words<T>() -> usize {
+    if mem::align_of::<T>() >= mem::align_of::<AtomicWord>() {
+        mem::size_of::<T>() / mem::size_of::<AtomicWord>()
+    } else {
+        0
+    }
+}
+
+/// Copies the `T` at `src` with relaxed atomic loads.
+///
+/// # Safety
+///
+/// `src` must be valid for a `T` and aligned, and every concurrent write to it must be made by
+/// [`store_atomically`].
+unsafe fn load_atomically<T: NoUninit>(src: *const T) -> MaybeUninit<T> {
+    let mut value = MaybeUninit::<T>::uninit();
+    let (src, dst) = (src as *const u8, value.as_mut_ptr() as *mut u8);
+    // # Safety
+    //
+    // The words and bytes lie within both values, and the words are aligned since `T` is.
+    unsafe {
+        for word in 0..words::<T>() {
+            let loaded = (*(src as *const AtomicWord).add(word)).load(Ordering::Relaxed);
+            (dst as *mut usize).add(word).write(loaded);
+        }
+        for byte in words::<T>() * mem::size_of::<AtomicWord>()..mem::size_of::<T>() {
+            let loaded = (*(src.add(byte) as *const AtomicU8)).load(Ordering::Relaxed);
+            dst.add(byte).write(loaded);
+        }
+    }
+    value
+}
+
+/// Writes `value` to `dst` with relaxed atomic stores.
+///
+/// # Safety
+///
+/// `dst` must be valid for a `T` and aligned, and every concurrent access to it must be made by
+/// [`load_atomically`].
+unsafe fn store_atomically<T: NoUninit>(dst: *mut T, value: &T) {
+    let (src, dst) = (value as *const T as *const u8, dst as *mut u8);
+    // # Safety
+    //
+    // The words and bytes lie within both values, and the words are aligned since `T` is. Every
+    // byte of `value` is initialised since `T` is `NoUninit`.
+    unsafe {
+        for word in 0..words::<T>() {
+            let stored = (src as *const usize).add(word).read();
+            (*(dst as *const AtomicWord).add(word)).store(stored, Ordering::Relaxed);
+        }
+        for byte in words::<T>() * mem::size_of::<AtomicWord>()..mem::size_of::<T>() {
+            (*(dst.add(byte) as *const AtomicU8)).store(src.add(byte).read(), Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn read_returns_latest_value() {
+        let seq_lock_box = SeqLockAtomBox::new_with_domain([1_u64, 2_u64], &TEST_DOMAIN);
+
+        let previous = seq_lock_box.swap([3, 4]);
+
+        assert_eq!(*previous, [1, 2], "Swap returns the previous value");
+        assert_eq!(seq_lock_box.read(), [3, 4], "Read returns the new value");
+        assert_eq!(*seq_lock_box.load(), [3, 4], "Load returns the new value");
+    }
+
+    #[test]
+    fn read_falls_back_while_writer_is_active() {
+        let seq_lock_box = SeqLockAtomBox::new_with_domain(10_u32, &TEST_DOMAIN);
+
+        let sequence = seq_lock_box.lock();
+
+        assert_eq!(
+            seq_lock_box.optimistic_read(),
+            None,
+            "Optimistic reads fail while a writer holds the lock"
+        );
+        assert_eq!(seq_lock_box.read(), 10, "Read falls back to the AtomBox");
+        seq_lock_box.sequence.store(sequence, Ordering::Release);
+        assert_eq!(
+            seq_lock_box.optimistic_read(),
+            Some(10),
+            "Optimistic reads succeed once the writer is done"
+        );
+    }
+
+    #[test]
+    fn concurrent_reads_are_never_torn() {
+        let seq_lock_box = SeqLockAtomBox::new_with_domain([0_u64; 4], &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for i in 1..=1000 {
+                    seq_lock_box.store([i; 4]);
+                }
+            });
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        let value = seq_lock_box.read();
+                        assert!(
+                            value.iter().all(|part| *part == value[0]),
+                            "Read a torn value {:?}",
+                            value
+                        );
+                    }
+                });
+            }
+        });
+
+        assert_eq!(seq_lock_box.read(), [1000; 4]);
+    }
+}
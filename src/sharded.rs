@@ -0,0 +1,280 @@
+//! Sharded
+//!
+//! An `AtomBox` which keeps a replica of its value per shard, so that readers on different cores
+//! do not contend on the same cache line.
+
+use crate::domain::Domain;
+use crate::sync::{AtomicBool, Ordering};
+use crate::{AtomBoxIn, LoadGuard, StoreGuard};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// A replica, aligned to keep neighbouring replicas on separate cache lines.
+#[derive(Debug)]
+#[repr(align(128))]
+struct Shard<'domain, T, const DOMAIN_ID: usize>(AtomBoxIn<'domain, T, DOMAIN_ID>);
+
+/// An `AtomBox` for read-hot, rarely written values, which keeps a replica of the value per
+/// shard.
+///
+/// Each thread loads from its own shard, so concurrent readers on different cores touch different
+/// cache lines. Stores are serialised and publish a clone of the value to every shard, retiring the
+/// replaced replicas through the domain. While a store is in progress, loads on different shards
+/// may observe the old and the new value.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::ShardedAtomBox;
+///
+/// let config = ShardedAtomBox::new(String::from("Hello"));
+/// assert_eq!(*config.load(), "Hello");
+///
+/// config.store(String::from("World"));
+/// assert_eq!(*config.load(), "World");
+/// ```
+#[derive(Debug)]
+pub struct ShardedAtomBox<'domain, T, const DOMAIN_ID: usize> {
+    shards: Box<[Shard<'domain, T, DOMAIN_ID>]>,
+    writing: AtomicBool,
+}
+
+#[cfg(not(loom))]
+impl<T: Clone> ShardedAtomBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `ShardedAtomBox` associated with the shared (global) domain, with one shard
+    /// per available core.
+    pub fn new(value: T) -> Self {
+        Self::new_with_domain(value, default_shards(), &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T: Clone, const DOMAIN_ID: usize> ShardedAtomBox<'domain, T, DOMAIN_ID> {
+    /// Creates a new `ShardedAtomBox` with `shards` replicas of `value`, associated with the given
+    /// domain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{ShardedAtomBox, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let sharded = ShardedAtomBox::new_with_domain(5, 4, &CUSTOM_DOMAIN);
+    /// assert_eq!(sharded.shards(), 4);
+    /// assert_eq!(*sharded.load_shard(3), 5);
+    /// ```
+    pub fn new_with_domain(value: T, shards: usize, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        assert!(shards > 0, "A ShardedAtomBox needs at least one shard");
+        let shards: Vec<_> = (0..shards)
+            .map(|_| Shard(AtomBoxIn::new_with_domain(value.clone(), domain)))
+            .collect();
+        Self {
+            shards: shards.into_boxed_slice(),
+            writing: AtomicBool::new(false),
+        }
+    }
+
+    /// Stores a new value in every shard.
+    pub fn store(&self, value: T) {
+        let _ = self.swap(value);
+    }
+
+    /// Stores a new value in every shard, returning the replica it replaced in the first shard.
+    ///
+    /// The other replaced replicas are retired.
+    pub fn swap(&self, value: T) -> StoreGuard<'domain, T, DOMAIN_ID> {
+        let _lock = self.lock();
+        let (first, rest) = self
+            .shards
+            .split_first()
+            .expect("There is at least one shard");
+        for shard in rest {
+            shard.0.store(value.clone());
+        }
+        first.0.swap(value)
+    }
+
+    /// Acquires the write lock, waiting for any other writer to finish.
+    ///
+    /// The lock is released when the returned guard is dropped, including if cloning the value
+    /// panics.
+    fn lock(&self) -> WriteLock<'_> {
+        while self
+            .writing
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        WriteLock(&self.writing)
+    }
+}
+
+/// Holds the write lock of a `ShardedAtomBox`, releasing it when dropped.
+struct WriteLock<'a>(&'a AtomicBool);
+
+impl Drop for WriteLock<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> ShardedAtomBox<'domain, T, DOMAIN_ID> {
+    /// Loads the value from the calling thread's shard.
+    ///
+    /// Threads are assigned shards in turn the first time they load from any `ShardedAtomBox`.
+    /// Without the `std` feature, every load uses the first shard; use
+    /// [`ShardedAtomBox::load_shard`] to choose the shard explicitly.
+    pub fn load(&self) -> LoadGuard<'domain, T, DOMAIN_ID> {
+        self.load_shard(current_shard())
+    }
+
+    /// Loads the value from shard `index`, modulo the number of shards.
+    pub fn load_shard(&self, index: usize) -> LoadGuard<'domain, T, DOMAIN_ID> {
+        self.shards[index % self.shards.len()].0.load()
+    }
+
+    /// The number of shards.
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+}
+
+#[cfg(all(feature = "std", not(loom)))]
+fn default_shards() -> usize {
+    std::thread::available_parallelism().map_or(1, usize::from)
+}
+
+#[cfg(all(not(feature = "std"), not(loom)))]
+fn default_shards() -> usize {
+    1
+}
+
+#[cfg(feature = "std")]
+fn current_shard() -> usize {
+    use core::cell::Cell;
+    use core::sync::atomic::AtomicUsize;
+
+    static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+    std::thread_local! {
+        static SHARD: Cell<Option<usize>> = const { Cell::new(None) };
+    }
+    SHARD.with(|shard| match shard.get() {
+        Some(index) => index,
+        None => {
+            let index = NEXT_SHARD.fetch_add(1, Ordering::Relaxed);
+            shard.set(Some(index));
+            index
+        }
+    })
+}
+
+#[cfg(not(feature = "std"))]
+fn current_shard() -> usize {
+    0
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn store_publishes_to_every_shard() {
+        let sharded = ShardedAtomBox::new_with_domain(1, 3, &TEST_DOMAIN);
+
+        let previous = sharded.swap(2);
+
+        assert_eq!(*previous, 1);
+        for index in 0..3 {
+            assert_eq!(*sharded.load_shard(index), 2, "Shard {} is updated", index);
+        }
+    }
+
+    #[test]
+    fn replaced_replicas_are_retired() {
+        let value = std::sync::Arc::new(1);
+        let sharded = ShardedAtomBox::new_with_domain(value.clone(), 4, &TEST_DOMAIN);
+        let replicas = std::sync::Arc::strong_count(&value) - 1;
+
+        sharded.store(std::sync::Arc::new(2));
+
+        assert_eq!(replicas, 4, "Each shard holds a replica");
+        assert_eq!(
+            std::sync::Arc::strong_count(&value),
+            1,
+            "Every replaced replica has been reclaimed"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn threads_load_from_their_own_shard() {
+        let sharded = ShardedAtomBox::new_with_domain(1, 2, &TEST_DOMAIN);
+
+        let shard = std::thread::spawn(current_shard).join().unwrap();
+        let other_shard = std::thread::spawn(current_shard).join().unwrap();
+
+        assert_ne!(shard, other_shard, "Threads are assigned shards in turn");
+        assert_eq!(current_shard(), current_shard(), "A thread keeps its shard");
+        assert_eq!(*sharded.load(), 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn panicking_clones_release_the_write_lock() {
+        #[derive(Debug)]
+        struct Fragile {
+            value: usize,
+            panic_on_clone: bool,
+        }
+
+        impl Clone for Fragile {
+            fn clone(&self) -> Self {
+                assert!(!self.panic_on_clone, "Cloning failed");
+                Self {
+                    value: self.value,
+                    panic_on_clone: false,
+                }
+            }
+        }
+
+        let sharded = ShardedAtomBox::new_with_domain(
+            Fragile {
+                value: 1,
+                panic_on_clone: false,
+            },
+            2,
+            &TEST_DOMAIN,
+        );
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            sharded.store(Fragile {
+                value: 2,
+                panic_on_clone: true,
+            })
+        }));
+        sharded.store(Fragile {
+            value: 3,
+            panic_on_clone: false,
+        });
+
+        assert!(result.is_err(), "The clone panicked");
+        for index in 0..2 {
+            assert_eq!(
+                sharded.load_shard(index).value,
+                3,
+                "Shard {} is updated",
+                index
+            );
+        }
+    }
+}
@@ -0,0 +1,692 @@
+//! Shared Memory
+//!
+//! An `AtomBox` which lives in a memory region shared between processes, enabled by the
+//! `shared-memory` feature.
+//!
+//! The region holds everything the box needs: its values, the hazard pointers protecting them and
+//! the registrations of the processes using it. Nothing in the region refers to an address, only
+//! to positions within the region, so each process can map it at a different address. Values are
+//! copied in and out of the region, so are restricted to `Copy` types whose layout is the same in
+//! every process, such as `#[repr(C)]` structs of plain data.
+//!
+//! Values are allocated from a fixed pool of slots within the region. A replaced value is retired,
+//! and any process can reclaim it once no process's hazard pointer protects it. Each slot's state
+//! is kept in a single word alongside a generation counter, which changes on every transition, so
+//! a process which was delayed while reclaiming a slot cannot free it after it has been reused.
+//!
+//! # Example
+//!
+//! ```
+//! use atom_box::shared_memory::SharedAtomBox;
+//!
+//! #[derive(Clone, Copy, Debug, PartialEq)]
+//! #[repr(C)]
+//! struct Config {
+//!     version: u32,
+//!     max_connections: u32,
+//! }
+//!
+//! // Usually a region mapped by each process, for example with `shm_open` and `mmap`.
+//! let mut region = vec![0_u64; SharedAtomBox::<Config>::region_size(4) / 8 + 1];
+//! let region = region.as_mut_ptr() as *mut u8;
+//! let len = SharedAtomBox::<Config>::region_size(4);
+//!
+//! // # Safety
+//! //
+//! // The region is valid for `len` bytes and outlives both handles.
+//! let publisher = unsafe {
+//!     SharedAtomBox::create(region, len, 4, Config { version: 1, max_connections: 10 })
+//! }
+//! .unwrap();
+//! let subscriber = unsafe { SharedAtomBox::<Config>::attach(region, len) }.unwrap();
+//!
+//! publisher.store(Config { version: 2, max_connections: 20 }).unwrap();
+//! assert_eq!(subscriber.load().version, 2);
+//! ```
+
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::fmt;
+use core::marker::PhantomData;
+use core::sync::atomic::{fence, AtomicUsize, Ordering};
+
+/// Marks a region which has been initialised by [`SharedAtomBox::create`].
+const MAGIC: usize = 0x4174_6f6d;
+
+// The states of a slot, held in the low bits of its state word. Above them, a slot being written
+// holds the registration writing it plus one, and the remaining high bits hold the generation.
+const FREE: usize = 0;
+const WRITING: usize = 1;
+const LIVE: usize = 2;
+const RETIRED: usize = 3;
+const STATE_BITS: u32 = 2;
+const STATE_MASK: usize = (1 << STATE_BITS) - 1;
+
+// A hazard pointer holds the index of the slot it protects plus one, or zero.
+const UNPROTECTED: usize = 0;
+
+/// The error returned when a region cannot be used for a [`SharedAtomBox`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum SharedMemoryError {
+    /// The region is smaller than [`SharedAtomBox::region_size`].
+    TooSmall,
+    /// The region is not aligned for the box's header or values.
+    Misaligned,
+    /// The region has not been initialised by [`SharedAtomBox::create`].
+    Uninitialised,
+    /// The region was created for values of a different size or alignment.
+    LayoutMismatch,
+    /// Every registration in the region is in use.
+    NoRegistrationAvailable,
+    /// Every slot in the region is in use, because stores were interrupted by processes which
+    /// exited part way through them.
+    NoSlotAvailable,
+}
+
+impl fmt::Display for SharedMemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::TooSmall => "the shared memory region is too small",
+            Self::Misaligned => "the shared memory region is misaligned",
+            Self::Uninitialised => "the shared memory region has not been initialised",
+            Self::LayoutMismatch => "the shared memory region holds values of a different layout",
+            Self::NoRegistrationAvailable => {
+                "every registration in the shared memory region is in use"
+            }
+            Self::NoSlotAvailable => "every slot in the shared memory region is in use",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SharedMemoryError {}
+
+#[repr(C)]
+struct Header {
+    magic: AtomicUsize,
+    value_size: usize,
+    value_align: usize,
+    registrations: usize,
+    // The index of the slot holding the current value.
+    current: AtomicUsize,
+}
+
+/// The offsets of the parts of a region, relative to its start.
+#[derive(Clone, Copy, Debug)]
+struct RegionLayout {
+    layout: Layout,
+    registrations: usize,
+    registered: usize,
+    hazards: usize,
+    states: usize,
+    values: usize,
+}
+
+impl RegionLayout {
+    fn new<T>(registrations: usize) -> Self {
+        let array = |len| Layout::array::<AtomicUsize>(len).expect("Too many registrations");
+        let slots = slots(registrations);
+        let header = Layout::new::<Header>();
+        let (layout, registered) = header
+            .extend(array(registrations))
+            .expect("Region overflow");
+        let (layout, hazards) = layout
+            .extend(array(registrations))
+            .expect("Region overflow");
+        let (layout, states) = layout.extend(array(slots)).expect("Region overflow");
+        let (layout, values) = layout
+            .extend(Layout::array::<T>(slots).expect("Too many registrations"))
+            .expect("Region overflow");
+        Self {
+            layout: layout.pad_to_align(),
+            registrations,
+            registered,
+            hazards,
+            states,
+            values,
+        }
+    }
+}
+
+/// The number of slots needed so that a store always finds a free slot once unprotected values
+/// have been reclaimed.
+///
+/// Each registration either protects one value or is storing one, and one more slot holds the
+/// current value.
+fn slots(registrations: usize) -> usize {
+    registrations + 2
+}
+
+/// An `AtomBox` stored in a memory region shared between processes.
+///
+/// A `SharedAtomBox` is a handle to the box, holding one of the region's registrations. Each
+/// process, or each thread, attaches its own handle. Handles are released when they are dropped.
+/// If a process exits without dropping its handles, their registrations, and any slot they were
+/// writing, can be recovered with [`SharedAtomBox::release_registration`].
+///
+/// See the [module documentation](self) for an example.
+pub struct SharedAtomBox<T> {
+    region: *mut u8,
+    layout: RegionLayout,
+    registration: usize,
+    _value: PhantomData<T>,
+}
+
+// A handle may be moved to another thread, but its hazard pointer can only be used by one thread
+// at a time.
+unsafe impl<T: Send> Send for SharedAtomBox<T> {}
+
+impl<T> fmt::Debug for SharedAtomBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedAtomBox")
+            .field("registration", &self.registration)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Copy> SharedAtomBox<T> {
+    /// Returns the size in bytes of a region holding a box with `registrations` registrations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size overflows `usize`.
+    pub fn region_size(registrations: usize) -> usize {
+        RegionLayout::new::<T>(registrations).layout.size()
+    }
+
+    /// Initialises a box holding `value` in the region of `len` bytes at `region`, with room for
+    /// `registrations` handles to be attached at once, and attaches a handle to it.
+    ///
+    /// # Safety
+    ///
+    /// `region` must be valid for reads and writes of `len` bytes for as long as any handle to
+    /// the box is in use, and must not be used by anything other than handles to this box. No
+    /// other process may use the region until this function returns. `T` must have the same
+    /// layout in every process using the region, and its values must remain valid when copied to
+    /// another process, so must not contain pointers or references.
+    pub unsafe fn create(
+        region: *mut u8,
+        len: usize,
+        registrations: usize,
+        value: T,
+    ) -> Result<Self, SharedMemoryError> {
+        let layout = RegionLayout::new::<T>(registrations);
+        check_region(region, len, layout.layout)?;
+        // # Safety
+        //
+        // The region is large enough and aligned for the layout, and we have exclusive access to
+        // it.
+        unsafe {
+            (region as *mut Header).write(Header {
+                magic: AtomicUsize::new(0),
+                value_size: core::mem::size_of::<T>(),
+                value_align: core::mem::align_of::<T>(),
+                registrations,
+                current: AtomicUsize::new(0),
+            });
+            for offset in [layout.registered, layout.hazards] {
+                let atomics = region.add(offset) as *mut AtomicUsize;
+                for index in 0..registrations {
+                    atomics.add(index).write(AtomicUsize::new(0));
+                }
+            }
+            let states = region.add(layout.states) as *mut AtomicUsize;
+            for index in 0..slots(registrations) {
+                states.add(index).write(AtomicUsize::new(FREE));
+            }
+            (region.add(layout.values) as *mut T).write(value);
+            (*states).store(LIVE, Ordering::Relaxed);
+            (*(region as *const Header))
+                .magic
+                .store(MAGIC, Ordering::Release);
+        }
+        // # Safety
+        //
+        // The region has been initialised.
+        unsafe { Self::register(region, layout) }
+    }
+
+    /// Attaches a handle to the box which was initialised in the region of `len` bytes at
+    /// `region` by [`SharedAtomBox::create`], possibly in another process.
+    ///
+    /// # Safety
+    ///
+    /// `region` must be valid for reads and writes of `len` bytes for as long as the handle is in
+    /// use, and must either have been initialised by [`SharedAtomBox::create`] with the same `T`,
+    /// or hold no initialised box. `T` must meet the requirements given by
+    /// [`SharedAtomBox::create`].
+    pub unsafe fn attach(region: *mut u8, len: usize) -> Result<Self, SharedMemoryError> {
+        check_region(region, len, Layout::new::<Header>())?;
+        // # Safety
+        //
+        // The region is large enough and aligned for the header.
+        let header = unsafe { &*(region as *const Header) };
+        if header.magic.load(Ordering::Acquire) != MAGIC {
+            return Err(SharedMemoryError::Uninitialised);
+        }
+        if header.value_size != core::mem::size_of::<T>()
+            || header.value_align != core::mem::align_of::<T>()
+        {
+            return Err(SharedMemoryError::LayoutMismatch);
+        }
+        let layout = RegionLayout::new::<T>(header.registrations);
+        check_region(region, len, layout.layout)?;
+        // # Safety
+        //
+        // The region has been initialised.
+        unsafe { Self::register(region, layout) }
+    }
+
+    /// Claims a registration in an initialised region.
+    ///
+    /// # Safety
+    ///
+    /// The region must have been initialised with `layout`.
+    unsafe fn register(region: *mut u8, layout: RegionLayout) -> Result<Self, SharedMemoryError> {
+        let mut shared_atom_box = Self {
+            region,
+            layout,
+            registration: 0,
+            _value: PhantomData,
+        };
+        shared_atom_box.registration = shared_atom_box
+            .registered()
+            .iter()
+            .position(|registered| {
+                registered
+                    .compare_exchange(0, 1, Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+            })
+            .ok_or(SharedMemoryError::NoRegistrationAvailable)?;
+        Ok(shared_atom_box)
+    }
+
+    /// Returns the index of the registration held by this handle.
+    pub fn registration(&self) -> usize {
+        self.registration
+    }
+
+    /// Copies the current value out of the box.
+    pub fn load(&self) -> T {
+        let hazard = &self.hazards()[self.registration];
+        let current = &self.header().current;
+        let mut slot = current.load(Ordering::Relaxed);
+        loop {
+            hazard.store(slot + 1, Ordering::SeqCst);
+            fence(Ordering::SeqCst);
+            let latest = current.load(Ordering::Acquire);
+            if latest == slot {
+                break;
+            }
+            slot = latest;
+        }
+        // # Safety
+        //
+        // The slot is protected by the hazard pointer, so is not reused while it is read.
+        let value = unsafe { self.value(slot).read() };
+        hazard.store(UNPROTECTED, Ordering::Release);
+        value
+    }
+
+    /// Stores a new value in the box.
+    ///
+    /// # Errors
+    ///
+    /// As for [`SharedAtomBox::swap`].
+    pub fn store(&self, value: T) -> Result<(), SharedMemoryError> {
+        self.swap(value).map(drop)
+    }
+
+    /// Stores a new value in the box, returning the previous value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SharedMemoryError::NoSlotAvailable`] if no slot can be freed to hold the value.
+    /// The region has enough slots for every registration to be storing a value at once, so this
+    /// only happens once slots have been lost to processes which exited part way through a store.
+    pub fn swap(&self, value: T) -> Result<T, SharedMemoryError> {
+        let slot = self.allocate()?;
+        // # Safety
+        //
+        // The slot is being written, so no other handle reads or writes it.
+        unsafe { self.value(slot).write(value) };
+        self.transition(slot, LIVE);
+        let previous_slot = self.header().current.swap(slot, Ordering::AcqRel);
+        // # Safety
+        //
+        // Only the handle which replaced the value can retire it, so it is not reused before it
+        // has been read.
+        let previous = unsafe { self.value(previous_slot).read() };
+        self.transition(previous_slot, RETIRED);
+        self.reclaim();
+        Ok(previous)
+    }
+
+    /// Releases a registration held by a handle in a process which exited without dropping it,
+    /// freeing the slot it was writing, if any.
+    ///
+    /// A process which exited after writing a value, but before it replaced the current value or
+    /// retired the value it replaced, leaves a slot which cannot be recovered.
+    ///
+    /// # Safety
+    ///
+    /// The handle holding the registration must never be used again.
+    pub unsafe fn release_registration(&self, registration: usize) {
+        for state in self.states() {
+            let word = state.load(Ordering::Acquire);
+            // Only the registration writing a slot changes its state, so it cannot change here.
+            if word & STATE_MASK == WRITING && self.writer(word) == registration + 1 {
+                state.store(self.next_word(word, FREE, 0), Ordering::Release);
+            }
+        }
+        self.hazards()[registration].store(UNPROTECTED, Ordering::Release);
+        self.registered()[registration].store(0, Ordering::Release);
+    }
+
+    /// Claims a free slot, reclaiming retired values if there is none.
+    ///
+    /// Fails if no slot changes state between two attempts, as no other handle can then free
+    /// one.
+    fn allocate(&self) -> Result<usize, SharedMemoryError> {
+        let mut previous_words = Vec::new();
+        loop {
+            let free = self.states().iter().position(|state| {
+                let word = state.load(Ordering::Relaxed);
+                word & STATE_MASK == FREE
+                    && state
+                        .compare_exchange(
+                            word,
+                            self.next_word(word, WRITING, self.registration + 1),
+                            Ordering::Acquire,
+                            Ordering::Relaxed,
+                        )
+                        .is_ok()
+            });
+            if let Some(slot) = free {
+                break Ok(slot);
+            }
+            if self.reclaim() == 0 {
+                let words: Vec<_> = self
+                    .states()
+                    .iter()
+                    .map(|state| state.load(Ordering::Acquire))
+                    .collect();
+                if words == previous_words {
+                    break Err(SharedMemoryError::NoSlotAvailable);
+                }
+                previous_words = words;
+                core::hint::spin_loop();
+            }
+        }
+    }
+
+    /// Frees the retired values which are not protected by any hazard pointer, returning how many
+    /// were freed.
+    fn reclaim(&self) -> usize {
+        fence(Ordering::SeqCst);
+        let mut reclaimed = 0;
+        for (slot, state) in self.states().iter().enumerate() {
+            let word = state.load(Ordering::Acquire);
+            // The slot is only freed if its generation is unchanged, so it cannot have been
+            // reused, and protected, since its hazard pointers were checked.
+            if word & STATE_MASK == RETIRED
+                && !self
+                    .hazards()
+                    .iter()
+                    .any(|hazard| hazard.load(Ordering::Acquire) == slot + 1)
+                && state
+                    .compare_exchange(
+                        word,
+                        self.next_word(word, FREE, 0),
+                        Ordering::AcqRel,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+            {
+                reclaimed += 1;
+            }
+        }
+        reclaimed
+    }
+
+    /// Moves a slot owned by this handle to `state`.
+    fn transition(&self, slot: usize, state: usize) {
+        let slot_state = &self.states()[slot];
+        let word = slot_state.load(Ordering::Relaxed);
+        slot_state.store(self.next_word(word, state, 0), Ordering::Release);
+    }
+
+    /// The number of bits above the state which hold the registration writing a slot.
+    fn writer_bits(&self) -> u32 {
+        usize::BITS - self.layout.registrations.leading_zeros()
+    }
+
+    /// Returns the registration writing the slot with state word `word`, plus one.
+    fn writer(&self, word: usize) -> usize {
+        (word >> STATE_BITS) & ((1 << self.writer_bits()) - 1)
+    }
+
+    /// Returns the state word following `word`, in the next generation.
+    fn next_word(&self, word: usize, state: usize, writer: usize) -> usize {
+        let generation_shift = STATE_BITS + self.writer_bits();
+        ((word >> generation_shift).wrapping_add(1) << generation_shift)
+            | writer << STATE_BITS
+            | state
+    }
+
+    fn header(&self) -> &Header {
+        // # Safety
+        //
+        // The region has been initialised.
+        unsafe { &*(self.region as *const Header) }
+    }
+
+    fn atomics(&self, offset: usize, len: usize) -> &[AtomicUsize] {
+        // # Safety
+        //
+        // The region has been initialised with `len` atomics at `offset`.
+        unsafe { core::slice::from_raw_parts(self.region.add(offset) as *const AtomicUsize, len) }
+    }
+
+    fn registered(&self) -> &[AtomicUsize] {
+        self.atomics(self.layout.registered, self.layout.registrations)
+    }
+
+    fn hazards(&self) -> &[AtomicUsize] {
+        self.atomics(self.layout.hazards, self.layout.registrations)
+    }
+
+    fn states(&self) -> &[AtomicUsize] {
+        self.atomics(self.layout.states, slots(self.layout.registrations))
+    }
+
+    fn value(&self, slot: usize) -> *mut T {
+        // # Safety
+        //
+        // The slot is within the region.
+        unsafe { (self.region.add(self.layout.values) as *mut T).add(slot) }
+    }
+}
+
+impl<T> Drop for SharedAtomBox<T> {
+    fn drop(&mut self) {
+        // # Safety
+        //
+        // The handle's hazard pointer is not protecting a value between operations, and it is
+        // not used again.
+        let registered = unsafe {
+            &*((self.region.add(self.layout.registered) as *const AtomicUsize)
+                .add(self.registration))
+        };
+        registered.store(0, Ordering::Release);
+    }
+}
+
+/// Checks that the region of `len` bytes at `region` can hold `layout`.
+fn check_region(region: *mut u8, len: usize, layout: Layout) -> Result<(), SharedMemoryError> {
+    if len < layout.size() {
+        return Err(SharedMemoryError::TooSmall);
+    }
+    if !(region as usize).is_multiple_of(layout.align()) {
+        return Err(SharedMemoryError::Misaligned);
+    }
+    Ok(())
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Pair {
+        first: u64,
+        second: u64,
+    }
+
+    fn region<T: Copy>(registrations: usize) -> (Vec<u64>, usize) {
+        let len = SharedAtomBox::<T>::region_size(registrations);
+        (vec![0; len / 8 + 1], len)
+    }
+
+    #[test]
+    fn handles_share_the_value() {
+        let (mut memory, len) = region::<Pair>(2);
+        let region = memory.as_mut_ptr() as *mut u8;
+        let first = Pair {
+            first: 1,
+            second: 1,
+        };
+        let publisher = unsafe { SharedAtomBox::create(region, len, 2, first) }.unwrap();
+        let subscriber = unsafe { SharedAtomBox::<Pair>::attach(region, len) }.unwrap();
+
+        let previous = publisher
+            .swap(Pair {
+                first: 2,
+                second: 2,
+            })
+            .unwrap();
+
+        assert_eq!(previous, first);
+        assert_eq!(subscriber.load().first, 2);
+        assert_eq!(
+            unsafe { SharedAtomBox::<Pair>::attach(region, len) }.err(),
+            Some(SharedMemoryError::NoRegistrationAvailable)
+        );
+        drop(subscriber);
+        assert!(unsafe { SharedAtomBox::<Pair>::attach(region, len) }.is_ok());
+    }
+
+    #[test]
+    fn regions_can_be_mapped_at_different_addresses() {
+        let (mut memory, len) = region::<u32>(1);
+        let publisher = unsafe { SharedAtomBox::create(memory.as_mut_ptr() as *mut u8, len, 1, 5) };
+        publisher.unwrap().store(6).unwrap();
+
+        let mut relocated = memory.clone();
+        let reader =
+            unsafe { SharedAtomBox::<u32>::attach(relocated.as_mut_ptr() as *mut u8, len) };
+
+        let reader = reader.unwrap();
+        assert_eq!(reader.load(), 6, "The region holds no absolute addresses");
+        reader.store(7).unwrap();
+        assert_eq!(reader.load(), 7);
+    }
+
+    #[test]
+    fn regions_are_validated() {
+        let (mut memory, len) = region::<u64>(1);
+        let region = memory.as_mut_ptr() as *mut u8;
+
+        let uninitialised = unsafe { SharedAtomBox::<u64>::attach(region, len) }.err();
+        let too_small = unsafe { SharedAtomBox::create(region, len - 1, 1, 0_u64) }.err();
+        let misaligned = unsafe { SharedAtomBox::create(region.add(1), len, 1, 0_u64) }.err();
+        drop(unsafe { SharedAtomBox::create(region, len, 1, 0_u64) });
+        let mismatched = unsafe { SharedAtomBox::<u8>::attach(region, len) }.err();
+
+        assert_eq!(uninitialised, Some(SharedMemoryError::Uninitialised));
+        assert_eq!(too_small, Some(SharedMemoryError::TooSmall));
+        assert_eq!(misaligned, Some(SharedMemoryError::Misaligned));
+        assert_eq!(mismatched, Some(SharedMemoryError::LayoutMismatch));
+    }
+
+    #[test]
+    fn releasing_a_registration_frees_the_slot_it_was_writing() {
+        let (mut memory, len) = region::<u64>(2);
+        let region = memory.as_mut_ptr() as *mut u8;
+        let survivor = unsafe { SharedAtomBox::create(region, len, 2, 0_u64) }.unwrap();
+        let crashed = unsafe { SharedAtomBox::<u64>::attach(region, len) }.unwrap();
+        let registration = crashed.registration();
+        crashed.allocate().unwrap();
+        core::mem::forget(crashed);
+
+        unsafe { survivor.release_registration(registration) };
+
+        assert!(
+            survivor
+                .states()
+                .iter()
+                .all(|state| state.load(Ordering::Relaxed) & STATE_MASK != WRITING),
+            "The slot being written is freed"
+        );
+        let replacement = unsafe { SharedAtomBox::<u64>::attach(region, len) }.unwrap();
+        for value in 1..=10 {
+            replacement.store(value).unwrap();
+            assert_eq!(survivor.swap(value * 10).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn stores_fail_once_slots_are_lost() {
+        let (mut memory, len) = region::<u64>(1);
+        let region = memory.as_mut_ptr() as *mut u8;
+        let handle = unsafe { SharedAtomBox::create(region, len, 1, 0_u64) }.unwrap();
+        // Slots left live by processes which exited between writing a value and storing it.
+        for state in &handle.states()[1..] {
+            state.store(LIVE, Ordering::Relaxed);
+        }
+
+        assert_eq!(handle.store(1), Err(SharedMemoryError::NoSlotAvailable));
+        assert_eq!(handle.load(), 0, "The value is unchanged");
+    }
+
+    #[test]
+    fn concurrent_handles_never_observe_torn_values() {
+        let (mut memory, len) = region::<Pair>(4);
+        let region = memory.as_mut_ptr() as usize;
+        let initial = Pair {
+            first: 0,
+            second: 0,
+        };
+        drop(unsafe { SharedAtomBox::create(region as *mut u8, len, 4, initial) }.unwrap());
+
+        std::thread::scope(|scope| {
+            for thread in 0..4_u64 {
+                scope.spawn(move || {
+                    let handle = unsafe { SharedAtomBox::<Pair>::attach(region as *mut u8, len) };
+                    let handle = handle.unwrap();
+                    for value in 0..500 {
+                        if thread == 0 {
+                            handle
+                                .store(Pair {
+                                    first: value,
+                                    second: value,
+                                })
+                                .unwrap();
+                        }
+                        let pair = handle.load();
+                        assert_eq!(pair.first, pair.second, "Values are never torn");
+                    }
+                });
+            }
+        });
+
+        let handle = unsafe { SharedAtomBox::<Pair>::attach(region as *mut u8, len) }.unwrap();
+        assert_eq!(handle.load().first, 499);
+    }
+}
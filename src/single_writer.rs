@@ -0,0 +1,242 @@
+//! Single Writer
+//!
+//! A box which is only ever stored to from a single thread, at a time, through a writer handle.
+
+use crate::domain::{Domain, WaitFreeHandle};
+use crate::sync::{AtomicBool, Ordering};
+use crate::{AtomBoxIn, LoadGuard};
+use alloc::boxed::Box;
+
+/// The size of a writer's retire stack.
+const WRITER_RETIRE_CAPACITY: usize = 64;
+
+/// A box with a single writer and any number of readers.
+///
+/// Readers load values under the protection of hazard pointers, as with an [`AtomBoxIn`]. Values
+/// are stored through the box's [`SingleWriter`], of which there is at most one at a time. Since
+/// no other thread can change the box, the writer replaces the current value with a plain store
+/// rather than an atomic swap, and retires the replaced value onto its own retire stack, without
+/// contending on the domain's retired list. The writer scans the domain's hazard pointers itself
+/// once its stack is full.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::SingleWriterAtomBox;
+///
+/// let prices = SingleWriterAtomBox::new(vec![100, 101]);
+///
+/// std::thread::scope(|scope| {
+///     scope.spawn(|| {
+///         let mut writer = prices.writer();
+///         for tick in 0..10 {
+///             let mut next = writer.get().clone();
+///             next.push(102 + tick);
+///             writer.store(next);
+///         }
+///     });
+///     scope.spawn(|| {
+///         let snapshot = prices.load();
+///         assert!(snapshot.starts_with(&[100, 101]));
+///     });
+/// });
+///
+/// assert_eq!(prices.load().len(), 12);
+/// ```
+#[derive(Debug)]
+pub struct SingleWriterAtomBox<'domain, T, const DOMAIN_ID: usize> {
+    atom_box: AtomBoxIn<'domain, T, DOMAIN_ID>,
+    writer_claimed: AtomicBool,
+}
+
+#[cfg(not(loom))]
+impl<T> SingleWriterAtomBox<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `SingleWriterAtomBox` associated with the shared (global) domain.
+    pub fn new(value: T) -> Self {
+        Self::new_with_domain(value, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> SingleWriterAtomBox<'domain, T, DOMAIN_ID> {
+    /// Creates a new `SingleWriterAtomBox` and associates it with the given domain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{SingleWriterAtomBox, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let routes = SingleWriterAtomBox::new_with_domain(vec!["/health"], &CUSTOM_DOMAIN);
+    /// routes.writer().store(vec!["/health", "/metrics"]);
+    /// assert_eq!(routes.load().len(), 2);
+    /// ```
+    pub fn new_with_domain(value: T, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        Self {
+            atom_box: AtomBoxIn::new_with_domain(value, domain),
+            writer_claimed: AtomicBool::new(false),
+        }
+    }
+
+    /// Loads the current value, protecting it with a hazard pointer.
+    pub fn load(&self) -> LoadGuard<'domain, T, DOMAIN_ID> {
+        self.atom_box.load()
+    }
+
+    /// Returns the writer of this box.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the box already has a writer. Use [`SingleWriterAtomBox::try_writer`] to handle
+    /// this case.
+    pub fn writer(&self) -> SingleWriter<'_, 'domain, T, DOMAIN_ID> {
+        self.try_writer()
+            .expect("A SingleWriterAtomBox can only have one writer at a time")
+    }
+
+    /// Returns the writer of this box, or `None` if it already has a writer.
+    pub fn try_writer(&self) -> Option<SingleWriter<'_, 'domain, T, DOMAIN_ID>> {
+        self.writer_claimed
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        Some(SingleWriter {
+            atom_box: self,
+            handle: self.atom_box.domain.register_thread(WRITER_RETIRE_CAPACITY),
+        })
+    }
+}
+
+/// The only handle through which a [`SingleWriterAtomBox`] can be stored to.
+///
+/// Created by [`SingleWriterAtomBox::writer`]. Values retired by the writer which are still
+/// protected when it is dropped are handed to the domain.
+pub struct SingleWriter<'a, 'domain, T, const DOMAIN_ID: usize> {
+    atom_box: &'a SingleWriterAtomBox<'domain, T, DOMAIN_ID>,
+    handle: WaitFreeHandle<'domain, DOMAIN_ID>,
+}
+
+impl<'a, 'domain, T, const DOMAIN_ID: usize> core::fmt::Debug
+    for SingleWriter<'a, 'domain, T, DOMAIN_ID>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SingleWriter")
+            .field("retired", &self.handle.retired())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, 'domain, T, const DOMAIN_ID: usize> SingleWriter<'a, 'domain, T, DOMAIN_ID> {
+    /// Returns the current value.
+    ///
+    /// Only the writer replaces values, so the current value needs no protection while the
+    /// writer is borrowed.
+    pub fn get(&self) -> &T {
+        // # Safety
+        //
+        // The value was created via a box and only this writer can replace and retire it, which
+        // requires a mutable borrow of the writer.
+        unsafe { &*self.atom_box.atom_box.ptr.load(Ordering::Relaxed) }
+    }
+
+    /// Replaces the current value, retiring the value it replaced.
+    pub fn store(&mut self, value: T) {
+        let ptr = &self.atom_box.atom_box.ptr;
+        let old_ptr = ptr.load(Ordering::Relaxed);
+        ptr.store(Box::into_raw(Box::new(value)), Ordering::Release);
+        // # Safety
+        //
+        // The value can no longer be loaded from the box, and only this writer replaces values,
+        // so this is the only place it will be retired.
+        unsafe { self.handle.retire(old_ptr) };
+    }
+
+    /// Reclaims the values retired by this writer which are not protected by any hazard
+    /// pointer, returning the number reclaimed.
+    pub fn reclaim(&mut self) -> usize {
+        self.handle.reclaim()
+    }
+}
+
+impl<'a, 'domain, T, const DOMAIN_ID: usize> Drop for SingleWriter<'a, 'domain, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        self.handle.flush();
+        self.atom_box.writer_claimed.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    #[test]
+    fn only_one_writer_at_a_time() {
+        let domain: Domain<1> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box = SingleWriterAtomBox::new_with_domain(1, &domain);
+
+        let mut writer = atom_box.writer();
+        let second_writer = atom_box.try_writer().map(|_| ());
+        writer.store(2);
+        drop(writer);
+        let writer_after_drop = atom_box.try_writer().map(|_| ());
+
+        assert_eq!(second_writer, None, "The box already has a writer");
+        assert_eq!(
+            writer_after_drop,
+            Some(()),
+            "Dropping the writer releases it"
+        );
+        assert_eq!(*atom_box.load(), 2);
+    }
+
+    #[test]
+    fn writer_reclaims_unprotected_values() {
+        let drop_counter = DropCounter::new();
+        let domain: Domain<2> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box = SingleWriterAtomBox::new_with_domain(drop_counter.track(1), &domain);
+        let mut writer = atom_box.writer();
+        let guard = atom_box.load();
+
+        writer.store(drop_counter.track(2));
+        writer.store(drop_counter.track(3));
+        let reclaimed_while_guarded = writer.reclaim();
+        drop(guard);
+        let reclaimed = writer.reclaim();
+
+        assert_eq!(reclaimed_while_guarded, 1, "The guarded value is kept");
+        assert_eq!(reclaimed, 1);
+        assert_eq!(**writer.get(), 3);
+        assert_eq!(domain.reclaim(), 0, "The writer retires its own values");
+        drop_counter.assert_drops(2);
+    }
+
+    #[test]
+    fn readers_see_every_store_in_order() {
+        let domain: Domain<3> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box = SingleWriterAtomBox::new_with_domain(0_usize, &domain);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let mut writer = atom_box.writer();
+                for value in 1..=1000 {
+                    writer.store(value);
+                }
+            });
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    let mut last = 0;
+                    for _ in 0..1000 {
+                        let value = *atom_box.load();
+                        assert!(value >= last, "Values are never seen out of order");
+                        last = value;
+                    }
+                });
+            }
+        });
+
+        assert_eq!(*atom_box.load(), 1000);
+    }
+}
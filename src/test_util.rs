@@ -0,0 +1,212 @@
+//! Test utilities
+//!
+//! Helpers for verifying when values stored in an `AtomBox` are reclaimed, and for owning the
+//! domains they are reclaimed in. These are the same utilities used by the tests in this crate
+//! and are available to downstream crates via the `test-util` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}, test_util::DropCounter};
+//!
+//! const TEST_DOMAIN_ID: usize = 7;
+//! static TEST_DOMAIN: Domain<TEST_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+//!
+//! let drop_counter = DropCounter::new();
+//! let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(10), &TEST_DOMAIN);
+//!
+//! let value = atom_box.load();
+//! atom_box.store(drop_counter.track(20));
+//! drop_counter.assert_drops(0);
+//!
+//! drop(value);
+//! atom_box.store(drop_counter.track(30));
+//! drop_counter.assert_drops(2);
+//! ```
+
+use crate::domain::{Domain, ReclaimStrategy};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// A handle to a shared count of the number of [`TrackedValue`]s which have been dropped.
+///
+/// Cloning a `DropCounter` produces a new handle to the same count.
+#[derive(Clone, Debug, Default)]
+pub struct DropCounter(Arc<AtomicUsize>);
+
+impl DropCounter {
+    /// Creates a new `DropCounter` with a count of zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wraps `value` in a [`TrackedValue`] which will increment this counter when it is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::test_util::DropCounter;
+    ///
+    /// let drop_counter = DropCounter::new();
+    /// let value = drop_counter.track("Hello");
+    /// assert_eq!(*value, "Hello");
+    ///
+    /// drop(value);
+    /// assert_eq!(drop_counter.count(), 1);
+    /// ```
+    pub fn track<T>(&self, value: T) -> TrackedValue<T> {
+        TrackedValue {
+            value,
+            drop_counter: self.clone(),
+        }
+    }
+
+    /// Returns the number of tracked values which have been dropped so far.
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Asserts that exactly `expected` tracked values have been dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of dropped values differs from `expected`.
+    #[track_caller]
+    pub fn assert_drops(&self, expected: usize) {
+        let count = self.count();
+        assert_eq!(
+            count, expected,
+            "Expected {} value(s) to have been dropped but {} were",
+            expected, count
+        );
+    }
+
+    /// Asserts that no tracked values have been dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any tracked value has been dropped.
+    #[track_caller]
+    pub fn assert_no_drops(&self) {
+        self.assert_drops(0);
+    }
+}
+
+/// A value which increments its associated [`DropCounter`] when it is dropped.
+///
+/// Created via [`DropCounter::track`]. Dereferences to the wrapped value.
+pub struct TrackedValue<T> {
+    value: T,
+    drop_counter: DropCounter,
+}
+
+impl<T> TrackedValue<T> {
+    /// Creates a new `TrackedValue` associated with `drop_counter`.
+    pub fn new(value: T, drop_counter: &DropCounter) -> Self {
+        drop_counter.track(value)
+    }
+}
+
+impl<T> Drop for TrackedValue<T> {
+    fn drop(&mut self) {
+        self.drop_counter.0.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+impl<T> Deref for TrackedValue<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for TrackedValue<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TrackedValue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TrackedValue").field(&self.value).finish()
+    }
+}
+
+/// A [`Domain`] owned by a single test, which is dropped along with any values still retired in
+/// it when the `TestDomain` is dropped.
+///
+/// Threads spawned by a test can borrow the domain via [`std::thread::scope`]. Loom models, whose
+/// threads must be `'static`, can own a `TestDomain` in a `loom::lazy_static!`, which loom drops
+/// at the end of each execution.
+///
+/// Created via [`Domain::new_for_test`].
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBoxIn, domain::Domain, test_util::DropCounter};
+///
+/// let drop_counter = DropCounter::new();
+/// let test_domain = Domain::<7>::new_for_test();
+/// let domain = test_domain.get();
+///
+/// std::thread::scope(|scope| {
+///     scope.spawn(|| {
+///         let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), domain);
+///         atom_box.store(drop_counter.track(2));
+///     });
+/// });
+///
+/// drop(test_domain);
+/// drop_counter.assert_drops(2);
+/// ```
+#[derive(Debug)]
+pub struct TestDomain<const DOMAIN_ID: usize> {
+    domain: Box<Domain<DOMAIN_ID>>,
+}
+
+impl<const DOMAIN_ID: usize> TestDomain<DOMAIN_ID> {
+    /// Creates a new `TestDomain` using the given `ReclaimStrategy`.
+    ///
+    /// [`Domain::new_for_test`] creates a domain which reclaims values eagerly. Tests which
+    /// control when values are reclaimed can use [`ReclaimStrategy::Manual`] instead.
+    pub fn new(reclaim_strategy: ReclaimStrategy) -> Self {
+        Self {
+            domain: Box::new(Domain::new(reclaim_strategy)),
+        }
+    }
+
+    /// Returns a reference to the domain.
+    pub fn get(&self) -> &Domain<DOMAIN_ID> {
+        &self.domain
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clones_share_a_count() {
+        let drop_counter = DropCounter::new();
+        let cloned_counter = drop_counter.clone();
+
+        drop(cloned_counter.track(1));
+        drop(TrackedValue::new(2, &drop_counter));
+
+        drop_counter.assert_drops(2);
+        cloned_counter.assert_drops(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected 0 value(s) to have been dropped but 1 were")]
+    fn assert_no_drops_panics_after_drop() {
+        let drop_counter = DropCounter::new();
+        drop(drop_counter.track(()));
+        drop_counter.assert_no_drops();
+    }
+}
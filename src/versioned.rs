@@ -0,0 +1,278 @@
+//! Versioned
+//!
+//! A cell which keeps a window of its most recent values readable, each identified by a version.
+
+use crate::collections::Hazard;
+use crate::domain::Domain;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use crate::LoadGuard;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+struct Version<T> {
+    version: usize,
+    value: T,
+}
+
+/// A cell which keeps its last `window` versions readable.
+///
+/// Every store appends a new version, numbered one more than the last, and evicts the version
+/// which has fallen out of the window. Readers can load the latest version, or any version still
+/// in the window, so a long running reader can keep working against a stable version, or return
+/// to it later, while writers keep publishing. Evicted versions are retired, so they are only
+/// reclaimed once no reader is protecting them.
+///
+/// # Example
+///
+/// ```
+/// use atom_box::AtomVersioned;
+///
+/// let snapshots = AtomVersioned::new("first", 2);
+/// let (version, _) = snapshots.load_latest();
+///
+/// snapshots.store("second");
+/// assert_eq!(*snapshots.load_version(version).unwrap(), "first");
+///
+/// snapshots.store("third");
+/// assert!(snapshots.load_version(version).is_none(), "Evicted from the window");
+/// assert_eq!(*snapshots.load_latest().1, "third");
+/// ```
+pub struct AtomVersioned<'domain, T, const DOMAIN_ID: usize> {
+    // The version `v` is stored in the slot `v % window`.
+    slots: Box<[AtomicPtr<Version<T>>]>,
+    // The version given to the next stored value.
+    next_version: AtomicUsize,
+    // The most recent version which has been published.
+    latest_version: AtomicUsize,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// Values stored on one thread may be read from, and dropped by, any other thread.
+unsafe impl<'domain, T: Send, const DOMAIN_ID: usize> Send
+    for AtomVersioned<'domain, T, DOMAIN_ID>
+{
+}
+unsafe impl<'domain, T: Send + Sync, const DOMAIN_ID: usize> Sync
+    for AtomVersioned<'domain, T, DOMAIN_ID>
+{
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> core::fmt::Debug for AtomVersioned<'domain, T, DOMAIN_ID> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AtomVersioned")
+            .field("window", &self.window())
+            .field("latest_version", &self.latest_version())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> AtomVersioned<'static, T, { crate::SHARED_DOMAIN_ID }> {
+    /// Creates a new `AtomVersioned` keeping the last `window` versions, associated with the shared
+    /// (global) domain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is zero.
+    pub fn new(value: T, window: usize) -> Self {
+        Self::new_with_domain(value, window, &crate::SHARED_DOMAIN)
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> AtomVersioned<'domain, T, DOMAIN_ID> {
+    /// Creates a new `AtomVersioned` keeping the last `window` versions, and associates it with the
+    /// given domain.
+    ///
+    /// The initial value has version zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use atom_box::{AtomVersioned, domain::{Domain, ReclaimStrategy}};
+    ///
+    /// const CUSTOM_DOMAIN_ID: usize = 42;
+    /// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Eager);
+    ///
+    /// let versioned = AtomVersioned::new_with_domain("Hello", 4, &CUSTOM_DOMAIN);
+    /// assert_eq!(versioned.latest_version(), 0);
+    /// ```
+    pub fn new_with_domain(value: T, window: usize, domain: &'domain Domain<DOMAIN_ID>) -> Self {
+        assert!(window > 0, "Window must be greater than zero");
+        let slots = (0..window)
+            .map(|_| AtomicPtr::new(core::ptr::null_mut()))
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+        slots[0].store(
+            Box::into_raw(Box::new(Version { version: 0, value })),
+            Ordering::Relaxed,
+        );
+        Self {
+            slots,
+            next_version: AtomicUsize::new(1),
+            latest_version: AtomicUsize::new(0),
+            domain,
+        }
+    }
+
+    /// Returns the number of versions which are kept readable.
+    pub fn window(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the most recently published version.
+    pub fn latest_version(&self) -> usize {
+        self.latest_version.load(Ordering::Acquire)
+    }
+
+    /// Loads the most recently published version, returning its version number along with its
+    /// value.
+    pub fn load_latest(&self) -> (usize, LoadGuard<'domain, T, DOMAIN_ID>) {
+        loop {
+            let version = self.latest_version();
+            // The version can only be missing if it was evicted by newer versions after it was
+            // read, in which case there is a newer latest version.
+            if let Some(guard) = self.load_version(version) {
+                break (version, guard);
+            }
+        }
+    }
+
+    /// Loads the value of `version`, returning `None` if it has not been published yet or has
+    /// been evicted from the window.
+    pub fn load_version(&self, version: usize) -> Option<LoadGuard<'domain, T, DOMAIN_ID>> {
+        let hazard = Hazard::new(self.domain);
+        let ptr = hazard.protect_ptr(&self.slots[version % self.slots.len()]);
+        // # Safety
+        //
+        // The version is protected by the hazard.
+        let stored = unsafe { ptr.as_ref() }?;
+        if stored.version != version {
+            return None;
+        }
+        Some(hazard.into_load_guard(&stored.value))
+    }
+
+    /// Publishes a new version, returning its version number.
+    ///
+    /// The version which falls out of the window is retired.
+    pub fn store(&self, value: T) -> usize {
+        let version = self.next_version.fetch_add(1, Ordering::Relaxed);
+        let new_ptr = Box::into_raw(Box::new(Version { version, value }));
+        let slot = &self.slots[version % self.slots.len()];
+        let hazard = Hazard::new(self.domain);
+        loop {
+            let current_ptr = hazard.protect_ptr(slot);
+            // # Safety
+            //
+            // The current version is protected by the hazard.
+            if let Some(current) = unsafe { current_ptr.as_ref() } {
+                if current.version > version {
+                    // A concurrent writer has already published a version a whole window newer,
+                    // so this version was evicted before it could be published.
+                    //
+                    // # Safety
+                    //
+                    // The version was never published, so we still own it.
+                    drop(unsafe { Box::from_raw(new_ptr) });
+                    break;
+                }
+            }
+            if slot
+                .compare_exchange(current_ptr, new_ptr, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                if !current_ptr.is_null() {
+                    // # Safety
+                    //
+                    // The evicted version was created via `Box::into_raw` and is no longer
+                    // reachable from the cell. Only the thread which replaced it can retire it.
+                    unsafe { self.domain.retire(current_ptr) };
+                }
+                break;
+            }
+        }
+        self.latest_version.fetch_max(version, Ordering::AcqRel);
+        version
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> Drop for AtomVersioned<'domain, T, DOMAIN_ID> {
+    fn drop(&mut self) {
+        for slot in self.slots.iter() {
+            let ptr = slot.load(Ordering::Relaxed);
+            if !ptr.is_null() {
+                // # Safety
+                //
+                // We have exclusive access to the cell, so each version is retired exactly once.
+                // Guards may still reference the versions, so they are retired rather than
+                // dropped.
+                unsafe { self.domain.retire(ptr) };
+            }
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    #[test]
+    fn versions_are_readable_until_evicted() {
+        let versioned = AtomVersioned::new_with_domain(0, 3, &TEST_DOMAIN);
+
+        let versions: Vec<_> = (1..=4).map(|value| versioned.store(value * 10)).collect();
+
+        assert_eq!(versions, [1, 2, 3, 4]);
+        assert!(versioned.load_version(0).is_none());
+        assert!(versioned.load_version(1).is_none());
+        assert_eq!(*versioned.load_version(2).unwrap(), 20);
+        assert_eq!(*versioned.load_version(4).unwrap(), 40);
+        assert!(versioned.load_version(5).is_none(), "Not yet published");
+        assert_eq!(versioned.load_latest().0, 4);
+    }
+
+    #[test]
+    fn evicted_versions_outlive_their_guards() {
+        let drop_counter = DropCounter::new();
+        let versioned = AtomVersioned::new_with_domain(drop_counter.track(0), 1, &TEST_DOMAIN);
+        let (_, guard) = versioned.load_latest();
+
+        versioned.store(drop_counter.track(1));
+        TEST_DOMAIN.reclaim();
+        let drops_while_guarded = drop_counter.count();
+        drop(guard);
+        TEST_DOMAIN.reclaim();
+
+        assert_eq!(drops_while_guarded, 0, "The guard keeps the version alive");
+        drop_counter.assert_drops(1);
+    }
+
+    #[test]
+    fn concurrent_writers_publish_every_version_in_order() {
+        let versioned = AtomVersioned::new_with_domain(0, 8, &TEST_DOMAIN);
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..500 {
+                        let version = versioned.store(0);
+                        let (latest, _) = versioned.load_latest();
+                        assert!(latest >= version, "The latest version never goes backwards");
+                    }
+                });
+            }
+        });
+
+        assert_eq!(versioned.latest_version(), 2000);
+        assert!((1993..=2000).all(|version| versioned.load_version(version).is_some()));
+    }
+}
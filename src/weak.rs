@@ -0,0 +1,154 @@
+//! Weak
+//!
+//! A handle to a value loaded from an `AtomBox`, which does not keep the value alive.
+
+use crate::collections::Hazard;
+use crate::domain::Domain;
+use crate::sync::Ordering;
+use crate::{AtomBoxIn, LoadGuard};
+
+/// A handle to a value which was held by an `AtomBox`, which does not protect it.
+///
+/// Unlike a [`LoadGuard`], a weak handle does not hold a hazard pointer, so it costs nothing to
+/// keep and does not delay reclamation. [`AtomWeak::try_upgrade`] protects the value again,
+/// provided it has not been reclaimed since the handle was created.
+///
+/// The domain is not told which values have weak handles, so once it has started reclaiming any
+/// retired value since the handle was created, it assumes the value may have been reclaimed, and
+/// upgrading fails. A failed upgrade should be followed by loading the box again.
+///
+/// Created by [`AtomBoxIn::downgrade`].
+///
+/// # Example
+///
+/// ```
+/// use atom_box::{AtomBoxIn, domain::{Domain, ReclaimStrategy}};
+///
+/// const CUSTOM_DOMAIN_ID: usize = 42;
+/// static CUSTOM_DOMAIN: Domain<CUSTOM_DOMAIN_ID> = Domain::new(ReclaimStrategy::Manual);
+///
+/// let routes = AtomBoxIn::new_with_domain(vec!["/health"], &CUSTOM_DOMAIN);
+/// let seen = routes.downgrade();
+///
+/// routes.store(vec!["/health", "/metrics"]);
+/// assert_eq!(*seen.try_upgrade().unwrap(), ["/health"], "Retired but not reclaimed");
+///
+/// CUSTOM_DOMAIN.reclaim();
+/// assert!(seen.try_upgrade().is_none());
+/// ```
+pub struct AtomWeak<'domain, T, const DOMAIN_ID: usize> {
+    ptr: *const T,
+    // The number of reclaim passes the domain had begun before the value was loaded.
+    reclaim_passes: usize,
+    domain: &'domain Domain<DOMAIN_ID>,
+}
+
+// Upgrading a handle shares the value with the thread which upgraded it, but never drops it.
+unsafe impl<'domain, T: Sync, const DOMAIN_ID: usize> Send for AtomWeak<'domain, T, DOMAIN_ID> {}
+unsafe impl<'domain, T: Sync, const DOMAIN_ID: usize> Sync for AtomWeak<'domain, T, DOMAIN_ID> {}
+
+impl<'domain, T, const DOMAIN_ID: usize> Clone for AtomWeak<'domain, T, DOMAIN_ID> {
+    fn clone(&self) -> Self {
+        Self {
+            ptr: self.ptr,
+            reclaim_passes: self.reclaim_passes,
+            domain: self.domain,
+        }
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> core::fmt::Debug for AtomWeak<'domain, T, DOMAIN_ID> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AtomWeak")
+            .field("ptr", &self.ptr)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> AtomWeak<'domain, T, DOMAIN_ID> {
+    /// Protects the value again, returning `None` if it may have been reclaimed.
+    pub fn try_upgrade(&self) -> Option<LoadGuard<'domain, T, DOMAIN_ID>> {
+        let hazard = Hazard::new(self.domain);
+        hazard.protect(self.ptr);
+        crate::sync::fence(Ordering::SeqCst);
+        // A reclaim pass which began after this check will find the value protected. If none
+        // began before it, since the value was loaded, the value has not been reclaimed, as it
+        // could only have been retired after it was loaded.
+        if self.domain.reclaim_passes() != self.reclaim_passes {
+            return None;
+        }
+        Some(hazard.into_load_guard(self.ptr))
+    }
+}
+
+impl<'domain, T, const DOMAIN_ID: usize> AtomBoxIn<'domain, T, DOMAIN_ID> {
+    /// Creates a weak handle to the value currently stored in the `AtomBox`.
+    ///
+    /// See [`AtomWeak`].
+    pub fn downgrade(&self) -> AtomWeak<'domain, T, DOMAIN_ID> {
+        let reclaim_passes = self.domain.reclaim_passes();
+        let guard = self.load();
+        AtomWeak {
+            ptr: guard.ptr,
+            reclaim_passes,
+            domain: self.domain,
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::ReclaimStrategy;
+    use crate::test_util::DropCounter;
+
+    #[test]
+    fn upgrades_succeed_until_the_domain_reclaims() {
+        let domain: Domain<1> = Domain::new(ReclaimStrategy::Manual);
+        let atom_box = AtomBoxIn::new_with_domain(1, &domain);
+        let weak = atom_box.downgrade();
+
+        let while_current = weak.try_upgrade().map(|guard| *guard);
+        atom_box.store(2);
+        let while_retired = weak.try_upgrade().map(|guard| *guard);
+        domain.reclaim();
+        let after_reclaim = weak.try_upgrade().map(|guard| *guard);
+
+        assert_eq!(while_current, Some(1));
+        assert_eq!(while_retired, Some(1));
+        assert_eq!(after_reclaim, None);
+        assert_eq!(*atom_box.downgrade().try_upgrade().unwrap(), 2);
+    }
+
+    #[test]
+    fn upgraded_values_are_protected() {
+        let drop_counter = DropCounter::new();
+        let domain: Domain<2> = Domain::new(ReclaimStrategy::Eager);
+        let atom_box = AtomBoxIn::new_with_domain(drop_counter.track(1), &domain);
+        let guard = atom_box.downgrade().try_upgrade().unwrap();
+
+        atom_box.store(drop_counter.track(2));
+        domain.reclaim();
+        let drops_while_upgraded = drop_counter.count();
+        drop(guard);
+        domain.reclaim();
+
+        assert_eq!(
+            drops_while_upgraded, 0,
+            "The upgraded guard protects the value"
+        );
+        drop_counter.assert_drops(1);
+    }
+
+    #[test]
+    fn immediately_freed_values_cannot_be_upgraded() {
+        let domain: Domain<3> = Domain::new(ReclaimStrategy::Manual).with_immediate_free();
+        let atom_box = AtomBoxIn::new_with_domain(1, &domain);
+        let weak = atom_box.downgrade();
+
+        atom_box.store(2);
+
+        assert!(weak.try_upgrade().is_none(), "The value has been freed");
+    }
+}
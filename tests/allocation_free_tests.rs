@@ -0,0 +1,72 @@
+#[cfg(not(loom))]
+mod allocation_free_test {
+    use atom_box::{domain::Domain, domain::ReclaimStrategy, AtomBoxIn};
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    struct CountingAllocator;
+
+    static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+    // Allocations are counted globally, so tests must not run concurrently.
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn try_load_does_not_allocate_once_hazard_pointers_are_reserved() {
+        let _serial = SERIAL.lock().unwrap();
+        TEST_DOMAIN.reserve_hazard_pointers(2);
+        let atom_box = AtomBoxIn::new_with_domain(5, &TEST_DOMAIN);
+        let allocations = ALLOCATIONS.load(Ordering::SeqCst);
+
+        for _ in 0..100 {
+            let first = atom_box
+                .try_load()
+                .expect("First hazard pointer is reserved");
+            let second = atom_box
+                .try_load()
+                .expect("Second hazard pointer is reserved");
+            assert_eq!(*first + *second, 10, "Both guards load the value");
+        }
+
+        assert_eq!(
+            ALLOCATIONS.load(Ordering::SeqCst),
+            allocations,
+            "Loading should not allocate"
+        );
+    }
+
+    #[test]
+    fn zero_sized_values_do_not_allocate() {
+        let _serial = SERIAL.lock().unwrap();
+        let atom_box = AtomBoxIn::new_with_domain((), &TEST_DOMAIN);
+        let allocations = ALLOCATIONS.load(Ordering::SeqCst);
+
+        for _ in 0..100 {
+            let _value = atom_box.load();
+            let _old_value = atom_box.swap(());
+        }
+
+        assert_eq!(
+            ALLOCATIONS.load(Ordering::SeqCst),
+            allocations,
+            "Loading and swapping should not allocate or retire"
+        );
+    }
+}
@@ -1,6 +1,8 @@
 #[cfg(loom)]
 mod loom_test {
-    use atom_box::{domain::Domain, domain::ReclaimStrategy, AtomBox};
+    use atom_box::domain::{Domain, ReclaimStrategy};
+    use atom_box::test_util::{DropCounter, TestDomain};
+    use atom_box::AtomBoxIn;
     use loom::sync::Arc;
     use loom::thread;
     use std::convert::From;
@@ -21,23 +23,27 @@ mod loom_test {
         let mut builder = loom::model::Builder::new();
         builder.preemption_bound = Some(6);
         builder.check(|| {
-            let test_domain: &'static Domain<1> =
-                Box::leak(Box::new(Domain::new(ReclaimStrategy::Eager)));
-
-            let atom_box1: &'static _ =
-                Box::leak(Box::new(AtomBox::new_with_domain(Value(0), test_domain)));
-            let atom_box2: &'static _ =
-                Box::leak(Box::new(AtomBox::new_with_domain(Value(0), test_domain)));
+            // Loom drops lazy statics at the end of each execution, so the domain is freed
+            // rather than leaked, after the boxes, which are shared by the threads.
+            loom::lazy_static! {
+                static ref TEST_DOMAIN: TestDomain<1> = Domain::new_for_test();
+            }
+            let domain: &'static Domain<1> = TEST_DOMAIN.get();
+            let atom_box1 = Arc::new(AtomBoxIn::new_with_domain(Value(0), domain));
+            let atom_box2 = Arc::new(AtomBoxIn::new_with_domain(Value(0), domain));
 
-            thread::spawn(move || {
-                let mut current_value = 0;
-                for _ in 1..=ITERATIONS {
-                    let new_value = atom_box1.load();
-                    assert!(new_value.0 >= current_value, "Value should not decrease");
-                    current_value = (*new_value).0;
+            let handle1 = thread::spawn({
+                let atom_box1 = atom_box1.clone();
+                move || {
+                    let mut current_value = 0;
+                    for _ in 1..=ITERATIONS {
+                        let new_value = atom_box1.load();
+                        assert!(new_value.0 >= current_value, "Value should not decrease");
+                        current_value = (*new_value).0;
+                    }
                 }
             });
-            thread::spawn(move || {
+            let handle2 = thread::spawn(move || {
                 for i in 1..=ITERATIONS {
                     let guard1 = atom_box1.swap(Value(i));
                     let value1 = (*guard1).0;
@@ -48,6 +54,9 @@ mod loom_test {
                     );
                 }
             });
+
+            handle1.join().unwrap();
+            handle2.join().unwrap();
         });
     }
 
@@ -56,51 +65,57 @@ mod loom_test {
         let mut builder = loom::model::Builder::new();
         builder.preemption_bound = Some(3);
         builder.check(|| {
-            let test_domain: &'static Domain<1> =
-                Box::leak(Box::new(Domain::new(ReclaimStrategy::Eager)));
-
-            let atom_box: &'static _ =
-                Box::leak(Box::new(AtomBox::new_with_domain(Value(0), test_domain)));
+            loom::lazy_static! {
+                static ref TEST_DOMAIN: TestDomain<1> = Domain::new_for_test();
+            }
+            let domain: &'static Domain<1> = TEST_DOMAIN.get();
+            let atom_box = Arc::new(AtomBoxIn::new_with_domain(Value(0), domain));
 
-            let handle1 = thread::spawn(move || {
-                let mut current_value = atom_box.load();
-                let initial_value = (*current_value).0;
-                let _ = loop {
-                    let new_value = Value((*current_value).0 + 1);
-                    match atom_box.compare_exchange(current_value, new_value) {
-                        Ok(value) => {
-                            break value;
+            let handle1 = thread::spawn({
+                let atom_box = atom_box.clone();
+                move || {
+                    let mut current_value = atom_box.load();
+                    let initial_value = (*current_value).0;
+                    let _ = loop {
+                        let new_value = Value((*current_value).0 + 1);
+                        match atom_box.compare_exchange(current_value, new_value) {
+                            Ok(value) => {
+                                break value;
+                            }
+                            Err(value) => {
+                                current_value = value;
+                            }
                         }
-                        Err(value) => {
-                            current_value = value;
-                        }
-                    }
-                };
-                let new_value = atom_box.load();
-                assert!(
-                    (*new_value).0 > initial_value,
-                    "Value should have been increased"
-                );
+                    };
+                    let new_value = atom_box.load();
+                    assert!(
+                        (*new_value).0 > initial_value,
+                        "Value should have been increased"
+                    );
+                }
             });
-            let handle2 = thread::spawn(move || {
-                let mut current_value = atom_box.load();
-                let initial_value = (*current_value).0;
-                let _ = loop {
-                    let new_value = Value((*current_value).0 + 1);
-                    match atom_box.compare_exchange(current_value, new_value) {
-                        Ok(value) => {
-                            break value;
+            let handle2 = thread::spawn({
+                let atom_box = atom_box.clone();
+                move || {
+                    let mut current_value = atom_box.load();
+                    let initial_value = (*current_value).0;
+                    let _ = loop {
+                        let new_value = Value((*current_value).0 + 1);
+                        match atom_box.compare_exchange(current_value, new_value) {
+                            Ok(value) => {
+                                break value;
+                            }
+                            Err(value) => {
+                                current_value = value;
+                            }
                         }
-                        Err(value) => {
-                            current_value = value;
-                        }
-                    }
-                };
-                let new_value = atom_box.load();
-                assert!(
-                    (*new_value).0 > initial_value,
-                    "Value should have been increased"
-                );
+                    };
+                    let new_value = atom_box.load();
+                    assert!(
+                        (*new_value).0 > initial_value,
+                        "Value should have been increased"
+                    );
+                }
             });
 
             match (handle1.join(), handle2.join()) {
@@ -119,51 +134,57 @@ mod loom_test {
         let mut builder = loom::model::Builder::new();
         builder.preemption_bound = Some(3);
         builder.check(|| {
-            let test_domain: &'static Domain<1> =
-                Box::leak(Box::new(Domain::new(ReclaimStrategy::Eager)));
-
-            let atom_box: &'static _ =
-                Box::leak(Box::new(AtomBox::new_with_domain(Value(0), test_domain)));
+            loom::lazy_static! {
+                static ref TEST_DOMAIN: TestDomain<1> = Domain::new_for_test();
+            }
+            let domain: &'static Domain<1> = TEST_DOMAIN.get();
+            let atom_box = Arc::new(AtomBoxIn::new_with_domain(Value(0), domain));
 
-            let handle1 = thread::spawn(move || {
-                let mut current_value = atom_box.load();
-                let initial_value = (*current_value).0;
-                let _ = loop {
-                    let new_value = Value((*current_value).0 + 1);
-                    match atom_box.compare_exchange_weak(current_value, new_value) {
-                        Ok(value) => {
-                            break value;
+            let handle1 = thread::spawn({
+                let atom_box = atom_box.clone();
+                move || {
+                    let mut current_value = atom_box.load();
+                    let initial_value = (*current_value).0;
+                    let _ = loop {
+                        let new_value = Value((*current_value).0 + 1);
+                        match atom_box.compare_exchange_weak(current_value, new_value) {
+                            Ok(value) => {
+                                break value;
+                            }
+                            Err(value) => {
+                                current_value = value;
+                            }
                         }
-                        Err(value) => {
-                            current_value = value;
-                        }
-                    }
-                };
-                let new_value = atom_box.load();
-                assert!(
-                    (*new_value).0 > initial_value,
-                    "Value should have been increased"
-                );
+                    };
+                    let new_value = atom_box.load();
+                    assert!(
+                        (*new_value).0 > initial_value,
+                        "Value should have been increased"
+                    );
+                }
             });
-            let handle2 = thread::spawn(move || {
-                let mut current_value = atom_box.load();
-                let initial_value = (*current_value).0;
-                let _ = loop {
-                    let new_value = Value((*current_value).0 + 1);
-                    match atom_box.compare_exchange_weak(current_value, new_value) {
-                        Ok(value) => {
-                            break value;
-                        }
-                        Err(value) => {
-                            current_value = value;
+            let handle2 = thread::spawn({
+                let atom_box = atom_box.clone();
+                move || {
+                    let mut current_value = atom_box.load();
+                    let initial_value = (*current_value).0;
+                    let _ = loop {
+                        let new_value = Value((*current_value).0 + 1);
+                        match atom_box.compare_exchange_weak(current_value, new_value) {
+                            Ok(value) => {
+                                break value;
+                            }
+                            Err(value) => {
+                                current_value = value;
+                            }
                         }
-                    }
-                };
-                let new_value = atom_box.load();
-                assert!(
-                    (*new_value).0 > initial_value,
-                    "Value should have been increased"
-                );
+                    };
+                    let new_value = atom_box.load();
+                    assert!(
+                        (*new_value).0 > initial_value,
+                        "Value should have been increased"
+                    );
+                }
             });
 
             match (handle1.join(), handle2.join()) {
@@ -177,19 +198,161 @@ mod loom_test {
         });
     }
 
+    #[test]
+    fn concurrency_store_from_guard_hands_off_value() {
+        let mut builder = loom::model::Builder::new();
+        builder.preemption_bound = Some(3);
+        builder.check(|| {
+            loom::lazy_static! {
+                static ref TEST_DOMAIN: TestDomain<1> = Domain::new_for_test();
+            }
+            let domain = TEST_DOMAIN.get();
+            let drop_counter = DropCounter::new();
+            let atom_box1 = Arc::new(AtomBoxIn::new_with_domain(drop_counter.track(10), domain));
+            let atom_box2 = Arc::new(AtomBoxIn::new_with_domain(drop_counter.track(20), domain));
+
+            let reader = thread::spawn({
+                let atom_box2 = atom_box2.clone();
+                move || {
+                    let value = **atom_box2.load();
+                    assert!(
+                        value == 20 || value == 10,
+                        "The second box should hold its own value or the one handed to it"
+                    );
+                }
+            });
+            let writer = thread::spawn({
+                let atom_box1 = atom_box1.clone();
+                let atom_box2 = atom_box2.clone();
+                let drop_counter = drop_counter.clone();
+                move || {
+                    let guard = atom_box1.swap(drop_counter.track(1));
+                    atom_box2.store_from_guard(guard);
+                }
+            });
+
+            reader.join().unwrap();
+            writer.join().unwrap();
+            assert_eq!(**atom_box1.load(), 1);
+            assert_eq!(
+                **atom_box2.load(),
+                10,
+                "The value should have been handed off"
+            );
+            drop((atom_box1, atom_box2));
+            domain.reclaim();
+            drop_counter.assert_drops(3);
+        });
+    }
+
+    #[test]
+    fn concurrency_manual_reclaim_races_with_load() {
+        let mut builder = loom::model::Builder::new();
+        builder.preemption_bound = Some(3);
+        builder.check(|| {
+            loom::lazy_static! {
+                static ref TEST_DOMAIN: TestDomain<1> = TestDomain::new(ReclaimStrategy::Manual);
+            }
+            let domain = TEST_DOMAIN.get();
+            let drop_counters = [DropCounter::new(), DropCounter::new()];
+            let atom_box = Arc::new(AtomBoxIn::new_with_domain(
+                drop_counters[0].track(0),
+                domain,
+            ));
+
+            let reader = thread::spawn({
+                let atom_box = atom_box.clone();
+                let drop_counters = drop_counters.clone();
+                move || {
+                    let value = atom_box.load();
+                    drop_counters[**value].assert_no_drops();
+                }
+            });
+            let writer = thread::spawn({
+                let atom_box = atom_box.clone();
+                let drop_counter = drop_counters[1].clone();
+                move || {
+                    atom_box.store(drop_counter.track(1));
+                    domain.reclaim();
+                }
+            });
+
+            reader.join().unwrap();
+            writer.join().unwrap();
+            domain.reclaim();
+            drop_counters[0].assert_drops(1);
+            drop(atom_box);
+            domain.reclaim();
+            drop_counters[1].assert_drops(1);
+        });
+    }
+
+    #[test]
+    fn concurrency_hazard_pointer_released_and_reacquired() {
+        let mut builder = loom::model::Builder::new();
+        builder.preemption_bound = Some(2);
+        builder.check(|| {
+            loom::lazy_static! {
+                static ref TEST_DOMAIN: TestDomain<1> = TestDomain::new(ReclaimStrategy::Manual);
+            }
+            let domain = TEST_DOMAIN.get();
+            let drop_counters = [DropCounter::new(), DropCounter::new(), DropCounter::new()];
+            let atom_box = Arc::new(AtomBoxIn::new_with_domain(
+                drop_counters[0].track(0),
+                domain,
+            ));
+
+            let reader = thread::spawn({
+                let atom_box = atom_box.clone();
+                let drop_counters = drop_counters.clone();
+                move || {
+                    let mut last = 0;
+                    for _ in 0..ITERATIONS {
+                        // Each load reuses the hazard pointer released by the previous guard.
+                        let value = atom_box.load();
+                        assert!(**value >= last, "Value should not decrease");
+                        drop_counters[**value].assert_no_drops();
+                        last = **value;
+                    }
+                }
+            });
+            let writer = thread::spawn({
+                let atom_box = atom_box.clone();
+                let drop_counters = drop_counters.clone();
+                move || {
+                    for value in 1..=ITERATIONS {
+                        atom_box.store(drop_counters[value].track(value));
+                        domain.reclaim();
+                    }
+                }
+            });
+
+            reader.join().unwrap();
+            writer.join().unwrap();
+            domain.reclaim();
+            drop_counters[0].assert_drops(1);
+            drop_counters[1].assert_drops(1);
+            drop(atom_box);
+            domain.reclaim();
+            drop_counters[2].assert_drops(1);
+        });
+    }
+
     #[test]
     fn concurrency_swap_with_arc() {
         let mut builder = loom::model::Builder::new();
         builder.preemption_bound = Some(3);
         builder.check(|| {
-            let test_domain: &'static Domain<1> =
-                Box::leak(Box::new(Domain::new(ReclaimStrategy::Eager)));
+            loom::lazy_static! {
+                static ref TEST_DOMAIN: TestDomain<1> = Domain::new_for_test();
+            }
+            let domain = TEST_DOMAIN.get();
 
-            let atom_box1 = Arc::new(AtomBox::new_with_domain(Value(0), test_domain));
-            let atom_box2 = Arc::new(AtomBox::new_with_domain(Value(0), test_domain));
+            let atom_box1 = Arc::new(AtomBoxIn::new_with_domain(Value(0), domain));
+            let atom_box2 = Arc::new(AtomBoxIn::new_with_domain(Value(0), domain));
 
             let atom_box = atom_box1.clone();
-            thread::spawn(move || {
+            let handle1 = thread::spawn(move || {
                 let mut current_value = 0;
                 for _ in 1..=ITERATIONS {
                     let new_value = atom_box.load();
@@ -199,7 +362,7 @@ mod loom_test {
             });
             let a_box1 = atom_box1.clone();
             let a_box2 = atom_box2.clone();
-            thread::spawn(move || {
+            let handle2 = thread::spawn(move || {
                 for i in 1..=ITERATIONS {
                     let guard1 = a_box1.swap(Value(i));
                     let value1 = (*guard1).0;
@@ -210,6 +373,9 @@ mod loom_test {
                     );
                 }
             });
+
+            handle1.join().unwrap();
+            handle2.join().unwrap();
         });
     }
 
@@ -218,10 +384,12 @@ mod loom_test {
         let mut builder = loom::model::Builder::new();
         builder.preemption_bound = Some(3);
         builder.check(|| {
-            let test_domain: &'static Domain<1> =
-                Box::leak(Box::new(Domain::new(ReclaimStrategy::Eager)));
+            loom::lazy_static! {
+                static ref TEST_DOMAIN: TestDomain<1> = Domain::new_for_test();
+            }
+            let domain = TEST_DOMAIN.get();
 
-            let atom_box = Arc::new(AtomBox::new_with_domain(Value(0), test_domain));
+            let atom_box = Arc::new(AtomBoxIn::new_with_domain(Value(0), domain));
 
             let atom_box1 = atom_box.clone();
             let handle1 = thread::spawn(move || {
@@ -283,10 +451,12 @@ mod loom_test {
         let mut builder = loom::model::Builder::new();
         builder.preemption_bound = Some(3);
         builder.check(|| {
-            let test_domain: &'static Domain<1> =
-                Box::leak(Box::new(Domain::new(ReclaimStrategy::Eager)));
+            loom::lazy_static! {
+                static ref TEST_DOMAIN: TestDomain<1> = Domain::new_for_test();
+            }
+            let domain = TEST_DOMAIN.get();
 
-            let atom_box = Arc::new(AtomBox::new_with_domain(Value(0), test_domain));
+            let atom_box = Arc::new(AtomBoxIn::new_with_domain(Value(0), domain));
 
             let atom_box1 = atom_box.clone();
             let handle1 = thread::spawn(move || {
@@ -0,0 +1,48 @@
+#![cfg(all(feature = "derive", not(loom)))]
+
+use atom_box::domain::{Domain, ReclaimStrategy};
+use atom_box::AtomFields;
+
+#[derive(AtomFields)]
+struct Config {
+    name: String,
+    timeout_ms: u64,
+    hosts: Vec<String>,
+}
+
+static TEST_DOMAIN: Domain<1> = Domain::new(ReclaimStrategy::Eager);
+
+#[test]
+fn fields_can_be_loaded_and_replaced_independently() {
+    let config = AtomConfig::new(Config {
+        name: String::from("service"),
+        timeout_ms: 100,
+        hosts: vec![String::from("a")],
+    });
+
+    config.store_timeout_ms(200);
+    let old_hosts = config.swap_hosts(vec![String::from("b"), String::from("c")]);
+
+    assert_eq!(*config.name(), "service", "Other fields are unchanged");
+    assert_eq!(*config.timeout_ms(), 200, "The stored value is loaded");
+    assert_eq!(*old_hosts, ["a"], "Swapping returns the old value");
+    assert_eq!(*config.hosts(), ["b", "c"]);
+}
+
+#[test]
+fn fields_share_the_given_domain() {
+    let config = AtomConfig::new_with_domain(
+        Config {
+            name: String::from("service"),
+            timeout_ms: 100,
+            hosts: Vec::new(),
+        },
+        &TEST_DOMAIN,
+    );
+
+    let name = config.name();
+
+    assert_eq!(*name, "service");
+    assert_eq!(*config.timeout_ms(), 100);
+    assert!(config.hosts().is_empty());
+}
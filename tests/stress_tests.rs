@@ -0,0 +1,197 @@
+//! Long running multi-threaded scenarios which check that no value is dropped while it is still
+//! protected, and that every value is eventually dropped exactly once.
+//!
+//! The unit tests only run for a few milliseconds, which is rarely long enough for reclamation
+//! races to surface, particularly on architectures with weaker memory ordering than x86_64. These
+//! scenarios are enabled by the `stress-tests` feature, and each runs for
+//! `ATOM_BOX_STRESS_SECONDS` seconds, two by default:
+//!
+//! ```text
+//! ATOM_BOX_STRESS_SECONDS=600 cargo test --release --features stress-tests --test stress_tests
+//! ```
+#![cfg(all(feature = "stress-tests", not(loom)))]
+
+use atom_box::domain::{Domain, ReclaimStrategy};
+use atom_box::test_util::{DropCounter, TestDomain, TrackedValue};
+use atom_box::AtomBoxIn;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const THREADS: usize = 8;
+
+fn duration() -> Duration {
+    let seconds = std::env::var("ATOM_BOX_STRESS_SECONDS")
+        .ok()
+        .and_then(|seconds| seconds.parse().ok())
+        .unwrap_or(2);
+    Duration::from_secs(seconds)
+}
+
+fn strategies() -> [ReclaimStrategy; 3] {
+    [
+        ReclaimStrategy::Eager,
+        ReclaimStrategy::default(),
+        ReclaimStrategy::Manual,
+    ]
+}
+
+/// A value which can tell whether it has been dropped, as long as its memory has not been reused.
+struct Checked {
+    value: usize,
+    check: usize,
+}
+
+impl Checked {
+    fn new(value: usize) -> Self {
+        Self {
+            value,
+            check: !value,
+        }
+    }
+
+    #[track_caller]
+    fn assert_alive(&self) -> usize {
+        // # Safety
+        //
+        // The field is read volatile so that the check is not optimised away.
+        let check = unsafe { std::ptr::read_volatile(&self.check) };
+        assert_eq!(check, !self.value, "Read a value which has been dropped");
+        self.value
+    }
+}
+
+impl Drop for Checked {
+    fn drop(&mut self) {
+        // # Safety
+        //
+        // The field is written volatile so that the write is not elided before the memory is
+        // freed.
+        unsafe { std::ptr::write_volatile(&mut self.check, self.value) };
+    }
+}
+
+/// Counts the values created by a scenario, so that their drops can be checked once it is done.
+#[derive(Default)]
+struct Values {
+    drop_counter: DropCounter,
+    created: AtomicUsize,
+}
+
+impl Values {
+    fn create(&self, value: usize) -> TrackedValue<Checked> {
+        self.created.fetch_add(1, Ordering::Relaxed);
+        self.drop_counter.track(Checked::new(value))
+    }
+
+    #[track_caller]
+    fn assert_all_dropped(&self) {
+        self.drop_counter
+            .assert_drops(self.created.load(Ordering::Relaxed));
+    }
+}
+
+/// Runs `scenario` with a fresh domain for each strategy, then drops the domain and checks that
+/// every value created was dropped exactly once.
+fn run(scenario: impl Fn(&Domain<1>, &Values, Instant)) {
+    for strategy in strategies() {
+        let test_domain = TestDomain::<1>::new(strategy);
+        let values = Values::default();
+        scenario(test_domain.get(), &values, Instant::now() + duration());
+        drop(test_domain);
+        values.assert_all_dropped();
+    }
+}
+
+#[test]
+fn swap_storm() {
+    run(|domain, values, deadline| {
+        let boxes: Vec<_> = (0..4)
+            .map(|value| AtomBoxIn::new_with_domain(values.create(value), domain))
+            .collect();
+        std::thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let boxes = &boxes;
+                scope.spawn(move || {
+                    let mut iteration = 0;
+                    while Instant::now() < deadline {
+                        let from = &boxes[(thread + iteration) % boxes.len()];
+                        let to = &boxes[(thread + iteration + 1) % boxes.len()];
+                        let guard = from.swap(values.create(iteration));
+                        guard.assert_alive();
+                        let guard = to.swap_from_guard(guard);
+                        guard.assert_alive();
+                        if iteration % 64 == 0 {
+                            domain.reclaim();
+                        }
+                        iteration += 1;
+                    }
+                });
+            }
+        });
+    });
+}
+
+#[test]
+fn reader_flood() {
+    run(|domain, values, deadline| {
+        let atom_box = AtomBoxIn::new_with_domain(values.create(0), domain);
+        let latest = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let mut value = 0;
+                while Instant::now() < deadline {
+                    value += 1;
+                    atom_box.store(values.create(value));
+                    latest.store(value, Ordering::Release);
+                    if value % 64 == 0 {
+                        domain.reclaim();
+                    }
+                }
+            });
+            for _ in 1..THREADS {
+                scope.spawn(|| {
+                    let mut last_seen = 0;
+                    while Instant::now() < deadline {
+                        let stored_before = latest.load(Ordering::Acquire);
+                        let value = atom_box.load().assert_alive();
+                        assert!(value >= last_seen, "Values are never seen out of order");
+                        assert!(value >= stored_before, "Loads see completed stores");
+                        last_seen = value;
+                    }
+                });
+            }
+        });
+    });
+}
+
+#[test]
+fn guard_holding_stragglers() {
+    run(|domain, values, deadline| {
+        let atom_box = AtomBoxIn::new_with_domain(values.create(0), domain);
+        std::thread::scope(|scope| {
+            for _ in 0..THREADS / 2 {
+                scope.spawn(|| {
+                    let mut value = 0;
+                    while Instant::now() < deadline {
+                        value += 1;
+                        atom_box.store(values.create(value));
+                        if value % 16 == 0 {
+                            domain.reclaim();
+                        }
+                    }
+                });
+            }
+            for straggler in 0..THREADS / 2 {
+                let atom_box = &atom_box;
+                scope.spawn(move || {
+                    while Instant::now() < deadline {
+                        let guard = atom_box.load();
+                        let value = guard.assert_alive();
+                        std::thread::sleep(Duration::from_millis(1 + straggler as u64));
+                        assert_eq!(guard.assert_alive(), value, "Guarded values are kept");
+                    }
+                });
+            }
+        });
+    });
+}